@@ -0,0 +1,134 @@
+//! SHA-256 checksums of image files for archival checks.
+//!
+//! Checksums are cached per file together with the file's modification time,
+//! so showing the hash again after navigating back skips re-reading the file.
+
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Size of the buffer the file is hashed in.
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Cached checksum of a single file.
+struct ChecksumEntry {
+    modified: Option<SystemTime>,
+    checksum: String,
+}
+
+/// Index of computed checksums keyed by file path.
+#[derive(Default)]
+pub struct ChecksumIndex {
+    entries: HashMap<PathBuf, ChecksumEntry>,
+}
+
+/// Returns the modification time of a file, if available.
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl ChecksumIndex {
+    /// Creates an empty checksum index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the indexed checksum if the file has not been modified since.
+    pub fn get(&self, path: &Path) -> Option<String> {
+        let entry = self.entries.get(path)?;
+        (entry.modified == modified_time(path)).then(|| entry.checksum.clone())
+    }
+
+    /// Records the checksum of a file along with its modification time.
+    ///
+    /// `modified` must be taken before hashing, so a file that changes while
+    /// it is hashed is hashed again on the next lookup.
+    pub fn insert(&mut self, path: PathBuf, modified: Option<SystemTime>, checksum: String) {
+        self.entries
+            .insert(path, ChecksumEntry { modified, checksum });
+    }
+}
+
+/// Computes the SHA-256 of the file's bytes as lowercase hex.
+pub fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; READ_BUFFER_SIZE];
+
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Returns the SHA-256 of a file through the index, hashing the file on a miss.
+///
+/// The lock is not held while hashing, so large files don't block other lookups.
+pub fn sha256_indexed(index: &Mutex<ChecksumIndex>, path: &Path) -> std::io::Result<String> {
+    if let Some(checksum) = index.lock().ok().and_then(|index| index.get(path)) {
+        log::debug!("Checksum cache hit: {:?}", path);
+        return Ok(checksum);
+    }
+
+    let modified = modified_time(path);
+    let checksum = sha256_file(path)?;
+    if let Ok(mut index) = index.lock() {
+        index.insert(path.to_path_buf(), modified, checksum.clone());
+    }
+    Ok(checksum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const ABC_SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    #[test]
+    fn hashes_file_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("abc.bin");
+        std::fs::write(&path, b"abc").unwrap();
+
+        assert_eq!(sha256_file(&path).unwrap(), ABC_SHA256);
+    }
+
+    #[test]
+    fn indexed_hash_is_stable_until_modified() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("image.png");
+        std::fs::write(&path, b"abc").unwrap();
+        let index = Mutex::new(ChecksumIndex::new());
+
+        assert_eq!(sha256_indexed(&index, &path).unwrap(), ABC_SHA256);
+        assert_eq!(
+            index.lock().unwrap().get(&path).as_deref(),
+            Some(ABC_SHA256)
+        );
+        assert_eq!(sha256_indexed(&index, &path).unwrap(), ABC_SHA256);
+
+        // ファイルシステムの時刻精度に依らないよう、更新日時を明示的にずらす
+        let modified = modified_time(&path).unwrap();
+        std::fs::write(&path, b"abcd").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified + Duration::from_secs(10))
+            .unwrap();
+
+        assert_eq!(index.lock().unwrap().get(&path), None);
+        let recomputed = sha256_indexed(&index, &path).unwrap();
+        assert_ne!(recomputed, ABC_SHA256);
+        assert_eq!(recomputed, sha256_file(&path).unwrap());
+    }
+}
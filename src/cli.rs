@@ -0,0 +1,258 @@
+//! Command line parsing.
+//!
+//! Flags are matched exactly, so typos are reported instead of being dropped.
+
+use std::ffi::OsString;
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Usage text printed for `--help` and after argument errors.
+const USAGE: &str = "\
+Usage: slint-sd-image-viewer [OPTIONS] [PATH]...
+
+Arguments:
+  [PATH]...  Image file or directory to open; the first usable one is opened
+
+Options:
+      --fullscreen          Start in fullscreen
+      --slideshow <SECS>    Advance to the next image every SECS seconds
+      --screenshot <OUT>    Render the image to OUT as PNG without opening a window
+  -h, --help                Print help
+  -V, --version             Print version";
+
+/// Prefix of the process serial number macOS passes to apps launched from Finder.
+const MACOS_PSN_PREFIX: &str = "-psn_";
+
+/// Options for a normal start, parsed from the command line.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct StartupOptions {
+    /// Positional image or directory paths, in the given order.
+    pub paths: Vec<PathBuf>,
+    /// Start in fullscreen.
+    pub fullscreen: bool,
+    /// Interval of the slideshow given with `--slideshow <secs>`.
+    pub slideshow: Option<Duration>,
+    /// Output path given with `--screenshot <out>`; no window is opened.
+    pub screenshot_output: Option<PathBuf>,
+}
+
+/// What the command line asks the application to do.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Start the viewer.
+    Run(StartupOptions),
+    /// Print the usage and exit.
+    Help,
+    /// Print the version and exit.
+    Version,
+}
+
+/// Errors in the command line arguments.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliError {
+    /// The flag is not known.
+    UnknownFlag(String),
+    /// The flag requires a value, but none was given.
+    MissingValue(&'static str),
+    /// The value of the flag could not be parsed.
+    InvalidValue { flag: &'static str, value: String },
+    /// A flag without a value was given one with `--flag=value`.
+    UnexpectedValue(&'static str),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownFlag(flag) => write!(f, "Unknown option: {}", flag),
+            Self::MissingValue(flag) => write!(f, "{} requires a value", flag),
+            Self::InvalidValue { flag, value } => {
+                write!(f, "Invalid value for {}: {}", flag, value)
+            }
+            Self::UnexpectedValue(flag) => write!(f, "{} does not take a value", flag),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/// Returns the usage text.
+pub fn usage() -> &'static str {
+    USAGE
+}
+
+/// Returns the name and version printed for `--version`.
+pub fn version() -> String {
+    format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+}
+
+/// Parses the process arguments, without the program name.
+pub fn parse_args() -> Result<Command, CliError> {
+    parse(std::env::args_os().skip(1))
+}
+
+/// Parses command line arguments, without the program name.
+///
+/// `--help` and `--version` stop parsing, ignoring the remaining arguments.
+/// Everything after `--` is treated as a path.
+pub fn parse(args: impl IntoIterator<Item = OsString>) -> Result<Command, CliError> {
+    let mut options = StartupOptions::default();
+    let mut args = args.into_iter();
+    let mut only_paths = false;
+
+    while let Some(arg) = args.next() {
+        let arg_str = arg.to_string_lossy();
+        if only_paths || !arg_str.starts_with('-') {
+            options.paths.push(PathBuf::from(arg));
+            continue;
+        }
+        if arg_str.starts_with(MACOS_PSN_PREFIX) {
+            continue;
+        }
+
+        let (flag, inline_value) = match arg_str.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+            _ => (arg_str.as_ref(), None),
+        };
+        let mut value = |name: &'static str| match inline_value.clone() {
+            Some(value) => Ok(value),
+            None => args
+                .next()
+                .map(|value| value.to_string_lossy().into_owned())
+                .ok_or(CliError::MissingValue(name)),
+        };
+        let no_value = |name: &'static str| match inline_value {
+            Some(_) => Err(CliError::UnexpectedValue(name)),
+            None => Ok(()),
+        };
+
+        match flag {
+            "--" => only_paths = true,
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "--fullscreen" => {
+                no_value("--fullscreen")?;
+                options.fullscreen = true;
+            }
+            "--slideshow" => {
+                let secs = value("--slideshow")?;
+                options.slideshow = Some(parse_interval("--slideshow", &secs)?);
+            }
+            "--screenshot" => {
+                options.screenshot_output = Some(PathBuf::from(value("--screenshot")?));
+            }
+            _ => return Err(CliError::UnknownFlag(flag.to_string())),
+        }
+    }
+
+    Ok(Command::Run(options))
+}
+
+/// Parses a positive number of seconds.
+fn parse_interval(flag: &'static str, value: &str) -> Result<Duration, CliError> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|secs| *secs > 0.0)
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+        .ok_or_else(|| CliError::InvalidValue {
+            flag,
+            value: value.to_string(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_strs(args: &[&str]) -> Result<Command, CliError> {
+        parse(args.iter().map(OsString::from))
+    }
+
+    #[test]
+    fn parses_screenshot_output() {
+        for args in [
+            &["a.png", "--screenshot", "out.png"][..],
+            &["--screenshot=out.png", "a.png"][..],
+        ] {
+            let Ok(Command::Run(options)) = parse_strs(args) else {
+                panic!("expected a run command for {:?}", args);
+            };
+            assert_eq!(options.screenshot_output, Some(PathBuf::from("out.png")));
+            assert_eq!(options.paths, [PathBuf::from("a.png")]);
+        }
+        assert_eq!(
+            parse_strs(&["a.png", "--screenshot"]),
+            Err(CliError::MissingValue("--screenshot"))
+        );
+    }
+
+    #[test]
+    fn parses_paths_and_flags() {
+        let expected = StartupOptions {
+            paths: vec![PathBuf::from("a.png"), PathBuf::from("dir")],
+            fullscreen: true,
+            slideshow: Some(Duration::from_secs_f64(2.5)),
+            screenshot_output: None,
+        };
+        assert_eq!(
+            parse_strs(&["a.png", "--fullscreen", "--slideshow", "2.5", "dir"]),
+            Ok(Command::Run(expected))
+        );
+    }
+
+    #[test]
+    fn treats_everything_after_double_dash_as_paths() {
+        let Ok(Command::Run(options)) = parse_strs(&["--", "--fullscreen"]) else {
+            panic!("expected a run command");
+        };
+        assert!(!options.fullscreen);
+        assert_eq!(options.paths, [PathBuf::from("--fullscreen")]);
+    }
+
+    #[test]
+    fn skips_macos_process_serial_number() {
+        assert_eq!(
+            parse_strs(&["-psn_0_12345"]),
+            Ok(Command::Run(StartupOptions::default()))
+        );
+    }
+
+    #[test]
+    fn help_and_version_stop_parsing() {
+        assert_eq!(parse_strs(&["-h", "--unknown"]), Ok(Command::Help));
+        assert_eq!(
+            parse_strs(&["--version", "--unknown"]),
+            Ok(Command::Version)
+        );
+    }
+
+    #[test]
+    fn reports_argument_errors() {
+        assert_eq!(
+            parse_strs(&["--fullscren"]),
+            Err(CliError::UnknownFlag("--fullscren".to_string()))
+        );
+        assert_eq!(
+            parse_strs(&["--slideshow"]),
+            Err(CliError::MissingValue("--slideshow"))
+        );
+        assert_eq!(
+            parse_strs(&["--fullscreen=yes"]),
+            Err(CliError::UnexpectedValue("--fullscreen"))
+        );
+    }
+
+    #[test]
+    fn rejects_non_positive_slideshow_interval() {
+        for value in ["0", "-1", "abc"] {
+            assert_eq!(
+                parse_strs(&["--slideshow", value]),
+                Err(CliError::InvalidValue {
+                    flag: "--slideshow",
+                    value: value.to_string(),
+                })
+            );
+        }
+    }
+}
@@ -1,4 +1,86 @@
 //! Application configuration constants.
 
 /// Supported image file extensions for scanning directories.
-pub const SUPPORTED_IMAGE_EXTENSIONS: [&str; 6] = ["jpg", "jpeg", "png", "gif", "bmp", "webp"];
+pub const SUPPORTED_IMAGE_EXTENSIONS: [&str; 7] =
+    ["jpg", "jpeg", "png", "gif", "bmp", "webp", "ico"];
+
+/// RAW file extensions shown through their embedded JPEG preview.
+///
+/// Only listed as supported when the `raw` feature is enabled.
+pub const RAW_IMAGE_EXTENSIONS: [&str; 3] = ["dng", "cr2", "nef"];
+
+/// Image file extensions whose formats support writing embedded XMP ratings.
+///
+/// A subset of [`SUPPORTED_IMAGE_EXTENSIONS`].
+pub const RATING_WRITABLE_EXTENSIONS: [&str; 4] = ["jpg", "jpeg", "png", "webp"];
+
+/// Number of recent log lines kept for the in-app log viewer.
+pub const LOG_BUFFER_CAPACITY: usize = 200;
+
+/// Frame delay used for animation frames that specify no usable delay.
+pub const DEFAULT_FRAME_DELAY_MS: u64 = 100;
+
+/// Maximum number of frames decoded for an animated image.
+pub const MAX_ANIMATION_FRAMES: usize = 1_000;
+
+/// Maximum decoded size in bytes of the frames of one animated image.
+///
+/// Longer or larger animations play only the frames that fit.
+pub const MAX_ANIMATION_BYTES: usize = 256 * 1024 * 1024;
+
+/// Number of entries kept in the recently opened files list.
+pub const RECENT_FILES_CAPACITY: usize = 10;
+
+/// Number of directories whose view preferences are remembered.
+pub const DIRECTORY_PREFS_CAPACITY: usize = 200;
+
+/// Upper bound for the preload radius, so preloaded neighbors fit in the image cache.
+pub const MAX_PRELOAD_RADIUS: usize = 4;
+
+/// Number of decoded images kept in the image cache in fixed mode.
+pub const IMAGE_CACHE_CAPACITY: usize = 10;
+
+/// Maximum decoded size in bytes of the images in the image cache,
+/// including animation frames and retained file bytes.
+pub const IMAGE_CACHE_MAX_BYTES: usize = 1024 * 1024 * 1024;
+
+/// Extra cache entries beyond the preload window in navigation window mode.
+pub const CACHE_WINDOW_BUFFER: usize = 2;
+
+/// Number of images in a directory above which the navigation list is windowed.
+pub const DIRECTORY_SOFT_CAP: usize = 10_000;
+
+/// Number of images kept on each side of the opened file when the list is windowed.
+///
+/// Each "load more" extends the window by this amount on both sides.
+pub const DIRECTORY_WINDOW_RADIUS: usize = 5_000;
+
+/// Maximum number of prompt tags shown in the info panel; copy operations keep all tags.
+pub const MAX_DISPLAYED_TAGS: usize = 500;
+
+/// Maximum number of characters of the prompt shown in the overlay on the image.
+pub const PROMPT_OVERLAY_MAX_CHARS: usize = 300;
+
+/// Maximum number of characters per line of the prompt overlay.
+pub const PROMPT_OVERLAY_LINE_WIDTH: usize = 100;
+
+/// Maximum number of threads used for directory-wide rating reads.
+pub const RATING_READ_THREADS: usize = 4;
+
+/// Time to wait after a dropped file for more files of the same drop.
+///
+/// winit reports each file of a multi-file drop as a separate event.
+#[cfg_attr(not(any(target_os = "macos", target_os = "windows")), allow(dead_code))]
+pub const DROP_COALESCE_MS: u64 = 150;
+
+/// Number of files read between progress updates of the rating prescan.
+pub const RATING_PRESCAN_PROGRESS_STEP: usize = 100;
+
+/// Number of files read between progress updates of the seed search.
+pub const SEED_SEARCH_PROGRESS_STEP: usize = 50;
+
+/// Maximum time to wait for pending rating and keyword writes when the app exits.
+pub const SHUTDOWN_WRITE_TIMEOUT_MS: u64 = 3_000;
+
+/// strftime format of the created / modified dates, used when the `date_format` setting is invalid.
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
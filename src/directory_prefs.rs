@@ -0,0 +1,255 @@
+//! Per-directory view preferences persisted across sessions.
+//!
+//! Stored as JSON next to the settings file, keyed by directory path.
+//! The store is bounded: when it is full, the least recently used
+//! directory is evicted.
+
+use crate::config::DIRECTORY_PREFS_CAPACITY;
+use crate::file_utils::SortOrder;
+use crate::ui::display_mode::DisplayMode;
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// File name of the directory preferences inside the configuration directory.
+const DIRECTORY_PREFS_FILE_NAME: &str = "directory_prefs.json";
+
+/// View preferences remembered for a single directory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DirectoryPrefs {
+    /// Sort order of the image list.
+    pub sort_order: SortOrder,
+    /// Display mode, or `None` to use the global setting.
+    pub display_mode: Option<DisplayMode>,
+}
+
+/// Stored preferences together with their last access for LRU eviction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PrefsEntry {
+    prefs: DirectoryPrefs,
+    /// Logical timestamp of the last access; larger is more recent.
+    last_access: u64,
+}
+
+/// Bounded map of directory preferences.
+#[derive(Debug, Clone)]
+pub struct DirectoryPrefsStore {
+    entries: HashMap<PathBuf, PrefsEntry>,
+    capacity: usize,
+    clock: u64,
+}
+
+impl DirectoryPrefsStore {
+    /// Creates a store from stored entries, evicting the oldest beyond `capacity`.
+    fn from_entries(entries: HashMap<PathBuf, PrefsEntry>, capacity: usize) -> Self {
+        let clock = entries.values().map(|e| e.last_access).max().unwrap_or(0);
+        let mut store = Self {
+            entries,
+            capacity,
+            clock,
+        };
+        store.evict();
+        store
+    }
+
+    /// Returns the preferences of a directory and marks it as recently used.
+    pub fn get(&mut self, dir: &Path) -> Option<DirectoryPrefs> {
+        let now = self.tick();
+        let entry = self.entries.get_mut(dir)?;
+        entry.last_access = now;
+        Some(entry.prefs)
+    }
+
+    /// Stores the preferences of a directory, evicting the least recently used if full.
+    pub fn set(&mut self, dir: PathBuf, prefs: DirectoryPrefs) {
+        let last_access = self.tick();
+        self.entries.insert(dir, PrefsEntry { prefs, last_access });
+        self.evict();
+    }
+
+    /// Advances the logical clock and returns the new time.
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    /// Removes the least recently used entries until the store fits its capacity.
+    fn evict(&mut self) {
+        while self.entries.len() > self.capacity {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_access)
+                .map(|(dir, _)| dir.clone())
+            else {
+                break;
+            };
+            debug!("Evicting directory preferences for {:?}", oldest);
+            self.entries.remove(&oldest);
+        }
+    }
+}
+
+static DIRECTORY_PREFS: Lazy<Mutex<DirectoryPrefsStore>> = Lazy::new(|| Mutex::new(load()));
+
+/// Returns the path of the directory preferences file.
+fn directory_prefs_path() -> Option<PathBuf> {
+    crate::settings::config_dir().map(|dir| dir.join(DIRECTORY_PREFS_FILE_NAME))
+}
+
+/// Loads the directory preferences from disk, returning an empty store on failure.
+fn load() -> DirectoryPrefsStore {
+    let entries = directory_prefs_path()
+        .and_then(|path| read_entries(&path))
+        .unwrap_or_default();
+
+    DirectoryPrefsStore::from_entries(entries, DIRECTORY_PREFS_CAPACITY)
+}
+
+/// Reads the stored entries from `path`, logging failures other than a missing file.
+fn read_entries(path: &Path) -> Option<HashMap<PathBuf, PrefsEntry>> {
+    let json = match std::fs::read_to_string(path) {
+        Ok(json) => {
+            info!("Loaded directory preferences from {:?}", path);
+            json
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(e) => {
+            warn!(
+                "Failed to read directory preferences from {:?}: {}",
+                path, e
+            );
+            return None;
+        }
+    };
+
+    match serde_json::from_str::<HashMap<PathBuf, PrefsEntry>>(&json) {
+        Ok(entries) => Some(entries),
+        Err(e) => {
+            warn!("Failed to parse directory preferences: {}", e);
+            None
+        }
+    }
+}
+
+/// Writes the directory preferences to disk.
+fn save(store: &DirectoryPrefsStore) -> std::io::Result<()> {
+    let path = directory_prefs_path().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "No configuration directory available",
+        )
+    })?;
+    write_entries(store, &path)
+}
+
+/// Writes the entries of `store` to `path` as JSON, creating the parent directory.
+fn write_entries(store: &DirectoryPrefsStore, path: &Path) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let json = serde_json::to_string_pretty(&store.entries).map_err(std::io::Error::other)?;
+    std::fs::write(path, json)
+}
+
+/// Returns the remembered preferences of a directory, if any.
+pub fn get(dir: &Path) -> Option<DirectoryPrefs> {
+    DIRECTORY_PREFS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(dir)
+}
+
+/// Remembers the preferences of a directory and persists the store.
+///
+/// Directories whose path is not valid UTF-8 are skipped, since JSON keys must be strings.
+pub fn set(dir: &Path, prefs: DirectoryPrefs) {
+    if dir.to_str().is_none() {
+        debug!("Not saving preferences for non-UTF-8 directory: {:?}", dir);
+        return;
+    }
+
+    let snapshot = {
+        let mut store = DIRECTORY_PREFS.lock().unwrap_or_else(|e| e.into_inner());
+        store.set(dir.to_path_buf(), prefs);
+        store.clone()
+    };
+
+    debug!("Saved directory preferences for {:?}: {:?}", dir, prefs);
+    if let Err(e) = save(&snapshot) {
+        warn!("Failed to save directory preferences: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prefs(sort_order: SortOrder, display_mode: Option<DisplayMode>) -> DirectoryPrefs {
+        DirectoryPrefs {
+            sort_order,
+            display_mode,
+        }
+    }
+
+    #[test]
+    fn store_round_trips_through_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config").join(DIRECTORY_PREFS_FILE_NAME);
+        let saved = prefs(SortOrder::ModifiedDate, Some(DisplayMode::Actual));
+        let mut store = DirectoryPrefsStore::from_entries(HashMap::new(), 4);
+        store.set(PathBuf::from("/images/a"), saved);
+        store.set(PathBuf::from("/images/b"), DirectoryPrefs::default());
+
+        write_entries(&store, &path).unwrap();
+        let mut loaded = DirectoryPrefsStore::from_entries(read_entries(&path).unwrap(), 4);
+
+        assert_eq!(loaded.get(Path::new("/images/a")), Some(saved));
+        assert_eq!(
+            loaded.get(Path::new("/images/b")),
+            Some(DirectoryPrefs::default())
+        );
+        assert_eq!(loaded.get(Path::new("/images/c")), None);
+        assert!(read_entries(&dir.path().join("missing.json")).is_none());
+    }
+
+    #[test]
+    fn evicts_least_recently_used_directory() {
+        let mut store = DirectoryPrefsStore::from_entries(HashMap::new(), 2);
+        store.set(PathBuf::from("a"), prefs(SortOrder::Name, None));
+        store.set(PathBuf::from("b"), prefs(SortOrder::ModifiedDate, None));
+        assert!(store.get(Path::new("a")).is_some());
+
+        store.set(
+            PathBuf::from("c"),
+            prefs(SortOrder::Name, Some(DisplayMode::Fit)),
+        );
+
+        assert!(store.get(Path::new("b")).is_none());
+        assert!(store.get(Path::new("a")).is_some());
+        assert!(store.get(Path::new("c")).is_some());
+    }
+
+    #[test]
+    fn loading_an_oversized_store_keeps_the_newest_entries() {
+        let entries = (0..5u64)
+            .map(|i| {
+                let entry = PrefsEntry {
+                    prefs: DirectoryPrefs::default(),
+                    last_access: i,
+                };
+                (PathBuf::from(i.to_string()), entry)
+            })
+            .collect();
+        let mut store = DirectoryPrefsStore::from_entries(entries, 2);
+
+        assert_eq!(store.entries.len(), 2);
+        assert!(store.get(Path::new("4")).is_some());
+        assert!(store.get(Path::new("3")).is_some());
+    }
+}
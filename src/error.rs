@@ -1,20 +1,30 @@
 //! Unified error types for the image viewer application.
 
+use crate::messages::{self, Language, Message};
 use std::fmt;
+use std::path::PathBuf;
 
 /// Application-specific errors.
 #[derive(Debug)]
 pub enum AppError {
     /// Error loading or decoding an image file
     ImageLoad(String),
+    /// Error encoding or writing an image file
+    ImageWrite(String),
     /// Error scanning directory for image files
     DirectoryScan(String),
     /// Error reading XMP metadata
     XmpRead(String),
     /// Error writing XMP metadata
     XmpWrite(String),
+    /// File cannot be written because it is read-only or access is denied
+    FileReadOnly(String),
     /// Error reading metadata (including SD parameters)
     MetadataRead(String),
+    /// Image data ends early, e.g. because the file is still being written
+    IncompleteImage(String),
+    /// File does not exist, e.g. because it was deleted after the directory scan
+    FileNotFound(String),
 }
 
 /// Navigation-specific errors.
@@ -26,35 +36,93 @@ pub enum NavigationError {
     NoCurrentPath,
     /// Failed to scan directory for image files
     DirectoryScanFailed(String),
+    /// No sibling directory containing images was found
+    NoSiblingDirectory,
+    /// No other image with the requested rating was found
+    NoImageWithRating(u8),
+    /// No other image with the requested seed was found
+    NoImageWithSeed(String),
+    /// The path is not part of the current image list
+    NotInList(PathBuf),
+    /// The index is outside the current image list
+    IndexOutOfRange(usize),
 }
 
 impl fmt::Display for AppError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            AppError::ImageLoad(msg) => write!(f, "画像読み込みエラー: {}", msg),
-            AppError::DirectoryScan(msg) => write!(f, "ディレクトリスキャンエラー: {}", msg),
-            AppError::XmpRead(msg) => write!(f, "XMP読み取りエラー: {}", msg),
-            AppError::XmpWrite(msg) => write!(f, "XMP書き込みエラー: {}", msg),
-            AppError::MetadataRead(msg) => write!(f, "メタデータ読み取りエラー: {}", msg),
-        }
+        f.write_str(&self.render(messages::current_language()))
     }
 }
 
 impl fmt::Display for NavigationError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(messages::current_language()))
+    }
+}
+
+impl AppError {
+    /// Returns the message of the error in `language`.
+    pub fn render(&self, language: Language) -> String {
+        let (message, detail) = match self {
+            AppError::ImageLoad(msg) => (Message::ImageLoad, msg),
+            AppError::ImageWrite(msg) => (Message::ImageWrite, msg),
+            AppError::DirectoryScan(msg) => (Message::DirectoryScan, msg),
+            AppError::XmpRead(msg) => (Message::XmpRead, msg),
+            AppError::XmpWrite(msg) => (Message::XmpWrite, msg),
+            AppError::FileReadOnly(msg) => (Message::FileReadOnly, msg),
+            AppError::MetadataRead(msg) => (Message::MetadataRead, msg),
+            AppError::IncompleteImage(msg) => (Message::IncompleteImage, msg),
+            AppError::FileNotFound(msg) => (Message::FileNotFound, msg),
+        };
+        messages::render(message, language, &[detail])
+    }
+
+    /// Returns whether the error may go away on its own, so the load should be retried later.
+    ///
+    /// Nothing is cached for failed loads, so the next auto-reload event or
+    /// navigation to the file decodes it again.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, AppError::IncompleteImage(_))
+    }
+}
+
+impl NavigationError {
+    /// Returns the message of the error in `language`.
+    pub fn render(&self, language: Language) -> String {
+        let render =
+            |message, args: &[&dyn fmt::Display]| messages::render(message, language, args);
         match self {
-            NavigationError::NoImages => write!(f, "No images available in the current directory"),
-            NavigationError::NoCurrentPath => write!(f, "No current file path is set"),
+            NavigationError::NoImages => render(Message::NoImages, &[]),
+            NavigationError::NoCurrentPath => render(Message::NoCurrentPath, &[]),
             NavigationError::DirectoryScanFailed(msg) => {
-                write!(f, "Failed to scan directory: {}", msg)
+                render(Message::DirectoryScanFailed, &[msg])
+            }
+            NavigationError::NoSiblingDirectory => render(Message::NoSiblingDirectory, &[]),
+            NavigationError::NoImageWithRating(rating) => {
+                render(Message::NoImageWithRating, &[rating])
+            }
+            NavigationError::NoImageWithSeed(seed) => render(Message::NoImageWithSeed, &[seed]),
+            NavigationError::NotInList(path) => render(Message::NotInList, &[&path.display()]),
+            NavigationError::IndexOutOfRange(index) => {
+                render(Message::IndexOutOfRange, &[&(index + 1)])
             }
         }
     }
 }
 
+/// Returns whether an I/O error means the data ended before decoding finished.
+fn is_unexpected_eof(err: &std::io::Error) -> bool {
+    err.kind() == std::io::ErrorKind::UnexpectedEof
+}
+
 impl From<png::DecodingError> for AppError {
     fn from(err: png::DecodingError) -> Self {
-        AppError::ImageLoad(format!("PNG decoding error: {}", err))
+        match err {
+            png::DecodingError::IoError(ref io_err) if is_unexpected_eof(io_err) => {
+                AppError::IncompleteImage(format!("PNG data ends early: {}", err))
+            }
+            _ => AppError::ImageLoad(format!("PNG decoding error: {}", err)),
+        }
     }
 }
 
@@ -64,7 +132,12 @@ impl std::error::Error for NavigationError {}
 
 impl From<image::ImageError> for AppError {
     fn from(err: image::ImageError) -> Self {
-        AppError::ImageLoad(err.to_string())
+        match err {
+            image::ImageError::IoError(ref io_err) if is_unexpected_eof(io_err) => {
+                AppError::IncompleteImage(err.to_string())
+            }
+            _ => AppError::ImageLoad(err.to_string()),
+        }
     }
 }
 
@@ -76,3 +149,28 @@ impl From<std::io::Error> for AppError {
 
 /// Type alias for Results in this application.
 pub type Result<T> = std::result::Result<T, AppError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_render_in_the_selected_language() {
+        let error = NavigationError::NoImageWithRating(4);
+        assert_eq!(
+            error.render(Language::English),
+            "No other image with rating 4 found"
+        );
+        assert_eq!(
+            error.render(Language::Japanese),
+            "レーティング4の他の画像が見つかりません"
+        );
+
+        let error = AppError::FileNotFound("a.png".to_string());
+        assert_eq!(error.render(Language::English), "File not found: a.png");
+        assert_eq!(
+            error.render(Language::Japanese),
+            "ファイルが見つかりません: a.png"
+        );
+    }
+}
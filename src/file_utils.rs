@@ -1,8 +1,15 @@
-use crate::config::SUPPORTED_IMAGE_EXTENSIONS;
+use crate::config::{RAW_IMAGE_EXTENSIONS, SUPPORTED_IMAGE_EXTENSIONS};
 use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+/// Longest path, in UTF-16 units including the terminator, that Win32 APIs
+/// accept without the `\\?\` prefix.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+const WINDOWS_MAX_PATH: usize = 260;
+
 /// Extension trait for Path to add logging utilities.
 pub trait PathExt {
     /// Formats a file path for compact logging.
@@ -12,7 +19,10 @@ pub trait PathExt {
 
 impl PathExt for Path {
     fn format_for_log(&self) -> String {
-        let filename = self.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        let filename = self
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_default();
 
         if filename.len() <= 23 {
             filename.to_string()
@@ -25,24 +35,341 @@ impl PathExt for Path {
     }
 }
 
+/// Sort order for image files within a directory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    /// Sort by file path (default).
+    #[default]
+    Name,
+    /// Sort by modification time, oldest first. Ties are broken by path.
+    ModifiedDate,
+}
+
+/// Sorts image files in place according to the given order.
+pub fn sort_image_files(files: &mut [PathBuf], order: SortOrder) {
+    match order {
+        SortOrder::Name => files.sort(),
+        SortOrder::ModifiedDate => files.sort_by_cached_key(|path| {
+            let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+            (modified, path.clone())
+        }),
+    }
+}
+
+/// Checks if a file extension (without the dot) belongs to a supported image format.
+///
+/// RAW extensions are included only when the `raw` feature is enabled.
+pub fn is_supported_extension(ext: &str) -> bool {
+    let ext = ext.to_lowercase();
+    SUPPORTED_IMAGE_EXTENSIONS.contains(&ext.as_str())
+        || (cfg!(feature = "raw") && RAW_IMAGE_EXTENSIONS.contains(&ext.as_str()))
+}
+
 /// Checks if a file is a supported image based on its extension.
 pub fn is_supported_image(path: &Path) -> bool {
     path.is_file()
         && path
             .extension()
             .and_then(|ext| ext.to_str())
-            .map(|ext_str| SUPPORTED_IMAGE_EXTENSIONS.contains(&ext_str.to_lowercase().as_str()))
+            .map(is_supported_extension)
+            .unwrap_or(false)
+}
+
+/// Returns the index range of at most `radius` items on each side of `center`.
+///
+/// The range is shifted to stay within `0..len`, so a window near either end
+/// still covers `2 * radius + 1` items when the list is long enough.
+pub fn window_range(len: usize, center: usize, radius: usize) -> std::ops::Range<usize> {
+    let size = radius.saturating_mul(2).saturating_add(1).min(len);
+    let start = center.saturating_sub(radius).min(len - size);
+    start..start + size
+}
+
+/// Removes the `\\?\` verbatim prefix from a Windows path string when the
+/// plain form names the same file.
+///
+/// `\\?\UNC\server\share\a.png` becomes `\\server\share\a.png` and
+/// `\\?\C:\a.png` becomes `C:\a.png`. The XMP SDK and apps reading file
+/// lists from the clipboard do not reliably accept verbatim paths.
+/// Paths are returned unchanged when the plain form would be too long or be
+/// normalized differently: `.` / `..` components, forward slashes, trailing
+/// dots or spaces and reserved device names are taken literally only in
+/// verbatim paths. Other paths, such as plain UNC paths, are returned as is.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub fn strip_verbatim_prefix(path: &str) -> Cow<'_, str> {
+    let plain = if let Some(rest) = path.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{}", rest)
+    } else if let Some(rest) = path.strip_prefix(r"\\?\") {
+        let bytes = rest.as_bytes();
+        let has_drive = bytes.len() >= 3
+            && bytes[0].is_ascii_alphabetic()
+            && bytes[1] == b':'
+            && bytes[2] == b'\\';
+        if !has_drive {
+            return Cow::Borrowed(path);
+        }
+        rest.to_string()
+    } else {
+        return Cow::Borrowed(path);
+    };
+
+    let too_long = plain.encode_utf16().count() >= WINDOWS_MAX_PATH;
+    let literal_only = plain.contains('/')
+        || plain
+            .split('\\')
+            .filter(|component| !component.is_empty())
+            .any(is_literal_only_component);
+    if too_long || literal_only {
+        return Cow::Borrowed(path);
+    }
+    Cow::Owned(plain)
+}
+
+/// Checks whether a path component would be changed or reinterpreted by
+/// Win32 path normalization, e.g. `..`, `name.` or `NUL.png`.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn is_literal_only_component(component: &str) -> bool {
+    const RESERVED: [&str; 4] = ["CON", "PRN", "AUX", "NUL"];
+
+    if component == "." || component == ".." || component.ends_with(['.', ' ']) {
+        return true;
+    }
+
+    let stem = component
+        .split('.')
+        .next()
+        .unwrap_or_default()
+        .trim_end()
+        .to_ascii_uppercase();
+    RESERVED.contains(&stem.as_str())
+        || (stem.len() == 4
+            && (stem.starts_with("COM") || stem.starts_with("LPT"))
+            && stem.as_bytes()[3].is_ascii_digit()
+            && stem.as_bytes()[3] != b'0')
+}
+
+/// Returns the path string to hand to APIs that expect plain Windows paths.
+///
+/// Strips the `\\?\` verbatim prefix on Windows, see [`strip_verbatim_prefix`].
+/// Other platforms return the string unchanged, since `\\?\` is an
+/// ordinary file name there.
+pub fn to_plain_path(path: &str) -> Cow<'_, str> {
+    #[cfg(target_os = "windows")]
+    {
+        strip_verbatim_prefix(path)
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        Cow::Borrowed(path)
+    }
+}
+
+/// Heuristically checks whether a path is on a network filesystem.
+///
+/// Detects UNC paths on Windows and network mounts listed in `/proc/mounts` on Linux.
+/// Mapped network drives and macOS network volumes are not detected.
+pub fn is_network_path(path: &Path) -> bool {
+    #[cfg(target_os = "windows")]
+    {
+        use std::path::{Component, Prefix};
+        matches!(
+            path.components().next(),
+            Some(Component::Prefix(prefix))
+                if matches!(prefix.kind(), Prefix::UNC(..) | Prefix::VerbatimUNC(..))
+        )
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        fs::read_to_string("/proc/mounts")
+            .map(|mounts| is_network_mount(&mounts, path))
+            .unwrap_or(false)
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    {
+        let _ = path;
+        false
+    }
+}
+
+/// Checks whether the mount containing `path` has a network filesystem type.
+#[cfg(target_os = "linux")]
+fn is_network_mount(mounts: &str, path: &Path) -> bool {
+    const NETWORK_FILESYSTEMS: [&str; 7] =
+        ["nfs", "nfs4", "cifs", "smbfs", "smb3", "fuse.sshfs", "9p"];
+
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount_point = fields.nth(1)?.replace("\\040", " ");
+            let fs_type = fields.next()?;
+            Some((PathBuf::from(mount_point), fs_type))
+        })
+        .filter(|(mount_point, _)| path.starts_with(mount_point))
+        .max_by_key(|(mount_point, _)| mount_point.components().count())
+        .is_some_and(|(_, fs_type)| NETWORK_FILESYSTEMS.contains(&fs_type))
+}
+
+/// Returns the directories next to `dir` (including `dir` itself), sorted by name.
+pub fn sibling_directories(dir: &Path) -> Result<Vec<PathBuf>> {
+    let Some(parent) = dir.parent() else {
+        return Ok(vec![dir.to_path_buf()]);
+    };
+
+    let mut dirs: Vec<PathBuf> = fs::read_dir(parent)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+
+    dirs.sort();
+    Ok(dirs)
+}
+
+/// Checks if a file is hidden.
+///
+/// Uses the hidden and system attributes on Windows and a leading dot in the
+/// file name elsewhere.
+pub fn is_hidden(path: &Path) -> bool {
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        const FILE_ATTRIBUTE_SYSTEM: u32 = 0x4;
+
+        fs::metadata(path)
+            .map(|m| m.file_attributes() & (FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM) != 0)
             .unwrap_or(false)
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        path.file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+    }
 }
 
 /// Scans a directory and returns a sorted list of supported image files.
-pub fn scan_directory(dir: &Path) -> Result<Vec<PathBuf>> {
+///
+/// Hidden files are left out when `skip_hidden` is set.
+pub fn scan_directory(dir: &Path, skip_hidden: bool) -> Result<Vec<PathBuf>> {
     let mut image_files: Vec<PathBuf> = fs::read_dir(dir)?
         .filter_map(|entry| entry.ok())
         .map(|entry| entry.path())
         .filter(|path| is_supported_image(path))
+        .filter(|path| !(skip_hidden && is_hidden(path)))
         .collect();
 
     image_files.sort();
     Ok(image_files)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_range_centers_on_current_file() {
+        assert_eq!(window_range(100, 50, 10), 40..61);
+    }
+
+    #[test]
+    fn window_range_shifts_at_list_ends() {
+        assert_eq!(window_range(100, 3, 10), 0..21);
+        assert_eq!(window_range(100, 98, 10), 79..100);
+    }
+
+    #[test]
+    fn window_range_covers_short_lists() {
+        assert_eq!(window_range(5, 2, 10), 0..5);
+        assert_eq!(window_range(0, 0, 10), 0..0);
+    }
+
+    #[test]
+    fn sibling_directories_lists_only_directories_by_name() {
+        let root = tempfile::tempdir().unwrap();
+        for name in ["b", "a", "c"] {
+            fs::create_dir(root.path().join(name)).unwrap();
+        }
+        fs::write(root.path().join("file.png"), b"").unwrap();
+
+        let siblings = sibling_directories(&root.path().join("b")).unwrap();
+        assert_eq!(siblings, ["a", "b", "c"].map(|name| root.path().join(name)));
+    }
+
+    #[test]
+    fn missing_directory_is_a_scan_error() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(matches!(
+            scan_directory(&dir.path().join("gone"), false),
+            Err(crate::error::AppError::DirectoryScan(_))
+        ));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn scan_directory_skips_dot_files_only_when_asked() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["a.png", ".hidden.png", "notes.txt"] {
+            fs::write(dir.path().join(name), b"").unwrap();
+        }
+
+        assert_eq!(
+            scan_directory(dir.path(), true).unwrap(),
+            [dir.path().join("a.png")]
+        );
+        assert_eq!(
+            scan_directory(dir.path(), false).unwrap(),
+            [dir.path().join(".hidden.png"), dir.path().join("a.png")]
+        );
+    }
+
+    #[test]
+    fn strip_verbatim_prefix_plain_forms() {
+        assert_eq!(
+            strip_verbatim_prefix(r"\\?\UNC\server\share\a.png"),
+            r"\\server\share\a.png"
+        );
+        assert_eq!(
+            strip_verbatim_prefix(r"\\?\C:\images\a.png"),
+            r"C:\images\a.png"
+        );
+    }
+
+    #[test]
+    fn strip_verbatim_prefix_keeps_other_paths() {
+        for path in [
+            r"C:\images\a.png",
+            r"\\server\share\a.png",
+            r"\\?\Volume{0}\a.png",
+        ] {
+            assert!(matches!(strip_verbatim_prefix(path), Cow::Borrowed(p) if p == path));
+        }
+    }
+
+    #[test]
+    fn strip_verbatim_prefix_keeps_literal_only_paths() {
+        for path in [
+            r"\\?\C:\images\..\a.png",
+            r"\\?\C:\images\a.png.",
+            r"\\?\C:\images\NUL.png",
+            r"\\?\C:\images\COM1",
+            r"\\?\C:\images/a.png",
+        ] {
+            assert_eq!(strip_verbatim_prefix(path), path);
+        }
+        let long = format!(r"\\?\C:\{}.png", "a".repeat(WINDOWS_MAX_PATH));
+        assert_eq!(strip_verbatim_prefix(&long), long);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn plain_path_is_unchanged_outside_windows() {
+        let path = r"\\?\UNC\server\share\a.png";
+        assert!(matches!(to_plain_path(path), Cow::Borrowed(p) if p == path));
+    }
+}
@@ -3,22 +3,62 @@
 //! Caches decoded RGB8 image data with metadata using an LRU policy.
 //! This allows instant display of recently viewed images.
 
+use crate::config;
 use crate::file_utils::PathExt;
 use crate::image_loader::LoadedImageData;
 use lru::LruCache;
+use serde::{Deserialize, Serialize};
 use std::num::NonZeroUsize;
 use std::path::PathBuf;
 
+/// How the image cache capacity is chosen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheMode {
+    /// Fixed capacity, grown only when the preload window would not fit.
+    #[default]
+    Fixed,
+    /// Sized to the preload window plus a small buffer.
+    NavigationWindow,
+}
+
+/// Returns the cache capacity for a preload radius.
+///
+/// The preload window (current ± radius) always fits, so preloaded
+/// neighbors are never evicted before they can be navigated to.
+pub fn capacity_for(mode: CacheMode, preload_radius: usize) -> usize {
+    let window = preload_radius * 2 + 1;
+    match mode {
+        CacheMode::Fixed => config::IMAGE_CACHE_CAPACITY.max(window),
+        CacheMode::NavigationWindow => window + config::CACHE_WINDOW_BUFFER,
+    }
+}
+
 /// LRU cache for storing decoded images.
+///
+/// Bounded both by the number of images and by their decoded size,
+/// so a few large animations cannot hold gigabytes of frames.
 pub struct ImageCache {
     cache: LruCache<PathBuf, LoadedImageData>,
+    /// Sum of [`LoadedImageData::memory_size`] over the cached images.
+    bytes: usize,
+    max_bytes: usize,
 }
 
 impl ImageCache {
     /// Creates a new image cache with the specified capacity.
     pub fn new(capacity: usize) -> Self {
+        Self::with_max_bytes(capacity, config::IMAGE_CACHE_MAX_BYTES)
+    }
+
+    /// Creates a new image cache with the specified capacity and size budget in bytes.
+    ///
+    /// The most recently stored image is kept even if it alone exceeds the budget.
+    pub fn with_max_bytes(capacity: usize, max_bytes: usize) -> Self {
         Self {
             cache: LruCache::new(NonZeroUsize::new(capacity).expect("Capacity must be non-zero")),
+            bytes: 0,
+            max_bytes,
         }
     }
 
@@ -34,14 +74,40 @@ impl ImageCache {
     }
 
     /// Stores an image in the cache.
+    ///
+    /// Evicts the least recently used images while the cache is over its size budget.
     pub fn put(&mut self, path: PathBuf, image_data: LoadedImageData) {
         log::info!(
-            "Cache PUT: {} ({}x{})",
+            "Cache PUT: {} ({}x{}, {} frames)",
             path.format_for_log(),
             image_data.width,
-            image_data.height
+            image_data.height,
+            image_data.frames.len()
         );
-        self.cache.put(path, image_data);
+        self.bytes += image_data.memory_size();
+        // Returns the replaced entry or the one evicted by the entry limit
+        if let Some((_, old)) = self.cache.push(path, image_data) {
+            self.bytes -= old.memory_size();
+        }
+
+        while self.bytes > self.max_bytes && self.cache.len() > 1 {
+            let Some((evicted, old)) = self.cache.pop_lru() else {
+                break;
+            };
+            self.bytes -= old.memory_size();
+            log::info!(
+                "Cache EVICT: {} (over size budget)",
+                evicted.format_for_log()
+            );
+        }
+    }
+
+    /// Removes an image from the cache, e.g. because its file changed.
+    pub fn remove(&mut self, path: &PathBuf) {
+        if let Some(old) = self.cache.pop(path) {
+            self.bytes -= old.memory_size();
+            log::info!("Cache REMOVE: {}", path.format_for_log());
+        }
     }
 
     /// Updates the rating of a cached image without changing its position in the LRU.
@@ -51,8 +117,128 @@ impl ImageCache {
         }
     }
 
-    /// Checks if an image is in the cache.
-    pub fn contains(&mut self, path: &PathBuf) -> bool {
-        self.cache.contains(path)
+    /// Updates the keywords of a cached image without changing its position in the LRU.
+    pub fn update_keywords(&mut self, path: &PathBuf, keywords: Vec<String>) {
+        if let Some(cached) = self.cache.peek_mut(path) {
+            cached.keywords = keywords;
+        }
+    }
+
+    /// Changes the capacity, evicting the least recently used images if it shrinks.
+    ///
+    /// A capacity of 0 is treated as 1.
+    pub fn resize(&mut self, capacity: usize) {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        if capacity == self.cache.cap() {
+            return;
+        }
+
+        log::info!("Cache RESIZE: {} -> {} entries", self.cache.cap(), capacity);
+        self.cache.resize(capacity);
+        self.bytes = self
+            .cache
+            .iter()
+            .map(|(_, cached)| cached.memory_size())
+            .sum();
+    }
+
+    /// Returns whether another image would evict one.
+    pub fn is_full(&self) -> bool {
+        self.cache.len() >= self.cache.cap().get()
+    }
+
+    /// Removes all cached images.
+    pub fn clear(&mut self) {
+        log::info!("Cache CLEAR: {} entries", self.cache.len());
+        self.cache.clear();
+        self.bytes = 0;
+    }
+
+    /// Returns a cached image without changing its position in the LRU.
+    ///
+    /// For presence checks and inspection that should not keep an image cached longer.
+    pub fn peek(&self, path: &PathBuf) -> Option<&LoadedImageData> {
+        self.cache.peek(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_least_recently_used_over_size_budget() {
+        // 10x10 RGB images are 300 bytes each
+        let mut cache = ImageCache::with_max_bytes(10, 700);
+        for name in ["a", "b"] {
+            cache.put(PathBuf::from(name), LoadedImageData::blank(name, 10, 10));
+        }
+        assert!(cache.get(&PathBuf::from("a")).is_some());
+
+        cache.put(PathBuf::from("c"), LoadedImageData::blank("c", 10, 10));
+        assert!(cache.peek(&PathBuf::from("b")).is_none());
+        assert!(cache.peek(&PathBuf::from("a")).is_some());
+        assert!(cache.peek(&PathBuf::from("c")).is_some());
+    }
+
+    #[test]
+    fn keeps_latest_image_larger_than_budget() {
+        let mut cache = ImageCache::with_max_bytes(10, 100);
+        cache.put(PathBuf::from("a"), LoadedImageData::blank("a", 2, 2));
+        cache.put(PathBuf::from("big"), LoadedImageData::blank("big", 10, 10));
+        assert!(cache.peek(&PathBuf::from("a")).is_none());
+        assert!(cache.peek(&PathBuf::from("big")).is_some());
+
+        // Replacing and removing entries keeps the size accounting right
+        cache.put(PathBuf::from("big"), LoadedImageData::blank("big", 2, 2));
+        cache.put(PathBuf::from("a"), LoadedImageData::blank("a", 2, 2));
+        assert!(cache.peek(&PathBuf::from("big")).is_some());
+        cache.remove(&PathBuf::from("big"));
+        assert_eq!(cache.bytes, 12);
+    }
+
+    #[test]
+    fn resizing_down_evicts_least_recently_used() {
+        let mut cache = ImageCache::new(3);
+        for name in ["a", "b", "c"] {
+            cache.put(PathBuf::from(name), LoadedImageData::blank(name, 2, 2));
+        }
+        assert!(cache.get(&PathBuf::from("a")).is_some());
+
+        cache.resize(2);
+        assert!(cache.peek(&PathBuf::from("b")).is_none());
+        assert!(cache.peek(&PathBuf::from("a")).is_some());
+        assert!(cache.peek(&PathBuf::from("c")).is_some());
+        assert!(cache.is_full());
+        assert_eq!(cache.bytes, 24);
+
+        // Growing keeps everything and makes room for more
+        cache.resize(4);
+        assert!(!cache.is_full());
+        cache.put(PathBuf::from("d"), LoadedImageData::blank("d", 2, 2));
+        cache.put(PathBuf::from("e"), LoadedImageData::blank("e", 2, 2));
+        for name in ["a", "c", "d", "e"] {
+            assert!(cache.peek(&PathBuf::from(name)).is_some());
+        }
+
+        cache.resize(0);
+        assert!(cache.peek(&PathBuf::from("e")).is_some());
+        assert!(cache.peek(&PathBuf::from("d")).is_none());
+        assert_eq!(cache.bytes, 12);
+    }
+
+    #[test]
+    fn peek_keeps_eviction_order() {
+        let mut cache = ImageCache::new(2);
+        for name in ["a", "b"] {
+            cache.put(PathBuf::from(name), LoadedImageData::blank(name, 2, 2));
+        }
+        assert!(cache.peek(&PathBuf::from("a")).is_some());
+
+        // "a" is still the least recently used despite the peek
+        cache.put(PathBuf::from("c"), LoadedImageData::blank("c", 2, 2));
+        assert!(cache.peek(&PathBuf::from("a")).is_none());
+        assert!(cache.peek(&PathBuf::from("b")).is_some());
+        assert!(cache.peek(&PathBuf::from("c")).is_some());
     }
 }
@@ -1,11 +1,47 @@
+use crate::config;
 use crate::error::{AppError, Result};
-use crate::metadata::{self, SdParameters};
-use crate::services::default_color_management_service;
+use crate::metadata::{self, SdParameters, SdParseFailure};
+use crate::rating_index::{self, RatingIndex};
+use crate::services::{ColorManagementOutcome, ColorTarget, default_color_management_service};
 use image::ImageFormat;
-use log::error;
+use log::{error, warn};
 use slint::{Image, Rgb8Pixel, SharedPixelBuffer};
 use std::io::Cursor;
 use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Single decoded frame of an animated image.
+///
+/// The pixels are shared, so cloning a frame or showing it again does not copy them.
+#[derive(Clone)]
+pub struct AnimationFrame {
+    pub pixels: SharedPixelBuffer<Rgb8Pixel>,
+    pub delay_ms: u32,
+}
+
+/// Original file bytes of an image, kept for copying without re-encoding.
+#[derive(Clone)]
+pub struct EncodedImage {
+    pub bytes: Arc<[u8]>,
+    /// MIME type of the bytes, e.g. `image/png`.
+    pub mime: &'static str,
+}
+
+impl EncodedImage {
+    /// Reads the original bytes of an image file, detecting the format from its content.
+    pub fn read(path: &Path) -> Result<Self> {
+        let file_bytes = read_file_bytes(path)?;
+        let format = detect_format(&create_image_reader(&file_bytes, path)?, path)?;
+        Ok(Self::new(file_bytes, format))
+    }
+
+    fn new(bytes: Vec<u8>, format: ImageFormat) -> Self {
+        Self {
+            bytes: bytes.into(),
+            mime: format.to_mime_type(),
+        }
+    }
+}
 
 /// Loaded image data with metadata
 #[derive(Clone)]
@@ -15,33 +51,205 @@ pub struct LoadedImageData {
     pub height: u32,
     pub rating: Option<u8>,
     pub sd_parameters: Option<SdParameters>,
+    /// Parameters text that was found but could not be parsed, with the reason.
+    pub sd_parse_error: Option<SdParseFailure>,
     pub file_name: String,
+    /// File size in bytes.
+    pub file_size: u64,
     pub file_size_formatted: String,
     pub created_date: String,
     pub modified_date: String,
+    /// Frames of an animated image, up to the animation limits. Empty for still images.
+    pub frames: Vec<AnimationFrame>,
+    /// Result of applying color management to the pixels.
+    pub color_management: ColorManagementOutcome,
+    /// XMP `dc:subject` keywords (including reconciled IPTC keywords).
+    pub keywords: Vec<String>,
+    /// Whether the pixels are the embedded preview of a RAW file rather than the RAW data.
+    pub raw_preview: bool,
+    /// Whether the image has an alpha channel.
+    ///
+    /// The alpha values themselves are not kept; [`load_alpha`] reads them for the alpha preview.
+    pub has_alpha: bool,
+    /// Original file bytes, kept only when `retain_encoded_bytes` is enabled.
+    pub encoded: Option<EncodedImage>,
+}
+
+impl LoadedImageData {
+    /// Returns the approximate memory held by the decoded image in bytes.
+    ///
+    /// Counts the pixels, the animation frames and the retained file bytes.
+    pub fn memory_size(&self) -> usize {
+        let frame_size = self.width as usize * self.height as usize * 3;
+        let encoded = self
+            .encoded
+            .as_ref()
+            .map_or(0, |encoded| encoded.bytes.len());
+        self.data.len() + self.frames.len() * frame_size + encoded
+    }
+}
+
+#[cfg(test)]
+impl LoadedImageData {
+    /// Black image without metadata, for tests.
+    pub fn blank(file_name: &str, width: u32, height: u32) -> Self {
+        Self {
+            data: vec![0; width as usize * height as usize * 3],
+            width,
+            height,
+            rating: None,
+            sd_parameters: None,
+            sd_parse_error: None,
+            file_name: file_name.to_string(),
+            file_size: 0,
+            file_size_formatted: String::new(),
+            created_date: String::new(),
+            modified_date: String::new(),
+            frames: Vec::new(),
+            color_management: ColorManagementOutcome::default(),
+            keywords: Vec::new(),
+            raw_preview: false,
+            has_alpha: false,
+            encoded: None,
+        }
+    }
 }
 
 /// Load image and metadata from a file path.
 /// Uses image crate for decoding all formats.
-/// - PNG: Single file I/O with metadata extracted from the same bytes
+/// - PNG: Single file I/O and a single parse for both pixels and metadata
+/// - WebP: Image data and XMP chunk both from memory
 /// - Other formats: Image data from memory, metadata from separate file I/O
 ///
 /// # Arguments
 ///
 /// * `path` - 画像ファイルパス
-/// * `screen_id` - 対象ディスプレイのスクリーンID（色管理用）
-pub fn load_image_with_metadata(path: &Path, screen_id: Option<u32>) -> Result<LoadedImageData> {
+/// * `screen_id` - 対象ディスプレイのスクリーンID（色管理用）。`force_srgb_output` 設定時は無視してsRGBへ変換
+/// * `rating_index` - XMPレーティングのインデックス。別ファイルI/Oでのレーティング読み込みを省く
+pub fn load_image_with_metadata(
+    path: &Path,
+    screen_id: Option<u32>,
+    rating_index: &Mutex<RatingIndex>,
+) -> Result<LoadedImageData> {
+    let settings = crate::settings::current();
+    let target = if settings.force_srgb_output {
+        ColorTarget::Srgb
+    } else {
+        ColorTarget::Display(screen_id)
+    };
+    load_image(
+        path,
+        target,
+        true,
+        settings.retain_encoded_bytes,
+        Some(rating_index),
+    )
+}
+
+/// Loads only the first frame of an image together with its metadata.
+///
+/// Animated images are not expanded into frames, so `frames` is always empty.
+/// Meant for one-off renders that never animate, such as screenshots.
+pub fn load_first_frame(path: &Path, target: ColorTarget) -> Result<LoadedImageData> {
+    load_image(path, target, false, false, None)
+}
+
+/// 画像とメタデータを読み込む。`decode_frames` が偽ならアニメーションの全フレームは展開しない。
+/// `retain_encoded` が真ならファイルの元のバイト列も保持する（RAWプレビューを除く）。
+fn load_image(
+    path: &Path,
+    target: ColorTarget,
+    decode_frames: bool,
+    retain_encoded: bool,
+    rating_index: Option<&Mutex<RatingIndex>>,
+) -> Result<LoadedImageData> {
     let file_bytes = read_file_bytes(path)?;
+
+    #[cfg(feature = "raw")]
+    if crate::raw_preview::is_raw_file(path) {
+        return load_raw_preview(path, &file_bytes, target, rating_index);
+    }
+
     let reader = create_image_reader(&file_bytes, path)?;
     let format = detect_format(&reader, path)?;
+    // アニメーションは一度だけデコードし、先頭フレームを静止画として使う
+    let animation = if decode_frames && format == ImageFormat::WebP {
+        decode_webp_animation(&file_bytes, path)
+    } else {
+        None
+    };
 
-    let (img, image_icc_profile) = decode_image_and_icc(reader, path)?;
-    let (mut data, width, height) = convert_to_rgb8(img);
-    apply_color_management(path, &mut data, image_icc_profile.as_deref(), screen_id);
+    let (img, image_icc_profile, rating, keywords, sd_parameters) = if format == ImageFormat::Png {
+        let decoded = decode_png_with_metadata(&file_bytes, path)?;
+        let sd_parameters = decoded
+            .sd_parameters
+            .map(|parsed| parsed.or_else(|| extract_stealth_parameters(&decoded.img)));
+        (
+            decoded.img,
+            decoded.image_icc_profile,
+            decoded.rating,
+            decoded.keywords,
+            sd_parameters,
+        )
+    } else {
+        let (img, image_icc_profile) = match &animation {
+            Some(animation) => (
+                image::DynamicImage::ImageRgba8(animation.first.clone()),
+                animation.icc_profile.clone(),
+            ),
+            None => decode_image_and_icc(reader, path)?,
+        };
+        let (rating, keywords) = if format == ImageFormat::WebP {
+            match metadata::extract_xmp_from_webp(&file_bytes) {
+                Some(xmp_rdf) => (
+                    metadata::parse_xmp_rating_from_rdf(&xmp_rdf, metadata::current_rating_scale()),
+                    metadata::parse_xmp_keywords_from_rdf(&xmp_rdf),
+                ),
+                None => (None, Vec::new()),
+            }
+        } else if format_supports_xmp(format) {
+            (
+                read_rating(path, rating_index),
+                metadata::read_xmp_keywords(path).unwrap_or_default(),
+            )
+        } else {
+            (None, Vec::new())
+        };
+        (img, image_icc_profile, rating, keywords, Ok(None))
+    };
 
-    let (rating, sd_parameters) = extract_metadata(path, &file_bytes, format)?;
+    // パースに失敗したパラメータは原文と理由を保持して表示する
+    let (sd_parameters, sd_parse_error) = match sd_parameters {
+        Ok(parsed) => (parsed, None),
+        Err(failure) => {
+            warn!(
+                "Failed to parse SD parameters in {:?}: {}",
+                path, failure.message
+            );
+            (None, Some(failure))
+        }
+    };
+
+    let has_alpha = img.color().has_alpha();
+    let (mut data, width, height) = convert_to_rgb8(img);
+    validate_rgb8_dimensions(&data, width, height)?;
+    let color_management =
+        apply_color_management(path, &mut data, image_icc_profile.as_deref(), target);
+    let frames = match animation {
+        Some(animation) => animation.into_frames(
+            &data,
+            width,
+            height,
+            path,
+            image_icc_profile.as_deref(),
+            target,
+        ),
+        None => Vec::new(),
+    };
     let (file_name, file_size_formatted, created_date, modified_date) =
         build_file_info(path, &file_bytes);
+    let file_size = file_bytes.len() as u64;
+    let encoded = retain_encoded.then(|| EncodedImage::new(file_bytes, format));
 
     Ok(LoadedImageData {
         data,
@@ -49,18 +257,82 @@ pub fn load_image_with_metadata(path: &Path, screen_id: Option<u32>) -> Result<L
         height,
         rating,
         sd_parameters,
+        sd_parse_error,
         file_name,
+        file_size,
         file_size_formatted,
         created_date,
         modified_date,
+        frames,
+        color_management,
+        keywords,
+        raw_preview: false,
+        has_alpha,
+        encoded,
     })
 }
 
+/// RAWファイルに埋め込まれたJPEGプレビューを読み込む（RAWデータの現像はしない）。
+#[cfg(feature = "raw")]
+fn load_raw_preview(
+    path: &Path,
+    file_bytes: &[u8],
+    target: ColorTarget,
+    rating_index: Option<&Mutex<RatingIndex>>,
+) -> Result<LoadedImageData> {
+    let img = crate::raw_preview::decode_embedded_preview(file_bytes).map_err(|e| {
+        error!("Failed to read RAW preview of {:?}: {}", path, e);
+        e
+    })?;
+
+    let (mut data, width, height) = convert_to_rgb8(img);
+    validate_rgb8_dimensions(&data, width, height)?;
+    let color_management = apply_color_management(path, &mut data, None, target);
+    let (file_name, file_size_formatted, created_date, modified_date) =
+        build_file_info(path, file_bytes);
+
+    Ok(LoadedImageData {
+        data,
+        width,
+        height,
+        rating: read_rating(path, rating_index),
+        sd_parameters: None,
+        sd_parse_error: None,
+        file_name,
+        file_size: file_bytes.len() as u64,
+        file_size_formatted,
+        created_date,
+        modified_date,
+        frames: Vec::new(),
+        color_management,
+        keywords: metadata::read_xmp_keywords(path).unwrap_or_default(),
+        raw_preview: true,
+        has_alpha: false,
+        encoded: None,
+    })
+}
+
+/// 別ファイルI/OでXMPレーティングを読み込む。
+///
+/// インデックスがあれば先に参照し、更新日時が変わっていなければXMPを開かない（未評価の結果も含む）。
+fn read_rating(path: &Path, rating_index: Option<&Mutex<RatingIndex>>) -> Option<u8> {
+    match rating_index {
+        Some(index) => rating_index::read_rating_indexed(index, path),
+        None => metadata::read_xmp_rating_scaled(path, metadata::current_rating_scale())
+            .ok()
+            .flatten(),
+    }
+}
+
 /// 画像ファイルをメモリへ読み込む。
 fn read_file_bytes(path: &Path) -> Result<Vec<u8>> {
     std::fs::read(path).map_err(|e| {
         error!("Failed to read file {:?}: {}", path, e);
-        e.into()
+        // スキャン後に削除されたファイルは呼び出し側で読み飛ばせるよう区別する
+        match e.kind() {
+            std::io::ErrorKind::NotFound => AppError::FileNotFound(e.to_string()),
+            _ => e.into(),
+        }
     })
 }
 
@@ -85,7 +357,17 @@ fn detect_format(reader: &image::ImageReader<Cursor<&[u8]>>, path: &Path) -> Res
     })
 }
 
-/// 画像をデコードし、取得可能ならICCプロファイルも返す。
+/// XMPメタデータを持ちうるフォーマットか判定する。
+///
+/// BMP・GIF・ICOはXMPを持たないため、無駄なファイルオープンを避ける。
+fn format_supports_xmp(format: ImageFormat) -> bool {
+    !matches!(
+        format,
+        ImageFormat::Bmp | ImageFormat::Gif | ImageFormat::Ico
+    )
+}
+
+/// 画像をデコードしてExifの向きを適用し、取得可能ならICCプロファイルも返す。
 fn decode_image_and_icc(
     reader: image::ImageReader<Cursor<&[u8]>>,
     path: &Path,
@@ -102,14 +384,76 @@ fn decode_image_and_icc(
         e
     })?;
 
-    let img = image::DynamicImage::from_decoder(decoder).map_err(|e| {
+    // 読めないExifは無視し、回転なしとして扱う
+    let orientation = decoder.orientation().unwrap_or_else(|e| {
+        warn!("Failed to read orientation for {:?}: {}", path, e);
+        image::metadata::Orientation::NoTransforms
+    });
+
+    let mut img = image::DynamicImage::from_decoder(decoder).map_err(|e| {
         error!("Failed to decode image {:?}: {}", path, e);
         e
     })?;
+    img.apply_orientation(orientation);
 
     Ok((img, image_icc_profile))
 }
 
+/// アルファチャンネルに埋め込まれたstealth pnginfoからSDパラメータを抽出する。
+fn extract_stealth_parameters(img: &image::DynamicImage) -> Option<SdParameters> {
+    if !img.color().has_alpha() {
+        return None;
+    }
+
+    // RGBA8ならコピーせずにそのまま読む
+    let payload = match img.as_rgba8() {
+        Some(rgba) => {
+            metadata::extract_stealth_metadata(rgba.as_raw(), rgba.width(), rgba.height())
+        }
+        None => {
+            let rgba = img.to_rgba8();
+            metadata::extract_stealth_metadata(rgba.as_raw(), rgba.width(), rgba.height())
+        }
+    }?;
+
+    metadata::parse_stealth_payload(&payload)
+}
+
+/// アルファチャンネルだけを読み直す（アルファプレビュー用）。
+///
+/// 表示用の読み込みと同じデコード経路を使うため、向きと寸法は表示中の画素と一致する。
+/// アルファを持たない画像（RAWプレビューを含む）ならNone。
+pub fn load_alpha(path: &Path) -> Result<Option<Vec<u8>>> {
+    let file_bytes = read_file_bytes(path)?;
+
+    #[cfg(feature = "raw")]
+    if crate::raw_preview::is_raw_file(path) {
+        return Ok(None);
+    }
+
+    let reader = create_image_reader(&file_bytes, path)?;
+    let img = if detect_format(&reader, path)? == ImageFormat::Png {
+        decode_png_with_metadata(&file_bytes, path)?.img
+    } else {
+        decode_image_and_icc(reader, path)?.0
+    };
+    Ok(extract_alpha(&img))
+}
+
+/// アルファチャンネルを1ピクセル1バイトで取り出す。アルファを持たない画像ならNone。
+fn extract_alpha(img: &image::DynamicImage) -> Option<Vec<u8>> {
+    if !img.color().has_alpha() {
+        return None;
+    }
+
+    // RGBA8ならコピーせずにそのまま読む
+    let alpha = match img.as_rgba8() {
+        Some(rgba) => rgba.pixels().map(|pixel| pixel[3]).collect(),
+        None => img.to_rgba8().pixels().map(|pixel| pixel[3]).collect(),
+    };
+    Some(alpha)
+}
+
 /// DynamicImageをRGB8生配列へ変換する。
 fn convert_to_rgb8(img: image::DynamicImage) -> (Vec<u8>, u32, u32) {
     let rgb8 = img.to_rgb8();
@@ -118,86 +462,306 @@ fn convert_to_rgb8(img: image::DynamicImage) -> (Vec<u8>, u32, u32) {
     (rgb8.into_raw(), width, height)
 }
 
-/// 色管理サービスを適用する。
+/// デコード済みのアニメーションWebP（色管理前）。
+struct WebpAnimation {
+    icc_profile: Option<Vec<u8>>,
+    /// 先頭フレーム。静止画と同じ経路で変換・色管理する
+    first: image::RgbaImage,
+    first_delay_ms: u32,
+    /// 2フレーム目以降
+    rest: Vec<image::Frame>,
+}
+
+impl WebpAnimation {
+    /// 色管理済みの先頭フレーム `first_rgb` と残りのフレームを再生用のフレームにする。
+    fn into_frames(
+        self,
+        first_rgb: &[u8],
+        width: u32,
+        height: u32,
+        path: &Path,
+        image_icc_profile: Option<&[u8]>,
+        target: ColorTarget,
+    ) -> Vec<AnimationFrame> {
+        let first = AnimationFrame {
+            pixels: SharedPixelBuffer::clone_from_slice(first_rgb, width, height),
+            delay_ms: self.first_delay_ms,
+        };
+        let rest = self.rest.into_iter().map(|frame| {
+            let delay_ms = frame_delay_ms(&frame);
+            let (mut data, frame_width, frame_height) =
+                convert_to_rgb8(image::DynamicImage::ImageRgba8(frame.into_buffer()));
+            apply_color_management(path, &mut data, image_icc_profile, target);
+            AnimationFrame {
+                pixels: SharedPixelBuffer::clone_from_slice(&data, frame_width, frame_height),
+                delay_ms,
+            }
+        });
+        std::iter::once(first).chain(rest).collect()
+    }
+}
+
+fn frame_delay_ms(frame: &image::Frame) -> u32 {
+    let (numer, denom) = frame.delay().numer_denom_ms();
+    numer.checked_div(denom).unwrap_or(0)
+}
+
+/// `width` x `height` のアニメーションでデコードするフレーム数の上限（先頭フレームを含む）。
+fn animation_frame_limit(width: u32, height: u32) -> usize {
+    let frame_size = (width as usize)
+        .saturating_mul(height as usize)
+        .saturating_mul(3);
+    config::MAX_ANIMATION_FRAMES
+        .min(config::MAX_ANIMATION_BYTES / frame_size.max(1))
+        .max(1)
+}
+
+/// アニメーションWebPのフレームをデコードする。
+///
+/// 静止画WebPや先頭フレームのデコードに失敗した場合は `None` を返し、通常の単一フレーム経路で表示する。
+/// フレームは `MAX_ANIMATION_FRAMES` 枚かつ `MAX_ANIMATION_BYTES` までに制限し、超えた分はデコードしない。
+fn decode_webp_animation(file_bytes: &[u8], path: &Path) -> Option<WebpAnimation> {
+    use image::codecs::webp::WebPDecoder;
+    use image::{AnimationDecoder, ImageDecoder};
+
+    let mut decoder = match WebPDecoder::new(Cursor::new(file_bytes)) {
+        Ok(decoder) => decoder,
+        Err(e) => {
+            warn!("Failed to create WebP decoder for {:?}: {}", path, e);
+            return None;
+        }
+    };
+
+    if !decoder.has_animation() {
+        return None;
+    }
+
+    let icc_profile = decoder.icc_profile().ok().flatten();
+    let (width, height) = decoder.dimensions();
+    let max_frames = animation_frame_limit(width, height);
+
+    let mut frames = decoder.into_frames();
+    let first = match frames.next()? {
+        Ok(frame) => frame,
+        Err(e) => {
+            warn!("Failed to decode WebP animation for {:?}: {}", path, e);
+            return None;
+        }
+    };
+
+    let mut rest = Vec::new();
+    for frame in frames.by_ref().take(max_frames - 1) {
+        match frame {
+            Ok(frame) => rest.push(frame),
+            Err(e) => {
+                warn!(
+                    "Failed to decode WebP animation for {:?}, playing {} frames: {}",
+                    path,
+                    rest.len() + 1,
+                    e
+                );
+                break;
+            }
+        }
+    }
+    if rest.len() + 1 == max_frames && frames.next().is_some() {
+        warn!(
+            "WebP animation {:?} exceeds the frame limit, playing the first {} frames",
+            path, max_frames
+        );
+    }
+
+    Some(WebpAnimation {
+        icc_profile,
+        first_delay_ms: frame_delay_ms(&first),
+        first: first.into_buffer(),
+        rest,
+    })
+}
+
+/// 色管理サービスを適用し、その結果を返す。
+///
+/// 画像ICCプロファイルが不正な場合はsRGBとして再変換する。
 fn apply_color_management(
     path: &Path,
     rgb_data: &mut [u8],
     image_icc_profile: Option<&[u8]>,
-    screen_id: Option<u32>,
-) {
-    if let Err(err) =
-        default_color_management_service().apply_to_rgb8(rgb_data, image_icc_profile, screen_id)
-    {
-        error!(
-            "Color management failed for {:?}, fallback to uncorrected pixels: {}",
+    target: ColorTarget,
+) -> ColorManagementOutcome {
+    let service = default_color_management_service();
+    let err = match service.apply_to_rgb8(rgb_data, image_icc_profile, target) {
+        Ok(()) => return ColorManagementOutcome::Success,
+        Err(err) => err,
+    };
+
+    let outcome = ColorManagementOutcome::from(&err);
+    if outcome == ColorManagementOutcome::SourceProfileInvalid {
+        warn!(
+            "Invalid embedded ICC profile in {:?}, treating as sRGB: {}",
             path, err
         );
+        if let Err(err) = service.apply_to_rgb8(rgb_data, None, target) {
+            error!(
+                "Color management failed for {:?}, fallback to uncorrected pixels: {}",
+                path, err
+            );
+        }
+        return outcome;
     }
+
+    error!(
+        "Color management failed for {:?}, fallback to uncorrected pixels: {}",
+        path, err
+    );
+    outcome
 }
 
-/// 画像メタデータを抽出する。
-fn extract_metadata(
-    path: &Path,
-    file_bytes: &[u8],
-    format: ImageFormat,
-) -> Result<(Option<u8>, Option<SdParameters>)> {
-    match format {
-        ImageFormat::Png => {
-            let decoder = png::Decoder::new(Cursor::new(file_bytes));
-            let reader = decoder.read_info().map_err(|e| {
-                error!("Failed to read PNG info for {:?}: {}", path, e);
-                e
-            })?;
+/// PNGの一回の解析で得られる画素とメタデータ。
+struct DecodedPng {
+    img: image::DynamicImage,
+    image_icc_profile: Option<Vec<u8>>,
+    rating: Option<u8>,
+    keywords: Vec<String>,
+    sd_parameters: std::result::Result<Option<SdParameters>, SdParseFailure>,
+}
 
-            let info = reader.info().clone();
+/// PNGを一度だけ解析し、画素・ICCプロファイル・メタデータをまとめて取得する。
+///
+/// 同じ `png::Reader` の `Info` からレーティングとSDパラメータを読むため、
+/// PNGヘッダやチャンクを二重に解析しない。
+fn decode_png_with_metadata(file_bytes: &[u8], path: &Path) -> Result<DecodedPng> {
+    let mut decoder = png::Decoder::new(Cursor::new(file_bytes));
+    // パレット・低ビット深度・tRNSを8/16bitのGray/GrayA/RGB/RGBAへ展開
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder.read_info().map_err(|e| {
+        error!("Failed to read PNG info for {:?}: {}", path, e);
+        e
+    })?;
 
-            let rating = metadata::extract_xmp_rdf_from_info(&info)
-                .ok()
-                .flatten()
-                .and_then(|xmp_rdf| metadata::parse_xmp_rating_from_rdf(&xmp_rdf));
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut buf).map_err(|e| {
+        error!("Failed to decode PNG image {:?}: {}", path, e);
+        e
+    })?;
+    buf.truncate(frame.buffer_size());
 
-            let sd_parameters = metadata::extract_sd_parameters_from_info(&info)
-                .ok()
-                .flatten()
-                .and_then(|param_str| SdParameters::parse(&param_str).ok());
+    // IDAT後のテキストチャンクも読むため末尾まで進める
+    if let Err(e) = reader.finish() {
+        warn!("Failed to read trailing PNG chunks for {:?}: {}", path, e);
+    }
 
-            Ok((rating, sd_parameters))
-        }
-        _ => {
-            let rating = metadata::read_xmp_rating(path).ok().flatten();
-            Ok((rating, None))
+    let (color_type, bit_depth) = reader.output_color_type();
+    let img = png_buffer_to_dynamic_image(buf, frame.width, frame.height, color_type, bit_depth)
+        .ok_or_else(|| {
+            error!(
+                "Unsupported PNG layout for {:?}: {:?} {:?}",
+                path, color_type, bit_depth
+            );
+            AppError::ImageLoad(format!(
+                "Unsupported PNG layout: {:?} {:?}",
+                color_type, bit_depth
+            ))
+        })?;
+
+    let info = reader.info();
+    let image_icc_profile = info.icc_profile.as_ref().map(|icc| icc.to_vec());
+
+    let xmp_rdf = metadata::extract_xmp_rdf_from_info(info).ok().flatten();
+    let rating = xmp_rdf.as_deref().and_then(|xmp_rdf| {
+        metadata::parse_xmp_rating_from_rdf(xmp_rdf, metadata::current_rating_scale())
+    });
+    let keywords = xmp_rdf
+        .as_deref()
+        .map(metadata::parse_xmp_keywords_from_rdf)
+        .unwrap_or_default();
+
+    let sd_parameters = metadata::parse_sd_parameters_from_info(info);
+
+    Ok(DecodedPng {
+        img,
+        image_icc_profile,
+        rating,
+        keywords,
+        sd_parameters,
+    })
+}
+
+/// 展開済みPNG画素バッファをDynamicImageへ変換する。
+fn png_buffer_to_dynamic_image(
+    buf: Vec<u8>,
+    width: u32,
+    height: u32,
+    color_type: png::ColorType,
+    bit_depth: png::BitDepth,
+) -> Option<image::DynamicImage> {
+    use image::{DynamicImage, ImageBuffer};
+    use png::{BitDepth, ColorType};
+
+    match bit_depth {
+        BitDepth::Eight => match color_type {
+            ColorType::Grayscale => {
+                ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageLuma8)
+            }
+            ColorType::GrayscaleAlpha => {
+                ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageLumaA8)
+            }
+            ColorType::Rgb => {
+                ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgb8)
+            }
+            ColorType::Rgba => {
+                ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgba8)
+            }
+            ColorType::Indexed => None,
+        },
+        BitDepth::Sixteen => {
+            // PNGの16bitサンプルはビッグエンディアン
+            let samples: Vec<u16> = buf
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            match color_type {
+                ColorType::Grayscale => {
+                    ImageBuffer::from_raw(width, height, samples).map(DynamicImage::ImageLuma16)
+                }
+                ColorType::GrayscaleAlpha => {
+                    ImageBuffer::from_raw(width, height, samples).map(DynamicImage::ImageLumaA16)
+                }
+                ColorType::Rgb => {
+                    ImageBuffer::from_raw(width, height, samples).map(DynamicImage::ImageRgb16)
+                }
+                ColorType::Rgba => {
+                    ImageBuffer::from_raw(width, height, samples).map(DynamicImage::ImageRgba16)
+                }
+                ColorType::Indexed => None,
+            }
         }
+        _ => None,
     }
 }
 
 /// 表示用のファイル情報を組み立てる。
 fn build_file_info(path: &Path, file_bytes: &[u8]) -> (String, String, String, String) {
+    // 非UTF-8のファイル名も表示できるよう置換文字で補う（操作には元のパスを使う）
     let file_name = path
         .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("Unknown")
-        .to_string();
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "Unknown".to_string());
 
     let file_size_bytes = file_bytes.len() as u64;
-    let file_size_formatted = format_file_size(file_size_bytes);
+    let file_size_formatted = format_file_size_human(file_size_bytes);
 
+    let date_format = crate::settings::current().date_format;
     let (created_date, modified_date) = if let Ok(metadata) = std::fs::metadata(path) {
         let created = metadata
             .created()
             .ok()
-            .map(|time| {
-                let datetime: chrono::DateTime<chrono::Local> = time.into();
-                datetime.format("%Y-%m-%d %H:%M:%S").to_string()
-            })
+            .map(|time| format_date(time, &date_format))
             .unwrap_or_else(|| "N/A".to_string());
 
         let modified = metadata
             .modified()
             .ok()
-            .map(|time| {
-                let datetime: chrono::DateTime<chrono::Local> = time.into();
-                datetime.format("%Y-%m-%d %H:%M:%S").to_string()
-            })
+            .map(|time| format_date(time, &date_format))
             .unwrap_or_else(|| "N/A".to_string());
 
         (created, modified)
@@ -208,6 +772,26 @@ fn build_file_info(path: &Path, file_bytes: &[u8]) -> (String, String, String, S
     (file_name, file_size_formatted, created_date, modified_date)
 }
 
+/// 日時をローカル時刻で書式化する。
+///
+/// chronoが解釈できない書式は既定の書式に置き換える（不正な書式のままだと書式化時にパニックする）。
+fn format_date(time: std::time::SystemTime, format: &str) -> String {
+    let datetime: chrono::DateTime<chrono::Local> = time.into();
+    datetime.format(valid_date_format(format)).to_string()
+}
+
+/// chronoが解釈できる書式ならそのまま、できなければ既定の書式を返す。
+fn valid_date_format(format: &str) -> &str {
+    use chrono::format::{Item, StrftimeItems};
+
+    if !format.is_empty() && !StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        format
+    } else {
+        warn!("Invalid date format {:?}, using the default", format);
+        config::DEFAULT_DATE_FORMAT
+    }
+}
+
 /// Format file size with thousand separators
 fn format_file_size(size: u64) -> String {
     let size_str = size.to_string();
@@ -224,8 +808,464 @@ fn format_file_size(size: u64) -> String {
     format!("{} bytes", result)
 }
 
+/// Format file size in human-readable units followed by the exact byte count.
+///
+/// Uses binary units with one decimal, e.g. "11.8 MB (12,345,678 bytes)".
+/// Sizes below 1 KB are shown as plain bytes, e.g. "0 bytes".
+fn format_file_size_human(size: u64) -> String {
+    if size < 1024 {
+        return format_file_size(size);
+    }
+
+    format!(
+        "{} ({})",
+        format_file_size_short(size),
+        format_file_size(size)
+    )
+}
+
+/// Format file size in human-readable units only, e.g. "11.8 MB".
+///
+/// Sizes below 1 KB are shown as plain bytes, e.g. "512 bytes".
+pub fn format_file_size_short(size: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    if size < 1024 {
+        return format_file_size(size);
+    }
+
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    // 丸め後に1024.0となる値（例: 1,048,575 bytes）も次の単位へ繰り上げる
+    while (value * 10.0).round() / 10.0 >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// RGB8データの色を反転したコピーを返す。
+pub fn invert_rgb8(data: &[u8]) -> Vec<u8> {
+    data.iter().map(|value| 255 - value).collect()
+}
+
+/// RGB8データをグレースケール化したコピーを返す（R=G=B）。
+///
+/// 輝度はRec.709の係数を整数近似して求める。
+pub fn to_grayscale_rgb8(data: &[u8]) -> Vec<u8> {
+    data.chunks_exact(3)
+        .flat_map(|pixel| {
+            let luma = (54 * pixel[0] as u32 + 183 * pixel[1] as u32 + 19 * pixel[2] as u32) >> 8;
+            [luma as u8; 3]
+        })
+        .collect()
+}
+
+/// アルファ値をグレースケールで表したRGB8データを返す（R=G=B=アルファ）。
+///
+/// アルファを持たない画像は完全な不透明として白一色を返す。
+pub fn alpha_to_gray(alpha: Option<&[u8]>, width: u32, height: u32) -> Vec<u8> {
+    let pixel_count = width as usize * height as usize;
+    match alpha {
+        Some(alpha) => alpha
+            .iter()
+            .take(pixel_count)
+            .flat_map(|&value| [value; 3])
+            .collect(),
+        None => vec![255; pixel_count * 3],
+    }
+}
+
+/// RGB8データの寸法が正しいか検証する。
+///
+/// 幅・高さが0の画像や、データ長が`width * height * 3`と一致しない画像は
+/// バッファ生成時にパニックするため、事前にエラーとして弾く。
+pub fn validate_rgb8_dimensions(data: &[u8], width: u32, height: u32) -> Result<()> {
+    let expected = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(3));
+
+    if width == 0 || height == 0 || expected != Some(data.len()) {
+        warn!(
+            "Degenerate image: {}x{} with {} bytes of RGB8 data",
+            width,
+            height,
+            data.len()
+        );
+        return Err(AppError::ImageLoad("degenerate dimensions".to_string()));
+    }
+    Ok(())
+}
+
 /// Convert RGB8 data to Slint Image (UIスレッドで軽い処理のみ)
-pub fn create_slint_image(data: &[u8], width: u32, height: u32) -> Image {
+pub fn create_slint_image(data: &[u8], width: u32, height: u32) -> Result<Image> {
+    validate_rgb8_dimensions(data, width, height)?;
     let buffer = SharedPixelBuffer::<Rgb8Pixel>::clone_from_slice(data, width, height);
-    Image::from_rgb8(buffer)
+    Ok(Image::from_rgb8(buffer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Appends a RIFF chunk, padded to an even length.
+    fn push_chunk(out: &mut Vec<u8>, fourcc: &[u8; 4], payload: &[u8]) {
+        out.extend_from_slice(fourcc);
+        out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        out.extend_from_slice(payload);
+        if payload.len() % 2 == 1 {
+            out.push(0);
+        }
+    }
+
+    fn u24(value: u32) -> [u8; 3] {
+        let [a, b, c, _] = value.to_le_bytes();
+        [a, b, c]
+    }
+
+    /// Builds an animated WebP of `delays.len()` lossless frames of `width` x `height`.
+    fn animated_webp(width: u32, height: u32, delays: &[u32]) -> Vec<u8> {
+        let mut chunks = Vec::new();
+        let mut vp8x = vec![0x02, 0, 0, 0];
+        vp8x.extend_from_slice(&u24(width - 1));
+        vp8x.extend_from_slice(&u24(height - 1));
+        push_chunk(&mut chunks, b"VP8X", &vp8x);
+        push_chunk(&mut chunks, b"ANIM", &[0, 0, 0, 0, 0, 0]);
+
+        for (index, delay) in delays.iter().enumerate() {
+            let rgba = vec![index as u8 * 40; width as usize * height as usize * 4];
+            let mut still = Vec::new();
+            image::codecs::webp::WebPEncoder::new_lossless(&mut still)
+                .encode(&rgba, width, height, image::ExtendedColorType::Rgba8)
+                .unwrap();
+
+            let mut anmf = Vec::new();
+            anmf.extend_from_slice(&u24(0));
+            anmf.extend_from_slice(&u24(0));
+            anmf.extend_from_slice(&u24(width - 1));
+            anmf.extend_from_slice(&u24(height - 1));
+            anmf.extend_from_slice(&u24(*delay));
+            anmf.push(0);
+            // The VP8L chunk of the still image, after the RIFF header
+            anmf.extend_from_slice(&still[12..]);
+            push_chunk(&mut chunks, b"ANMF", &anmf);
+        }
+
+        let mut webp = b"RIFF".to_vec();
+        webp.extend_from_slice(&(chunks.len() as u32 + 4).to_le_bytes());
+        webp.extend_from_slice(b"WEBP");
+        webp.extend_from_slice(&chunks);
+        webp
+    }
+
+    #[test]
+    fn decodes_every_frame_of_animated_webp() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("anim.webp");
+        std::fs::write(&path, animated_webp(4, 2, &[50, 80, 120])).unwrap();
+
+        let loaded = load_image(&path, ColorTarget::Srgb, true, false, None).unwrap();
+        assert_eq!((loaded.width, loaded.height), (4, 2));
+        assert_eq!(loaded.frames.len(), 3);
+        let delays: Vec<u32> = loaded.frames.iter().map(|f| f.delay_ms).collect();
+        assert_eq!(delays, [50, 80, 120]);
+        assert_eq!(loaded.memory_size(), 4 * 2 * 3 * 4);
+
+        let first = load_first_frame(&path, ColorTarget::Srgb).unwrap();
+        assert!(first.frames.is_empty());
+        assert_eq!(first.data, loaded.data);
+    }
+
+    #[test]
+    fn still_webp_has_no_frames() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("still.webp");
+        image::save_buffer(&path, &[0u8; 16], 2, 2, image::ExtendedColorType::Rgba8).unwrap();
+
+        let loaded = load_image(&path, ColorTarget::Srgb, true, false, None).unwrap();
+        assert!(loaded.frames.is_empty());
+    }
+
+    #[test]
+    fn animation_frame_limit_follows_frame_size() {
+        assert_eq!(animation_frame_limit(1, 1), config::MAX_ANIMATION_FRAMES);
+        assert_eq!(
+            animation_frame_limit(4096, 4096),
+            config::MAX_ANIMATION_BYTES / (4096 * 4096 * 3)
+        );
+        // At least the first frame, however large
+        assert_eq!(animation_frame_limit(u32::MAX, u32::MAX), 1);
+        assert_eq!(animation_frame_limit(0, 0), config::MAX_ANIMATION_FRAMES);
+    }
+
+    /// Encodes a 2x1 RGB PNG with the given tEXt and iTXt chunks.
+    fn png_with_text(text: &[(&str, &str)], itxt: &[(&str, &str)]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, 2, 1);
+        encoder.set_color(png::ColorType::Rgb);
+        for (keyword, value) in text {
+            encoder
+                .add_text_chunk(keyword.to_string(), value.to_string())
+                .unwrap();
+        }
+        for (keyword, value) in itxt {
+            encoder
+                .add_itxt_chunk(keyword.to_string(), value.to_string())
+                .unwrap();
+        }
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[255, 0, 0, 0, 0, 255]).unwrap();
+        writer.finish().unwrap();
+        bytes
+    }
+
+    #[test]
+    fn png_pixels_and_metadata_come_from_one_decode() {
+        let xmp = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"><rdf:Description rdf:about="" xmlns:xmp="http://ns.adobe.com/xap/1.0/" xmp:Rating="4"/></rdf:RDF></x:xmpmeta>"#;
+        let bytes = png_with_text(
+            &[(
+                "parameters",
+                "a cat\nNegative prompt: blurry\nSteps: 20, Sampler: Euler a, Seed: 7",
+            )],
+            &[("XML:com.adobe.xmp", xmp)],
+        );
+
+        let decoded = decode_png_with_metadata(&bytes, Path::new("a.png")).unwrap();
+        assert_eq!(decoded.img.to_rgb8().into_raw(), [255, 0, 0, 0, 0, 255]);
+        assert_eq!(decoded.rating, Some(4));
+        let params = decoded.sd_parameters.unwrap().unwrap();
+        assert_eq!(params.positive_sd_tags[0].name, "a cat");
+        assert_eq!(params.negative_sd_tags[0].name, "blurry");
+        assert_eq!(params.steps.as_deref(), Some("20"));
+        assert_eq!(params.seed.as_deref(), Some("7"));
+    }
+
+    #[test]
+    fn bmp_and_ico_skip_metadata() {
+        assert!(!format_supports_xmp(ImageFormat::Bmp));
+        assert!(!format_supports_xmp(ImageFormat::Ico));
+        assert!(format_supports_xmp(ImageFormat::Jpeg));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.bmp");
+        image::save_buffer(&path, &[0u8; 12], 2, 2, image::ExtendedColorType::Rgb8).unwrap();
+
+        let loaded = load_image(&path, ColorTarget::Srgb, true, false, None).unwrap();
+        assert_eq!((loaded.width, loaded.height), (2, 2));
+        assert_eq!(loaded.rating, None);
+        assert!(loaded.keywords.is_empty());
+        assert!(loaded.sd_parameters.is_none());
+        assert!(loaded.sd_parse_error.is_none());
+    }
+
+    #[test]
+    fn formats_file_size_across_ranges() {
+        assert_eq!(format_file_size_human(0), "0 bytes");
+        assert_eq!(format_file_size_human(1023), "1,023 bytes");
+        assert_eq!(format_file_size_human(1024), "1.0 KB (1,024 bytes)");
+        assert_eq!(format_file_size_human(1536), "1.5 KB (1,536 bytes)");
+        assert_eq!(
+            format_file_size_human(12_345_678),
+            "11.8 MB (12,345,678 bytes)"
+        );
+        assert_eq!(
+            format_file_size_human(3 * 1024 * 1024 * 1024),
+            "3.0 GB (3,221,225,472 bytes)"
+        );
+    }
+
+    #[test]
+    fn rounds_up_to_next_unit_at_boundaries() {
+        assert_eq!(format_file_size_short(1_048_575), "1.0 MB");
+        assert_eq!(format_file_size_short(1024 * 1024 * 1024 - 1), "1.0 GB");
+        assert_eq!(format_file_size_short(u64::MAX), "16777216.0 TB");
+    }
+
+    #[test]
+    fn invert_is_its_own_inverse() {
+        let data: Vec<u8> = (0..=255).collect();
+        let inverted = invert_rgb8(&data);
+        assert_eq!(inverted[0], 255);
+        assert_eq!(inverted[255], 0);
+        assert_eq!(invert_rgb8(&inverted), data);
+    }
+
+    #[test]
+    fn grayscale_has_equal_channels() {
+        let data = [255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 12, 200, 90];
+        let gray = to_grayscale_rgb8(&data);
+        assert_eq!(gray.len(), data.len());
+        for pixel in gray.chunks_exact(3) {
+            assert!(pixel[0] == pixel[1] && pixel[1] == pixel[2], "{:?}", pixel);
+        }
+        assert_eq!(&gray[9..12], [255, 255, 255]);
+    }
+
+    #[test]
+    fn truncated_png_is_reported_as_incomplete() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("partial.png");
+        let pixels: Vec<u8> = (0..64 * 64 * 3).map(|i| (i * 7 % 251) as u8).collect();
+        image::save_buffer(&path, &pixels, 64, 64, image::ExtendedColorType::Rgb8).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() / 2]).unwrap();
+
+        let Err(error) = load_first_frame(&path, ColorTarget::Srgb) else {
+            panic!("truncated PNG decoded");
+        };
+        assert!(matches!(error, AppError::IncompleteImage(_)), "{:?}", error);
+        assert!(error.is_transient());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn loads_non_utf8_path_with_lossy_name() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(std::ffi::OsStr::from_bytes(b"caf\xe9.png"));
+        image::save_buffer(&path, &[0u8; 12], 2, 2, image::ExtendedColorType::Rgb8).unwrap();
+
+        let Ok(loaded) = load_first_frame(&path, ColorTarget::Srgb) else {
+            panic!("failed to load non-UTF-8 path");
+        };
+        assert_eq!(loaded.file_name, "caf\u{fffd}.png");
+        assert_eq!((loaded.width, loaded.height), (2, 2));
+    }
+
+    #[test]
+    fn first_frame_load_keeps_only_the_first_frame() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("anim.webp");
+        std::fs::write(&path, animated_webp(4, 2, &[50, 80, 120, 160])).unwrap();
+
+        let first = load_first_frame(&path, ColorTarget::Srgb).unwrap();
+        assert_eq!((first.width, first.height), (4, 2));
+        assert!(first.frames.is_empty());
+        assert!(first.encoded.is_none());
+        assert_eq!(first.memory_size(), 4 * 2 * 3);
+        // The first frame is filled with 0
+        assert!(first.data.iter().all(|&value| value == 0));
+    }
+
+    #[test]
+    fn missing_file_is_reported_as_not_found() {
+        let dir = tempfile::tempdir().unwrap();
+        let Err(error) = load_first_frame(&dir.path().join("gone.png"), ColorTarget::Srgb) else {
+            panic!("missing file loaded");
+        };
+        assert!(matches!(error, AppError::FileNotFound(_)), "{:?}", error);
+    }
+
+    #[test]
+    fn encoded_image_mime_follows_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let img = image::DynamicImage::new_rgb8(4, 4);
+        for (format, mime) in [
+            (ImageFormat::Png, "image/png"),
+            (ImageFormat::Jpeg, "image/jpeg"),
+            (ImageFormat::Gif, "image/gif"),
+            (ImageFormat::WebP, "image/webp"),
+            (ImageFormat::Bmp, "image/bmp"),
+            (ImageFormat::Ico, "image/x-icon"),
+        ] {
+            let mut bytes = Vec::new();
+            img.write_to(&mut Cursor::new(&mut bytes), format).unwrap();
+            // The extension is deliberately wrong, the bytes decide
+            let path = dir.path().join("image.png");
+            std::fs::write(&path, &bytes).unwrap();
+
+            let encoded = EncodedImage::read(&path).unwrap();
+            assert_eq!(encoded.mime, mime, "{:?}", format);
+            assert_eq!(&encoded.bytes[..], &bytes[..]);
+        }
+    }
+
+    #[test]
+    fn formats_dates_with_custom_formats() {
+        use chrono::TimeZone;
+
+        let time: std::time::SystemTime = chrono::Local
+            .with_ymd_and_hms(2024, 3, 5, 14, 7, 9)
+            .unwrap()
+            .into();
+        assert_eq!(format_date(time, "%d/%m/%Y"), "05/03/2024");
+        assert_eq!(
+            format_date(time, "%Y年%m月%d日 %H:%M"),
+            "2024年03月05日 14:07"
+        );
+        assert_eq!(
+            format_date(time, config::DEFAULT_DATE_FORMAT),
+            "2024-03-05 14:07:09"
+        );
+
+        // Invalid or empty formats fall back to the default
+        for format in ["%Q", "%", ""] {
+            assert_eq!(
+                format_date(time, format),
+                "2024-03-05 14:07:09",
+                "{:?}",
+                format
+            );
+        }
+    }
+
+    #[test]
+    fn alpha_plane_of_known_patterns() {
+        let rgba = image::RgbaImage::from_fn(2, 2, |x, y| {
+            image::Rgba([255, 0, 0, [0, 64, 128, 255][(y * 2 + x) as usize]])
+        });
+        let alpha = extract_alpha(&image::DynamicImage::ImageRgba8(rgba.clone()));
+        assert_eq!(alpha.as_deref(), Some(&[0, 64, 128, 255][..]));
+        assert_eq!(
+            alpha_to_gray(alpha.as_deref(), 2, 2),
+            [0, 0, 0, 64, 64, 64, 128, 128, 128, 255, 255, 255]
+        );
+
+        // 16-bit alpha is scaled down to 8 bits
+        let luma_alpha = image::ImageBuffer::from_fn(2, 1, |x, _| {
+            image::LumaA([0u16, [0, u16::MAX][x as usize]])
+        });
+        assert_eq!(
+            extract_alpha(&image::DynamicImage::ImageLumaA16(luma_alpha)).as_deref(),
+            Some(&[0, 255][..])
+        );
+
+        // Images without alpha are shown fully opaque
+        let rgb = image::DynamicImage::new_rgb8(2, 1);
+        assert_eq!(extract_alpha(&rgb), None);
+        assert_eq!(alpha_to_gray(None, 2, 1), [255; 6]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("alpha.png");
+        rgba.save(&path).unwrap();
+        assert_eq!(load_alpha(&path).unwrap(), Some(vec![0, 64, 128, 255]));
+    }
+
+    #[test]
+    fn mismatched_data_length_is_an_error() {
+        assert!(validate_rgb8_dimensions(&[0; 12], 2, 2).is_ok());
+        assert!(matches!(
+            validate_rgb8_dimensions(&[0; 11], 2, 2),
+            Err(AppError::ImageLoad(_))
+        ));
+        assert!(create_slint_image(&[0; 13], 2, 2).is_err());
+        assert!(create_slint_image(&[0; 12], 2, 2).is_ok());
+    }
+
+    #[test]
+    fn zero_and_overflowing_dimensions_are_errors() {
+        assert!(validate_rgb8_dimensions(&[], 0, 0).is_err());
+        assert!(validate_rgb8_dimensions(&[], 0, 5).is_err());
+        assert!(validate_rgb8_dimensions(&[0; 3], u32::MAX, u32::MAX).is_err());
+    }
+
+    #[test]
+    fn short_file_size_rounds_up_to_next_unit() {
+        assert_eq!(format_file_size_short(1023), "1,023 bytes");
+        assert_eq!(format_file_size_short(1024), "1.0 KB");
+        assert_eq!(format_file_size_short(1_048_575), "1.0 MB");
+        assert_eq!(format_file_size_short(1_048_576), "1.0 MB");
+    }
 }
@@ -0,0 +1,367 @@
+//! Lossless rotation of JPEG files through the Exif orientation tag.
+//!
+//! The compressed image data is not touched. Only the Orientation entry of
+//! IFD0 in the Exif APP1 segment is rewritten, or added when it is missing,
+//! so all other metadata segments are kept as they are.
+
+use crate::error::{AppError, Result};
+use image::metadata::Orientation;
+
+const MARKER_SOI: u8 = 0xD8;
+const MARKER_SOS: u8 = 0xDA;
+const MARKER_APP0: u8 = 0xE0;
+const MARKER_APP1: u8 = 0xE1;
+
+/// Header of an Exif APP1 payload, followed by TIFF data.
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+
+const TAG_ORIENTATION: u16 = 0x0112;
+const TYPE_SHORT: u16 = 3;
+
+/// Size of a single IFD entry.
+const IFD_ENTRY_SIZE: usize = 12;
+
+/// Maximum payload of a JPEG segment, as its length field includes itself.
+const MAX_SEGMENT_PAYLOAD: usize = u16::MAX as usize - 2;
+
+/// Returns whether the data starts with a JPEG SOI marker.
+pub fn is_jpeg(data: &[u8]) -> bool {
+    data.starts_with(&[0xFF, MARKER_SOI])
+}
+
+/// Returns the orientation after rotating an image shown with `orientation`
+/// by `quarter_turns` times 90 degrees clockwise (negative turns counterclockwise).
+pub fn rotate_orientation(orientation: Orientation, quarter_turns: i32) -> Orientation {
+    // 回転量（時計回りの90度単位）と、その後の左右反転の有無に分解する
+    let (rotation, flipped) = match orientation {
+        Orientation::NoTransforms => (0, false),
+        Orientation::Rotate90 => (1, false),
+        Orientation::Rotate180 => (2, false),
+        Orientation::Rotate270 => (3, false),
+        Orientation::FlipHorizontal => (0, true),
+        Orientation::Rotate90FlipH => (1, true),
+        Orientation::FlipVertical => (2, true),
+        Orientation::Rotate270FlipH => (3, true),
+    };
+
+    // 反転後の回転は、反転前の逆回転と等しい
+    let turns = if flipped {
+        -quarter_turns
+    } else {
+        quarter_turns
+    };
+    match ((rotation + turns).rem_euclid(4), flipped) {
+        (0, false) => Orientation::NoTransforms,
+        (1, false) => Orientation::Rotate90,
+        (2, false) => Orientation::Rotate180,
+        (3, false) => Orientation::Rotate270,
+        (0, true) => Orientation::FlipHorizontal,
+        (1, true) => Orientation::Rotate90FlipH,
+        (2, true) => Orientation::FlipVertical,
+        _ => Orientation::Rotate270FlipH,
+    }
+}
+
+/// Returns a copy of the JPEG data rotated by `quarter_turns` through its Exif orientation.
+pub fn rotate(data: &[u8], quarter_turns: i32) -> Result<Vec<u8>> {
+    if !is_jpeg(data) {
+        return Err(AppError::ImageWrite("Not a JPEG file".to_string()));
+    }
+
+    let segments = segments(data)?;
+    let exif = segments.iter().find(|segment| {
+        segment.marker == MARKER_APP1 && data[segment.payload.clone()].starts_with(EXIF_HEADER)
+    });
+
+    let (tiff, insert_at, replace_end) = match exif {
+        Some(segment) => {
+            let tiff =
+                data[segment.payload.start + EXIF_HEADER.len()..segment.payload.end].to_vec();
+            (tiff, segment.start, segment.payload.end)
+        }
+        None => {
+            // JFIFのAPP0は先頭に置く必要があるため、その直後に挿入する
+            let insert_at = segments
+                .first()
+                .filter(|segment| segment.marker == MARKER_APP0)
+                .map_or(2, |segment| segment.payload.end);
+            (empty_tiff(), insert_at, insert_at)
+        }
+    };
+
+    let current = read_orientation(&tiff).unwrap_or(Orientation::NoTransforms);
+    let target = rotate_orientation(current, quarter_turns);
+    let tiff = write_orientation(tiff, target)?;
+
+    let mut payload = EXIF_HEADER.to_vec();
+    payload.extend_from_slice(&tiff);
+    if payload.len() > MAX_SEGMENT_PAYLOAD {
+        return Err(AppError::ImageWrite(
+            "Exif segment is too large".to_string(),
+        ));
+    }
+
+    let mut output = Vec::with_capacity(data.len() + payload.len());
+    output.extend_from_slice(&data[..insert_at]);
+    output.extend_from_slice(&[0xFF, MARKER_APP1]);
+    output.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+    output.extend_from_slice(&payload);
+    output.extend_from_slice(&data[replace_end..]);
+    Ok(output)
+}
+
+/// A marker segment before the image data.
+struct Segment {
+    marker: u8,
+    /// Offset of the 0xFF byte of the marker.
+    start: usize,
+    /// Range of the payload, after the length field.
+    payload: std::ops::Range<usize>,
+}
+
+/// Lists the marker segments between SOI and the first SOS.
+fn segments(data: &[u8]) -> Result<Vec<Segment>> {
+    let malformed = || AppError::ImageWrite("Malformed JPEG segment".to_string());
+    let mut segments = Vec::new();
+    let mut offset = 2;
+
+    loop {
+        let start = offset;
+        if data.get(offset) != Some(&0xFF) {
+            return Err(malformed());
+        }
+        // マーカー前の0xFFは詰め物として読み飛ばす
+        while data.get(offset) == Some(&0xFF) {
+            offset += 1;
+        }
+        let marker = *data.get(offset).ok_or_else(malformed)?;
+        offset += 1;
+
+        if marker == MARKER_SOS {
+            return Ok(segments);
+        }
+        if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            continue;
+        }
+
+        let length = data
+            .get(offset..offset + 2)
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]) as usize)
+            .filter(|length| *length >= 2)
+            .ok_or_else(malformed)?;
+        let payload = offset + 2..offset + length;
+        if payload.end > data.len() {
+            return Err(malformed());
+        }
+        offset = payload.end;
+        segments.push(Segment {
+            marker,
+            start,
+            payload,
+        });
+    }
+}
+
+/// Byte order of TIFF data.
+#[derive(Clone, Copy)]
+struct ByteOrder {
+    little_endian: bool,
+}
+
+impl ByteOrder {
+    fn of(tiff: &[u8]) -> Option<Self> {
+        match tiff.get(..4)? {
+            [b'I', b'I', 42, 0] => Some(Self {
+                little_endian: true,
+            }),
+            [b'M', b'M', 0, 42] => Some(Self {
+                little_endian: false,
+            }),
+            _ => None,
+        }
+    }
+
+    fn u16(self, tiff: &[u8], offset: usize) -> Option<u16> {
+        let bytes: [u8; 2] = tiff.get(offset..offset + 2)?.try_into().ok()?;
+        Some(if self.little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        })
+    }
+
+    fn u32(self, tiff: &[u8], offset: usize) -> Option<u32> {
+        let bytes: [u8; 4] = tiff.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    fn u16_bytes(self, value: u16) -> [u8; 2] {
+        if self.little_endian {
+            value.to_le_bytes()
+        } else {
+            value.to_be_bytes()
+        }
+    }
+
+    fn u32_bytes(self, value: u32) -> [u8; 4] {
+        if self.little_endian {
+            value.to_le_bytes()
+        } else {
+            value.to_be_bytes()
+        }
+    }
+}
+
+/// Returns big-endian TIFF data with an empty IFD0.
+fn empty_tiff() -> Vec<u8> {
+    let mut tiff = b"MM\0\x2A".to_vec();
+    tiff.extend_from_slice(&8u32.to_be_bytes());
+    tiff.extend_from_slice(&0u16.to_be_bytes());
+    tiff.extend_from_slice(&0u32.to_be_bytes());
+    tiff
+}
+
+/// Returns the IFD0 offset and its entry count.
+fn ifd0(tiff: &[u8], order: ByteOrder) -> Option<(usize, usize)> {
+    let offset = order.u32(tiff, 4)? as usize;
+    let count = order.u16(tiff, offset)? as usize;
+    tiff.get(offset..offset + 2 + count * IFD_ENTRY_SIZE + 4)?;
+    Some((offset, count))
+}
+
+/// Returns the offset of the IFD0 Orientation entry, if any.
+fn orientation_entry(tiff: &[u8], order: ByteOrder) -> Option<usize> {
+    let (ifd, count) = ifd0(tiff, order)?;
+    (0..count)
+        .map(|i| ifd + 2 + i * IFD_ENTRY_SIZE)
+        .find(|&entry| order.u16(tiff, entry) == Some(TAG_ORIENTATION))
+}
+
+/// Reads the orientation from IFD0 of TIFF data.
+fn read_orientation(tiff: &[u8]) -> Option<Orientation> {
+    let order = ByteOrder::of(tiff)?;
+    let entry = orientation_entry(tiff, order)?;
+    let value = order.u16(tiff, entry + 8)?;
+    Orientation::from_exif(u8::try_from(value).ok()?)
+}
+
+/// Sets the orientation in IFD0 of TIFF data.
+///
+/// An existing entry is overwritten in place. Otherwise IFD0 is copied with
+/// the new entry to the end of the data, since growing it in place would
+/// move every value stored after it.
+fn write_orientation(mut tiff: Vec<u8>, orientation: Orientation) -> Result<Vec<u8>> {
+    let malformed = || AppError::ImageWrite("Malformed Exif data".to_string());
+    let order = ByteOrder::of(&tiff).ok_or_else(malformed)?;
+    let value = u16::from(orientation.to_exif());
+
+    if let Some(entry) = orientation_entry(&tiff, order) {
+        tiff[entry + 2..entry + 4].copy_from_slice(&order.u16_bytes(TYPE_SHORT));
+        tiff[entry + 4..entry + 8].copy_from_slice(&order.u32_bytes(1));
+        tiff[entry + 8..entry + 12].fill(0);
+        tiff[entry + 8..entry + 10].copy_from_slice(&order.u16_bytes(value));
+        return Ok(tiff);
+    }
+
+    let (ifd, count) = ifd0(&tiff, order).ok_or_else(malformed)?;
+    let entries_end = ifd + 2 + count * IFD_ENTRY_SIZE;
+    let mut entries: Vec<[u8; IFD_ENTRY_SIZE]> = tiff[ifd + 2..entries_end]
+        .chunks_exact(IFD_ENTRY_SIZE)
+        .filter_map(|entry| entry.try_into().ok())
+        .collect();
+    let next_ifd = tiff[entries_end..entries_end + 4].to_vec();
+
+    let mut new_entry = [0u8; IFD_ENTRY_SIZE];
+    new_entry[..2].copy_from_slice(&order.u16_bytes(TAG_ORIENTATION));
+    new_entry[2..4].copy_from_slice(&order.u16_bytes(TYPE_SHORT));
+    new_entry[4..8].copy_from_slice(&order.u32_bytes(1));
+    new_entry[8..10].copy_from_slice(&order.u16_bytes(value));
+    entries.push(new_entry);
+    // IFDのエントリはタグ順に並べる必要がある
+    entries.sort_by_key(|entry| order.u16(entry, 0));
+
+    // IFDは偶数オフセットから始める
+    if tiff.len() % 2 == 1 {
+        tiff.push(0);
+    }
+    let new_ifd = u32::try_from(tiff.len()).map_err(|_| malformed())?;
+    tiff.extend_from_slice(&order.u16_bytes(entries.len() as u16));
+    for entry in &entries {
+        tiff.extend_from_slice(entry);
+    }
+    tiff.extend_from_slice(&next_ifd);
+    tiff[4..8].copy_from_slice(&order.u32_bytes(new_ifd));
+    Ok(tiff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::ImageDecoder;
+    use std::io::Cursor;
+
+    /// Encodes a `width` x `height` JPEG, which has a JFIF APP0 segment and no Exif.
+    fn jpeg(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        image::DynamicImage::new_rgb8(width, height)
+            .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Jpeg)
+            .unwrap();
+        bytes
+    }
+
+    fn orientation_of(data: &[u8]) -> Orientation {
+        image::codecs::jpeg::JpegDecoder::new(Cursor::new(data))
+            .unwrap()
+            .orientation()
+            .unwrap()
+    }
+
+    #[test]
+    fn rotation_adds_and_updates_exif_orientation() {
+        let original = jpeg(4, 2);
+        assert_eq!(orientation_of(&original), Orientation::NoTransforms);
+
+        let rotated = rotate(&original, 1).unwrap();
+        assert_eq!(orientation_of(&rotated), Orientation::Rotate90);
+        let mut img = image::load_from_memory(&rotated).unwrap();
+        img.apply_orientation(orientation_of(&rotated));
+        assert_eq!((img.width(), img.height()), (2, 4));
+
+        // The existing entry is rewritten rather than a second one added
+        let rotated = rotate(&rotated, 2).unwrap();
+        assert_eq!(orientation_of(&rotated), Orientation::Rotate270);
+        let rotated = rotate(&rotated, 1).unwrap();
+        assert_eq!(orientation_of(&rotated), Orientation::NoTransforms);
+
+        // The compressed image data is kept byte for byte
+        let sos = |data: &[u8]| {
+            data.windows(2)
+                .position(|w| w == [0xFF, MARKER_SOS])
+                .unwrap()
+        };
+        assert_eq!(rotated[sos(&rotated)..], original[sos(&original)..]);
+        // SOI and the 16-byte JFIF APP0 segment stay first
+        assert!(rotated.starts_with(&original[..20]));
+    }
+
+    #[test]
+    fn rotating_a_flipped_orientation_keeps_the_flip() {
+        assert_eq!(
+            rotate_orientation(Orientation::FlipHorizontal, 1),
+            Orientation::Rotate270FlipH
+        );
+        assert_eq!(
+            rotate_orientation(Orientation::Rotate90, -1),
+            Orientation::NoTransforms
+        );
+    }
+
+    #[test]
+    fn rejects_non_jpeg_data() {
+        assert!(rotate(b"\x89PNG\r\n\x1a\n", 1).is_err());
+    }
+}
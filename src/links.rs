@@ -0,0 +1,58 @@
+//! External links built from image metadata.
+
+use crate::metadata::SdParameters;
+
+/// Civitai search page used to look up a model by its hash.
+const CIVITAI_MODEL_SEARCH_URL: &str = "https://civitai.com/search/models?query=";
+
+/// Builds a model lookup URL from the `Model hash` of the parameters.
+///
+/// Returns `None` if there is no model hash or it is not a hexadecimal string,
+/// so arbitrary metadata text never ends up in a URL.
+pub fn model_lookup_url(params: &SdParameters) -> Option<String> {
+    let hash = params.model_hash.as_deref()?.trim();
+    if hash.is_empty() || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    Some(format!(
+        "{}{}",
+        CIVITAI_MODEL_SEARCH_URL,
+        hash.to_ascii_lowercase()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params_with_hash(hash: Option<&str>) -> SdParameters {
+        SdParameters {
+            model_hash: hash.map(str::to_string),
+            ..SdParameters::default()
+        }
+    }
+
+    #[test]
+    fn builds_lookup_url_from_hash() {
+        assert_eq!(
+            model_lookup_url(&params_with_hash(Some("6CE0161689"))).as_deref(),
+            Some("https://civitai.com/search/models?query=6ce0161689")
+        );
+        assert_eq!(
+            model_lookup_url(&params_with_hash(Some(" e6bb9ea85b \n"))).as_deref(),
+            Some("https://civitai.com/search/models?query=e6bb9ea85b")
+        );
+    }
+
+    #[test]
+    fn rejects_missing_or_non_hex_hash() {
+        assert_eq!(model_lookup_url(&params_with_hash(None)), None);
+        assert_eq!(model_lookup_url(&params_with_hash(Some(""))), None);
+        assert_eq!(
+            model_lookup_url(&params_with_hash(Some("abc&query=evil"))),
+            None
+        );
+        assert_eq!(model_lookup_url(&params_with_hash(Some("xyz123"))), None);
+    }
+}
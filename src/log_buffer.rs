@@ -0,0 +1,180 @@
+//! In-memory buffer of recent log records.
+//!
+//! Wraps the console logger so that recent messages can be shown in the UI,
+//! even when the app runs without a console (e.g. Windows release builds).
+
+use crate::config::LOG_BUFFER_CAPACITY;
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use once_cell::sync::Lazy;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// Bounded, thread-safe buffer that keeps only the most recent log lines.
+pub struct LogBuffer {
+    capacity: usize,
+    lines: Mutex<VecDeque<String>>,
+}
+
+impl LogBuffer {
+    /// Creates a buffer that retains at most `capacity` lines.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            lines: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// Appends a line, dropping the oldest one when the buffer is full.
+    pub fn push(&self, line: String) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        if lines.len() >= self.capacity {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    /// Returns the buffered lines, oldest first.
+    pub fn recent(&self) -> Vec<String> {
+        let lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        lines.iter().cloned().collect()
+    }
+}
+
+static LOG_BUFFER: Lazy<LogBuffer> = Lazy::new(|| LogBuffer::new(LOG_BUFFER_CAPACITY));
+
+/// Highest level buffered for records from other crates, so that their
+/// debug output does not push this app's messages out of the buffer.
+const DEPENDENCY_LEVEL: LevelFilter = LevelFilter::Warn;
+
+/// Returns the highest level buffered for records with `target`.
+///
+/// Records from this crate are buffered up to `level`,
+/// records from other crates up to [`DEPENDENCY_LEVEL`] at most.
+fn buffer_level(target: &str, level: LevelFilter) -> LevelFilter {
+    let crate_name = env!("CARGO_CRATE_NAME");
+    let own = target
+        .strip_prefix(crate_name)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"));
+    if own {
+        level
+    } else {
+        level.min(DEPENDENCY_LEVEL)
+    }
+}
+
+/// Logger that records this crate's messages and other crates' warnings
+/// into [`LOG_BUFFER`] and forwards every record to the console logger
+/// if one is configured.
+struct BufferingLogger {
+    console: Option<env_logger::Logger>,
+    level: LevelFilter,
+}
+
+impl BufferingLogger {
+    fn buffers(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= buffer_level(metadata.target(), self.level)
+    }
+}
+
+impl Log for BufferingLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.buffers(metadata)
+            || self
+                .console
+                .as_ref()
+                .is_some_and(|console| console.enabled(metadata))
+    }
+
+    fn log(&self, record: &Record) {
+        if self.buffers(record.metadata()) {
+            LOG_BUFFER.push(format!(
+                "[{} {} {}] {}",
+                chrono::Local::now().format("%H:%M:%S"),
+                record.level(),
+                record.target(),
+                record.args()
+            ));
+        }
+
+        if let Some(console) = &self.console
+            && console.matches(record)
+        {
+            console.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(console) = &self.console {
+            console.flush();
+        }
+    }
+}
+
+/// Installs the buffering logger as the global logger.
+///
+/// `console` is the optional console logger to forward records to.
+/// Without it, this crate's records at `Info` and above are buffered.
+pub fn init(console: Option<env_logger::Logger>) -> Result<(), SetLoggerError> {
+    let level = console
+        .as_ref()
+        .map(|console| console.filter())
+        .unwrap_or(LevelFilter::Info);
+
+    log::set_boxed_logger(Box::new(BufferingLogger { console, level }))?;
+    log::set_max_level(level);
+    Ok(())
+}
+
+/// Returns the most recent log lines, oldest first.
+pub fn recent_logs() -> Vec<String> {
+    LOG_BUFFER.recent()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_only_the_most_recent_lines() {
+        let buffer = LogBuffer::new(2);
+        for line in ["first", "second", "third"] {
+            buffer.push(line.to_string());
+        }
+        assert_eq!(buffer.recent(), ["second", "third"]);
+    }
+
+    #[test]
+    fn zero_capacity_keeps_nothing() {
+        let buffer = LogBuffer::new(0);
+        buffer.push("line".to_string());
+        assert!(buffer.recent().is_empty());
+    }
+
+    #[test]
+    fn buffers_debug_only_from_this_crate() {
+        let own = format!("{}::ui::handlers", env!("CARGO_CRATE_NAME"));
+        assert_eq!(buffer_level(&own, LevelFilter::Debug), LevelFilter::Debug);
+        assert_eq!(
+            buffer_level(env!("CARGO_CRATE_NAME"), LevelFilter::Debug),
+            LevelFilter::Debug
+        );
+        assert_eq!(
+            buffer_level("winit::window", LevelFilter::Debug),
+            LevelFilter::Warn
+        );
+        // A crate whose name merely starts with this one is a dependency
+        let lookalike = format!("{}_extra", env!("CARGO_CRATE_NAME"));
+        assert_eq!(
+            buffer_level(&lookalike, LevelFilter::Debug),
+            LevelFilter::Warn
+        );
+        assert_eq!(
+            buffer_level("winit", LevelFilter::Error),
+            LevelFilter::Error
+        );
+    }
+}
@@ -3,50 +3,89 @@
 
 slint::include_modules!();
 
+mod checksum;
+mod cli;
 mod config;
+mod directory_prefs;
 mod error;
 mod file_utils;
 mod image_cache;
 mod image_loader;
+mod jpeg_orientation;
+mod links;
+mod log_buffer;
+mod messages;
 mod metadata;
+#[cfg(feature = "raw")]
+mod raw_preview;
+mod rating_index;
+mod recent_files;
 mod services;
+mod settings;
 mod startup;
 mod state;
 mod ui;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let options = match cli::parse_args() {
+        Ok(cli::Command::Run(options)) => options,
+        Ok(cli::Command::Help) => {
+            println!("{}", cli::usage());
+            return Ok(());
+        }
+        Ok(cli::Command::Version) => {
+            println!("{}", cli::version());
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::usage());
+            std::process::exit(2);
+        }
+    };
+
     #[cfg(debug_assertions)]
-    env_logger::Builder::from_default_env()
-        .filter_level(log::LevelFilter::Debug)
-        .format(|buf, record| {
-            use std::io::Write;
-
-            let pkg_name = env!("CARGO_PKG_NAME").replace("-", "_");
-            let prefix = format!("{}::", pkg_name);
-            let target = record
-                .target()
-                .strip_prefix(&prefix)
-                .unwrap_or(record.target());
-
-            let level_style = buf.default_level_style(record.level());
-            let level = level_style.render();
-            let reset = level_style.render_reset();
-
-            // JSTタイムスタンプ（時刻のみ）
-            let timestamp = chrono::Local::now().format("%H:%M:%S");
-
-            writeln!(
-                buf,
-                "[{} {}{}{} {}] {}",
-                timestamp,
-                level,
-                record.level(),
-                reset,
-                target,
-                record.args()
-            )
-        })
-        .init();
+    let console_logger = Some(
+        env_logger::Builder::from_default_env()
+            .filter_level(log::LevelFilter::Debug)
+            .format(|buf, record| {
+                use std::io::Write;
+
+                let pkg_name = env!("CARGO_PKG_NAME").replace("-", "_");
+                let prefix = format!("{}::", pkg_name);
+                let target = record
+                    .target()
+                    .strip_prefix(&prefix)
+                    .unwrap_or(record.target());
+
+                let level_style = buf.default_level_style(record.level());
+                let level = level_style.render();
+                let reset = level_style.render_reset();
+
+                // JSTタイムスタンプ（時刻のみ）
+                let timestamp = chrono::Local::now().format("%H:%M:%S");
+
+                writeln!(
+                    buf,
+                    "[{} {}{}{} {}] {}",
+                    timestamp,
+                    level,
+                    record.level(),
+                    reset,
+                    target,
+                    record.args()
+                )
+            })
+            .build(),
+    );
+    #[cfg(not(debug_assertions))]
+    let console_logger = None;
+
+    log_buffer::init(console_logger)?;
+
+    // Headless mode: render the image to a file and exit before creating the window
+    if let Some(output) = &options.screenshot_output {
+        return startup::render_screenshot(&options, output);
+    }
 
     let app = AppWindow::new()?;
     let app_state = state::AppState::new();
@@ -54,12 +93,23 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Create display tracker for color management
     let display_tracker = ui::DisplayTracker::new();
 
-    startup::configure_startup_opening(&app, &app_state, &display_tracker);
+    startup::configure_startup_opening(&app, &app_state, &display_tracker, &options);
+
+    let pending_writes = app_state.pending_writes.clone();
 
     // Setup all UI event handlers
     ui::setup_handlers(&app, app_state, display_tracker);
 
     app.run()?;
 
+    // Rating and keyword writes run on background threads, which end with the process
+    let timeout = std::time::Duration::from_millis(config::SHUTDOWN_WRITE_TIMEOUT_MS);
+    let unfinished = pending_writes.wait(timeout);
+    if unfinished > 0 {
+        log::warn!("Exiting with {} XMP writes unfinished", unfinished);
+    }
+
+    services::FileOpsService::new().cleanup_staged_files();
+
     Ok(())
 }
@@ -0,0 +1,161 @@
+//! Catalog of user-facing error messages in English and Japanese.
+//!
+//! Error `Display` impls look up their text here, so every error shown in the
+//! UI uses the same language. The language comes from the `language` setting,
+//! or the system locale when it is `"auto"`.
+
+use serde::{Deserialize, Serialize};
+
+/// Language of user-facing error messages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Language {
+    /// Japanese if the system locale is Japanese, English otherwise.
+    #[default]
+    Auto,
+    English,
+    Japanese,
+}
+
+impl Language {
+    /// Resolves `Auto` to a concrete language from the system locale.
+    pub fn resolve(self) -> Self {
+        match self {
+            Self::Auto => Self::from_locale(&system_locale()),
+            language => language,
+        }
+    }
+
+    /// Returns the language for a locale name such as `ja_JP.UTF-8` or `en-US`.
+    fn from_locale(locale: &str) -> Self {
+        if locale.to_ascii_lowercase().starts_with("ja") {
+            Self::Japanese
+        } else {
+            Self::English
+        }
+    }
+}
+
+/// Returns the system locale from the POSIX locale variables, or an empty string.
+///
+/// The first non-empty of `LC_ALL`, `LC_MESSAGES` and `LANG` wins, following POSIX precedence.
+fn system_locale() -> String {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default()
+}
+
+/// Returns the language error messages are currently shown in.
+pub fn current_language() -> Language {
+    crate::settings::current().language.resolve()
+}
+
+/// Keys of the message catalog.
+///
+/// Templates may contain `{}` placeholders, filled in order by [`render`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Message {
+    ImageLoad,
+    ImageWrite,
+    DirectoryScan,
+    XmpRead,
+    XmpWrite,
+    FileReadOnly,
+    MetadataRead,
+    IncompleteImage,
+    FileNotFound,
+    NoImages,
+    NoCurrentPath,
+    DirectoryScanFailed,
+    NoSiblingDirectory,
+    NoImageWithRating,
+    NoImageWithSeed,
+    NotInList,
+    IndexOutOfRange,
+    NoFilesToCopy,
+    NoValidPaths,
+    UnsupportedClipboardType,
+}
+
+impl Message {
+    /// Returns the template of the message in `language`.
+    ///
+    /// `Auto` is resolved from the system locale.
+    pub fn template(self, language: Language) -> &'static str {
+        match language.resolve() {
+            Language::Japanese => self.japanese(),
+            _ => self.english(),
+        }
+    }
+
+    fn english(self) -> &'static str {
+        match self {
+            Self::ImageLoad => "Image load error: {}",
+            Self::ImageWrite => "Image write error: {}",
+            Self::DirectoryScan => "Directory scan error: {}",
+            Self::XmpRead => "XMP read error: {}",
+            Self::XmpWrite => "XMP write error: {}",
+            Self::FileReadOnly => "File is read-only: {}",
+            Self::MetadataRead => "Metadata read error: {}",
+            Self::IncompleteImage => "Image is incomplete (it may still be downloading): {}",
+            Self::FileNotFound => "File not found: {}",
+            Self::NoImages => "No images available in the current directory",
+            Self::NoCurrentPath => "No current file path is set",
+            Self::DirectoryScanFailed => "Failed to scan directory: {}",
+            Self::NoSiblingDirectory => "No other directory with images found",
+            Self::NoImageWithRating => "No other image with rating {} found",
+            Self::NoImageWithSeed => "No other PNG image with seed {} found",
+            Self::NotInList => "Not in the current image list: {}",
+            Self::IndexOutOfRange => "No image at position {} in the current list",
+            Self::NoFilesToCopy => "No files to copy",
+            Self::NoValidPaths => "No valid file paths",
+            Self::UnsupportedClipboardType => "Unsupported clipboard image type: {}",
+        }
+    }
+
+    fn japanese(self) -> &'static str {
+        match self {
+            Self::ImageLoad => "画像読み込みエラー: {}",
+            Self::ImageWrite => "画像書き込みエラー: {}",
+            Self::DirectoryScan => "ディレクトリスキャンエラー: {}",
+            Self::XmpRead => "XMP読み取りエラー: {}",
+            Self::XmpWrite => "XMP書き込みエラー: {}",
+            Self::FileReadOnly => "ファイルが読み取り専用です: {}",
+            Self::MetadataRead => "メタデータ読み取りエラー: {}",
+            Self::IncompleteImage => "画像が不完全です（ダウンロード中の可能性があります）: {}",
+            Self::FileNotFound => "ファイルが見つかりません: {}",
+            Self::NoImages => "現在のディレクトリに画像がありません",
+            Self::NoCurrentPath => "現在のファイルが設定されていません",
+            Self::DirectoryScanFailed => "ディレクトリのスキャンに失敗しました: {}",
+            Self::NoSiblingDirectory => "画像のある他のディレクトリが見つかりません",
+            Self::NoImageWithRating => "レーティング{}の他の画像が見つかりません",
+            Self::NoImageWithSeed => "シード{}の他のPNG画像が見つかりません",
+            Self::NotInList => "現在の画像リストにありません: {}",
+            Self::IndexOutOfRange => "現在のリストの{}番目に画像がありません",
+            Self::NoFilesToCopy => "コピーするファイルがありません",
+            Self::NoValidPaths => "有効なファイルパスがありません",
+            Self::UnsupportedClipboardType => "クリップボードに対応していない画像形式です: {}",
+        }
+    }
+}
+
+/// Renders `message` in `language`, replacing each `{}` with the next argument.
+pub fn render(message: Message, language: Language, args: &[&dyn std::fmt::Display]) -> String {
+    let mut args = args.iter();
+    let mut parts = message.template(language).split("{}");
+    let mut output = parts.next().unwrap_or_default().to_string();
+    for part in parts {
+        if let Some(arg) = args.next() {
+            output.push_str(&arg.to_string());
+        }
+        output.push_str(part);
+    }
+    output
+}
+
+/// Renders `message` in the current language.
+pub fn localized(message: Message, args: &[&dyn std::fmt::Display]) -> String {
+    render(message, current_language(), args)
+}
@@ -1,34 +1,108 @@
 //! XMP metadata handling for image files.
 
 use crate::error::{AppError, Result};
+use crate::file_utils;
 use once_cell::sync::Lazy;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
-use xmp_toolkit::{OpenFileOptions, XmpFile, XmpMeta, XmpValue};
+use std::borrow::Cow;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use xmp_toolkit::{OpenFileOptions, XmpFile, XmpMeta, XmpValue, xmp_ns};
 
 const XMP_NAMESPACE: &str = "http://ns.adobe.com/xap/1.0/";
 const RATING_PROPERTY: &str = "Rating";
 const MAX_RATING: u8 = 5;
+const KEYWORDS_PROPERTY: &str = "subject";
+
+/// Extensions of sidecar XMP files, tried in this order.
+const SIDECAR_EXTENSIONS: &[&str] = &["xmp", "XMP"];
+/// PNG text chunk keyword holding A1111 style generation parameters.
+pub const PARAMETERS_KEYWORD: &str = "parameters";
+
+/// Custom namespace holding the fine-grained rating used by [`RatingScale::Extended`].
+const EXTENDED_RATING_NAMESPACE: &str = "http://ns.tenpamk2.github.io/sd-image-viewer/1.0/";
+const EXTENDED_RATING_PREFIX: &str = "sdiv";
+const EXTENDED_RATING_PROPERTY: &str = "RatingPercent";
+/// Maximum rating in the extended 0-10 display scale.
+const MAX_EXTENDED_RATING: u8 = 10;
+/// Maximum value stored in the extended rating property.
+const MAX_RATING_PERCENT: u8 = 100;
+
+/// Stealth pnginfo signature for uncompressed payloads in the alpha channel.
+const STEALTH_SIGNATURE_PLAIN: &[u8] = b"stealth_pnginfo";
+/// Stealth pnginfo signature for gzip-compressed payloads in the alpha channel.
+const STEALTH_SIGNATURE_COMPRESSED: &[u8] = b"stealth_pngcomp";
+
+/// ComfyUI sampler node types whose inputs describe the generation.
+const COMFYUI_SAMPLER_NODES: &[&str] = &["KSampler", "KSamplerAdvanced"];
+
+/// ComfyUI node types that load a checkpoint or diffusion model.
+const COMFYUI_CHECKPOINT_NODES: &[&str] =
+    &["CheckpointLoaderSimple", "CheckpointLoader", "UNETLoader"];
+
+/// ComfyUI node types that apply a LoRA to the model.
+const COMFYUI_LORA_NODES: &[&str] = &["LoraLoader", "LoraLoaderModelOnly"];
+
+/// Upper bound for following node links, guarding against cycles.
+const COMFYUI_MAX_LINK_DEPTH: usize = 16;
 
 // 正規表現を一度だけコンパイル（起動時エラーで早期発見）
 static TAG_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"\(([^:]+):([0-9]+(?:\.[0-9]+)?)\)").expect("Invalid regex pattern for SD tags")
+    // 重みは負の値（例: `(tag:-0.5)`）も許可
+    Regex::new(r"\(([^:]+):(-?[0-9]+(?:\.[0-9]+)?)\)").expect("Invalid regex pattern for SD tags")
+});
+
+// 改行を伴わない "Negative prompt:" 区切り（文字列先頭または空白の直後）
+static NEGATIVE_PROMPT_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?:^|\s)Negative prompt:").expect("Invalid regex pattern for negative prompt")
 });
 
 static FIELD_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"(Steps|Sampler|Schedule type|CFG scale|Seed|Size|Model|Denoising strength|Clip skip):\s*([^,]+)")
+    Regex::new(r"(Steps|Sampler|Schedule type|CFG scale|Seed|Size|Model hash|Model|Denoising strength|Clip skip):\s*([^,]+)")
         .expect("Invalid regex pattern for SD fields")
 });
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct SdTag {
     pub name: String,
     pub weight: Option<f32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Tool that wrote the generation parameters, determined by the parser that matched.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SdSource {
+    /// A1111 style text with "Negative prompt:" and "Steps:" sections.
+    Automatic1111,
+    /// ComfyUI "prompt" JSON in API format.
+    ComfyUI,
+    /// NovelAI JSON, usually from stealth pnginfo.
+    NovelAI,
+    /// Fooocus metadata JSON.
+    Fooocus,
+    /// Metadata of an unknown tool.
+    #[default]
+    Unknown,
+}
+
+impl SdSource {
+    /// Returns the name shown in the info panel.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Automatic1111 => "AUTOMATIC1111",
+            Self::ComfyUI => "ComfyUI",
+            Self::NovelAI => "NovelAI",
+            Self::Fooocus => "Fooocus",
+            Self::Unknown => "Unknown",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SdParameters {
+    /// Tool that wrote the parameters.
+    pub source: SdSource,
     pub positive_sd_tags: Vec<SdTag>,
     pub negative_sd_tags: Vec<SdTag>,
     pub steps: Option<String>,
@@ -38,14 +112,216 @@ pub struct SdParameters {
     pub seed: Option<String>,
     pub size: Option<String>,
     pub model: Option<String>,
+    pub model_hash: Option<String>,
     pub denoising_strength: Option<String>,
     pub clip_skip: Option<String>,
+    pub vae: Option<String>,
+    /// LoRA names with their model strength, e.g. `detail.safetensors:0.8`.
+    pub loras: Vec<String>,
     pub raw: String,
 }
 
+/// SD parameters text that was found but could not be parsed.
+#[derive(Debug, Clone)]
+pub struct SdParseFailure {
+    /// Unparsed parameters text.
+    pub raw: String,
+    /// Reason the parse failed.
+    pub message: String,
+}
+
+impl SdParseFailure {
+    fn new(raw: &str, error: &AppError) -> Self {
+        Self {
+            raw: raw.to_string(),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Tag whose weight differs between two prompts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagWeightChange {
+    pub name: String,
+    pub before: Option<f32>,
+    pub after: Option<f32>,
+}
+
+/// Differences between the tags of two prompts, matched by tag name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TagDiff {
+    /// Tags only in the second prompt.
+    pub added: Vec<SdTag>,
+    /// Tags only in the first prompt.
+    pub removed: Vec<SdTag>,
+    /// Tags in both prompts with a different weight.
+    pub reweighted: Vec<TagWeightChange>,
+}
+
+/// Generation setting whose value differs between two images.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    /// Display name of the setting, e.g. `Seed`.
+    pub field: &'static str,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/// Differences between the SD parameters of two images, see [`diff_prompts`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PromptDiff {
+    pub positive: TagDiff,
+    pub negative: TagDiff,
+    pub fields: Vec<FieldChange>,
+}
+
+/// Rating scale used when reading and writing ratings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RatingScale {
+    /// Standard XMP Rating (0-5).
+    #[default]
+    Standard,
+    /// Fine-grained 0-10 rating stored as 0-100 in a custom property.
+    ///
+    /// The standard 0-5 Rating is written alongside for compatibility.
+    Extended,
+}
+
+impl RatingScale {
+    /// Returns the maximum rating shown in the UI for this scale.
+    pub fn max_rating(self) -> u8 {
+        match self {
+            RatingScale::Standard => MAX_RATING,
+            RatingScale::Extended => MAX_EXTENDED_RATING,
+        }
+    }
+}
+
+/// Returns the rating scale ratings are currently read and written with.
+pub fn current_rating_scale() -> RatingScale {
+    crate::settings::read(|settings| settings.rating_scale)
+}
+
+/// Converts an extended rating (0-10) to the stored percent value (0-100).
+pub fn extended_rating_to_percent(rating: u8) -> u8 {
+    rating.min(MAX_EXTENDED_RATING) * (MAX_RATING_PERCENT / MAX_EXTENDED_RATING)
+}
+
+/// Converts a stored percent value (0-100) to an extended rating (0-10), rounding to nearest.
+pub fn percent_to_extended_rating(percent: u8) -> u8 {
+    let percent = percent.min(MAX_RATING_PERCENT) as u16;
+    ((percent + 5) / 10) as u8
+}
+
+/// Converts an extended rating (0-10) to the standard XMP Rating (0-5), rounding half up.
+pub fn extended_rating_to_standard(rating: u8) -> u8 {
+    rating.min(MAX_EXTENDED_RATING).div_ceil(2)
+}
+
+/// Sequence number for temporary copies of files with non-UTF-8 paths.
+static STAGED_XMP_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// Runs `op` with a UTF-8 path to the file, since xmp_toolkit only accepts UTF-8 paths.
+///
+/// Paths that are not valid UTF-8 are staged under an ASCII name in the temp
+/// directory. Reads use a symlink to the file where possible instead of a copy.
+/// When `write_back` is set, the file is checked for writability first and the
+/// staged copy replaces the original after `op` succeeds, through a temporary
+/// file next to it and a rename, so the original is never left half-written.
+fn with_utf8_path<T>(
+    path: &Path,
+    write_back: bool,
+    op: impl FnOnce(&Path) -> Result<T>,
+) -> Result<T> {
+    if path.to_str().is_some() {
+        return op(path);
+    }
+
+    if write_back {
+        ensure_writable(path)?;
+    }
+
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    // Ratings are read in parallel, so every staged copy gets its own name
+    let sequence = STAGED_XMP_SEQUENCE.fetch_add(1, Ordering::Relaxed);
+    let staged = std::env::temp_dir().join(format!(
+        "{}-xmp-{}-{}.{}",
+        env!("CARGO_PKG_NAME"),
+        std::process::id(),
+        sequence,
+        extension
+    ));
+    log::debug!("Staging non-UTF-8 path {:?} as {:?}", path, staged);
+
+    let to_error = |e: std::io::Error| {
+        let msg = format!("Failed to stage non-UTF-8 path: {}", e);
+        if write_back {
+            AppError::XmpWrite(msg)
+        } else {
+            AppError::XmpRead(msg)
+        }
+    };
+    // Reads only need a UTF-8 name for the file, so the image isn't copied if it can be linked
+    if write_back || !link_for_read(path, &staged) {
+        std::fs::copy(path, &staged).map_err(to_error)?;
+    }
+
+    let result = op(&staged).and_then(|value| {
+        if write_back {
+            replace_from_staged(&staged, path).map_err(to_error)?;
+        }
+        Ok(value)
+    });
+
+    if let Err(e) = std::fs::remove_file(&staged) {
+        log::warn!("Failed to remove staged file {:?}: {}", staged, e);
+    }
+    result
+}
+
+/// Creates `staged` as a symlink to `path`, so it can be read without copying it.
+///
+/// Returns whether the link was created.
+#[cfg(unix)]
+fn link_for_read(path: &Path, staged: &Path) -> bool {
+    std::fs::canonicalize(path)
+        .and_then(|target| std::os::unix::fs::symlink(target, staged))
+        .map_err(|e| log::debug!("Failed to link {:?}, copying it instead: {}", path, e))
+        .is_ok()
+}
+
+/// Symlinks need extra privileges on Windows, so staged reads always copy the file.
+#[cfg(not(unix))]
+fn link_for_read(_path: &Path, _staged: &Path) -> bool {
+    false
+}
+
+/// Replaces `path` with the staged copy through a temporary file in the same directory.
+///
+/// The original is swapped by a rename, so it stays intact if copying fails.
+fn replace_from_staged(staged: &Path, path: &Path) -> std::io::Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = std::fs::copy(staged, &temp_path).and_then(|_| std::fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
 /// Converts a path to a string, returning an error if the path is not valid UTF-8.
-fn path_to_str(path: &Path) -> Result<&str> {
+///
+/// Windows verbatim paths (`\\?\UNC\server\share\...`) are passed to the XMP SDK
+/// in their plain form, which it handles reliably.
+fn path_to_str(path: &Path) -> Result<Cow<'_, str>> {
     path.to_str()
+        .map(file_utils::to_plain_path)
         .ok_or_else(|| AppError::XmpRead("Invalid UTF-8 in file path".to_string()))
 }
 
@@ -56,7 +332,7 @@ fn open_xmp_for_read(path: &Path) -> Result<XmpFile> {
 
     xmp_file
         .open_file(
-            path_to_str(path)?,
+            &*path_to_str(path)?,
             OpenFileOptions::default().only_xmp().for_read(),
         )
         .map_err(|e| AppError::XmpRead(format!("Failed to open file: {}", e)))?;
@@ -64,14 +340,39 @@ fn open_xmp_for_read(path: &Path) -> Result<XmpFile> {
     Ok(xmp_file)
 }
 
+/// Checks that the file can be opened for writing.
+///
+/// Returns `AppError::FileReadOnly` for read-only files and permission errors,
+/// so callers can show an actionable message instead of a generic XMP failure.
+fn ensure_writable(path: &Path) -> Result<()> {
+    let metadata = std::fs::metadata(path)
+        .map_err(|e| AppError::XmpWrite(format!("Failed to read file metadata: {}", e)))?;
+    if metadata.permissions().readonly() {
+        return Err(AppError::FileReadOnly(path.display().to_string()));
+    }
+
+    match std::fs::OpenOptions::new().write(true).open(path) {
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+            Err(AppError::FileReadOnly(path.display().to_string()))
+        }
+        Err(e) => Err(AppError::XmpWrite(format!(
+            "Failed to open file for writing: {}",
+            e
+        ))),
+    }
+}
+
 /// Opens an XMP file for update.
 fn open_xmp_for_update(path: &Path) -> Result<XmpFile> {
+    ensure_writable(path)?;
+
     let mut xmp_file = XmpFile::new()
         .map_err(|e| AppError::XmpWrite(format!("Failed to create XmpFile: {}", e)))?;
 
     xmp_file
         .open_file(
-            path_to_str(path).map_err(|e| match e {
+            &*path_to_str(path).map_err(|e| match e {
                 AppError::XmpRead(msg) => AppError::XmpWrite(msg),
                 other => other,
             })?,
@@ -94,24 +395,176 @@ fn extract_rating_from_xmp(xmp_meta: XmpMeta) -> Option<u8> {
     }
 }
 
+/// Extracts the extended rating (0-10) from XMP metadata.
+///
+/// The custom property is used while it matches the standard Rating. When
+/// another application changed the standard Rating since, or the custom
+/// property is absent, the standard Rating doubled is used instead.
+fn extract_extended_rating_from_xmp(xmp_meta: XmpMeta) -> Option<u8> {
+    register_extended_namespace().ok()?;
+
+    let extended = xmp_meta
+        .property(EXTENDED_RATING_NAMESPACE, EXTENDED_RATING_PROPERTY)
+        .and_then(|prop| prop.value.parse::<u8>().ok())
+        .filter(|percent| *percent <= MAX_RATING_PERCENT)
+        .map(percent_to_extended_rating);
+
+    match (extended, extract_rating_from_xmp(xmp_meta)) {
+        (Some(extended), Some(standard)) if extended_rating_to_standard(extended) != standard => {
+            Some(standard * 2)
+        }
+        (Some(extended), _) => Some(extended),
+        (None, standard) => standard.map(|rating| rating * 2),
+    }
+}
+
+/// Extracts the rating from XMP metadata according to the given scale.
+fn extract_scaled_rating_from_xmp(xmp_meta: XmpMeta, scale: RatingScale) -> Option<u8> {
+    match scale {
+        RatingScale::Standard => extract_rating_from_xmp(xmp_meta),
+        RatingScale::Extended => extract_extended_rating_from_xmp(xmp_meta),
+    }
+}
+
+/// Extracts the `dc:subject` keywords from XMP metadata.
+///
+/// Empty entries are skipped; the original order is preserved.
+fn extract_keywords_from_xmp(xmp_meta: &XmpMeta) -> Vec<String> {
+    xmp_meta
+        .property_array(xmp_ns::DC, KEYWORDS_PROPERTY)
+        .map(|item| item.value.trim().to_string())
+        .filter(|keyword| !keyword.is_empty())
+        .collect()
+}
+
+/// Registers the custom namespace used for extended ratings.
+fn register_extended_namespace() -> Result<()> {
+    XmpMeta::register_namespace(EXTENDED_RATING_NAMESPACE, EXTENDED_RATING_PREFIX)
+        .map(|_| ())
+        .map_err(|e| AppError::XmpWrite(format!("Failed to register namespace: {}", e)))
+}
+
 /// Read XMP Rating from an image file.
 ///
 /// Returns `Ok(Some(rating))` if rating exists (0-5),
 /// `Ok(None)` if no rating is set,
 /// `Err` if reading fails.
 pub fn read_xmp_rating(path: &Path) -> Result<Option<u8>> {
-    let mut xmp_file = open_xmp_for_read(path)?;
-    let rating = xmp_file.xmp().and_then(extract_rating_from_xmp);
-    xmp_file.close();
-    Ok(rating)
+    with_utf8_path(path, false, |path| {
+        let mut xmp_file = open_xmp_for_read(path)?;
+        let rating = xmp_file.xmp().and_then(extract_rating_from_xmp);
+        xmp_file.close();
+        Ok(rating)
+    })
+}
+
+/// Read the extended rating (0-10) from an image file.
+///
+/// Uses the custom 0-100 property when present, otherwise the standard
+/// Rating mapped onto the 0-10 scale.
+pub fn read_xmp_rating_extended(path: &Path) -> Result<Option<u8>> {
+    with_utf8_path(path, false, |path| {
+        let mut xmp_file = open_xmp_for_read(path)?;
+        let rating = xmp_file.xmp().and_then(extract_extended_rating_from_xmp);
+        xmp_file.close();
+        Ok(rating)
+    })
+}
+
+/// Read the XMP `dc:subject` keywords from an image file.
+///
+/// The file is opened with the smart handler and without `only_xmp`, so legacy
+/// IPTC keywords (e.g. in JPEG/TIFF) are reconciled into `dc:subject` as well.
+/// Returns an empty list when no keywords are set.
+pub fn read_xmp_keywords(path: &Path) -> Result<Vec<String>> {
+    with_utf8_path(path, false, |path| {
+        let mut xmp_file = XmpFile::new()
+            .map_err(|e| AppError::XmpRead(format!("Failed to create XmpFile: {}", e)))?;
+
+        xmp_file
+            .open_file(
+                &*path_to_str(path)?,
+                OpenFileOptions::default().for_read().use_smart_handler(),
+            )
+            .map_err(|e| AppError::XmpRead(format!("Failed to open file: {}", e)))?;
+
+        let keywords = xmp_file
+            .xmp()
+            .map(|xmp_meta| extract_keywords_from_xmp(&xmp_meta))
+            .unwrap_or_default();
+        xmp_file.close();
+        Ok(keywords)
+    })
+}
+
+/// Read a rating from an image file using the given scale.
+///
+/// When the file has no embedded rating (or its XMP can't be read), the
+/// rating of its sidecar XMP file is used instead, see [`find_sidecar_xmp`].
+pub fn read_xmp_rating_scaled(path: &Path, scale: RatingScale) -> Result<Option<u8>> {
+    let embedded = match scale {
+        RatingScale::Standard => read_xmp_rating(path),
+        RatingScale::Extended => read_xmp_rating_extended(path),
+    };
+
+    match embedded {
+        Ok(Some(rating)) => Ok(Some(rating)),
+        // 埋め込みのレーティングがなければサイドカーXMPを参照する
+        embedded => match read_sidecar_rating(path, scale) {
+            Some(rating) => Ok(Some(rating)),
+            None => embedded,
+        },
+    }
+}
+
+/// Returns the sidecar XMP file of an image, if one exists.
+///
+/// `image.jpg.xmp` (darktable) is preferred over `image.xmp` (Lightroom, RawTherapee).
+pub fn find_sidecar_xmp(path: &Path) -> Option<PathBuf> {
+    let appended = SIDECAR_EXTENSIONS.iter().map(|ext| {
+        let mut name = path.as_os_str().to_owned();
+        name.push(".");
+        name.push(ext);
+        PathBuf::from(name)
+    });
+    // 拡張子がない場合、置き換えた名前は上の候補と同じになる
+    let replaced = SIDECAR_EXTENSIONS
+        .iter()
+        .filter(|_| path.extension().is_some())
+        .map(|ext| path.with_extension(ext));
+
+    appended
+        .chain(replaced)
+        .find(|candidate| candidate.is_file())
+}
+
+/// サイドカーXMPファイルからレーティングを読む。
+fn read_sidecar_rating(path: &Path, scale: RatingScale) -> Option<u8> {
+    let sidecar = find_sidecar_xmp(path)?;
+    let xmp_rdf = std::fs::read_to_string(&sidecar)
+        .map_err(|e| log::warn!("Failed to read sidecar XMP {:?}: {}", sidecar, e))
+        .ok()?;
+    parse_xmp_rating_from_rdf(&xmp_rdf, scale)
+}
+
+/// Returns whether ratings can be written to the file based on its extension.
+///
+/// Formats such as GIF and BMP have no reliable embedded XMP support,
+/// so rating writes are rejected up front for them.
+pub fn supports_rating_write(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| crate::config::RATING_WRITABLE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
 }
 
 /// Validates the rating value.
-fn validate_rating(rating: u8) -> Result<()> {
-    if rating > MAX_RATING {
+fn validate_rating(rating: u8, scale: RatingScale) -> Result<()> {
+    let max_rating = scale.max_rating();
+    if rating > max_rating {
         Err(AppError::XmpWrite(format!(
             "Rating must be 0-{}, got {}",
-            MAX_RATING, rating
+            max_rating, rating
         )))
     } else {
         Ok(())
@@ -147,15 +600,124 @@ fn write_xmp_to_file(xmp_file: &mut XmpFile, xmp_meta: &XmpMeta) -> Result<()> {
 /// Rating must be in range 0-5.
 /// Returns `Err` if writing fails or rating is out of range.
 pub fn write_xmp_rating(path: &Path, rating: u8) -> Result<()> {
-    validate_rating(rating)?;
+    validate_rating(rating, RatingScale::Standard)?;
+
+    with_utf8_path(path, true, |path| {
+        let mut xmp_file = open_xmp_for_update(path)?;
+        let mut xmp_meta = get_or_create_xmp_meta(&mut xmp_file)?;
+        set_rating_property(&mut xmp_meta, rating)?;
+        write_xmp_to_file(&mut xmp_file, &xmp_meta)?;
+        xmp_file.close();
+
+        Ok(())
+    })
+}
+
+/// Validates and normalizes a keyword before writing.
+fn normalize_keyword(keyword: &str) -> Result<&str> {
+    let keyword = keyword.trim();
+    if keyword.is_empty() {
+        Err(AppError::XmpWrite("Keyword must not be empty".to_string()))
+    } else {
+        Ok(keyword)
+    }
+}
+
+/// Add a keyword to the XMP `dc:subject` bag of an image file.
+///
+/// Creates the bag when the file has none. Keywords that already exist
+/// (compared case-sensitively after trimming) are not added again.
+/// Returns the keywords after the update.
+pub fn add_xmp_keyword(path: &Path, keyword: &str) -> Result<Vec<String>> {
+    let keyword = normalize_keyword(keyword)?;
+
+    with_utf8_path(path, true, |path| {
+        let mut xmp_file = open_xmp_for_update(path)?;
+        let mut xmp_meta = get_or_create_xmp_meta(&mut xmp_file)?;
+        let mut keywords = extract_keywords_from_xmp(&xmp_meta);
+
+        if !keywords.iter().any(|existing| existing == keyword) {
+            let array_name = XmpValue::new(KEYWORDS_PROPERTY.to_string()).set_is_array(true);
+            xmp_meta
+                .append_array_item(xmp_ns::DC, &array_name, &XmpValue::new(keyword.to_string()))
+                .map_err(|e| AppError::XmpWrite(format!("Failed to add keyword: {}", e)))?;
+            write_xmp_to_file(&mut xmp_file, &xmp_meta)?;
+            keywords.push(keyword.to_string());
+        }
+        xmp_file.close();
+
+        Ok(keywords)
+    })
+}
+
+/// Remove a keyword from the XMP `dc:subject` bag of an image file.
+///
+/// Does nothing if the keyword is not present.
+/// Returns the keywords after the update.
+pub fn remove_xmp_keyword(path: &Path, keyword: &str) -> Result<Vec<String>> {
+    let keyword = normalize_keyword(keyword)?;
+
+    with_utf8_path(path, true, |path| {
+        let mut xmp_file = open_xmp_for_update(path)?;
+        let mut xmp_meta = get_or_create_xmp_meta(&mut xmp_file)?;
+
+        // XMP array indices are 1-based
+        let indices: Vec<i32> = xmp_meta
+            .property_array(xmp_ns::DC, KEYWORDS_PROPERTY)
+            .enumerate()
+            .filter(|(_, item)| item.value.trim() == keyword)
+            .map(|(i, _)| i as i32 + 1)
+            .collect();
+
+        if !indices.is_empty() {
+            // Delete from the back so earlier indices stay valid
+            for index in indices.into_iter().rev() {
+                xmp_meta
+                    .delete_array_item(xmp_ns::DC, KEYWORDS_PROPERTY, index)
+                    .map_err(|e| AppError::XmpWrite(format!("Failed to remove keyword: {}", e)))?;
+            }
+            write_xmp_to_file(&mut xmp_file, &xmp_meta)?;
+        }
+        let keywords = extract_keywords_from_xmp(&xmp_meta);
+        xmp_file.close();
+
+        Ok(keywords)
+    })
+}
 
-    let mut xmp_file = open_xmp_for_update(path)?;
-    let mut xmp_meta = get_or_create_xmp_meta(&mut xmp_file)?;
-    set_rating_property(&mut xmp_meta, rating)?;
-    write_xmp_to_file(&mut xmp_file, &xmp_meta)?;
-    xmp_file.close();
+/// Write an extended rating (0-10) to an image file.
+///
+/// Stores the value as 0-100 in a custom property and also writes the
+/// standard 0-5 Rating so other applications still see a rating.
+pub fn write_xmp_rating_extended(path: &Path, rating: u8) -> Result<()> {
+    validate_rating(rating, RatingScale::Extended)?;
+    register_extended_namespace()?;
+
+    with_utf8_path(path, true, |path| {
+        let mut xmp_file = open_xmp_for_update(path)?;
+        let mut xmp_meta = get_or_create_xmp_meta(&mut xmp_file)?;
+        let percent_value = XmpValue::new(extended_rating_to_percent(rating).to_string());
+        xmp_meta
+            .set_property(
+                EXTENDED_RATING_NAMESPACE,
+                EXTENDED_RATING_PROPERTY,
+                &percent_value,
+            )
+            .map_err(|e| AppError::XmpWrite(format!("Failed to set RatingPercent: {}", e)))?;
+        set_rating_property(&mut xmp_meta, extended_rating_to_standard(rating))?;
+        write_xmp_to_file(&mut xmp_file, &xmp_meta)?;
+        xmp_file.close();
 
-    Ok(())
+        Ok(())
+    })
+}
+
+/// Write a rating to an image file using the given scale.
+pub fn write_xmp_rating_scaled(path: &Path, rating: u8, scale: RatingScale) -> Result<()> {
+    match scale {
+        RatingScale::Standard => write_xmp_rating(path, rating),
+        RatingScale::Extended => write_xmp_rating_extended(path, rating),
+    }
 }
 
 impl SdParameters {
@@ -206,6 +768,7 @@ impl SdParameters {
         Option<String>, // seed
         Option<String>, // size
         Option<String>, // model
+        Option<String>, // model_hash
         Option<String>, // denoising_strength
         Option<String>, // clip_skip
     ) {
@@ -216,6 +779,7 @@ impl SdParameters {
         let mut seed = None;
         let mut size = None;
         let mut model = None;
+        let mut model_hash = None;
         let mut denoising_strength = None;
         let mut clip_skip = None;
 
@@ -237,6 +801,7 @@ impl SdParameters {
                     "Seed" => seed = Some(value.to_string()),
                     "Size" => size = Some(value.to_string()),
                     "Model" => model = Some(value.to_string()),
+                    "Model hash" => model_hash = Some(value.to_string()),
                     "Denoising strength" => denoising_strength = Some(value.to_string()),
                     "Clip skip" => clip_skip = Some(value.to_string()),
                     _ => {}
@@ -252,6 +817,7 @@ impl SdParameters {
             seed,
             size,
             model,
+            model_hash,
             denoising_strength,
             clip_skip,
         )
@@ -264,22 +830,19 @@ impl SdParameters {
         }
 
         // "Negative prompt:" で分割
-        let pp_separated: Vec<&str> = parameter.splitn(2, "\nNegative prompt:").collect();
-        if pp_separated.len() != 2 {
-            return Err(AppError::MetadataRead(
-                "\"Negative prompt:\" section not found".to_string(),
-            ));
-        }
+        let (positive, rest) = Self::split_negative_prompt(parameter).ok_or_else(|| {
+            AppError::MetadataRead("\"Negative prompt:\" section not found".to_string())
+        })?;
 
         // "Steps:" で分割
-        let np_separated: Vec<&str> = pp_separated[1].splitn(2, "\nSteps:").collect();
+        let np_separated: Vec<&str> = rest.splitn(2, "\nSteps:").collect();
         if np_separated.len() != 2 {
             return Err(AppError::MetadataRead(
                 "\"Steps:\" section not found".to_string(),
             ));
         }
 
-        let positive_sd_tags = Self::parse_sd_tags(pp_separated[0]);
+        let positive_sd_tags = Self::parse_sd_tags(positive);
         let negative_sd_tags = Self::parse_sd_tags(np_separated[0]);
 
         // フィールド部分から必要な値を一括抽出
@@ -292,6 +855,7 @@ impl SdParameters {
             seed,
             size,
             model,
+            model_hash,
             denoising_strength,
             clip_skip,
         ) = Self::extract_all_fields(fields_section);
@@ -306,21 +870,412 @@ impl SdParameters {
             seed,
             size,
             model,
+            model_hash,
             denoising_strength,
             clip_skip,
+            source: SdSource::Automatic1111,
             raw: parameter.to_string(),
+            ..Default::default()
+        })
+    }
+
+    /// "Negative prompt:" の区切りでポジティブプロンプトと残りに分ける
+    ///
+    /// 改行直後の区切りを優先する。なければ文字列先頭か空白の直後にある最後の区切りを使い、
+    /// "xNegative prompt:" のような単語の一部は区切りとみなさない。
+    fn split_negative_prompt(parameter: &str) -> Option<(&str, &str)> {
+        const MARKER: &str = "Negative prompt:";
+
+        if let Some(split) = parameter.split_once(&format!("\n{}", MARKER)) {
+            return Some(split);
+        }
+
+        let found = NEGATIVE_PROMPT_REGEX.find_iter(parameter).last()?;
+        let marker_start = found.end() - MARKER.len();
+        Some((&parameter[..marker_start], &parameter[found.end()..]))
+    }
+
+    /// NovelAI形式のJSONをパースする
+    ///
+    /// Accepts either the outer chunk map (`{"Comment": "{...}", ...}`) or the
+    /// inner generation settings object directly.
+    pub fn parse_novelai_json(json: &str) -> Result<SdParameters> {
+        let value: serde_json::Value = serde_json::from_str(json)
+            .map_err(|e| AppError::MetadataRead(format!("Invalid NovelAI JSON: {}", e)))?;
+
+        // Commentフィールドに設定JSONが文字列として埋め込まれている場合は展開する
+        let settings = match value.get("Comment").and_then(|c| c.as_str()) {
+            Some(comment) => serde_json::from_str::<serde_json::Value>(comment).map_err(|e| {
+                AppError::MetadataRead(format!("Invalid NovelAI Comment JSON: {}", e))
+            })?,
+            None => value.clone(),
+        };
+
+        let prompt = settings
+            .get("prompt")
+            .and_then(|p| p.as_str())
+            .or_else(|| value.get("Description").and_then(|d| d.as_str()))
+            .ok_or_else(|| AppError::MetadataRead("NovelAI prompt not found".to_string()))?;
+        let negative = settings.get("uc").and_then(|uc| uc.as_str()).unwrap_or("");
+
+        let field = |key: &str| -> Option<String> {
+            match settings.get(key)? {
+                serde_json::Value::String(s) if !s.is_empty() => Some(s.clone()),
+                serde_json::Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            }
+        };
+
+        let size = match (field("width"), field("height")) {
+            (Some(width), Some(height)) => Some(format!("{}x{}", width, height)),
+            _ => None,
+        };
+
+        Ok(SdParameters {
+            positive_sd_tags: Self::parse_sd_tags(prompt),
+            negative_sd_tags: Self::parse_sd_tags(negative),
+            steps: field("steps"),
+            sampler: field("sampler"),
+            cfg_scale: field("scale"),
+            seed: field("seed"),
+            size,
+            source: SdSource::NovelAI,
+            raw: json.to_string(),
+            ..Default::default()
+        })
+    }
+
+    /// Fooocus形式のJSONをパースする
+    ///
+    /// Accepts both the current key names (`sampler`, `base_model`) and the
+    /// older log scheme (`sampler_name`, `base_model_name`).
+    pub fn parse_fooocus_json(json: &str) -> Result<SdParameters> {
+        let value: serde_json::Value = serde_json::from_str(json)
+            .map_err(|e| AppError::MetadataRead(format!("Invalid Fooocus JSON: {}", e)))?;
+
+        // NovelAIなど他ツールのJSONと区別するため、Fooocus固有のキーを確認する
+        let is_fooocus = [
+            "negative_prompt",
+            "guidance_scale",
+            "base_model",
+            "base_model_name",
+        ]
+        .iter()
+        .any(|key| value.get(key).is_some());
+        if !is_fooocus {
+            return Err(AppError::MetadataRead(
+                "Not a Fooocus metadata JSON".to_string(),
+            ));
+        }
+
+        let prompt = value
+            .get("prompt")
+            .and_then(|p| p.as_str())
+            .ok_or_else(|| AppError::MetadataRead("Fooocus prompt not found".to_string()))?;
+        let negative = value
+            .get("negative_prompt")
+            .and_then(|n| n.as_str())
+            .unwrap_or("");
+
+        let field = |keys: &[&str]| -> Option<String> {
+            keys.iter().find_map(|key| match value.get(*key)? {
+                serde_json::Value::String(s) if !s.is_empty() => Some(s.clone()),
+                serde_json::Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+        };
+
+        // resolution は "(1024, 1024)" 形式の文字列
+        let size = field(&["resolution"]).map(|resolution| {
+            resolution
+                .trim_matches(|c| c == '(' || c == ')')
+                .split(',')
+                .map(str::trim)
+                .collect::<Vec<_>>()
+                .join("x")
+        });
+
+        Ok(SdParameters {
+            positive_sd_tags: Self::parse_sd_tags(prompt),
+            negative_sd_tags: Self::parse_sd_tags(negative),
+            steps: field(&["steps"]),
+            sampler: field(&["sampler", "sampler_name"]),
+            schedule_type: field(&["scheduler"]),
+            cfg_scale: field(&["guidance_scale"]),
+            seed: field(&["seed"]),
+            size,
+            model: field(&["base_model", "base_model_name"]),
+            source: SdSource::Fooocus,
+            raw: json.to_string(),
+            ..Default::default()
+        })
+    }
+
+    /// ComfyUIの "prompt" JSON（ノードIDをキーとしたAPI形式）をパースする
+    ///
+    /// Starts from the sampler node and follows its input links: `positive` /
+    /// `negative` to the text encoders, `latent_image` to the size and `model`
+    /// back through LoRA loaders to the checkpoint loader. Loader nodes that are
+    /// not linked from a sampler are still picked up by their node type.
+    pub fn parse_comfyui_prompt(json: &str) -> Result<SdParameters> {
+        let value: serde_json::Value = serde_json::from_str(json)
+            .map_err(|e| AppError::MetadataRead(format!("Invalid ComfyUI JSON: {}", e)))?;
+        let nodes = value
+            .as_object()
+            .ok_or_else(|| AppError::MetadataRead("ComfyUI prompt is not an object".to_string()))?;
+
+        let sampler = nodes
+            .values()
+            .find(|node| COMFYUI_SAMPLER_NODES.contains(&comfyui_class_type(node)))
+            .ok_or_else(|| AppError::MetadataRead("ComfyUI sampler node not found".to_string()))?;
+
+        let positive = comfyui_resolve_text(nodes, comfyui_input(sampler, "positive"), 0);
+        let negative = comfyui_resolve_text(nodes, comfyui_input(sampler, "negative"), 0);
+
+        let size = comfyui_linked_node(nodes, comfyui_input(sampler, "latent_image"))
+            .or_else(|| comfyui_find_node(nodes, &["EmptyLatentImage"]))
+            .and_then(|latent| {
+                let width = comfyui_literal(comfyui_input(latent, "width"))?;
+                let height = comfyui_literal(comfyui_input(latent, "height"))?;
+                Some(format!("{}x{}", width, height))
+            });
+
+        // model入力をLoRAローダー経由でチェックポイントまで辿る
+        let mut model = None;
+        let mut loras = Vec::new();
+        let mut current = comfyui_linked_node(nodes, comfyui_input(sampler, "model"));
+        for _ in 0..COMFYUI_MAX_LINK_DEPTH {
+            let Some(node) = current else {
+                break;
+            };
+            let class_type = comfyui_class_type(node);
+            if COMFYUI_LORA_NODES.contains(&class_type) {
+                loras.extend(comfyui_lora(node));
+            } else if COMFYUI_CHECKPOINT_NODES.contains(&class_type) {
+                model = comfyui_checkpoint_name(node);
+                break;
+            }
+            current = comfyui_linked_node(nodes, comfyui_input(node, "model"));
+        }
+
+        // サンプラーから辿れない場合はノード種別で探す
+        if model.is_none() {
+            model = comfyui_find_node(nodes, COMFYUI_CHECKPOINT_NODES)
+                .and_then(comfyui_checkpoint_name);
+        }
+        if loras.is_empty() {
+            loras = nodes
+                .values()
+                .filter(|node| COMFYUI_LORA_NODES.contains(&comfyui_class_type(node)))
+                .filter_map(comfyui_lora)
+                .collect();
+        }
+        let vae = comfyui_find_node(nodes, &["VAELoader"])
+            .and_then(|node| comfyui_literal(comfyui_input(node, "vae_name")));
+
+        let literal = |keys: &[&str]| {
+            keys.iter()
+                .find_map(|key| comfyui_literal(comfyui_input(sampler, key)))
+        };
+
+        Ok(SdParameters {
+            positive_sd_tags: Self::parse_sd_tags(positive.as_deref().unwrap_or("")),
+            negative_sd_tags: Self::parse_sd_tags(negative.as_deref().unwrap_or("")),
+            steps: literal(&["steps"]),
+            sampler: literal(&["sampler_name"]),
+            schedule_type: literal(&["scheduler"]),
+            cfg_scale: literal(&["cfg"]),
+            seed: literal(&["seed", "noise_seed"]),
+            size,
+            model,
+            denoising_strength: literal(&["denoise"]),
+            vae,
+            loras,
+            source: SdSource::ComfyUI,
+            raw: json.to_string(),
+            ..Default::default()
+        })
+    }
+}
+
+/// Compares the SD parameters of two images, `a` being the earlier one.
+///
+/// Tags are matched by name, so reordering a prompt is not reported.
+/// Generation settings are compared as text; LoRAs are compared as one list.
+pub fn diff_prompts(a: &SdParameters, b: &SdParameters) -> PromptDiff {
+    let fields = comparable_fields(a)
+        .into_iter()
+        .zip(comparable_fields(b))
+        .filter(|((_, before), (_, after))| before != after)
+        .map(|((field, before), (_, after))| FieldChange {
+            field,
+            before,
+            after,
         })
+        .collect();
+
+    PromptDiff {
+        positive: diff_tags(&a.positive_sd_tags, &b.positive_sd_tags),
+        negative: diff_tags(&a.negative_sd_tags, &b.negative_sd_tags),
+        fields,
+    }
+}
+
+/// タグ名で突き合わせて追加・削除・重み変更を求める
+///
+/// 同名タグが重複している場合は最初の一件だけを比較する
+fn diff_tags(before: &[SdTag], after: &[SdTag]) -> TagDiff {
+    let before = unique_by_name(before);
+    let after = unique_by_name(after);
+    fn find<'a>(tags: &[&'a SdTag], name: &str) -> Option<&'a SdTag> {
+        tags.iter().find(|t| t.name == name).copied()
+    }
+
+    let mut diff = TagDiff::default();
+    for &tag in &after {
+        match find(&before, &tag.name) {
+            None => diff.added.push(tag.clone()),
+            Some(old) if old.weight != tag.weight => diff.reweighted.push(TagWeightChange {
+                name: tag.name.clone(),
+                before: old.weight,
+                after: tag.weight,
+            }),
+            Some(_) => {}
+        }
+    }
+    diff.removed = before
+        .iter()
+        .filter(|tag| find(&after, &tag.name).is_none())
+        .map(|&tag| tag.clone())
+        .collect();
+    diff
+}
+
+/// Returns the tags without repeated names, keeping the first occurrence.
+fn unique_by_name(tags: &[SdTag]) -> Vec<&SdTag> {
+    let mut seen = std::collections::HashSet::new();
+    tags.iter()
+        .filter(|tag| seen.insert(tag.name.as_str()))
+        .collect()
+}
+
+/// Generation settings compared by [`diff_prompts`], in display order.
+fn comparable_fields(params: &SdParameters) -> [(&'static str, Option<String>); 12] {
+    [
+        ("Steps", params.steps.clone()),
+        ("Sampler", params.sampler.clone()),
+        ("Schedule type", params.schedule_type.clone()),
+        ("CFG scale", params.cfg_scale.clone()),
+        ("Seed", params.seed.clone()),
+        ("Size", params.size.clone()),
+        ("Model", params.model.clone()),
+        ("Model hash", params.model_hash.clone()),
+        ("Denoising strength", params.denoising_strength.clone()),
+        ("Clip skip", params.clip_skip.clone()),
+        ("VAE", params.vae.clone()),
+        (
+            "LoRA",
+            (!params.loras.is_empty()).then(|| params.loras.join(", ")),
+        ),
+    ]
+}
+
+/// Returns the `class_type` of a ComfyUI node, or an empty string.
+fn comfyui_class_type(node: &serde_json::Value) -> &str {
+    node.get("class_type")
+        .and_then(|c| c.as_str())
+        .unwrap_or("")
+}
+
+/// Returns an input of a ComfyUI node, which is either a literal or a link.
+fn comfyui_input<'a>(node: &'a serde_json::Value, name: &str) -> Option<&'a serde_json::Value> {
+    node.get("inputs")?.get(name)
+}
+
+/// Resolves a link input (`["<node id>", <output index>]`) to the linked node.
+fn comfyui_linked_node<'a>(
+    nodes: &'a serde_json::Map<String, serde_json::Value>,
+    input: Option<&serde_json::Value>,
+) -> Option<&'a serde_json::Value> {
+    let link = input?.as_array()?;
+    let node_id = match link.first()? {
+        serde_json::Value::String(id) => id.clone(),
+        serde_json::Value::Number(id) => id.to_string(),
+        _ => return None,
+    };
+    nodes.get(&node_id)
+}
+
+/// Returns a literal (non-link) input value as a string.
+fn comfyui_literal(input: Option<&serde_json::Value>) -> Option<String> {
+    match input? {
+        serde_json::Value::String(s) if !s.is_empty() => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Finds the first node of one of the given types.
+fn comfyui_find_node<'a>(
+    nodes: &'a serde_json::Map<String, serde_json::Value>,
+    class_types: &[&str],
+) -> Option<&'a serde_json::Value> {
+    nodes
+        .values()
+        .find(|node| class_types.contains(&comfyui_class_type(node)))
+}
+
+/// Returns the model file name of a checkpoint loader node.
+fn comfyui_checkpoint_name(node: &serde_json::Value) -> Option<String> {
+    comfyui_literal(comfyui_input(node, "ckpt_name"))
+        .or_else(|| comfyui_literal(comfyui_input(node, "unet_name")))
+}
+
+/// Returns `name:strength` for a LoRA loader node.
+fn comfyui_lora(node: &serde_json::Value) -> Option<String> {
+    let name = comfyui_literal(comfyui_input(node, "lora_name"))?;
+    match comfyui_literal(comfyui_input(node, "strength_model")) {
+        Some(strength) => Some(format!("{}:{}", name, strength)),
+        None => Some(name),
     }
 }
 
+/// Resolves a prompt text input, following links through text encoder and primitive nodes.
+fn comfyui_resolve_text(
+    nodes: &serde_json::Map<String, serde_json::Value>,
+    input: Option<&serde_json::Value>,
+    depth: usize,
+) -> Option<String> {
+    if depth >= COMFYUI_MAX_LINK_DEPTH {
+        return None;
+    }
+    if let Some(text) = input?.as_str() {
+        return Some(text.to_string());
+    }
+
+    let node = comfyui_linked_node(nodes, input)?;
+    ["text", "text_g", "value", "string"]
+        .iter()
+        .find_map(|name| comfyui_resolve_text(nodes, comfyui_input(node, name), depth + 1))
+}
+
 /// Parses XMP RDF string and extracts rating.
 ///
-/// Returns `Some(rating)` if rating exists and is valid (0-5),
+/// Returns `Some(rating)` if rating exists and is valid for the scale,
 /// `None` if rating doesn't exist or is invalid.
-pub fn parse_xmp_rating_from_rdf(xmp_rdf: &str) -> Option<u8> {
+pub fn parse_xmp_rating_from_rdf(xmp_rdf: &str, scale: RatingScale) -> Option<u8> {
     XmpMeta::from_str_with_options(xmp_rdf, Default::default())
         .ok()
-        .and_then(extract_rating_from_xmp)
+        .and_then(|xmp_meta| extract_scaled_rating_from_xmp(xmp_meta, scale))
+}
+
+/// Parses XMP RDF string and extracts the `dc:subject` keywords.
+///
+/// Returns an empty list if the RDF can't be parsed or has no keywords.
+pub fn parse_xmp_keywords_from_rdf(xmp_rdf: &str) -> Vec<String> {
+    XmpMeta::from_str_with_options(xmp_rdf, Default::default())
+        .map(|xmp_meta| extract_keywords_from_xmp(&xmp_meta))
+        .unwrap_or_default()
 }
 
 /// Extracts XMP RDF string from PNG Info's iTXt chunks.
@@ -345,14 +1300,950 @@ pub fn extract_xmp_rdf_from_info(info: &png::Info) -> Result<Option<String>> {
     Ok(None)
 }
 
-/// Extracts SD parameters string from PNG Info's tEXt chunks.
+/// Extracts the XMP packet from a WebP file's `XMP ` RIFF chunk.
 ///
-/// Searches for "parameters" keyword in tEXt chunks.
-pub fn extract_sd_parameters_from_info(info: &png::Info) -> Result<Option<String>> {
+/// Walks the RIFF chunk list directly instead of going through xmp_toolkit.
+/// Returns `None` if the data is not a WebP file or has no XMP chunk.
+pub fn extract_xmp_from_webp(bytes: &[u8]) -> Option<String> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WEBP" {
+        return None;
+    }
+
+    let mut offset = 12;
+    while offset + 8 <= bytes.len() {
+        let fourcc = &bytes[offset..offset + 4];
+        let size = u32::from_le_bytes(bytes[offset + 4..offset + 8].try_into().ok()?) as usize;
+        let data_start = offset + 8;
+        let data_end = data_start.checked_add(size)?;
+        if data_end > bytes.len() {
+            return None;
+        }
+
+        if fourcc == b"XMP " {
+            return Some(String::from_utf8_lossy(&bytes[data_start..data_end]).into_owned());
+        }
+
+        // チャンクは偶数バイト境界にパディングされる
+        offset = data_end + (size & 1);
+    }
+    None
+}
+
+/// Finds a text chunk by keyword in PNG Info's tEXt, zTXt and iTXt chunks.
+///
+/// tEXt chunks are searched first, so an uncompressed value takes precedence.
+/// iTXt is searched last, for text that is not representable in Latin-1.
+fn find_text(info: &png::Info, keyword: &str) -> Result<Option<String>> {
     for chunk in &info.uncompressed_latin1_text {
-        if chunk.keyword == "parameters" {
+        if chunk.keyword == keyword {
             return Ok(Some(chunk.text.clone()));
         }
     }
+
+    for chunk in &info.compressed_latin1_text {
+        if chunk.keyword == keyword {
+            let text = chunk.get_text().map_err(|e| {
+                AppError::MetadataRead(format!("Failed to decompress zTXt text: {}", e))
+            })?;
+            return Ok(Some(text));
+        }
+    }
+
+    for chunk in &info.utf8_text {
+        if chunk.keyword == keyword {
+            let text = chunk.get_text().map_err(|e| {
+                AppError::MetadataRead(format!("Failed to decode iTXt text: {}", e))
+            })?;
+            return Ok(Some(text));
+        }
+    }
     Ok(None)
 }
+
+/// Finds all text chunks with the keyword and joins them in file order.
+///
+/// Only chunks of the first type that has the keyword are joined, searched
+/// in the same order as [`find_text`]. Chunk types are stored separately in
+/// PNG Info, so their relative order in the file is not known.
+fn find_joined_text(info: &png::Info, keyword: &str) -> Result<Option<String>> {
+    let uncompressed: Vec<&str> = info
+        .uncompressed_latin1_text
+        .iter()
+        .filter(|chunk| chunk.keyword == keyword)
+        .map(|chunk| chunk.text.as_str())
+        .collect();
+    if !uncompressed.is_empty() {
+        return Ok(Some(uncompressed.concat()));
+    }
+
+    let compressed = info
+        .compressed_latin1_text
+        .iter()
+        .filter(|chunk| chunk.keyword == keyword)
+        .map(|chunk| {
+            chunk.get_text().map_err(|e| {
+                AppError::MetadataRead(format!("Failed to decompress zTXt text: {}", e))
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if !compressed.is_empty() {
+        return Ok(Some(compressed.concat()));
+    }
+
+    let utf8 = info
+        .utf8_text
+        .iter()
+        .filter(|chunk| chunk.keyword == keyword)
+        .map(|chunk| {
+            chunk
+                .get_text()
+                .map_err(|e| AppError::MetadataRead(format!("Failed to decode iTXt text: {}", e)))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok((!utf8.is_empty()).then(|| utf8.concat()))
+}
+
+/// Extracts SD parameters string from PNG Info's text chunks.
+///
+/// Searches for "parameters" keyword in tEXt chunks first, then in compressed
+/// zTXt chunks and finally in iTXt chunks, so an uncompressed value takes precedence.
+/// Long prompts split over several "parameters" chunks are joined in file order.
+pub fn extract_sd_parameters_from_info(info: &png::Info) -> Result<Option<String>> {
+    find_joined_text(info, PARAMETERS_KEYWORD)
+}
+
+/// Extracts the "Comment" text chunk from PNG Info, as written by Fooocus.
+pub fn extract_comment_from_info(info: &png::Info) -> Result<Option<String>> {
+    find_text(info, "Comment")
+}
+
+/// Reassembles A1111-style parameters text from prompts split across text chunks.
+///
+/// Some exporters write no "parameters" chunk but spread its parts over several:
+/// - "Description" holds the positive prompt
+/// - "Comment" holds the rest (negative prompt and generation fields), or all
+///   of it when there is no "Description"
+/// - "Negative prompt" holds the negative prompt, used only if the text above has none
+///
+/// JSON in "Comment" (NovelAI, Fooocus) is left to the JSON parsers.
+/// Returns `None` if neither "Description" nor a text "Comment" exists.
+pub fn assemble_split_parameters(info: &png::Info) -> Option<String> {
+    const NEGATIVE_MARKER: &str = "Negative prompt:";
+
+    let text = |keyword: &str| find_text(info, keyword).ok().flatten();
+    let comment = text("Comment").filter(|comment| !comment.trim_start().starts_with('{'));
+
+    let mut assembled = match (text("Description"), comment) {
+        (Some(description), Some(comment)) => {
+            format!("{}\n{}", description.trim_end(), comment.trim())
+        }
+        (Some(description), None) => description.trim_end().to_string(),
+        (None, Some(comment)) => comment.trim().to_string(),
+        (None, None) => return None,
+    };
+
+    // 後続のパースは "Negative prompt:" の行を必須とするため、空でも "Steps:" 行の前に補う
+    if !assembled.contains(NEGATIVE_MARKER) {
+        let negative = text("Negative prompt").unwrap_or_default();
+        let line = format!("\n{} {}", NEGATIVE_MARKER, negative.trim());
+        match assembled.find("\nSteps:") {
+            Some(at) => assembled.insert_str(at, &line),
+            None => assembled.push_str(&line),
+        }
+    }
+    Some(assembled)
+}
+
+/// Parses SD parameters from PNG text chunks.
+///
+/// Tries A1111 text in "parameters" first, then Fooocus JSON in
+/// "parameters" or "Comment", then A1111 text split across "Description",
+/// "Comment" and "Negative prompt" (only without "parameters"), then the
+/// ComfyUI "prompt" JSON.
+///
+/// Returns `Ok(None)` when there is no parameters text, and the raw text
+/// with the reason when "parameters" or "prompt" exists but cannot be parsed.
+pub fn parse_sd_parameters_from_info(
+    info: &png::Info,
+) -> std::result::Result<Option<SdParameters>, SdParseFailure> {
+    let parameters = extract_sd_parameters_from_info(info).ok().flatten();
+    let mut failure = None;
+
+    if let Some(text) = parameters.as_deref() {
+        match SdParameters::parse(text) {
+            Ok(parsed) => return Ok(Some(parsed)),
+            Err(e) => match SdParameters::parse_fooocus_json(text) {
+                Ok(parsed) => return Ok(Some(parsed)),
+                // A1111形式として読めなかった理由を残す
+                Err(_) => failure = Some(SdParseFailure::new(text, &e)),
+            },
+        }
+    }
+
+    // Commentは任意のテキストもあり得るため、失敗は記録しない
+    if let Some(parsed) = extract_comment_from_info(info)
+        .ok()
+        .flatten()
+        .and_then(|comment| SdParameters::parse_fooocus_json(&comment).ok())
+    {
+        return Ok(Some(parsed));
+    }
+
+    // 分割されたチャンクも任意のテキストであり得るため、失敗は記録しない
+    if parameters.is_none()
+        && let Some(parsed) = assemble_split_parameters(info)
+            .and_then(|assembled| SdParameters::parse(&assembled).ok())
+    {
+        return Ok(Some(parsed));
+    }
+
+    if let Some(prompt) = find_text(info, "prompt").ok().flatten() {
+        match SdParameters::parse_comfyui_prompt(&prompt) {
+            Ok(parsed) => return Ok(Some(parsed)),
+            Err(e) => {
+                failure.get_or_insert_with(|| SdParseFailure::new(&prompt, &e));
+            }
+        }
+    }
+
+    failure.map_or(Ok(None), Err)
+}
+
+/// Reads the SD parameters of a PNG file without decoding its pixels.
+///
+/// Text chunks after the image data are read too. Returns `Ok(None)` when the
+/// file has no parameters or they cannot be parsed.
+pub fn read_png_sd_parameters(path: &Path) -> Result<Option<SdParameters>> {
+    let file = std::io::BufReader::new(std::fs::File::open(path)?);
+    let mut reader = png::Decoder::new(file).read_info()?;
+    // 画素は展開せず、IDAT後のテキストチャンクまで読み進める
+    reader.finish()?;
+    Ok(parse_sd_parameters_from_info(reader.info()).ok().flatten())
+}
+
+/// Extracts a "stealth pnginfo" payload hidden in the alpha channel's LSBs.
+///
+/// Bits are read column by column (x outer, y inner), as written by NovelAI and
+/// the stealth-pnginfo extension. The payload starts with a 15-byte signature,
+/// followed by a 32-bit big-endian bit length and the data itself, which is
+/// gzip-compressed for the `stealth_pngcomp` variant.
+///
+/// Returns `None` if the buffer does not carry a valid stealth payload.
+pub fn extract_stealth_metadata(rgba: &[u8], width: u32, height: u32) -> Option<String> {
+    let (width, height) = (width as usize, height as usize);
+    if width == 0 || height == 0 || rgba.len() != width * height * 4 {
+        return None;
+    }
+
+    // アルファ値の最下位ビットを列優先で読み出す
+    let mut bits = (0..width)
+        .flat_map(move |x| (0..height).map(move |y| (y * width + x) * 4 + 3))
+        .map(|index| rgba[index] & 1);
+
+    let signature = read_stealth_bytes(&mut bits, STEALTH_SIGNATURE_PLAIN.len())?;
+    let compressed = if signature == STEALTH_SIGNATURE_PLAIN {
+        false
+    } else if signature == STEALTH_SIGNATURE_COMPRESSED {
+        true
+    } else {
+        return None;
+    };
+
+    let length_bytes = read_stealth_bytes(&mut bits, 4)?;
+    let bit_length = u32::from_be_bytes(length_bytes.try_into().ok()?) as usize;
+    let payload = read_stealth_bytes(&mut bits, bit_length / 8)?;
+
+    if compressed {
+        let mut text = String::new();
+        flate2::read::GzDecoder::new(payload.as_slice())
+            .read_to_string(&mut text)
+            .ok()?;
+        Some(text)
+    } else {
+        String::from_utf8(payload).ok()
+    }
+}
+
+/// Reads `count` bytes (MSB first) from a stream of single bits.
+fn read_stealth_bytes(bits: &mut impl Iterator<Item = u8>, count: usize) -> Option<Vec<u8>> {
+    (0..count)
+        .map(|_| (0..8).try_fold(0u8, |byte, _| bits.next().map(|bit| (byte << 1) | bit)))
+        .collect()
+}
+
+/// Parses a stealth pnginfo payload into SD parameters.
+///
+/// NovelAI payloads are JSON; others are expected to be A1111-style text.
+pub fn parse_stealth_payload(payload: &str) -> Option<SdParameters> {
+    if payload.trim_start().starts_with('{') {
+        SdParameters::parse_novelai_json(payload).ok()
+    } else {
+        SdParameters::parse(payload).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// Builds an RGBA buffer whose alpha LSBs carry `signature`, the bit length and `data`.
+    fn stealth_rgba(signature: &[u8], data: &[u8], width: usize, height: usize) -> Vec<u8> {
+        let mut bytes = signature.to_vec();
+        bytes.extend_from_slice(&((data.len() * 8) as u32).to_be_bytes());
+        bytes.extend_from_slice(data);
+        let bits: Vec<u8> = bytes
+            .iter()
+            .flat_map(|byte| (0..8).rev().map(move |shift| (byte >> shift) & 1))
+            .collect();
+        assert!(bits.len() <= width * height, "payload does not fit");
+
+        let mut rgba = vec![0xFE; width * height * 4];
+        for (i, bit) in bits.into_iter().enumerate() {
+            let (x, y) = (i / height, i % height);
+            rgba[(y * width + x) * 4 + 3] |= bit;
+        }
+        rgba
+    }
+
+    #[test]
+    fn extracts_plain_stealth_payload() {
+        let rgba = stealth_rgba(STEALTH_SIGNATURE_PLAIN, b"a cat, Steps: 20", 24, 20);
+        assert_eq!(
+            extract_stealth_metadata(&rgba, 24, 20).as_deref(),
+            Some("a cat, Steps: 20")
+        );
+    }
+
+    #[test]
+    fn extracts_compressed_stealth_payload() {
+        let json = r#"{"Comment": "{\"prompt\": \"a cat\", \"uc\": \"blurry\", \"steps\": 28, \"seed\": 42}"}"#;
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(json.as_bytes()).unwrap();
+        let rgba = stealth_rgba(
+            STEALTH_SIGNATURE_COMPRESSED,
+            &encoder.finish().unwrap(),
+            40,
+            40,
+        );
+
+        let payload = extract_stealth_metadata(&rgba, 40, 40).unwrap();
+        assert_eq!(payload, json);
+        let params = parse_stealth_payload(&payload).unwrap();
+        assert_eq!(params.source, SdSource::NovelAI);
+        assert_eq!(params.positive_sd_tags[0].name, "a cat");
+        assert_eq!(params.negative_sd_tags[0].name, "blurry");
+        assert_eq!(params.steps.as_deref(), Some("28"));
+        assert_eq!(params.seed.as_deref(), Some("42"));
+    }
+
+    /// Writes a small RGB PNG named `name` into `dir`.
+    fn write_png(dir: &Path, name: &str) -> PathBuf {
+        let path = dir.join(name);
+        image::save_buffer(&path, &[0u8; 12], 2, 2, image::ExtendedColorType::Rgb8).unwrap();
+        path
+    }
+
+    /// XMP packet with the given standard and custom percent ratings.
+    fn rating_rdf(rating: Option<u8>, percent: Option<u8>) -> String {
+        let attribute = |name: &str, value: Option<u8>| {
+            value
+                .map(|value| format!(r#" {}="{}""#, name, value))
+                .unwrap_or_default()
+        };
+        format!(
+            r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"><rdf:Description rdf:about="" xmlns:xmp="{}" xmlns:sdiv="{}"{}{}/></rdf:RDF></x:xmpmeta>"#,
+            XMP_NAMESPACE,
+            EXTENDED_RATING_NAMESPACE,
+            attribute("xmp:Rating", rating),
+            attribute("sdiv:RatingPercent", percent),
+        )
+    }
+
+    #[test]
+    fn rejects_buffers_without_stealth_payload() {
+        let rgba = vec![0xFF; 20 * 20 * 4];
+        assert_eq!(extract_stealth_metadata(&rgba, 20, 20), None);
+        // Size that does not match the buffer
+        assert_eq!(extract_stealth_metadata(&rgba, 21, 20), None);
+    }
+
+    #[test]
+    fn maps_extended_ratings() {
+        assert_eq!(extended_rating_to_percent(7), 70);
+        assert_eq!(extended_rating_to_percent(12), 100);
+        assert_eq!(percent_to_extended_rating(74), 7);
+        assert_eq!(percent_to_extended_rating(75), 8);
+        assert_eq!(percent_to_extended_rating(200), 10);
+        assert_eq!(extended_rating_to_standard(0), 0);
+        assert_eq!(extended_rating_to_standard(7), 4);
+        assert_eq!(extended_rating_to_standard(10), 5);
+    }
+
+    #[test]
+    fn validates_rating_per_scale() {
+        assert_eq!(RatingScale::Standard.max_rating(), 5);
+        assert_eq!(RatingScale::Extended.max_rating(), 10);
+        assert!(validate_rating(5, RatingScale::Standard).is_ok());
+        assert!(validate_rating(6, RatingScale::Standard).is_err());
+        assert!(validate_rating(10, RatingScale::Extended).is_ok());
+        assert!(validate_rating(11, RatingScale::Extended).is_err());
+    }
+
+    #[test]
+    fn extended_rating_prefers_standard_rating_on_mismatch() {
+        let read = |rating, percent| {
+            parse_xmp_rating_from_rdf(&rating_rdf(rating, percent), RatingScale::Extended)
+        };
+        assert_eq!(read(Some(4), Some(70)), Some(7));
+        // Another app changed the standard Rating after the extended write
+        assert_eq!(read(Some(2), Some(70)), Some(4));
+        assert_eq!(read(Some(3), None), Some(6));
+        assert_eq!(read(None, Some(70)), Some(7));
+        assert_eq!(read(None, None), None);
+    }
+
+    #[test]
+    fn extended_write_keeps_standard_rating_compatible() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_png(dir.path(), "image.png");
+
+        write_xmp_rating_extended(&path, 7).unwrap();
+        assert_eq!(read_xmp_rating_extended(&path).unwrap(), Some(7));
+        assert_eq!(read_xmp_rating(&path).unwrap(), Some(4));
+
+        write_xmp_rating(&path, 1).unwrap();
+        assert_eq!(read_xmp_rating_extended(&path).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn rating_write_support_per_extension() {
+        for name in ["a.jpg", "a.jpeg", "a.png", "a.webp", "a.JPG", "a.Png"] {
+            assert!(supports_rating_write(Path::new(name)), "{}", name);
+        }
+        for name in ["a.gif", "a.bmp", "a.ico", "a.dng", "a.txt", "png"] {
+            assert!(!supports_rating_write(Path::new(name)), "{}", name);
+        }
+    }
+
+    #[test]
+    fn rating_writable_extensions_are_supported_images() {
+        for ext in crate::config::RATING_WRITABLE_EXTENSIONS {
+            assert!(file_utils::is_supported_extension(ext), "{}", ext);
+        }
+    }
+
+    #[test]
+    fn rating_write_to_read_only_file_reports_read_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_png(dir.path(), "locked.png");
+        let mut permissions = std::fs::metadata(&path).unwrap().permissions();
+        permissions.set_readonly(true);
+        std::fs::set_permissions(&path, permissions).unwrap();
+
+        assert!(matches!(
+            write_xmp_rating(&path, 3),
+            Err(AppError::FileReadOnly(_))
+        ));
+        assert_eq!(read_xmp_rating(&path).unwrap(), None);
+    }
+
+    /// Decodes the `png::Info` of a 1x1 PNG with the given tEXt and zTXt chunks.
+    fn png_info(text: &[(&str, &str)], ztxt: &[(&str, &str)]) -> png::Info<'static> {
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, 1, 1);
+        encoder.set_color(png::ColorType::Grayscale);
+        for (keyword, value) in text {
+            encoder
+                .add_text_chunk(keyword.to_string(), value.to_string())
+                .unwrap();
+        }
+        for (keyword, value) in ztxt {
+            encoder
+                .add_ztxt_chunk(keyword.to_string(), value.to_string())
+                .unwrap();
+        }
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[0]).unwrap();
+        writer.finish().unwrap();
+
+        let reader = png::Decoder::new(std::io::Cursor::new(bytes))
+            .read_info()
+            .unwrap();
+        reader.info().clone()
+    }
+
+    #[test]
+    fn reads_parameters_from_ztxt() {
+        let info = png_info(
+            &[],
+            &[(
+                "parameters",
+                "a cat\nNegative prompt: blurry\nSteps: 20, Sampler: Euler a, Seed: 7",
+            )],
+        );
+        assert!(info.uncompressed_latin1_text.is_empty());
+
+        let params = parse_sd_parameters_from_info(&info).unwrap().unwrap();
+        assert_eq!(params.positive_sd_tags[0].name, "a cat");
+        assert_eq!(params.negative_sd_tags[0].name, "blurry");
+        assert_eq!(params.steps.as_deref(), Some("20"));
+        assert_eq!(params.seed.as_deref(), Some("7"));
+    }
+
+    #[test]
+    fn reads_fooocus_comment() {
+        let comment = r#"{"prompt": "a cat, (night:1.2)", "negative_prompt": "blurry", "styles": "['Fooocus V2']", "performance": "Speed", "resolution": "(1024, 1024)", "guidance_scale": 4, "sharpness": 2, "base_model": "juggernautXL_v8Rundiffusion.safetensors", "sampler": "dpmpp_2m_sde_gpu", "scheduler": "karras", "seed": "1234567890", "steps": 30, "version": "Fooocus v2.3.1"}"#;
+        let info = png_info(&[("Comment", comment)], &[]);
+
+        let params = parse_sd_parameters_from_info(&info).unwrap().unwrap();
+        assert_eq!(params.source, SdSource::Fooocus);
+        assert_eq!(params.positive_sd_tags[0].name, "a cat");
+        assert_eq!(params.positive_sd_tags[1].name, "night");
+        assert_eq!(params.positive_sd_tags[1].weight, Some(1.2));
+        assert_eq!(params.negative_sd_tags[0].name, "blurry");
+        assert_eq!(params.steps.as_deref(), Some("30"));
+        assert_eq!(params.sampler.as_deref(), Some("dpmpp_2m_sde_gpu"));
+        assert_eq!(params.schedule_type.as_deref(), Some("karras"));
+        assert_eq!(params.cfg_scale.as_deref(), Some("4"));
+        assert_eq!(params.seed.as_deref(), Some("1234567890"));
+        assert_eq!(params.size.as_deref(), Some("1024x1024"));
+        assert_eq!(
+            params.model.as_deref(),
+            Some("juggernautXL_v8Rundiffusion.safetensors")
+        );
+    }
+
+    #[test]
+    fn plain_comment_is_not_parameters() {
+        let info = png_info(&[("Comment", "Made with love")], &[]);
+        assert!(parse_sd_parameters_from_info(&info).unwrap().is_none());
+    }
+
+    /// Builds a WebP RIFF container from `chunks`, padding odd-sized chunks.
+    fn webp_with_chunks(chunks: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+        let mut body = b"WEBP".to_vec();
+        for (fourcc, data) in chunks {
+            body.extend_from_slice(*fourcc);
+            body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            body.extend_from_slice(data);
+            if data.len() % 2 == 1 {
+                body.push(0);
+            }
+        }
+        let mut bytes = b"RIFF".to_vec();
+        bytes.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&body);
+        bytes
+    }
+
+    #[test]
+    fn reads_rating_from_webp_xmp_chunk() {
+        let rdf = rating_rdf(Some(4), None);
+        let bytes = webp_with_chunks(&[(b"VP8L", &[0x2f, 0, 0, 0, 0]), (b"XMP ", rdf.as_bytes())]);
+
+        let xmp_rdf = extract_xmp_from_webp(&bytes).unwrap();
+        assert_eq!(xmp_rdf, rdf);
+        assert_eq!(
+            parse_xmp_rating_from_rdf(&xmp_rdf, RatingScale::Standard),
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn webp_without_xmp_chunk_has_no_packet() {
+        let bytes = webp_with_chunks(&[(b"VP8L", &[0x2f, 0, 0, 0, 0])]);
+        assert_eq!(extract_xmp_from_webp(&bytes), None);
+        assert_eq!(extract_xmp_from_webp(b"RIFF\0\0\0\0WAVE"), None);
+    }
+
+    #[test]
+    fn reads_keywords_from_dc_subject() {
+        let rdf = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"><rdf:Description rdf:about="" xmlns:dc="http://purl.org/dc/elements/1.1/"><dc:subject><rdf:Bag><rdf:li>cat</rdf:li><rdf:li>night sky</rdf:li></rdf:Bag></dc:subject></rdf:Description></rdf:RDF></x:xmpmeta>"#;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tagged.png");
+        let file = std::fs::File::create(&path).unwrap();
+        let mut encoder = png::Encoder::new(file, 1, 1);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder
+            .add_itxt_chunk("XML:com.adobe.xmp".to_string(), rdf.to_string())
+            .unwrap();
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[0]).unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!(read_xmp_keywords(&path).unwrap(), ["cat", "night sky"]);
+        assert_eq!(parse_xmp_keywords_from_rdf(rdf), ["cat", "night sky"]);
+    }
+
+    #[test]
+    fn keyword_add_and_remove_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_png(dir.path(), "keywords.png");
+
+        assert_eq!(add_xmp_keyword(&path, " cat ").unwrap(), ["cat"]);
+        assert_eq!(add_xmp_keyword(&path, "dog").unwrap(), ["cat", "dog"]);
+        assert_eq!(add_xmp_keyword(&path, "cat").unwrap(), ["cat", "dog"]);
+        assert_eq!(read_xmp_keywords(&path).unwrap(), ["cat", "dog"]);
+
+        assert_eq!(remove_xmp_keyword(&path, "cat").unwrap(), ["dog"]);
+        assert_eq!(remove_xmp_keyword(&path, "cat").unwrap(), ["dog"]);
+        assert_eq!(read_xmp_keywords(&path).unwrap(), ["dog"]);
+        assert!(matches!(
+            add_xmp_keyword(&path, "  "),
+            Err(AppError::XmpWrite(_))
+        ));
+    }
+
+    #[test]
+    fn reads_comfyui_checkpoint_and_lora_loaders() {
+        let json = r#"{
+            "3": {"class_type": "KSampler", "inputs": {
+                "seed": 1234, "steps": 25, "cfg": 6.5, "sampler_name": "euler",
+                "scheduler": "karras", "denoise": 1, "model": ["10", 0],
+                "positive": ["6", 0], "negative": ["7", 0], "latent_image": ["5", 0]}},
+            "4": {"class_type": "CheckpointLoaderSimple", "inputs": {"ckpt_name": "sdxl_base.safetensors"}},
+            "5": {"class_type": "EmptyLatentImage", "inputs": {"width": 832, "height": 1216, "batch_size": 1}},
+            "6": {"class_type": "CLIPTextEncode", "inputs": {"text": "a cat, (night:1.2)", "clip": ["10", 1]}},
+            "7": {"class_type": "CLIPTextEncode", "inputs": {"text": "blurry", "clip": ["10", 1]}},
+            "10": {"class_type": "LoraLoader", "inputs": {
+                "lora_name": "detail.safetensors", "strength_model": 0.8, "strength_clip": 1,
+                "model": ["4", 0], "clip": ["4", 1]}}
+        }"#;
+
+        let params = SdParameters::parse_comfyui_prompt(json).unwrap();
+        assert_eq!(params.source, SdSource::ComfyUI);
+        assert_eq!(params.model.as_deref(), Some("sdxl_base.safetensors"));
+        assert_eq!(params.loras, ["detail.safetensors:0.8"]);
+        assert_eq!(params.seed.as_deref(), Some("1234"));
+        assert_eq!(params.steps.as_deref(), Some("25"));
+        assert_eq!(params.sampler.as_deref(), Some("euler"));
+        assert_eq!(params.size.as_deref(), Some("832x1216"));
+        let names: Vec<_> = params
+            .positive_sd_tags
+            .iter()
+            .map(|t| t.name.as_str())
+            .collect();
+        assert_eq!(names, ["a cat", "night"]);
+        assert_eq!(params.positive_sd_tags[1].weight, Some(1.2));
+        assert_eq!(params.negative_sd_tags[0].name, "blurry");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rating_round_trips_on_non_utf8_path() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let source = write_png(dir.path(), "source.png");
+        let path = dir.path().join(std::ffi::OsStr::from_bytes(b"caf\xe9.png"));
+        std::fs::rename(&source, &path).unwrap();
+        assert!(path.to_str().is_none());
+
+        write_xmp_rating(&path, 4).unwrap();
+        assert_eq!(read_xmp_rating(&path).unwrap(), Some(4));
+        assert_eq!(add_xmp_keyword(&path, "cat").unwrap(), ["cat"]);
+        assert_eq!(read_xmp_rating(&path).unwrap(), Some(4));
+        assert!(image::open(&path).is_ok());
+    }
+
+    #[test]
+    fn malformed_parameters_keep_raw_text_and_reason() {
+        let raw = "a cat sitting on a mat, no settings here";
+        let info = png_info(&[("parameters", raw)], &[]);
+
+        let Err(failure) = parse_sd_parameters_from_info(&info) else {
+            panic!("malformed parameters parsed");
+        };
+        assert_eq!(failure.raw, raw);
+        assert!(
+            failure.message.contains("Negative prompt"),
+            "{}",
+            failure.message
+        );
+    }
+
+    #[test]
+    fn malformed_comfyui_prompt_keeps_raw_text_and_reason() {
+        let raw = r#"{"1": {"class_type": "CLIPTextEncode", "inputs": {"text": "cat"}}}"#;
+        let info = png_info(&[("prompt", raw)], &[]);
+
+        let Err(failure) = parse_sd_parameters_from_info(&info) else {
+            panic!("prompt without a sampler parsed");
+        };
+        assert_eq!(failure.raw, raw);
+        assert!(failure.message.contains("sampler"), "{}", failure.message);
+        assert!(matches!(
+            parse_sd_parameters_from_info(&png_info(&[], &[])),
+            Ok(None)
+        ));
+    }
+
+    #[test]
+    fn diff_prompts_reports_tag_and_field_changes() {
+        let a = SdParameters::parse(
+            "1girl, (smile:1.2), outdoors\nNegative prompt: lowres\nSteps: 20, Seed: 1",
+        )
+        .unwrap();
+        let b = SdParameters::parse(
+            "outdoors, 1girl, (smile:1.4), hat\nNegative prompt: lowres, blurry\nSteps: 20, Seed: 2",
+        )
+        .unwrap();
+
+        let diff = diff_prompts(&a, &b);
+        let names = |tags: &[SdTag]| tags.iter().map(|t| t.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&diff.positive.added), ["hat"]);
+        assert!(diff.positive.removed.is_empty());
+        assert_eq!(
+            diff.positive.reweighted,
+            [TagWeightChange {
+                name: "smile".to_string(),
+                before: Some(1.2),
+                after: Some(1.4),
+            }]
+        );
+        assert_eq!(names(&diff.negative.added), ["blurry"]);
+        assert_eq!(
+            diff.fields,
+            [FieldChange {
+                field: "Seed",
+                before: Some("1".to_string()),
+                after: Some("2".to_string()),
+            }]
+        );
+
+        let reverse = diff_prompts(&b, &a);
+        assert_eq!(names(&reverse.positive.removed), ["hat"]);
+        assert_eq!(names(&reverse.negative.removed), ["blurry"]);
+        assert_eq!(diff_prompts(&a, &a), PromptDiff::default());
+    }
+
+    #[test]
+    fn negative_prompt_marker_on_the_same_line() {
+        let params =
+            SdParameters::parse("1girl, smile Negative prompt: lowres, blurry\nSteps: 20, Seed: 3")
+                .unwrap();
+        let names: Vec<_> = params.positive_sd_tags.iter().map(|t| &t.name).collect();
+        assert_eq!(names, ["1girl", "smile"]);
+        let names: Vec<_> = params.negative_sd_tags.iter().map(|t| &t.name).collect();
+        assert_eq!(names, ["lowres", "blurry"]);
+        assert_eq!(params.seed.as_deref(), Some("3"));
+    }
+
+    #[test]
+    fn negative_prompt_marker_at_the_start() {
+        let params = SdParameters::parse("Negative prompt: lowres\nSteps: 20").unwrap();
+        assert!(params.positive_sd_tags.is_empty());
+        let names: Vec<_> = params.negative_sd_tags.iter().map(|t| &t.name).collect();
+        assert_eq!(names, ["lowres"]);
+
+        // Part of a word is not a marker
+        assert!(SdParameters::parse("xNegative prompt: lowres\nSteps: 20").is_err());
+    }
+
+    #[test]
+    fn detects_the_generation_source_of_payloads() {
+        let source = |text: &[(&str, &str)]| {
+            parse_sd_parameters_from_info(&png_info(text, &[]))
+                .unwrap()
+                .unwrap()
+                .source
+        };
+        assert_eq!(
+            source(&[("parameters", "a cat\nNegative prompt: blurry\nSteps: 20")]),
+            SdSource::Automatic1111
+        );
+        assert_eq!(
+            source(&[(
+                "parameters",
+                r#"{"prompt": "a cat", "negative_prompt": "blurry", "steps": 30}"#
+            )]),
+            SdSource::Fooocus
+        );
+        assert_eq!(
+            source(&[(
+                "prompt",
+                r#"{"1": {"class_type": "KSampler", "inputs": {"positive": ["2", 0]}},
+                    "2": {"class_type": "CLIPTextEncode", "inputs": {"text": "a cat"}}}"#
+            )]),
+            SdSource::ComfyUI
+        );
+
+        let stealth = |payload: &str| parse_stealth_payload(payload).unwrap().source;
+        assert_eq!(
+            stealth(r#"{"prompt": "a cat", "uc": "blurry"}"#),
+            SdSource::NovelAI
+        );
+        assert_eq!(
+            stealth("a cat\nNegative prompt: blurry\nSteps: 20"),
+            SdSource::Automatic1111
+        );
+
+        assert_eq!(SdSource::default().label(), "Unknown");
+        assert_eq!(SdSource::Automatic1111.label(), "AUTOMATIC1111");
+    }
+
+    #[test]
+    fn reads_ratings_from_sidecar_conventions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_png(dir.path(), "image.png");
+        let read = || read_xmp_rating_scaled(&path, RatingScale::Standard).unwrap();
+        assert_eq!(find_sidecar_xmp(&path), None);
+        assert_eq!(read(), None);
+
+        // Lightroom / RawTherapee: the extension is replaced
+        std::fs::write(dir.path().join("image.xmp"), rating_rdf(Some(2), None)).unwrap();
+        assert_eq!(find_sidecar_xmp(&path), Some(dir.path().join("image.xmp")));
+        assert_eq!(read(), Some(2));
+
+        // darktable: the extension is kept, and wins over the other convention
+        std::fs::write(dir.path().join("image.png.xmp"), rating_rdf(Some(3), None)).unwrap();
+        assert_eq!(
+            find_sidecar_xmp(&path),
+            Some(dir.path().join("image.png.xmp"))
+        );
+        assert_eq!(read(), Some(3));
+
+        // An embedded rating wins over both sidecars
+        write_xmp_rating_scaled(&path, 5, RatingScale::Standard).unwrap();
+        assert_eq!(read(), Some(5));
+    }
+
+    #[test]
+    fn finds_sidecar_of_file_without_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("image");
+        std::fs::write(&path, b"").unwrap();
+        std::fs::write(dir.path().join("image.xmp"), rating_rdf(Some(4), None)).unwrap();
+        assert_eq!(find_sidecar_xmp(&path), Some(dir.path().join("image.xmp")));
+        assert_eq!(read_sidecar_rating(&path, RatingScale::Standard), Some(4));
+    }
+
+    fn tag_names(tags: &[SdTag]) -> Vec<&str> {
+        tags.iter().map(|tag| tag.name.as_str()).collect()
+    }
+
+    #[test]
+    fn split_chunks_yield_positive_and_negative_prompts() {
+        let info = png_info(
+            &[
+                ("Description", "a cat, night"),
+                ("Comment", "Negative prompt: blurry\nSteps: 20, Seed: 42"),
+            ],
+            &[],
+        );
+        let params = parse_sd_parameters_from_info(&info).unwrap().unwrap();
+        assert_eq!(tag_names(&params.positive_sd_tags), ["a cat", "night"]);
+        assert_eq!(tag_names(&params.negative_sd_tags), ["blurry"]);
+        assert_eq!(params.steps.as_deref(), Some("20"));
+        assert_eq!(params.seed.as_deref(), Some("42"));
+    }
+
+    #[test]
+    fn comment_alone_holds_the_whole_text() {
+        let info = png_info(
+            &[("Comment", "a cat\nNegative prompt: blurry\nSteps: 20")],
+            &[],
+        );
+        let params = parse_sd_parameters_from_info(&info).unwrap().unwrap();
+        assert_eq!(tag_names(&params.positive_sd_tags), ["a cat"]);
+        assert_eq!(tag_names(&params.negative_sd_tags), ["blurry"]);
+    }
+
+    #[test]
+    fn negative_prompt_chunk_is_used_only_without_marker() {
+        let info = png_info(
+            &[
+                ("Description", "a cat"),
+                ("Comment", "Steps: 20"),
+                ("Negative prompt", "blurry"),
+            ],
+            &[],
+        );
+        assert_eq!(
+            assemble_split_parameters(&info).as_deref(),
+            Some("a cat\nNegative prompt: blurry\nSteps: 20")
+        );
+
+        let info = png_info(
+            &[
+                ("Description", "a cat"),
+                ("Comment", "Negative prompt: lowres\nSteps: 20"),
+                ("Negative prompt", "blurry"),
+            ],
+            &[],
+        );
+        let params = parse_sd_parameters_from_info(&info).unwrap().unwrap();
+        assert_eq!(tag_names(&params.negative_sd_tags), ["lowres"]);
+    }
+
+    #[test]
+    fn parameters_chunk_takes_precedence_over_split_chunks() {
+        let info = png_info(
+            &[
+                ("parameters", "a dog\nNegative prompt: lowres\nSteps: 30"),
+                ("Description", "a cat"),
+                ("Comment", "Negative prompt: blurry\nSteps: 20"),
+            ],
+            &[],
+        );
+        let params = parse_sd_parameters_from_info(&info).unwrap().unwrap();
+        assert_eq!(tag_names(&params.positive_sd_tags), ["a dog"]);
+        assert_eq!(params.steps.as_deref(), Some("30"));
+    }
+
+    #[test]
+    fn json_comment_is_not_assembled() {
+        let info = png_info(&[("Comment", r#"{"prompt": "a cat"}"#)], &[]);
+        assert_eq!(assemble_split_parameters(&info), None);
+    }
+
+    #[test]
+    fn joins_parameters_split_over_text_chunks() {
+        let info = png_info(
+            &[
+                ("parameters", "a cat, night\nNegative prompt: blu"),
+                ("Software", "test"),
+                ("parameters", "rry\nSteps: 20, Seed: 42"),
+            ],
+            &[("parameters", "a dog\nNegative prompt: lowres\nSteps: 30")],
+        );
+
+        assert_eq!(
+            extract_sd_parameters_from_info(&info).unwrap().as_deref(),
+            Some("a cat, night\nNegative prompt: blurry\nSteps: 20, Seed: 42")
+        );
+        let params = parse_sd_parameters_from_info(&info).unwrap().unwrap();
+        assert_eq!(tag_names(&params.positive_sd_tags), ["a cat", "night"]);
+        assert_eq!(tag_names(&params.negative_sd_tags), ["blurry"]);
+        assert_eq!(params.seed.as_deref(), Some("42"));
+    }
+
+    fn tag(name: &str, weight: Option<f32>) -> SdTag {
+        SdTag {
+            name: name.to_string(),
+            weight,
+        }
+    }
+
+    #[test]
+    fn parse_sd_tags_reads_weights() {
+        assert_eq!(
+            SdParameters::parse_sd_tags("masterpiece, (sky:1.2), ,(sea:-0.5), (sand:0)"),
+            [
+                tag("masterpiece", None),
+                tag("sky", Some(1.2)),
+                tag("sea", Some(-0.5)),
+                tag("sand", Some(0.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_sd_tags_keeps_non_numeric_weights_as_text() {
+        assert_eq!(
+            SdParameters::parse_sd_tags("(sky:high)"),
+            [tag("(sky:high)", None)]
+        );
+    }
+}
@@ -0,0 +1,329 @@
+//! Rating index for fast directory-wide rating lookups.
+//!
+//! Caches XMP ratings per file together with the file's modification time,
+//! so repeated lookups skip the XMP read until the file changes on disk.
+
+use crate::metadata;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Modification times a rating was read at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    modified: Option<SystemTime>,
+    /// Modification time of the sidecar XMP file, which can hold the rating too.
+    sidecar_modified: Option<SystemTime>,
+}
+
+impl FileStamp {
+    /// Reads the current modification times of the file and its sidecar XMP file.
+    pub fn read(path: &Path) -> Self {
+        Self {
+            modified: modified_time(path),
+            sidecar_modified: sidecar_modified_time(path),
+        }
+    }
+}
+
+/// Cached rating for a single file.
+struct RatingEntry {
+    stamp: FileStamp,
+    rating: Option<u8>,
+}
+
+/// Ratings counted by the last summary of an image list.
+struct CountedList {
+    /// Revision of the image list that was summarized.
+    revision: u64,
+    /// Rating of each file counted as known (`None` = unrated).
+    known: HashMap<PathBuf, Option<u8>>,
+    summary: RatingSummary,
+}
+
+/// Index of known ratings keyed by file path.
+#[derive(Default)]
+pub struct RatingIndex {
+    entries: HashMap<PathBuf, RatingEntry>,
+    /// Last list summary, kept so a single rating change can update it.
+    counted: Option<CountedList>,
+}
+
+/// Returns the modification time of a file, if available.
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Returns the modification time of the sidecar XMP file of an image, if any.
+fn sidecar_modified_time(path: &Path) -> Option<SystemTime> {
+    metadata::find_sidecar_xmp(path).and_then(|sidecar| modified_time(&sidecar))
+}
+
+impl RatingIndex {
+    /// Creates an empty rating index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the indexed rating if the entry is still fresh.
+    ///
+    /// `stamp` is the current [`FileStamp`] of the file, taken before locking
+    /// the index so the lock is never held for file system calls.
+    /// The outer `Option` is `None` on a miss (unknown or modified file or sidecar);
+    /// the inner `Option` is the rating itself (`None` = unrated).
+    pub fn get(&self, path: &Path, stamp: &FileStamp) -> Option<Option<u8>> {
+        let entry = self.entries.get(path)?;
+        if entry.stamp == *stamp {
+            Some(entry.rating)
+        } else {
+            None
+        }
+    }
+
+    /// Records the rating of a file along with the modification times it was read at.
+    ///
+    /// `stamp` must be taken before reading the rating, so a file that changes
+    /// while it is read is read again on the next lookup.
+    pub fn insert(&mut self, path: PathBuf, stamp: FileStamp, rating: Option<u8>) {
+        self.entries.insert(path, RatingEntry { stamp, rating });
+    }
+
+    /// Summarizes the ratings of `files` from the index alone.
+    ///
+    /// Each file comes with its current [`FileStamp`]. Files without a fresh
+    /// entry are counted as unknown instead of being read. The counts are kept
+    /// for [`RatingIndex::update_summary`] together with the list `revision`.
+    pub fn summarize<'a>(
+        &mut self,
+        revision: u64,
+        files: impl IntoIterator<Item = (&'a Path, &'a FileStamp)>,
+    ) -> RatingSummary {
+        let mut summary = RatingSummary::default();
+        let mut known = HashMap::new();
+        for (path, stamp) in files {
+            match self.get(path, stamp) {
+                Some(rating) => {
+                    summary.add(rating);
+                    known.insert(path.to_path_buf(), rating);
+                }
+                None => summary.unknown += 1,
+            }
+        }
+
+        self.counted = Some(CountedList {
+            revision,
+            known,
+            summary,
+        });
+        summary
+    }
+
+    /// Updates the last summary after the rating of `path` changed, without
+    /// looking at the other files.
+    ///
+    /// Returns `None` if the last summary was of another list revision or did
+    /// not count `path`, in which case the list has to be summarized again.
+    pub fn update_summary(
+        &mut self,
+        revision: u64,
+        path: &Path,
+        rating: Option<u8>,
+    ) -> Option<RatingSummary> {
+        let counted = self.counted.as_mut().filter(|c| c.revision == revision)?;
+        match counted.known.get_mut(path) {
+            Some(known) => counted.summary.remove(std::mem::replace(known, rating)),
+            // Not read at summary time, so it was counted as unknown
+            None if counted.summary.unknown > 0 => {
+                counted.summary.unknown -= 1;
+                counted.known.insert(path.to_path_buf(), rating);
+            }
+            None => return None,
+        }
+        counted.summary.add(rating);
+        Some(counted.summary)
+    }
+}
+
+/// Counts of ratings over a list of images.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RatingSummary {
+    /// Images rated 1 to 5 stars.
+    pub rated: usize,
+    /// Images without a rating or rated 0 stars.
+    pub unrated: usize,
+    /// Images per star count, with unrated images counted as 0 stars.
+    pub by_star: [usize; 6],
+    /// Images whose rating has not been read into the index yet.
+    pub unknown: usize,
+}
+
+impl RatingSummary {
+    /// Counts an image with a known rating (`None` = unrated).
+    pub fn add(&mut self, rating: Option<u8>) {
+        let stars = usize::from(rating.unwrap_or(0).min(5));
+        self.by_star[stars] += 1;
+        if stars == 0 {
+            self.unrated += 1;
+        } else {
+            self.rated += 1;
+        }
+    }
+
+    /// Uncounts an image with a known rating, reverting [`RatingSummary::add`].
+    pub fn remove(&mut self, rating: Option<u8>) {
+        let stars = usize::from(rating.unwrap_or(0).min(5));
+        self.by_star[stars] = self.by_star[stars].saturating_sub(1);
+        if stars == 0 {
+            self.unrated = self.unrated.saturating_sub(1);
+        } else {
+            self.rated = self.rated.saturating_sub(1);
+        }
+    }
+
+    /// Returns the number of counted images, including unknown ones.
+    pub fn total(&self) -> usize {
+        self.rated + self.unrated + self.unknown
+    }
+
+    /// Returns a line like `Rated: 40 / 128 (88 unrated)` for the info panel.
+    pub fn label(&self) -> String {
+        let mut label = format!(
+            "Rated: {} / {} ({} unrated",
+            self.rated,
+            self.total(),
+            self.unrated
+        );
+        if self.unknown > 0 {
+            label.push_str(&format!(", {} not read yet", self.unknown));
+        }
+        label.push(')');
+        label
+    }
+}
+
+/// Reads the rating of a file through the index, falling back to XMP on a miss.
+///
+/// The lock is only held for the lookup and the insert, never for file system
+/// calls, so this can be called from multiple threads in parallel.
+pub fn read_rating_indexed(index: &Mutex<RatingIndex>, path: &Path) -> Option<u8> {
+    read_rating_indexed_with(index, path, |path| {
+        metadata::read_xmp_rating_scaled(path, metadata::current_rating_scale())
+            .ok()
+            .flatten()
+    })
+}
+
+/// [`read_rating_indexed`] with the XMP read on a miss done by `read_xmp`.
+fn read_rating_indexed_with(
+    index: &Mutex<RatingIndex>,
+    path: &Path,
+    read_xmp: impl FnOnce(&Path) -> Option<u8>,
+) -> Option<u8> {
+    let stamp = FileStamp::read(path);
+    if let Some(rating) = index.lock().ok().and_then(|index| index.get(path, &stamp)) {
+        return rating;
+    }
+
+    let rating = read_xmp(path);
+    if let Ok(mut index) = index.lock() {
+        index.insert(path.to_path_buf(), stamp, rating);
+    }
+    rating
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stamp() -> FileStamp {
+        FileStamp {
+            modified: Some(SystemTime::UNIX_EPOCH),
+            sidecar_modified: None,
+        }
+    }
+
+    #[test]
+    fn stale_entries_are_not_returned() {
+        let mut index = RatingIndex::new();
+        index.insert(PathBuf::from("a.png"), stamp(), Some(3));
+        let changed = FileStamp {
+            modified: None,
+            ..stamp()
+        };
+        assert_eq!(index.get(Path::new("a.png"), &stamp()), Some(Some(3)));
+        assert_eq!(index.get(Path::new("a.png"), &changed), None);
+    }
+
+    #[test]
+    fn unrated_file_is_read_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.webp");
+        std::fs::write(&path, b"").unwrap();
+        let index = Mutex::new(RatingIndex::new());
+
+        let mut reads = 0;
+        let rating = read_rating_indexed_with(&index, &path, |_| {
+            reads += 1;
+            None
+        });
+        assert_eq!((rating, reads), (None, 1));
+
+        let rating = read_rating_indexed_with(&index, &path, |_| panic!("XMP read again"));
+        assert_eq!(rating, None);
+    }
+
+    #[test]
+    fn adding_a_sidecar_changes_the_stamp() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.png");
+        std::fs::write(&path, b"").unwrap();
+        let before = FileStamp::read(&path);
+
+        std::fs::write(dir.path().join("a.png.xmp"), b"").unwrap();
+        let after = FileStamp::read(&path);
+        assert_eq!(after.modified, before.modified);
+        assert_ne!(after, before);
+    }
+
+    #[test]
+    fn summarize_counts_unread_files_as_unknown() {
+        let mut index = RatingIndex::new();
+        index.insert(PathBuf::from("a.png"), stamp(), Some(3));
+        index.insert(PathBuf::from("b.png"), stamp(), None);
+        let stamps = [stamp(); 3];
+        let files = [Path::new("a.png"), Path::new("b.png"), Path::new("c.png")];
+
+        let summary = index.summarize(1, files.into_iter().zip(&stamps));
+        assert_eq!((summary.rated, summary.unrated, summary.unknown), (1, 1, 1));
+        assert_eq!(summary.by_star, [1, 0, 0, 1, 0, 0]);
+    }
+
+    #[test]
+    fn update_summary_moves_changed_file() {
+        let mut index = RatingIndex::new();
+        index.insert(PathBuf::from("a.png"), stamp(), Some(3));
+        let stamps = [stamp(); 2];
+        let files = [Path::new("a.png"), Path::new("b.png")];
+        index.summarize(1, files.into_iter().zip(&stamps));
+
+        let summary = index
+            .update_summary(1, Path::new("a.png"), Some(5))
+            .unwrap();
+        assert_eq!(summary.by_star, [0, 0, 0, 0, 0, 1]);
+        assert_eq!(summary.unknown, 1);
+
+        let summary = index
+            .update_summary(1, Path::new("b.png"), Some(0))
+            .unwrap();
+        assert_eq!((summary.rated, summary.unrated, summary.unknown), (1, 1, 0));
+    }
+
+    #[test]
+    fn update_summary_needs_same_list_revision() {
+        let mut index = RatingIndex::new();
+        let stamps = [stamp()];
+        index.summarize(1, [Path::new("a.png")].into_iter().zip(&stamps));
+        assert_eq!(index.update_summary(2, Path::new("a.png"), Some(1)), None);
+    }
+}
@@ -0,0 +1,277 @@
+//! Embedded JPEG previews of RAW files (DNG, CR2, NEF).
+//!
+//! The RAW sensor data itself is not demosaiced. These formats are TIFF based,
+//! so all IFDs (including SubIFDs) are walked for JPEG streams and the largest
+//! one that decodes is shown instead.
+
+use crate::error::{AppError, Result};
+use image::ImageFormat;
+use log::debug;
+use std::collections::HashMap;
+use std::path::Path;
+
+const TAG_COMPRESSION: u16 = 0x0103;
+const TAG_STRIP_OFFSETS: u16 = 0x0111;
+const TAG_STRIP_BYTE_COUNTS: u16 = 0x0117;
+const TAG_SUB_IFDS: u16 = 0x014A;
+const TAG_JPEG_INTERCHANGE_FORMAT: u16 = 0x0201;
+const TAG_JPEG_INTERCHANGE_FORMAT_LENGTH: u16 = 0x0202;
+
+/// TIFF compression values used for JPEG image data (old-style and new-style).
+const JPEG_COMPRESSIONS: [u32; 2] = [6, 7];
+
+/// Upper bound on visited IFDs, guarding against cyclic or malicious files.
+const MAX_IFDS: usize = 64;
+
+/// Upper bound on values read from a single IFD entry.
+const MAX_ENTRY_VALUES: u32 = 256;
+
+/// Returns whether the path has a RAW extension handled by this module.
+pub fn is_raw_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| crate::config::RAW_IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Decodes the largest embedded JPEG preview of a RAW file.
+///
+/// Lossless JPEG streams (used for RAW data in DNG) cannot be decoded and are skipped.
+pub fn decode_embedded_preview(data: &[u8]) -> Result<image::DynamicImage> {
+    for candidate in jpeg_candidates(data) {
+        match image::load_from_memory_with_format(candidate, ImageFormat::Jpeg) {
+            Ok(img) => return Ok(img),
+            Err(e) => debug!(
+                "Skipping undecodable embedded JPEG ({} bytes): {}",
+                candidate.len(),
+                e
+            ),
+        }
+    }
+
+    Err(AppError::ImageLoad(
+        "No embedded JPEG preview found".to_string(),
+    ))
+}
+
+/// Byte-order aware reader over TIFF data.
+struct TiffReader<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+impl<'a> TiffReader<'a> {
+    /// Creates a reader if the data starts with a TIFF header.
+    fn new(data: &'a [u8]) -> Option<Self> {
+        let little_endian = match data.get(..2)? {
+            b"II" => true,
+            b"MM" => false,
+            _ => return None,
+        };
+        // CR2 and NEF use the regular TIFF magic, ORF / RW2 variants are not handled
+        let reader = Self {
+            data,
+            little_endian,
+        };
+        (reader.u16(2)? == 42).then_some(reader)
+    }
+
+    fn u16(&self, offset: usize) -> Option<u16> {
+        let bytes: [u8; 2] = self.data.get(offset..offset + 2)?.try_into().ok()?;
+        Some(if self.little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        let bytes: [u8; 4] = self.data.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    /// Reads the tag and integer values of the 12-byte IFD entry at `offset`.
+    ///
+    /// Only SHORT, LONG and IFD typed entries are read; others yield no values.
+    fn entry(&self, offset: usize) -> Option<(u16, Vec<u32>)> {
+        let tag = self.u16(offset)?;
+        let field_type = self.u16(offset + 2)?;
+        let count = self.u32(offset + 4)?.min(MAX_ENTRY_VALUES) as usize;
+
+        let size = match field_type {
+            3 => 2,
+            4 | 13 => 4,
+            _ => return Some((tag, Vec::new())),
+        };
+        // 4バイト以内の値はエントリ内に直接格納される
+        let values_offset = if size * count <= 4 {
+            offset + 8
+        } else {
+            self.u32(offset + 8)? as usize
+        };
+
+        let values = (0..count)
+            .map(|i| {
+                let at = values_offset + i * size;
+                if size == 2 {
+                    self.u16(at).map(u32::from)
+                } else {
+                    self.u32(at)
+                }
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some((tag, values))
+    }
+}
+
+/// Collects embedded JPEG streams from all IFDs, largest first.
+fn jpeg_candidates(data: &[u8]) -> Vec<&[u8]> {
+    let Some(reader) = TiffReader::new(data) else {
+        return Vec::new();
+    };
+
+    let mut candidates: Vec<&[u8]> = Vec::new();
+    let mut pending: Vec<usize> = reader.u32(4).map(|o| o as usize).into_iter().collect();
+    let mut visited: Vec<usize> = Vec::new();
+
+    while let Some(ifd) = pending.pop() {
+        if ifd == 0 || visited.len() >= MAX_IFDS || visited.contains(&ifd) {
+            continue;
+        }
+        visited.push(ifd);
+
+        let Some(entry_count) = reader.u16(ifd) else {
+            continue;
+        };
+        let fields: HashMap<u16, Vec<u32>> = (0..entry_count as usize)
+            .filter_map(|i| reader.entry(ifd + 2 + i * 12))
+            .collect();
+        let first = |tag: u16| fields.get(&tag).and_then(|values| values.first().copied());
+
+        // NEFなどのサムネイル形式
+        if let (Some(offset), Some(length)) = (
+            first(TAG_JPEG_INTERCHANGE_FORMAT),
+            first(TAG_JPEG_INTERCHANGE_FORMAT_LENGTH),
+        ) {
+            candidates.extend(slice(data, offset, length));
+        }
+
+        // CR2やDNGのプレビューは単一ストリップのJPEGとして格納される
+        let is_jpeg = first(TAG_COMPRESSION).is_some_and(|c| JPEG_COMPRESSIONS.contains(&c));
+        if let (true, Some(offsets), Some(lengths)) = (
+            is_jpeg,
+            fields.get(&TAG_STRIP_OFFSETS),
+            fields.get(&TAG_STRIP_BYTE_COUNTS),
+        ) && offsets.len() == 1
+            && lengths.len() == 1
+        {
+            candidates.extend(slice(data, offsets[0], lengths[0]));
+        }
+
+        if let Some(sub_ifds) = fields.get(&TAG_SUB_IFDS) {
+            pending.extend(sub_ifds.iter().map(|&o| o as usize));
+        }
+        if let Some(next) = reader.u32(ifd + 2 + entry_count as usize * 12) {
+            pending.push(next as usize);
+        }
+    }
+
+    candidates.retain(|candidate| candidate.starts_with(&[0xFF, 0xD8]));
+    candidates.sort_by_key(|candidate| std::cmp::Reverse(candidate.len()));
+    candidates.dedup();
+    candidates
+}
+
+/// Returns `data[offset..offset + length]` if it is in bounds.
+fn slice(data: &[u8], offset: u32, length: u32) -> Option<&[u8]> {
+    let start = offset as usize;
+    let end = start.checked_add(length as usize)?;
+    data.get(start..end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jpeg(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let pixels = vec![128u8; width as usize * height as usize * 3];
+        image::codecs::jpeg::JpegEncoder::new(&mut bytes)
+            .encode(&pixels, width, height, image::ExtendedColorType::Rgb8)
+            .unwrap();
+        bytes
+    }
+
+    /// Appends a little-endian IFD of LONG entries followed by a zero next-IFD offset.
+    fn push_ifd(out: &mut Vec<u8>, entries: &[(u16, u32)]) {
+        out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        for (tag, value) in entries {
+            out.extend_from_slice(&tag.to_le_bytes());
+            out.extend_from_slice(&4u16.to_le_bytes());
+            out.extend_from_slice(&1u32.to_le_bytes());
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        out.extend_from_slice(&0u32.to_le_bytes());
+    }
+
+    /// Builds a DNG-like TIFF with a thumbnail in IFD0 and a larger preview strip in a SubIFD.
+    fn dng_with_previews(thumbnail: &[u8], preview: &[u8]) -> Vec<u8> {
+        const IFD0: u32 = 8;
+        const IFD0_SIZE: u32 = 2 + 3 * 12 + 4;
+        const SUB_IFD: u32 = IFD0 + IFD0_SIZE;
+        const DATA: u32 = SUB_IFD + IFD0_SIZE;
+        let preview_offset = DATA + thumbnail.len() as u32;
+
+        let mut dng = b"II".to_vec();
+        dng.extend_from_slice(&42u16.to_le_bytes());
+        dng.extend_from_slice(&IFD0.to_le_bytes());
+        push_ifd(
+            &mut dng,
+            &[
+                (TAG_SUB_IFDS, SUB_IFD),
+                (TAG_JPEG_INTERCHANGE_FORMAT, DATA),
+                (TAG_JPEG_INTERCHANGE_FORMAT_LENGTH, thumbnail.len() as u32),
+            ],
+        );
+        push_ifd(
+            &mut dng,
+            &[
+                (TAG_COMPRESSION, 7),
+                (TAG_STRIP_OFFSETS, preview_offset),
+                (TAG_STRIP_BYTE_COUNTS, preview.len() as u32),
+            ],
+        );
+        dng.extend_from_slice(thumbnail);
+        dng.extend_from_slice(preview);
+        dng
+    }
+
+    #[test]
+    fn decodes_largest_embedded_preview() {
+        let dng = dng_with_previews(&jpeg(16, 8), &jpeg(64, 32));
+
+        assert_eq!(jpeg_candidates(&dng).len(), 2);
+        let preview = decode_embedded_preview(&dng).unwrap();
+        assert_eq!((preview.width(), preview.height()), (64, 32));
+    }
+
+    #[test]
+    fn non_tiff_data_has_no_preview() {
+        assert!(jpeg_candidates(b"not a raw file").is_empty());
+        assert!(matches!(
+            decode_embedded_preview(&jpeg(4, 4)),
+            Err(AppError::ImageLoad(_))
+        ));
+    }
+
+    #[test]
+    fn raw_extensions_are_case_insensitive() {
+        assert!(is_raw_file(Path::new("photo.DNG")));
+        assert!(is_raw_file(Path::new("photo.nef")));
+        assert!(!is_raw_file(Path::new("photo.jpg")));
+    }
+}
@@ -0,0 +1,171 @@
+//! Recently opened files persisted across sessions.
+//!
+//! Stored as JSON next to the settings file and kept in memory behind a
+//! global lock, mirroring how settings are handled.
+
+use crate::config::RECENT_FILES_CAPACITY;
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// File name of the recent files list inside the configuration directory.
+const RECENT_FILES_FILE_NAME: &str = "recent_files.json";
+
+/// Most-recently-used list of opened files, newest first.
+#[derive(Debug, Clone)]
+pub struct RecentFiles {
+    paths: Vec<PathBuf>,
+    capacity: usize,
+}
+
+impl RecentFiles {
+    /// Creates an empty list that keeps at most `capacity` entries.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            paths: Vec::new(),
+            capacity,
+        }
+    }
+
+    /// Creates a list from stored paths, trimming it to `capacity`.
+    pub fn from_paths(paths: Vec<PathBuf>, capacity: usize) -> Self {
+        let mut recent = Self::new(capacity);
+        for path in paths.into_iter().rev() {
+            recent.add(path);
+        }
+        recent
+    }
+
+    /// Adds a path to the front, moving it there if it is already listed.
+    pub fn add(&mut self, path: PathBuf) {
+        self.paths.retain(|p| p != &path);
+        self.paths.insert(0, path);
+        self.paths.truncate(self.capacity);
+    }
+
+    /// Returns all entries, newest first.
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Returns the entries whose files still exist, newest first.
+    pub fn existing(&self) -> Vec<PathBuf> {
+        self.paths.iter().filter(|p| p.is_file()).cloned().collect()
+    }
+}
+
+static RECENT_FILES: Lazy<Mutex<RecentFiles>> = Lazy::new(|| Mutex::new(load()));
+
+/// Returns the path of the recent files list.
+fn recent_files_path() -> Option<PathBuf> {
+    crate::settings::config_dir().map(|dir| dir.join(RECENT_FILES_FILE_NAME))
+}
+
+/// Loads the recent files list from disk, returning an empty list on failure.
+fn load() -> RecentFiles {
+    let paths = recent_files_path()
+        .and_then(|path| match std::fs::read_to_string(&path) {
+            Ok(json) => Some(json),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                warn!("Failed to read recent files from {:?}: {}", path, e);
+                None
+            }
+        })
+        .and_then(|json| match serde_json::from_str::<Vec<PathBuf>>(&json) {
+            Ok(paths) => Some(paths),
+            Err(e) => {
+                warn!("Failed to parse recent files: {}", e);
+                None
+            }
+        })
+        .unwrap_or_default();
+
+    RecentFiles::from_paths(paths, RECENT_FILES_CAPACITY)
+}
+
+/// Writes the recent files list to disk.
+fn save(recent: &RecentFiles) -> std::io::Result<()> {
+    let path = recent_files_path().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "No configuration directory available",
+        )
+    })?;
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let json = serde_json::to_string_pretty(recent.paths()).map_err(std::io::Error::other)?;
+    std::fs::write(&path, json)
+}
+
+/// Records a successfully opened file and persists the list.
+///
+/// Paths that are not valid UTF-8 are skipped, since the JSON list cannot store them.
+pub fn record(path: &Path) {
+    if path.to_str().is_none() {
+        info!("Not recording non-UTF-8 path as recent: {:?}", path);
+        return;
+    }
+
+    let snapshot = {
+        let mut recent = RECENT_FILES.lock().unwrap_or_else(|e| e.into_inner());
+        recent.add(path.to_path_buf());
+        recent.clone()
+    };
+
+    info!("Recorded recent file: {:?}", path);
+    if let Err(e) = save(&snapshot) {
+        warn!("Failed to save recent files: {}", e);
+    }
+}
+
+/// Returns the recent files that still exist, newest first.
+pub fn existing() -> Vec<PathBuf> {
+    RECENT_FILES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .existing()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_moves_existing_path_to_front() {
+        let mut recent = RecentFiles::new(3);
+        recent.add(PathBuf::from("a.png"));
+        recent.add(PathBuf::from("b.png"));
+        recent.add(PathBuf::from("a.png"));
+        assert_eq!(
+            recent.paths(),
+            [PathBuf::from("a.png"), PathBuf::from("b.png")]
+        );
+    }
+
+    #[test]
+    fn add_drops_oldest_beyond_capacity() {
+        let mut recent = RecentFiles::new(2);
+        for name in ["a.png", "b.png", "c.png"] {
+            recent.add(PathBuf::from(name));
+        }
+        assert_eq!(
+            recent.paths(),
+            [PathBuf::from("c.png"), PathBuf::from("b.png")]
+        );
+    }
+
+    #[test]
+    fn from_paths_keeps_stored_order_and_trims() {
+        let stored = ["a.png", "b.png", "a.png", "c.png"].map(PathBuf::from);
+        let recent = RecentFiles::from_paths(stored.to_vec(), 2);
+        assert_eq!(
+            recent.paths(),
+            [PathBuf::from("a.png"), PathBuf::from("b.png")]
+        );
+    }
+}
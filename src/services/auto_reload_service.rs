@@ -2,30 +2,123 @@
 //!
 //! Provides directory monitoring and change detection for auto-reload feature.
 
-use crate::config::SUPPORTED_IMAGE_EXTENSIONS;
 use crate::error::NavigationError;
-use crate::file_utils::PathExt;
-use crate::services::NavigationService;
-use log::{debug, warn};
-use notify_debouncer_mini::{new_debouncer_opt, notify::RecursiveMode, Config};
-use std::path::PathBuf;
+use crate::file_utils::{self, PathExt};
+use crate::services::{NavigationService, lock_or_recover};
+use crate::state::{AutoReloadDebouncer, AutoReloadWatcher};
+use log::{debug, info, warn};
+use notify_debouncer_mini::{
+    Config, Debouncer, new_debouncer_opt,
+    notify::{RecommendedWatcher, RecursiveMode, Watcher},
+};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
+/// File system watcher backend used for auto-reload.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatcherBackend {
+    /// Native notifications on local paths, polling on network paths.
+    #[default]
+    Auto,
+    /// Platform native file system notifications.
+    Native,
+    /// Periodic polling of the directory.
+    Poll,
+}
+
+impl WatcherBackend {
+    /// Resolves `Auto` to a concrete backend depending on whether the directory is remote.
+    pub fn resolve(self, is_network_path: bool) -> Self {
+        match self {
+            Self::Auto if is_network_path => Self::Poll,
+            Self::Auto => Self::Native,
+            backend => backend,
+        }
+    }
+}
+
+/// What auto-reload does when new images appear in the watched directory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoReloadMode {
+    /// Jump to the newest image.
+    #[default]
+    FollowNewest,
+    /// Only rescan the directory, keeping the current image selected.
+    StayOnCurrent,
+}
+
+impl AutoReloadMode {
+    /// Returns the other mode.
+    pub fn toggled(self) -> Self {
+        match self {
+            Self::FollowNewest => Self::StayOnCurrent,
+            Self::StayOnCurrent => Self::FollowNewest,
+        }
+    }
+}
+
+/// Change reported to the auto-reload callback after a rescan.
+#[derive(Debug, Clone)]
+pub enum AutoReloadEvent {
+    /// The newest image was selected and should be displayed.
+    Newest(PathBuf),
+    /// The list was rescanned without moving the selection.
+    /// Carries the 1-based position of the current image and the image count.
+    Rescanned { current: i32, total: i32 },
+    /// The rescan left no image in the list, so the view should be cleared.
+    Emptied,
+}
+
+/// Returns whether a watcher on `watched` has to move to `current` to keep
+/// following the open directory.
+///
+/// Only a running watcher (`watched` is set) is moved, and only when the
+/// directory changed.
+pub fn needs_retarget(watched: Option<&Path>, current: Option<&Path>) -> bool {
+    matches!((watched, current), (Some(watched), Some(current)) if watched != current)
+}
+
 /// Service for managing auto-reload checks.
 pub struct AutoReloadService {
     navigation_service: NavigationService,
 }
 
 /// Handles debounced file system events.
+///
+/// Passes the change from [`reload_event`] to `on_change` on the UI thread.
 fn handle_debounced_events<F>(
     events: Vec<notify_debouncer_mini::DebouncedEvent>,
     navigation_service: &NavigationService,
+    mode: AutoReloadMode,
     on_change: &std::sync::Arc<F>,
 ) where
-    F: Fn(PathBuf) + Send + Sync + 'static,
+    F: Fn(AutoReloadEvent) + Send + Sync + 'static,
 {
-    if events.is_empty() {
+    let Some(event) = reload_event(events, navigation_service, mode) else {
         return;
+    };
+    let on_change_clone = on_change.clone();
+    let _ = slint::invoke_from_event_loop(move || {
+        on_change_clone(event);
+    });
+}
+
+/// Rescans the directory for debounced file system events.
+///
+/// Then either jumps to the newest image or keeps the current one depending
+/// on `mode`. An empty list is reported as [`AutoReloadEvent::Emptied`]
+/// regardless of `mode`. Returns `None` when no image file changed or the
+/// rescan failed.
+fn reload_event(
+    events: Vec<notify_debouncer_mini::DebouncedEvent>,
+    navigation_service: &NavigationService,
+    mode: AutoReloadMode,
+) -> Option<AutoReloadEvent> {
+    if events.is_empty() {
+        return None;
     }
 
     // Filter out non-image files - we only care about supported image formats
@@ -36,15 +129,13 @@ fn handle_debounced_events<F>(
                 .path
                 .extension()
                 .and_then(|ext| ext.to_str())
-                .map(|ext_str| {
-                    SUPPORTED_IMAGE_EXTENSIONS.contains(&ext_str.to_lowercase().as_str())
-                })
+                .map(file_utils::is_supported_extension)
                 .unwrap_or(false)
         })
         .collect();
 
     if file_events.is_empty() {
-        return;
+        return None;
     }
 
     debug!("Debounced file system events: {} events", file_events.len());
@@ -52,23 +143,34 @@ fn handle_debounced_events<F>(
         debug!("  - {:?} for {}", event.kind, event.path.format_for_log());
     }
 
-    if let Err(e) = navigation_service.rescan_directory() {
-        warn!("Failed to rescan directory: {}", e);
-        return;
-    }
-
-    debug!("Navigating to last image");
-    let path = match navigation_service.navigate_to_last() {
-        Ok(path) => path,
+    let event = match navigation_service.rescan_directory() {
+        Ok(0) => {
+            debug!("No images left after rescan");
+            AutoReloadEvent::Emptied
+        }
+        Ok(_) => match mode {
+            AutoReloadMode::FollowNewest => {
+                debug!("Navigating to last image");
+                match navigation_service.navigate_to_last() {
+                    Ok(path) => AutoReloadEvent::Newest(path),
+                    Err(e) => {
+                        warn!("Failed to navigate to last image: {}", e);
+                        return None;
+                    }
+                }
+            }
+            AutoReloadMode::StayOnCurrent => {
+                let (current, total) = navigation_service.position();
+                debug!("Staying on current image ({}/{})", current, total);
+                AutoReloadEvent::Rescanned { current, total }
+            }
+        },
         Err(e) => {
-            warn!("Failed to navigate to last image: {}", e);
-            return;
+            warn!("Failed to rescan directory: {}", e);
+            return None;
         }
     };
-    let on_change_clone = on_change.clone();
-    let _ = slint::invoke_from_event_loop(move || {
-        on_change_clone(path);
-    });
+    Some(event)
 }
 
 impl AutoReloadService {
@@ -81,40 +183,87 @@ impl AutoReloadService {
     ///
     /// Returns a `Debouncer` that monitors the directory for file changes.
     /// When changes are detected (after a 300ms debounce period), it rescans
-    /// the directory and navigates to the last image.
+    /// the directory and, depending on the `auto_reload_mode` setting at that
+    /// time, navigates to the last image or keeps the current one.
+    ///
+    /// The watcher backend comes from the `watcher_backend` setting. `Auto`
+    /// uses native notifications on local paths and polling on network paths.
     pub fn start_watching<F>(
         &self,
         state: std::sync::Arc<std::sync::Mutex<crate::state::NavigationState>>,
         on_change: F,
-    ) -> Result<crate::state::AutoReloadDebouncer, NavigationError>
+    ) -> Result<AutoReloadWatcher, NavigationError>
     where
-        F: Fn(PathBuf) + Send + Sync + 'static,
+        F: Fn(AutoReloadEvent) + Send + Sync + 'static,
     {
         // Get the current directory to watch
         let directory = {
-            let state_lock = state.lock().map_err(|_| {
-                NavigationError::DirectoryScanFailed("Failed to lock state".to_string())
-            })?;
+            let state_lock = lock_or_recover(&state);
             state_lock.get_current_directory().ok_or_else(|| {
                 NavigationError::DirectoryScanFailed("No directory selected".to_string())
             })?
         };
 
-        let navigation_service = self.navigation_service.clone();
         let on_change = std::sync::Arc::new(on_change);
+        let backend = crate::settings::current()
+            .watcher_backend
+            .resolve(file_utils::is_network_path(&directory));
+
+        let mut debouncer = match backend {
+            WatcherBackend::Native => {
+                match self.create_debouncer::<RecommendedWatcher, F>(&on_change) {
+                    Ok(debouncer) => AutoReloadDebouncer::Native(debouncer),
+                    Err(e) => {
+                        warn!("Native watcher unavailable, falling back to polling: {}", e);
+                        AutoReloadDebouncer::Poll(self.create_debouncer(&on_change)?)
+                    }
+                }
+            }
+            _ => AutoReloadDebouncer::Poll(self.create_debouncer(&on_change)?),
+        };
+        info!(
+            "Watching {} with {:?} backend",
+            directory.format_for_log(),
+            backend
+        );
+
+        // Start watching the directory (non-recursive)
+        debouncer
+            .watcher()
+            .watch(&directory, RecursiveMode::NonRecursive)
+            .map_err(|e| {
+                NavigationError::DirectoryScanFailed(format!("Failed to watch directory: {}", e))
+            })?;
+
+        Ok(AutoReloadWatcher::new(debouncer, directory))
+    }
+
+    /// Creates a debounced watcher using the watcher type `T`.
+    ///
+    /// Events are debounced for 500ms. The poll interval only applies to `PollWatcher`.
+    fn create_debouncer<T, F>(
+        &self,
+        on_change: &std::sync::Arc<F>,
+    ) -> Result<Debouncer<T>, NavigationError>
+    where
+        T: Watcher,
+        F: Fn(AutoReloadEvent) + Send + Sync + 'static,
+    {
+        let navigation_service = self.navigation_service.clone();
+        let on_change = on_change.clone();
 
-        // Create a debounced watcher with 300ms debounce period using PollWatcher backend
         let notify_config = notify_debouncer_mini::notify::Config::default()
             .with_poll_interval(Duration::from_secs(2));
         let debouncer_config = Config::default()
             .with_timeout(Duration::from_millis(500))
             .with_notify_config(notify_config);
 
-        let mut debouncer = new_debouncer_opt::<_, notify_debouncer_mini::notify::PollWatcher>(
+        new_debouncer_opt::<_, T>(
             debouncer_config,
             move |res: notify_debouncer_mini::DebounceEventResult| match res {
                 Ok(events) => {
-                    handle_debounced_events(events, &navigation_service, &on_change);
+                    let mode = crate::settings::current().auto_reload_mode;
+                    handle_debounced_events(events, &navigation_service, mode, &on_change);
                 }
                 Err(error) => {
                     let error_msg = error.to_string();
@@ -126,17 +275,7 @@ impl AutoReloadService {
         )
         .map_err(|e| {
             NavigationError::DirectoryScanFailed(format!("Failed to create debouncer: {}", e))
-        })?;
-
-        // Start watching the directory (non-recursive) using PollWatcher backend
-        debouncer
-            .watcher()
-            .watch(&directory, RecursiveMode::NonRecursive)
-            .map_err(|e| {
-                NavigationError::DirectoryScanFailed(format!("Failed to watch directory: {}", e))
-            })?;
-
-        Ok(debouncer)
+        })
     }
 
     /// Navigates to the last image without checking for changes.
@@ -144,3 +283,117 @@ impl AutoReloadService {
         self.navigation_service.navigate_to_last()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_backend_polls_only_network_paths() {
+        assert_eq!(WatcherBackend::Auto.resolve(false), WatcherBackend::Native);
+        assert_eq!(WatcherBackend::Auto.resolve(true), WatcherBackend::Poll);
+    }
+
+    #[test]
+    fn explicit_backend_is_kept() {
+        for is_network_path in [false, true] {
+            assert_eq!(
+                WatcherBackend::Native.resolve(is_network_path),
+                WatcherBackend::Native
+            );
+            assert_eq!(
+                WatcherBackend::Poll.resolve(is_network_path),
+                WatcherBackend::Poll
+            );
+        }
+    }
+
+    #[test]
+    fn watcher_backend_setting_parses() {
+        let settings = crate::settings::AppSettings::from_json(r#"{"watcher_backend": "poll"}"#);
+        assert_eq!(settings.watcher_backend, WatcherBackend::Poll);
+        let settings = crate::settings::AppSettings::from_json("{}");
+        assert_eq!(settings.watcher_backend, WatcherBackend::Auto);
+    }
+
+    /// Opens `a.png` of a directory with `a.png` and `b.png`, then adds `c.png`.
+    fn directory_with_new_file() -> (
+        tempfile::TempDir,
+        NavigationService,
+        Vec<notify_debouncer_mini::DebouncedEvent>,
+    ) {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["a.png", "b.png"] {
+            std::fs::write(dir.path().join(name), b"").unwrap();
+        }
+        let mut nav_state = crate::state::NavigationState::new();
+        nav_state
+            .update_directory(dir.path().join("a.png"))
+            .unwrap();
+        let service = NavigationService::new(
+            std::sync::Arc::new(std::sync::Mutex::new(nav_state)),
+            Default::default(),
+        );
+
+        let created = dir.path().join("c.png");
+        std::fs::write(&created, b"").unwrap();
+        let events = vec![notify_debouncer_mini::DebouncedEvent {
+            path: created,
+            kind: notify_debouncer_mini::DebouncedEventKind::Any,
+        }];
+        (dir, service, events)
+    }
+
+    #[test]
+    fn follow_newest_jumps_to_created_file() {
+        let (dir, service, events) = directory_with_new_file();
+        let event = reload_event(events, &service, AutoReloadMode::FollowNewest);
+        assert!(
+            matches!(&event, Some(AutoReloadEvent::Newest(path)) if *path == dir.path().join("c.png")),
+            "{:?}",
+            event
+        );
+    }
+
+    #[test]
+    fn stay_on_current_keeps_selection() {
+        let (_dir, service, events) = directory_with_new_file();
+        let event = reload_event(events, &service, AutoReloadMode::StayOnCurrent);
+        assert!(
+            matches!(
+                event,
+                Some(AutoReloadEvent::Rescanned {
+                    current: 1,
+                    total: 3
+                })
+            ),
+            "{:?}",
+            event
+        );
+    }
+
+    #[test]
+    fn non_image_events_are_ignored() {
+        let (dir, service, _) = directory_with_new_file();
+        let events = vec![notify_debouncer_mini::DebouncedEvent {
+            path: dir.path().join("notes.txt"),
+            kind: notify_debouncer_mini::DebouncedEventKind::Any,
+        }];
+        assert!(reload_event(events, &service, AutoReloadMode::FollowNewest).is_none());
+    }
+
+    #[test]
+    fn retargets_running_watcher_on_directory_change() {
+        assert!(needs_retarget(Some(Path::new("/a")), Some(Path::new("/b"))));
+    }
+
+    #[test]
+    fn keeps_watcher_on_same_or_no_directory() {
+        assert!(!needs_retarget(
+            Some(Path::new("/a")),
+            Some(Path::new("/a"))
+        ));
+        assert!(!needs_retarget(Some(Path::new("/a")), None));
+        assert!(!needs_retarget(None, Some(Path::new("/b"))));
+    }
+}
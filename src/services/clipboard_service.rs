@@ -1,8 +1,10 @@
-//! Clipboard service for copying file paths to OS clipboard.
+//! Clipboard service for copying file paths and text to OS clipboard.
 //!
 //! Supports macOS, Windows, and Linux with platform-specific implementations
-//! for copying file lists in native formats.
+//! for copying file lists and plain text in native formats. Encoded image
+//! bytes can be copied on macOS and Windows.
 
+use crate::messages::{self, Message};
 use log::info;
 use std::fmt;
 use std::path::PathBuf;
@@ -17,19 +19,27 @@ pub enum ClipboardError {
     InvalidPaths,
     /// One or more files do not exist.
     FileNotFound(PathBuf),
+    /// The MIME type has no clipboard type on this platform.
+    UnsupportedType(String),
     /// Platform-specific error occurred.
     PlatformError(String),
 }
 
 impl fmt::Display for ClipboardError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::EmptyPaths => write!(f, "No files to copy"),
+        let text = match self {
+            Self::EmptyPaths => messages::localized(Message::NoFilesToCopy, &[]),
             #[cfg(any(target_os = "macos", target_os = "linux"))]
-            Self::InvalidPaths => write!(f, "No valid file paths"),
-            Self::FileNotFound(path) => write!(f, "File not found: {:?}", path),
-            Self::PlatformError(msg) => write!(f, "{}", msg),
-        }
+            Self::InvalidPaths => messages::localized(Message::NoValidPaths, &[]),
+            Self::FileNotFound(path) => {
+                messages::localized(Message::FileNotFound, &[&path.display()])
+            }
+            Self::UnsupportedType(mime) => {
+                messages::localized(Message::UnsupportedClipboardType, &[mime])
+            }
+            Self::PlatformError(msg) => msg.clone(),
+        };
+        f.write_str(&text)
     }
 }
 
@@ -40,8 +50,8 @@ use {
     objc2::rc::{autoreleasepool, Retained},
     objc2::runtime::ProtocolObject,
     objc2::{msg_send, ClassType},
-    objc2_app_kit::{NSPasteboard, NSPasteboardWriting},
-    objc2_foundation::{NSArray, NSString, NSURL},
+    objc2_app_kit::{NSPasteboard, NSPasteboardTypeString, NSPasteboardWriting},
+    objc2_foundation::{NSArray, NSData, NSString, NSURL},
 };
 
 #[cfg(target_os = "windows")]
@@ -51,16 +61,56 @@ use {
         Win32::Foundation::{HANDLE, HWND},
         Win32::System::Com::{CoInitialize, CoUninitialize},
         Win32::System::DataExchange::{
-            CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData,
+            CloseClipboard, EmptyClipboard, OpenClipboard, RegisterClipboardFormatW,
+            SetClipboardData,
         },
         Win32::System::Memory::{GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE},
         Win32::UI::Shell::DROPFILES,
+        core::HSTRING,
     },
 };
 
 #[cfg(target_os = "linux")]
 use arboard::Clipboard;
 
+/// Clipboard type names of encoded images: MIME type, macOS UTI and Windows
+/// registered format name.
+///
+/// Windows has no widely read registered format for WebP, BMP or ICO data.
+const ENCODED_IMAGE_TYPES: [(&str, &str, Option<&str>); 6] = [
+    ("image/png", "public.png", Some("PNG")),
+    ("image/jpeg", "public.jpeg", Some("JFIF")),
+    ("image/gif", "com.compuserve.gif", Some("GIF")),
+    ("image/webp", "org.webmproject.webp", None),
+    ("image/bmp", "com.microsoft.bmp", None),
+    ("image/x-icon", "com.microsoft.ico", None),
+];
+
+/// Returns the clipboard type that encoded image bytes of `mime` are placed under.
+///
+/// This is the pasteboard type (UTI) on macOS and the registered clipboard
+/// format name on Windows. Linux is not supported, as arboard only writes
+/// text, bitmaps and file lists.
+pub fn clipboard_type_for(mime: &str) -> Result<&'static str, ClipboardError> {
+    let entry = ENCODED_IMAGE_TYPES
+        .iter()
+        .find(|(known, _, _)| known.eq_ignore_ascii_case(mime));
+
+    #[cfg(target_os = "macos")]
+    let clipboard_type = entry.map(|(_, uti, _)| *uti);
+
+    #[cfg(target_os = "windows")]
+    let clipboard_type = entry.and_then(|(_, _, format_name)| *format_name);
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let clipboard_type: Option<&'static str> = {
+        let _ = entry;
+        None
+    };
+
+    clipboard_type.ok_or_else(|| ClipboardError::UnsupportedType(mime.to_string()))
+}
+
 /// Service for managing clipboard operations.
 pub struct ClipboardService;
 
@@ -100,6 +150,66 @@ impl ClipboardService {
         }
     }
 
+    /// Copies plain text to the clipboard.
+    pub fn copy_text(&self, text: &str) -> Result<(), ClipboardError> {
+        info!("Copying {} bytes of text to clipboard", text.len());
+
+        #[cfg(target_os = "macos")]
+        {
+            self.copy_text_macos(text)
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            self.copy_text_windows(text)
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            self.copy_text_linux(text)
+        }
+
+        #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+        {
+            let _ = text;
+            Err(ClipboardError::PlatformError(
+                "Clipboard operation not supported on this platform".to_string(),
+            ))
+        }
+    }
+
+    /// Copies encoded image bytes (e.g. a PNG file as is) under the clipboard type of `mime`.
+    ///
+    /// Unlike a bitmap copy, the pasted data keeps its embedded metadata.
+    pub fn copy_encoded(&self, bytes: &[u8], mime: &str) -> Result<(), ClipboardError> {
+        let clipboard_type = clipboard_type_for(mime)?;
+        info!(
+            "Copying {} bytes of {} to clipboard as {}",
+            bytes.len(),
+            mime,
+            clipboard_type
+        );
+
+        #[cfg(target_os = "macos")]
+        {
+            self.copy_encoded_macos(bytes, clipboard_type)
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            self.copy_encoded_windows(bytes, clipboard_type)
+        }
+
+        #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+        {
+            let _ = bytes;
+            Err(ClipboardError::PlatformError(format!(
+                "Copying {} data is not supported on this platform",
+                clipboard_type
+            )))
+        }
+    }
+
     /// Validates that paths are not empty and all files exist.
     fn validate_paths(paths: &[PathBuf]) -> Result<(), ClipboardError> {
         if paths.is_empty() {
@@ -181,6 +291,60 @@ impl ClipboardService {
         })
     }
 
+    /// macOS implementation: Copy text using NSPasteboard string type
+    #[cfg(target_os = "macos")]
+    fn copy_text_macos(&self, text: &str) -> Result<(), ClipboardError> {
+        autoreleasepool(|_| {
+            let pasteboard: Option<Retained<NSPasteboard>> =
+                unsafe { msg_send![NSPasteboard::class(), generalPasteboard] };
+
+            let pasteboard = pasteboard.ok_or_else(|| {
+                ClipboardError::PlatformError("Failed to get pasteboard".to_string())
+            })?;
+
+            pasteboard.clearContents();
+
+            let ns_string = NSString::from_str(text);
+            let string_type = unsafe { NSPasteboardTypeString };
+
+            if pasteboard.setString_forType(&ns_string, string_type) {
+                info!("Successfully copied text to clipboard");
+                Ok(())
+            } else {
+                Err(ClipboardError::PlatformError(
+                    "Failed to write to clipboard".to_string(),
+                ))
+            }
+        })
+    }
+
+    /// macOS implementation: Copy encoded bytes as NSData under a pasteboard type
+    #[cfg(target_os = "macos")]
+    fn copy_encoded_macos(&self, bytes: &[u8], uti: &str) -> Result<(), ClipboardError> {
+        autoreleasepool(|_| {
+            let pasteboard: Option<Retained<NSPasteboard>> =
+                unsafe { msg_send![NSPasteboard::class(), generalPasteboard] };
+
+            let pasteboard = pasteboard.ok_or_else(|| {
+                ClipboardError::PlatformError("Failed to get pasteboard".to_string())
+            })?;
+
+            pasteboard.clearContents();
+
+            let data = NSData::with_bytes(bytes);
+            let data_type = NSString::from_str(uti);
+
+            if pasteboard.setData_forType(Some(&data), &data_type) {
+                info!("Successfully copied image data to clipboard");
+                Ok(())
+            } else {
+                Err(ClipboardError::PlatformError(
+                    "Failed to write to clipboard".to_string(),
+                ))
+            }
+        })
+    }
+
     /// Windows implementation: Copy files using CF_HDROP format
     #[cfg(target_os = "windows")]
     fn copy_files_windows(&self, paths: Vec<PathBuf>) -> Result<(), ClipboardError> {
@@ -226,10 +390,14 @@ impl ClipboardService {
                 buffer.resize(dropfiles_size, 0u8);
 
                 for path in &paths {
-                    let wide_path: Vec<u16> = std::ffi::OsStr::new(path)
-                        .encode_wide()
-                        .chain(std::iter::once(0))
-                        .collect();
+                    // Apps reading the file list don't reliably accept \\?\UNC\ paths
+                    let plain = path.to_str().map(crate::file_utils::to_plain_path);
+                    let path = match &plain {
+                        Some(plain) => std::ffi::OsStr::new(plain.as_ref()),
+                        None => path.as_os_str(),
+                    };
+                    let wide_path: Vec<u16> =
+                        path.encode_wide().chain(std::iter::once(0)).collect();
 
                     let byte_slice = std::slice::from_raw_parts(
                         wide_path.as_ptr() as *const u8,
@@ -288,6 +456,134 @@ impl ClipboardService {
         result
     }
 
+    /// Windows implementation: Copy text using CF_UNICODETEXT format
+    #[cfg(target_os = "windows")]
+    fn copy_text_windows(&self, text: &str) -> Result<(), ClipboardError> {
+        // RAII guard for clipboard - automatically closes on drop
+        struct ClipboardGuard;
+        impl Drop for ClipboardGuard {
+            fn drop(&mut self) {
+                unsafe {
+                    let _ = CloseClipboard();
+                }
+            }
+        }
+
+        unsafe {
+            OpenClipboard(Some(HWND::default())).map_err(|_| {
+                ClipboardError::PlatformError("Failed to open clipboard".to_string())
+            })?;
+
+            // Guard ensures clipboard is closed even on early return
+            let _guard = ClipboardGuard;
+
+            EmptyClipboard().map_err(|_| {
+                ClipboardError::PlatformError("Failed to clear clipboard".to_string())
+            })?;
+
+            // CF_UNICODETEXT format
+            let cf_unicodetext = 13u32;
+
+            // Null-terminated UTF-16 text
+            let wide_text: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+            let byte_len = wide_text.len() * 2;
+
+            let hmem = GlobalAlloc(GMEM_MOVEABLE, byte_len).map_err(|_| {
+                ClipboardError::PlatformError("Failed to allocate global memory".to_string())
+            })?;
+
+            if hmem.is_invalid() {
+                return Err(ClipboardError::PlatformError(
+                    "Failed to allocate global memory".to_string(),
+                ));
+            }
+
+            let ptr = GlobalLock(hmem);
+            if ptr.is_null() {
+                return Err(ClipboardError::PlatformError(
+                    "Failed to lock global memory".to_string(),
+                ));
+            }
+
+            std::ptr::copy_nonoverlapping(
+                wide_text.as_ptr() as *const u8,
+                ptr as *mut u8,
+                byte_len,
+            );
+            GlobalUnlock(hmem).ok();
+
+            SetClipboardData(cf_unicodetext, Some(HANDLE(hmem.0))).map_err(|_| {
+                ClipboardError::PlatformError("Failed to set clipboard data".to_string())
+            })?;
+        }
+
+        info!("Successfully copied text to clipboard");
+        Ok(())
+    }
+
+    /// Windows implementation: Copy encoded bytes under a registered clipboard format
+    #[cfg(target_os = "windows")]
+    fn copy_encoded_windows(&self, bytes: &[u8], format_name: &str) -> Result<(), ClipboardError> {
+        // RAII guard for clipboard - automatically closes on drop
+        struct ClipboardGuard;
+        impl Drop for ClipboardGuard {
+            fn drop(&mut self) {
+                unsafe {
+                    let _ = CloseClipboard();
+                }
+            }
+        }
+
+        unsafe {
+            // Registering an existing name returns the same format ID
+            let format = RegisterClipboardFormatW(&HSTRING::from(format_name));
+            if format == 0 {
+                return Err(ClipboardError::PlatformError(format!(
+                    "Failed to register clipboard format {}",
+                    format_name
+                )));
+            }
+
+            OpenClipboard(Some(HWND::default())).map_err(|_| {
+                ClipboardError::PlatformError("Failed to open clipboard".to_string())
+            })?;
+
+            // Guard ensures clipboard is closed even on early return
+            let _guard = ClipboardGuard;
+
+            EmptyClipboard().map_err(|_| {
+                ClipboardError::PlatformError("Failed to clear clipboard".to_string())
+            })?;
+
+            let hmem = GlobalAlloc(GMEM_MOVEABLE, bytes.len()).map_err(|_| {
+                ClipboardError::PlatformError("Failed to allocate global memory".to_string())
+            })?;
+
+            if hmem.is_invalid() {
+                return Err(ClipboardError::PlatformError(
+                    "Failed to allocate global memory".to_string(),
+                ));
+            }
+
+            let ptr = GlobalLock(hmem);
+            if ptr.is_null() {
+                return Err(ClipboardError::PlatformError(
+                    "Failed to lock global memory".to_string(),
+                ));
+            }
+
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr as *mut u8, bytes.len());
+            GlobalUnlock(hmem).ok();
+
+            SetClipboardData(format, Some(HANDLE(hmem.0))).map_err(|_| {
+                ClipboardError::PlatformError("Failed to set clipboard data".to_string())
+            })?;
+        }
+
+        info!("Successfully copied image data to clipboard");
+        Ok(())
+    }
+
     /// Linux implementation: Copy files using arboard with file URI list
     #[cfg(target_os = "linux")]
     fn copy_files_linux(&self, paths: Vec<PathBuf>) -> Result<(), ClipboardError> {
@@ -310,4 +606,93 @@ impl ClipboardService {
         info!("Successfully copied files to clipboard");
         Ok(())
     }
+
+    /// Linux implementation: Copy text using arboard
+    #[cfg(target_os = "linux")]
+    fn copy_text_linux(&self, text: &str) -> Result<(), ClipboardError> {
+        let mut clipboard = Clipboard::new().map_err(|e| {
+            ClipboardError::PlatformError(format!("Failed to access clipboard: {}", e))
+        })?;
+
+        clipboard.set_text(text).map_err(|e| {
+            ClipboardError::PlatformError(format!("Failed to set clipboard: {}", e))
+        })?;
+
+        info!("Successfully copied text to clipboard");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clipboard_type_per_format() {
+        #[cfg(target_os = "macos")]
+        {
+            assert_eq!(clipboard_type_for("image/png").unwrap(), "public.png");
+            assert_eq!(clipboard_type_for("IMAGE/JPEG").unwrap(), "public.jpeg");
+            assert_eq!(
+                clipboard_type_for("image/webp").unwrap(),
+                "org.webmproject.webp"
+            );
+        }
+        #[cfg(target_os = "windows")]
+        {
+            assert_eq!(clipboard_type_for("image/png").unwrap(), "PNG");
+            assert_eq!(clipboard_type_for("IMAGE/JPEG").unwrap(), "JFIF");
+            assert!(matches!(
+                clipboard_type_for("image/webp"),
+                Err(ClipboardError::UnsupportedType(_))
+            ));
+        }
+        #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+        assert!(matches!(
+            clipboard_type_for("image/png"),
+            Err(ClipboardError::UnsupportedType(mime)) if mime == "image/png"
+        ));
+
+        assert!(matches!(
+            clipboard_type_for("image/tiff"),
+            Err(ClipboardError::UnsupportedType(_))
+        ));
+    }
+
+    #[test]
+    fn validate_paths_maps_missing_files() {
+        assert!(matches!(
+            ClipboardService::validate_paths(&[]),
+            Err(ClipboardError::EmptyPaths)
+        ));
+
+        let dir = tempfile::tempdir().unwrap();
+        let existing = dir.path().join("a.png");
+        std::fs::write(&existing, b"").unwrap();
+        let missing = dir.path().join("missing.png");
+        assert!(ClipboardService::validate_paths(std::slice::from_ref(&existing)).is_ok());
+
+        let Err(err) = ClipboardService::validate_paths(&[existing, missing.clone()]) else {
+            panic!("missing file accepted");
+        };
+        assert!(matches!(&err, ClipboardError::FileNotFound(path) if *path == missing));
+        assert!(err.to_string().contains("missing.png"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn paths_to_strings_drops_non_utf8_paths() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let invalid = PathBuf::from(std::ffi::OsStr::from_bytes(b"/tmp/\xff.png"));
+        let valid = PathBuf::from("/tmp/a.png");
+        assert_eq!(
+            ClipboardService::paths_to_strings(&[invalid.clone(), valid]).unwrap(),
+            ["/tmp/a.png"]
+        );
+        assert!(matches!(
+            ClipboardService::paths_to_strings(&[invalid]),
+            Err(ClipboardError::InvalidPaths)
+        ));
+    }
 }
@@ -44,20 +44,65 @@ impl fmt::Display for ColorManagementError {
 
 impl std::error::Error for ColorManagementError {}
 
+/// 色変換の変換先プロファイル。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorTarget {
+    /// スクリーンIDのディスプレイICCプロファイル（`None`の場合は先頭ディスプレイ）。
+    Display(Option<u32>),
+    /// ディスプレイに依存しないsRGB。スクリーンショットなどに使う。
+    Srgb,
+}
+
+/// 色管理処理の結果。UIへの表示に使う。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorManagementOutcome {
+    /// 色変換を適用した（または不要だった）。
+    #[default]
+    Success,
+    /// 画像ICCプロファイルが不正なため、sRGBとして扱った。
+    SourceProfileInvalid,
+    /// ディスプレイ側の色管理に失敗し、未補正で表示した。
+    DisplayProfileFailed,
+}
+
+impl ColorManagementOutcome {
+    /// UIに表示する警告メッセージを返す。成功時は空文字列。
+    pub fn ui_message(self) -> &'static str {
+        match self {
+            Self::Success => "",
+            Self::SourceProfileInvalid => "Embedded color profile invalid; displayed as sRGB",
+            Self::DisplayProfileFailed => {
+                "Display color profile unavailable; colors are not corrected"
+            }
+        }
+    }
+}
+
+impl From<&ColorManagementError> for ColorManagementOutcome {
+    fn from(err: &ColorManagementError) -> Self {
+        match err {
+            ColorManagementError::SourceProfileParse(_) => Self::SourceProfileInvalid,
+            ColorManagementError::DisplayProfileLoad(_)
+            | ColorManagementError::DestinationProfileParse(_)
+            | ColorManagementError::TransformCreate(_) => Self::DisplayProfileFailed,
+        }
+    }
+}
+
 /// RGB8画像データに色管理を適用するサービス。
 pub trait ColorManagementService: Send + Sync {
-    /// 画像ICCとディスプレイICCを使って色変換を適用する。
+    /// 画像ICCと変換先のICCを使って色変換を適用する。
     ///
     /// # Arguments
     ///
     /// * `rgb_data` - 変換対象のRGB8データ（in-place変換）
     /// * `image_icc_profile` - 画像に埋め込まれたICCプロファイル（あれば）
-    /// * `screen_id` - 対象ディスプレイのスクリーンID（`None`の場合は先頭ディスプレイ）
+    /// * `target` - 変換先（ディスプレイまたはsRGB）
     fn apply_to_rgb8(
         &self,
         rgb_data: &mut [u8],
         image_icc_profile: Option<&[u8]>,
-        screen_id: Option<u32>,
+        target: ColorTarget,
     ) -> Result<(), ColorManagementError>;
 }
 
@@ -71,7 +116,7 @@ impl ColorManagementService for NoopColorManagementService {
         &self,
         _rgb_data: &mut [u8],
         _image_icc_profile: Option<&[u8]>,
-        _screen_id: Option<u32>,
+        _target: ColorTarget,
     ) -> Result<(), ColorManagementError> {
         Ok(())
     }
@@ -96,6 +141,21 @@ impl IccColorManagementService {
             display_profile_service: DisplayProfileService::new(),
         }
     }
+
+    /// 変換先のプロファイルを作る。sRGB指定ならディスプレイICCは読まない。
+    fn destination_profile(&self, target: ColorTarget) -> Result<Profile, ColorManagementError> {
+        match target {
+            ColorTarget::Display(screen_id) => {
+                let display_icc_profile = self
+                    .display_profile_service
+                    .load_display_icc_profile(screen_id)
+                    .map_err(|e| ColorManagementError::DisplayProfileLoad(e.to_string()))?;
+                Profile::new_icc(&display_icc_profile)
+                    .map_err(|e| ColorManagementError::DestinationProfileParse(e.to_string()))
+            }
+            ColorTarget::Srgb => Ok(Profile::new_srgb()),
+        }
+    }
 }
 
 #[cfg(any(target_os = "macos", target_os = "windows"))]
@@ -104,21 +164,22 @@ impl ColorManagementService for IccColorManagementService {
         &self,
         rgb_data: &mut [u8],
         image_icc_profile: Option<&[u8]>,
-        screen_id: Option<u32>,
+        target: ColorTarget,
     ) -> Result<(), ColorManagementError> {
-        let display_icc_profile = self
-            .display_profile_service
-            .load_display_icc_profile(screen_id)
-            .map_err(|e| ColorManagementError::DisplayProfileLoad(e.to_string()))?;
+        let image_icc_profile = image_icc_profile.filter(|icc| !icc.is_empty());
+
+        // sRGBからsRGBへの変換は不要
+        if target == ColorTarget::Srgb && image_icc_profile.is_none() {
+            return Ok(());
+        }
 
         let src_profile = match image_icc_profile {
-            Some(icc) if !icc.is_empty() => Profile::new_icc(icc)
+            Some(icc) => Profile::new_icc(icc)
                 .map_err(|e| ColorManagementError::SourceProfileParse(e.to_string()))?,
-            _ => Profile::new_srgb(),
+            None => Profile::new_srgb(),
         };
 
-        let dst_profile = Profile::new_icc(&display_icc_profile)
-            .map_err(|e| ColorManagementError::DestinationProfileParse(e.to_string()))?;
+        let dst_profile = self.destination_profile(target)?;
 
         let transform = Transform::new_flags(
             &src_profile,
@@ -153,3 +214,71 @@ static DEFAULT_COLOR_MANAGEMENT_SERVICE: Lazy<NoopColorManagementService> =
 pub fn default_color_management_service() -> &'static dyn ColorManagementService {
     &*DEFAULT_COLOR_MANAGEMENT_SERVICE
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_errors_to_outcomes() {
+        let cases = [
+            (
+                ColorManagementError::SourceProfileParse("bad".into()),
+                ColorManagementOutcome::SourceProfileInvalid,
+            ),
+            (
+                ColorManagementError::DisplayProfileLoad("bad".into()),
+                ColorManagementOutcome::DisplayProfileFailed,
+            ),
+            (
+                ColorManagementError::DestinationProfileParse("bad".into()),
+                ColorManagementOutcome::DisplayProfileFailed,
+            ),
+            (
+                ColorManagementError::TransformCreate("bad".into()),
+                ColorManagementOutcome::DisplayProfileFailed,
+            ),
+        ];
+        for (error, outcome) in cases {
+            assert_eq!(ColorManagementOutcome::from(&error), outcome, "{}", error);
+        }
+    }
+
+    #[test]
+    fn outcome_messages() {
+        assert_eq!(ColorManagementOutcome::Success.ui_message(), "");
+        assert_eq!(
+            ColorManagementOutcome::SourceProfileInvalid.ui_message(),
+            "Embedded color profile invalid; displayed as sRGB"
+        );
+        assert_eq!(
+            ColorManagementOutcome::DisplayProfileFailed.ui_message(),
+            "Display color profile unavailable; colors are not corrected"
+        );
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    #[test]
+    fn srgb_target_uses_builtin_srgb_destination() {
+        use lcms2::{InfoType, Locale};
+
+        let service = IccColorManagementService::new();
+        let Ok(profile) = service.destination_profile(ColorTarget::Srgb) else {
+            panic!("sRGB destination failed");
+        };
+        assert_eq!(
+            profile.info(InfoType::Description, Locale::none()).as_deref(),
+            Some("sRGB built-in")
+        );
+
+        // sRGB input is converted onto the same profile, so pixels stay as they are
+        let srgb_icc = Profile::new_srgb().icc().unwrap();
+        let mut rgb = vec![12, 128, 250, 0, 0, 0];
+        service
+            .apply_to_rgb8(&mut rgb, Some(&srgb_icc), ColorTarget::Srgb)
+            .unwrap();
+        for (converted, original) in rgb.iter().zip([12u8, 128, 250, 0, 0, 0]) {
+            assert!(converted.abs_diff(original) <= 1, "{:?}", rgb);
+        }
+    }
+}
@@ -1,5 +1,6 @@
 //! ディスプレイICCプロファイル取得サービス。
 
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
 #[cfg(target_os = "windows")]
@@ -9,6 +10,48 @@ use std::hash::{Hash, Hasher};
 #[cfg(target_os = "windows")]
 use std::os::windows::ffi::OsStringExt;
 
+/// 起動時にウィンドウを表示するモニター。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetMonitor {
+    /// OSに任せる（移動しない）。
+    #[default]
+    System,
+    /// プライマリモニター。
+    Primary,
+    /// 指定スクリーンIDのモニター（`screen_id_from_position` と同じID）。
+    Screen(u32),
+}
+
+impl TargetMonitor {
+    /// 移動先のスクリーンIDを決める。
+    ///
+    /// # Arguments
+    ///
+    /// * `available` - 接続中のモニターのスクリーンID
+    /// * `primary` - プライマリモニターのスクリーンID
+    ///
+    /// # Returns
+    ///
+    /// 移動先のスクリーンID。`System` の場合は `None`。
+    /// 指定モニターが接続されていない場合はプライマリモニターへフォールバック。
+    #[cfg_attr(not(any(target_os = "macos", target_os = "windows")), allow(dead_code))]
+    pub fn resolve(self, available: &[u32], primary: Option<u32>) -> Option<u32> {
+        match self {
+            Self::System => None,
+            Self::Primary => primary,
+            Self::Screen(id) if available.contains(&id) => Some(id),
+            Self::Screen(id) => {
+                log::warn!(
+                    "Target monitor {} is not connected, falling back to primary",
+                    id
+                );
+                primary
+            }
+        }
+    }
+}
+
 /// ディスプレイICCプロファイル取得時のエラー。
 #[derive(Debug)]
 pub enum DisplayProfileError {
@@ -424,3 +467,30 @@ impl DisplayProfileService {
         hasher.finish() as u32
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_monitor_resolves_against_available_screens() {
+        let available = [10, 20, 30];
+        assert_eq!(TargetMonitor::System.resolve(&available, Some(10)), None);
+        assert_eq!(
+            TargetMonitor::Primary.resolve(&available, Some(10)),
+            Some(10)
+        );
+        assert_eq!(TargetMonitor::Primary.resolve(&available, None), None);
+        assert_eq!(
+            TargetMonitor::Screen(30).resolve(&available, Some(10)),
+            Some(30)
+        );
+
+        // A disconnected monitor falls back to the primary one
+        assert_eq!(
+            TargetMonitor::Screen(40).resolve(&available, Some(10)),
+            Some(10)
+        );
+        assert_eq!(TargetMonitor::Screen(40).resolve(&[], None), None);
+    }
+}
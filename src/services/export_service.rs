@@ -0,0 +1,78 @@
+//! Service for exporting directory information to external formats.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Header row of the ratings CSV.
+const RATINGS_CSV_HEADER: &str = "filename,rating";
+
+/// Service for writing export files.
+pub struct ExportService;
+
+impl ExportService {
+    /// Creates a new export service.
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Writes the given images and ratings as a CSV file.
+    ///
+    /// Unrated images get an empty rating cell.
+    pub fn write_ratings_csv(
+        &self,
+        dest: &Path,
+        entries: &[(PathBuf, Option<u8>)],
+    ) -> std::io::Result<()> {
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(dest)?);
+        writeln!(writer, "{}", RATINGS_CSV_HEADER)?;
+        for (path, rating) in entries {
+            writeln!(writer, "{}", ratings_csv_row(path, *rating))?;
+        }
+        writer.flush()
+    }
+}
+
+/// Formats a single CSV row with the file name and rating.
+pub fn ratings_csv_row(path: &Path, rating: Option<u8>) -> String {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy())
+        .unwrap_or_default();
+    let rating = rating.map(|r| r.to_string()).unwrap_or_default();
+    format!("{},{}", escape_csv_field(&file_name), rating)
+}
+
+/// Quotes a CSV field if it contains a delimiter, quote, or line break.
+fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_row_has_file_name_and_rating() {
+        assert_eq!(
+            ratings_csv_row(Path::new("/images/a.png"), Some(4)),
+            "a.png,4"
+        );
+    }
+
+    #[test]
+    fn csv_row_leaves_unrated_cell_empty() {
+        assert_eq!(ratings_csv_row(Path::new("/images/a.png"), None), "a.png,");
+    }
+
+    #[test]
+    fn csv_row_quotes_special_file_names() {
+        assert_eq!(
+            ratings_csv_row(Path::new("/images/a, \"b\".png"), Some(1)),
+            "\"a, \"\"b\"\".png\",1"
+        );
+    }
+}
@@ -0,0 +1,374 @@
+//! Service for writing image data to files.
+//!
+//! Some apps accept pasted files but not raw bitmaps, so the displayed pixels
+//! are written to a file that can be placed on the clipboard. Saved copies can
+//! carry over the generation parameters and rating of their source, and files
+//! can be rotated in place.
+
+use crate::error::{AppError, Result};
+use crate::services::ColorTarget;
+use crate::{image_loader, jpeg_orientation, metadata};
+use image::{ExtendedColorType, ImageFormat};
+use log::{debug, info, warn};
+use std::io::{BufWriter, Cursor};
+use std::path::{Path, PathBuf};
+
+/// Base name of the staged file, without extension.
+const STAGED_FILE_STEM: &str = "staged";
+
+/// PNG chunks carried over when a PNG file is re-encoded.
+///
+/// Chunks that depend on the pixel layout (palette, transparency, physical
+/// size) or on animation are dropped.
+const PNG_METADATA_CHUNKS: [&[u8; 4]; 8] = [
+    b"tEXt", b"zTXt", b"iTXt", b"iCCP", b"sRGB", b"gAMA", b"cHRM", b"tIME",
+];
+
+/// PNG file signature.
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Service for temporary file operations.
+pub struct FileOpsService;
+
+impl FileOpsService {
+    /// Creates a new file operations service.
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Writes RGB8 pixels to a temporary file in the given format and returns its path.
+    ///
+    /// A single path per session and format is reused, so repeated copies
+    /// overwrite the previous file instead of piling up.
+    pub fn stage_temp_copy(
+        &self,
+        rgb_data: &[u8],
+        width: u32,
+        height: u32,
+        format: ImageFormat,
+    ) -> std::io::Result<PathBuf> {
+        let extension = format.extensions_str().first().copied().unwrap_or("img");
+        let dir = session_temp_dir();
+        std::fs::create_dir_all(&dir)?;
+
+        let path = dir.join(format!("{}.{}", STAGED_FILE_STEM, extension));
+        self.write_rgb8(&path, rgb_data, width, height, format)?;
+
+        debug!("Staged {}x{} image at {:?}", width, height, path);
+        Ok(path)
+    }
+
+    /// Writes RGB8 pixels to the given path in the given format.
+    pub fn write_rgb8(
+        &self,
+        path: &Path,
+        rgb_data: &[u8],
+        width: u32,
+        height: u32,
+        format: ImageFormat,
+    ) -> std::io::Result<()> {
+        image::save_buffer_with_format(
+            path,
+            rgb_data,
+            width,
+            height,
+            ExtendedColorType::Rgb8,
+            format,
+        )
+        .map_err(std::io::Error::other)
+    }
+
+    /// Saves the image file `src` to `dest` as PNG with its metadata.
+    ///
+    /// The file is decoded again and converted to sRGB rather than taking the
+    /// displayed pixels, which are converted to the display profile. The copy
+    /// is marked as sRGB and keeps the alpha channel. The parameters text of
+    /// `src` (also when it could not be parsed) is stored in a `parameters`
+    /// text chunk and the rating in XMP, so the copy shows the same metadata
+    /// as the source.
+    pub fn save_with_metadata(&self, src: &Path, dest: &Path) -> Result<()> {
+        let mut loaded = image_loader::load_first_frame(src, ColorTarget::Srgb)?;
+        let alpha = if loaded.has_alpha {
+            image_loader::load_alpha(src)?
+        } else {
+            None
+        };
+        let (color_type, pixels) = match alpha {
+            Some(alpha) => (png::ColorType::Rgba, interleave_alpha(&loaded.data, &alpha)),
+            None => (png::ColorType::Rgb, std::mem::take(&mut loaded.data)),
+        };
+
+        let file = std::fs::File::create(dest)
+            .map_err(|e| AppError::ImageWrite(format!("Failed to create {:?}: {}", dest, e)))?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), loaded.width, loaded.height);
+        encoder.set_color(color_type);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+
+        let parameters = loaded
+            .sd_parameters
+            .as_ref()
+            .map(|params| params.raw.clone())
+            .or_else(|| {
+                loaded
+                    .sd_parse_error
+                    .as_ref()
+                    .map(|failure| failure.raw.clone())
+            });
+        if let Some(parameters) = parameters {
+            add_parameters_chunk(&mut encoder, parameters).map_err(png_write_error)?;
+        }
+
+        let mut writer = encoder.write_header().map_err(png_write_error)?;
+        writer.write_image_data(&pixels).map_err(png_write_error)?;
+        writer.finish().map_err(png_write_error)?;
+
+        if let Some(rating) = loaded.rating {
+            metadata::write_xmp_rating_scaled(dest, rating, metadata::current_rating_scale())?;
+        }
+
+        debug!(
+            "Saved {}x{} image with metadata of {} to {:?}",
+            loaded.width, loaded.height, loaded.file_name, dest
+        );
+        Ok(())
+    }
+
+    /// Rotates a file in place by `quarter_turns` times 90 degrees clockwise
+    /// (negative turns counterclockwise).
+    ///
+    /// JPEG files are rotated losslessly with [`Self::rotate_jpeg_lossless`].
+    /// PNG, BMP and ICO files are decoded, rotated and re-encoded; PNG files
+    /// keep their text chunks (including generation parameters and XMP) and
+    /// color profile. Other formats are not supported, since re-encoding would
+    /// drop their animation or metadata.
+    pub fn rotate_and_save(&self, path: &Path, quarter_turns: i32) -> Result<()> {
+        let bytes = std::fs::read(path)?;
+        if jpeg_orientation::is_jpeg(&bytes) {
+            return self.rotate_jpeg_lossless(path, quarter_turns);
+        }
+
+        let format = image::guess_format(&bytes)?;
+        if !matches!(
+            format,
+            ImageFormat::Png | ImageFormat::Bmp | ImageFormat::Ico
+        ) {
+            return Err(AppError::ImageWrite(format!(
+                "Rotating {:?} files is not supported",
+                format
+            )));
+        }
+
+        let img = image::load_from_memory_with_format(&bytes, format)?;
+        let img = match quarter_turns.rem_euclid(4) {
+            1 => img.rotate90(),
+            2 => img.rotate180(),
+            3 => img.rotate270(),
+            _ => img,
+        };
+
+        let mut encoded = Cursor::new(Vec::new());
+        img.write_to(&mut encoded, format)
+            .map_err(|e| AppError::ImageWrite(e.to_string()))?;
+        let mut encoded = encoded.into_inner();
+        if format == ImageFormat::Png {
+            encoded = copy_png_metadata_chunks(&bytes, encoded)?;
+        }
+
+        replace_file(path, &encoded)?;
+        info!(
+            "Rotated {:?} by {} quarter turns (re-encoded)",
+            path, quarter_turns
+        );
+        Ok(())
+    }
+
+    /// Rotates a JPEG file in place without re-encoding it.
+    ///
+    /// Only the Exif orientation tag is changed, so the image data and all
+    /// other metadata stay as they are. Viewers that ignore the Exif
+    /// orientation show the file unrotated.
+    pub fn rotate_jpeg_lossless(&self, path: &Path, quarter_turns: i32) -> Result<()> {
+        let bytes = std::fs::read(path)?;
+        let rotated = jpeg_orientation::rotate(&bytes, quarter_turns)?;
+        replace_file(path, &rotated)?;
+        info!(
+            "Rotated {:?} by {} quarter turns (Exif orientation)",
+            path, quarter_turns
+        );
+        Ok(())
+    }
+
+    /// Removes the files staged during this session.
+    pub fn cleanup_staged_files(&self) {
+        let dir = session_temp_dir();
+        match std::fs::remove_dir_all(&dir) {
+            Ok(()) => debug!("Removed staged files in {:?}", dir),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Failed to remove staged files in {:?}: {}", dir, e),
+        }
+    }
+}
+
+/// Adds the parameters text as tEXt, or as iTXt when it is not representable in Latin-1.
+fn add_parameters_chunk<W: std::io::Write>(
+    encoder: &mut png::Encoder<W>,
+    parameters: String,
+) -> std::result::Result<(), png::EncodingError> {
+    let keyword = metadata::PARAMETERS_KEYWORD.to_string();
+    if parameters.chars().all(|c| (c as u32) <= 0xFF) {
+        encoder.add_text_chunk(keyword, parameters)
+    } else {
+        encoder.add_itxt_chunk(keyword, parameters)
+    }
+}
+
+/// Combines RGB8 pixels with one alpha byte per pixel into RGBA8 pixels.
+fn interleave_alpha(rgb_data: &[u8], alpha: &[u8]) -> Vec<u8> {
+    rgb_data
+        .chunks_exact(3)
+        .zip(alpha)
+        .flat_map(|(rgb, &a)| [rgb[0], rgb[1], rgb[2], a])
+        .collect()
+}
+
+/// Converts a PNG encoding error into an image write error.
+fn png_write_error(err: png::EncodingError) -> AppError {
+    AppError::ImageWrite(format!("PNG encoding error: {}", err))
+}
+
+/// Replaces the contents of a file through a temporary file in the same directory.
+///
+/// The original stays intact if writing fails.
+fn replace_file(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let result =
+        std::fs::write(&temp_path, contents).and_then(|()| std::fs::rename(&temp_path, path));
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(match e.kind() {
+            std::io::ErrorKind::PermissionDenied => AppError::FileReadOnly(e.to_string()),
+            _ => AppError::ImageWrite(format!("Failed to write {:?}: {}", path, e)),
+        });
+    }
+    Ok(())
+}
+
+/// Splits PNG data into its raw chunks, each including length, type and CRC.
+fn png_chunks(data: &[u8]) -> Option<Vec<&[u8]>> {
+    let mut chunks = Vec::new();
+    let mut offset = PNG_SIGNATURE.len();
+    if !data.starts_with(PNG_SIGNATURE) {
+        return None;
+    }
+
+    while offset < data.len() {
+        let length = u32::from_be_bytes(data.get(offset..offset + 4)?.try_into().ok()?) as usize;
+        let end = offset.checked_add(12 + length)?;
+        chunks.push(data.get(offset..end)?);
+        offset = end;
+    }
+    Some(chunks)
+}
+
+/// Copies the metadata chunks of `original` into the re-encoded PNG `encoded`.
+///
+/// The chunks are placed right after IHDR, as color chunks must precede the
+/// image data. Chunk types the encoder already wrote are not copied.
+fn copy_png_metadata_chunks(original: &[u8], encoded: Vec<u8>) -> Result<Vec<u8>> {
+    let malformed = || AppError::ImageWrite("Malformed PNG data".to_string());
+    let chunk_type = |chunk: &[u8]| -> [u8; 4] { chunk[4..8].try_into().unwrap_or_default() };
+
+    let original_chunks = png_chunks(original).ok_or_else(malformed)?;
+    let encoded_chunks = png_chunks(&encoded).ok_or_else(malformed)?;
+    let (ihdr, rest) = encoded_chunks.split_first().ok_or_else(malformed)?;
+
+    let mut output = PNG_SIGNATURE.to_vec();
+    output.extend_from_slice(ihdr);
+    for chunk in original_chunks {
+        let kind = chunk_type(chunk);
+        if PNG_METADATA_CHUNKS.contains(&&kind)
+            && !rest.iter().any(|encoded| chunk_type(encoded) == kind)
+        {
+            output.extend_from_slice(chunk);
+        }
+    }
+    for chunk in rest {
+        output.extend_from_slice(chunk);
+    }
+    Ok(output)
+}
+
+/// Returns the temporary directory used by this process.
+fn session_temp_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("{}-{}", env!("CARGO_PKG_NAME"), std::process::id()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn staged_png_decodes_with_source_dimensions() {
+        let rgb: Vec<u8> = (0..3 * 2 * 3).map(|i| i as u8).collect();
+        let path = FileOpsService::new()
+            .stage_temp_copy(&rgb, 3, 2, ImageFormat::Png)
+            .unwrap();
+
+        assert_eq!(path.extension().unwrap(), "png");
+        let decoded = image::open(&path).unwrap().to_rgb8();
+        assert_eq!(decoded.dimensions(), (3, 2));
+        assert_eq!(decoded.as_raw(), &rgb);
+    }
+
+    #[test]
+    fn interleave_alpha_appends_one_alpha_per_pixel() {
+        assert_eq!(
+            interleave_alpha(&[1, 2, 3, 4, 5, 6], &[7, 8]),
+            [1, 2, 3, 7, 4, 5, 6, 8]
+        );
+    }
+
+    #[test]
+    fn saved_copy_keeps_parameters_rating_and_alpha() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src.png");
+        let parameters = "猫, (smile:1.2)\nNegative prompt: lowres\nSteps: 20, Seed: 7";
+        {
+            let file = std::fs::File::create(&src).unwrap();
+            let mut encoder = png::Encoder::new(BufWriter::new(file), 2, 1);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            add_parameters_chunk(&mut encoder, parameters.to_string()).unwrap();
+            let mut writer = encoder.write_header().unwrap();
+            writer
+                .write_image_data(&[10, 20, 30, 255, 40, 50, 60, 128])
+                .unwrap();
+            writer.finish().unwrap();
+        }
+        metadata::write_xmp_rating_scaled(&src, 4, metadata::current_rating_scale()).unwrap();
+
+        let dest = dir.path().join("copy.png");
+        FileOpsService::new()
+            .save_with_metadata(&src, &dest)
+            .unwrap();
+
+        let source = image_loader::load_first_frame(&src, ColorTarget::Srgb).unwrap();
+        let copy = image_loader::load_first_frame(&dest, ColorTarget::Srgb).unwrap();
+        assert_eq!(
+            copy.sd_parameters.as_ref().map(|params| &params.raw),
+            source.sd_parameters.as_ref().map(|params| &params.raw)
+        );
+        assert_eq!(copy.sd_parameters.unwrap().seed.as_deref(), Some("7"));
+        assert_eq!(copy.rating, Some(4));
+        assert_eq!((copy.width, copy.height), (2, 1));
+        assert_eq!(copy.data, source.data);
+        assert_eq!(
+            image_loader::load_alpha(&dest).unwrap(),
+            Some(vec![255, 128])
+        );
+    }
+}
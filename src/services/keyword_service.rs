@@ -0,0 +1,72 @@
+//! Service for editing XMP keywords of the current image.
+//!
+//! Writes `dc:subject` keywords and keeps the image cache in sync.
+
+use crate::error::{AppError, Result};
+use crate::image_cache::ImageCache;
+use crate::metadata;
+use crate::services::lock_or_recover;
+use crate::state::{NavigationState, XmpWriteGuard};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Service for adding and removing keywords on the current image.
+pub struct KeywordService {
+    navigation: Arc<Mutex<NavigationState>>,
+    cache: Arc<Mutex<ImageCache>>,
+    xmp_writes: XmpWriteGuard,
+}
+
+impl KeywordService {
+    /// Creates a new keyword service.
+    ///
+    /// `xmp_writes` must be shared with the rating service, so rating and
+    /// keyword writes to the same file don't overlap.
+    pub fn new(
+        navigation: Arc<Mutex<NavigationState>>,
+        cache: Arc<Mutex<ImageCache>>,
+        xmp_writes: XmpWriteGuard,
+    ) -> Self {
+        Self {
+            navigation,
+            cache,
+            xmp_writes,
+        }
+    }
+
+    /// Adds a keyword to the current image and returns the updated keywords.
+    pub fn add_keyword(&self, keyword: &str) -> Result<Vec<String>> {
+        self.update_keywords(|path| metadata::add_xmp_keyword(path, keyword))
+    }
+
+    /// Removes a keyword from the current image and returns the updated keywords.
+    pub fn remove_keyword(&self, keyword: &str) -> Result<Vec<String>> {
+        self.update_keywords(|path| metadata::remove_xmp_keyword(path, keyword))
+    }
+
+    /// Applies a keyword write to the current image and stores the result in the cache.
+    ///
+    /// Fails while a rating or keyword write to the same file is in progress.
+    fn update_keywords(
+        &self,
+        write: impl FnOnce(&Path) -> Result<Vec<String>>,
+    ) -> Result<Vec<String>> {
+        let path = lock_or_recover(&self.navigation)
+            .current_path()
+            .ok_or_else(|| AppError::XmpWrite("No image file selected".to_string()))?;
+
+        if !metadata::supports_rating_write(&path) {
+            return Err(AppError::XmpWrite(
+                "Keywords are not supported for this file format".to_string(),
+            ));
+        }
+
+        let _xmp_write = self.xmp_writes.try_begin(&path).ok_or_else(|| {
+            AppError::XmpWrite("Write already in progress for this file".to_string())
+        })?;
+
+        let keywords = write(&path)?;
+        lock_or_recover(&self.cache).update_keywords(&path, keywords.clone());
+        Ok(keywords)
+    }
+}
@@ -6,13 +6,60 @@ pub mod auto_reload_service;
 pub mod clipboard_service;
 pub mod color_management_service;
 pub mod display_profile_service;
+pub mod export_service;
+pub mod file_ops_service;
+pub mod keyword_service;
 pub mod navigation_service;
 pub mod rating_service;
 
-pub use auto_reload_service::AutoReloadService;
+pub use auto_reload_service::{AutoReloadEvent, AutoReloadMode, AutoReloadService, WatcherBackend};
 pub use clipboard_service::ClipboardService;
-pub use color_management_service::default_color_management_service;
+pub use color_management_service::{
+    ColorManagementOutcome, ColorTarget, default_color_management_service,
+};
 #[cfg(any(target_os = "macos", target_os = "windows"))]
 pub use display_profile_service::DisplayProfileService;
+pub use display_profile_service::TargetMonitor;
+pub use export_service::ExportService;
+pub use file_ops_service::FileOpsService;
+pub use keyword_service::KeywordService;
 pub use navigation_service::NavigationService;
 pub use rating_service::RatingService;
+
+use std::sync::{Mutex, MutexGuard};
+
+/// Locks a mutex, recovering the guard if another thread panicked while holding it.
+///
+/// The shared state guarded here stays consistent across a panic (plain field updates),
+/// so recovering is preferable to cascading the panic through the UI.
+pub(crate) fn lock_or_recover<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| {
+        log::warn!("Recovered from a poisoned lock");
+        mutex.clear_poison();
+        poisoned.into_inner()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recovers_poisoned_lock() {
+        let mutex = std::sync::Arc::new(Mutex::new(1));
+        let poisoner = mutex.clone();
+        let result = std::thread::spawn(move || {
+            let mut value = poisoner.lock().unwrap();
+            *value = 2;
+            panic!("poison the lock");
+        })
+        .join();
+        assert!(result.is_err());
+        assert!(mutex.is_poisoned());
+
+        assert_eq!(*lock_or_recover(&mutex), 2);
+        assert!(!mutex.is_poisoned());
+        *lock_or_recover(&mutex) = 3;
+        assert_eq!(*mutex.lock().unwrap(), 3);
+    }
+}
@@ -3,11 +3,41 @@
 //! Provides high-level navigation methods that coordinate between
 //! NavigationState, ImageCache, and file system operations.
 
+use crate::config::RATING_READ_THREADS;
 use crate::error::NavigationError;
-use crate::state::NavigationState;
-use std::path::PathBuf;
+use crate::file_utils::{self, SortOrder};
+use crate::metadata;
+use crate::rating_index::{self, FileStamp, RatingIndex, RatingSummary};
+use crate::recent_files;
+use crate::services::lock_or_recover;
+use crate::state::{LoadGeneration, NavigationState};
+use once_cell::sync::Lazy;
+use rayon::prelude::*;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 
+/// Thread pool for directory-wide rating reads, built on first use.
+///
+/// Bounds how many files are opened for XMP at once. `None` if the pool
+/// could not be built, in which case files are read sequentially.
+static RATING_READ_POOL: Lazy<Option<rayon::ThreadPool>> = Lazy::new(|| {
+    match rayon::ThreadPoolBuilder::new()
+        .num_threads(RATING_READ_THREADS)
+        .thread_name(|index| format!("rating-read-{}", index))
+        .build()
+    {
+        Ok(pool) => Some(pool),
+        Err(e) => {
+            log::warn!(
+                "Failed to build rating read pool, reading sequentially: {}",
+                e
+            );
+            None
+        }
+    }
+});
+
 /// Result type for navigation operations.
 pub type NavigationResult = Result<PathBuf, NavigationError>;
 
@@ -15,17 +45,24 @@ pub type NavigationResult = Result<PathBuf, NavigationError>;
 #[derive(Clone)]
 pub struct NavigationService {
     navigation: Arc<Mutex<NavigationState>>,
+    rating_index: Arc<Mutex<RatingIndex>>,
 }
 
 impl NavigationService {
     /// Creates a new navigation service.
-    pub fn new(navigation: Arc<Mutex<NavigationState>>) -> Self {
-        Self { navigation }
+    pub fn new(
+        navigation: Arc<Mutex<NavigationState>>,
+        rating_index: Arc<Mutex<RatingIndex>>,
+    ) -> Self {
+        Self {
+            navigation,
+            rating_index,
+        }
     }
 
     /// Navigates to the next image and returns its path.
     pub fn next(&self) -> NavigationResult {
-        let mut nav_state = self.navigation.lock().unwrap();
+        let mut nav_state = lock_or_recover(&self.navigation);
         nav_state.navigate_next()?;
         nav_state
             .current_path()
@@ -34,25 +71,221 @@ impl NavigationService {
 
     /// Navigates to the previous image and returns its path.
     pub fn previous(&self) -> NavigationResult {
-        let mut nav_state = self.navigation.lock().unwrap();
+        let mut nav_state = lock_or_recover(&self.navigation);
         nav_state.navigate_prev()?;
         nav_state
             .current_path()
             .ok_or(NavigationError::NoCurrentPath)
     }
 
+    /// Jumps `delta` images away from the current one and returns its path.
+    ///
+    /// Wraps around or stops at the ends of the list per the wrap setting.
+    pub fn jump(&self, delta: isize) -> NavigationResult {
+        let mut nav_state = lock_or_recover(&self.navigation);
+        nav_state.navigate_by(delta)?;
+        nav_state
+            .current_path()
+            .ok_or(NavigationError::NoCurrentPath)
+    }
+
+    /// Moves to the next sibling directory and returns the path of its first image.
+    pub fn next_directory(&self) -> NavigationResult {
+        let mut nav_state = lock_or_recover(&self.navigation);
+        nav_state.navigate_next_directory()?;
+        nav_state
+            .current_path()
+            .ok_or(NavigationError::NoCurrentPath)
+    }
+
+    /// Moves to the previous sibling directory and returns the path of its first image.
+    pub fn previous_directory(&self) -> NavigationResult {
+        let mut nav_state = lock_or_recover(&self.navigation);
+        nav_state.navigate_prev_directory()?;
+        nav_state
+            .current_path()
+            .ok_or(NavigationError::NoCurrentPath)
+    }
+
+    /// Jumps to the next image with exactly the given rating, wrapping once.
+    ///
+    /// This is a one-shot jump; regular navigation is not filtered afterwards.
+    pub fn next_with_rating(&self, rating: u8) -> NavigationResult {
+        self.jump_to_rating(rating, false)
+    }
+
+    /// Jumps to the previous image with exactly the given rating, wrapping once.
+    pub fn prev_with_rating(&self, rating: u8) -> NavigationResult {
+        self.jump_to_rating(rating, true)
+    }
+
+    /// Scans the list from the current image for the given rating and selects the first match.
+    ///
+    /// Ratings are read through the rating index without holding the navigation lock.
+    fn jump_to_rating(&self, rating: u8, backward: bool) -> NavigationResult {
+        let candidates = {
+            let nav_state = lock_or_recover(&self.navigation);
+            if nav_state.is_empty() {
+                return Err(NavigationError::NoImages);
+            }
+            nav_state.files_in_scan_order(backward)
+        };
+
+        let target = candidates
+            .into_iter()
+            .find(|path| {
+                rating_index::read_rating_indexed(&self.rating_index, path) == Some(rating)
+            })
+            .ok_or(NavigationError::NoImageWithRating(rating))?;
+
+        lock_or_recover(&self.navigation).set_current_by_path(&target)?;
+        Ok(target)
+    }
+
+    /// Jumps to the next PNG image whose generation seed is `seed`, wrapping once.
+    ///
+    /// Other formats are skipped. Files are read in parallel and the scan stops
+    /// at the first match in list order. `on_progress` is called with the
+    /// number of files read so far and the number of files to read.
+    pub fn find_by_seed(
+        &self,
+        seed: &str,
+        on_progress: impl Fn(usize, usize) + Sync,
+    ) -> NavigationResult {
+        let candidates: Vec<PathBuf> = {
+            let nav_state = lock_or_recover(&self.navigation);
+            if nav_state.is_empty() {
+                return Err(NavigationError::NoImages);
+            }
+            nav_state
+                .files_in_scan_order(false)
+                .into_iter()
+                .filter(|path| {
+                    path.extension()
+                        .is_some_and(|ext| ext.eq_ignore_ascii_case("png"))
+                })
+                .collect()
+        };
+
+        let total = candidates.len();
+        let done = AtomicUsize::new(0);
+        let target = candidates
+            .par_iter()
+            .find_first(|path| {
+                let matches = metadata::read_png_sd_parameters(path)
+                    .map_err(|e| log::debug!("Skipping {:?} in seed search: {}", path, e))
+                    .ok()
+                    .flatten()
+                    .and_then(|params| params.seed)
+                    .is_some_and(|found| found.trim() == seed);
+                on_progress(done.fetch_add(1, Ordering::SeqCst) + 1, total);
+                matches
+            })
+            .cloned()
+            .ok_or_else(|| NavigationError::NoImageWithSeed(seed.to_string()))?;
+
+        lock_or_recover(&self.navigation).set_current_by_path(&target)?;
+        Ok(target)
+    }
+
+    /// Summarizes the ratings of the current list from the rating index.
+    ///
+    /// Only the current image is read from disk if its entry is stale, so its
+    /// count is right after a rating change. Other files the index does not
+    /// know yet are counted as unknown until the prescan or a jump reads them.
+    pub fn rating_summary(&self) -> RatingSummary {
+        let (files, current, revision) = {
+            let nav_state = lock_or_recover(&self.navigation);
+            (
+                nav_state.image_files_snapshot(),
+                nav_state.current_path(),
+                nav_state.list_revision(),
+            )
+        };
+
+        if let Some(current) = current {
+            rating_index::read_rating_indexed(&self.rating_index, &current);
+        }
+        // Stat the files before locking, so the index isn't blocked meanwhile
+        let stamps: Vec<FileStamp> = files.par_iter().map(|path| FileStamp::read(path)).collect();
+        lock_or_recover(&self.rating_index)
+            .summarize(revision, files.iter().map(PathBuf::as_path).zip(&stamps))
+    }
+
+    /// Updates the rating summary after the rating of `path` changed to `rating`.
+    ///
+    /// Adjusts the last summary instead of reading the whole list again,
+    /// unless the list changed since it was summarized.
+    pub fn update_rating_summary(&self, path: &Path, rating: Option<u8>) -> RatingSummary {
+        let revision = lock_or_recover(&self.navigation).list_revision();
+        let updated = lock_or_recover(&self.rating_index).update_summary(revision, path, rating);
+        updated.unwrap_or_else(|| self.rating_summary())
+    }
+
+    /// Flips whether navigation wraps around at the ends and returns the new value.
+    ///
+    /// The file list is kept as is, so the next navigation already uses the new mode.
+    pub fn toggle_wrap(&self) -> bool {
+        let mut nav_state = lock_or_recover(&self.navigation);
+        let wrap = !nav_state.wraps();
+        nav_state.set_wrap(wrap);
+        wrap
+    }
+
     /// Selects a specific image file and updates the directory context.
     ///
     /// This scans the parent directory and sets up the file list for navigation.
-    pub fn select_image(&self, path: PathBuf) -> Result<PathBuf, NavigationError> {
-        let mut nav_state = self.navigation.lock().unwrap();
+    /// Returns the image that became current, which differs from `path` when
+    /// the `open_at` setting picks the first or last image.
+    /// On success the file is recorded in the recently opened files list.
+    pub fn select_image(&self, path: PathBuf) -> NavigationResult {
+        let mut nav_state = lock_or_recover(&self.navigation);
         nav_state.update_directory(path.clone())?;
+        let current = nav_state
+            .current_path()
+            .ok_or(NavigationError::NoCurrentPath)?;
+        drop(nav_state);
+
+        recent_files::record(&path);
+        Ok(current)
+    }
+
+    /// Replaces the list with the given images and returns the first given one.
+    ///
+    /// On success the first image is recorded in the recently opened files list.
+    #[cfg_attr(not(any(target_os = "macos", target_os = "windows")), allow(dead_code))]
+    pub fn select_images(&self, paths: Vec<PathBuf>) -> NavigationResult {
+        let mut nav_state = lock_or_recover(&self.navigation);
+        nav_state.set_explicit_list(paths)?;
+        let first = nav_state
+            .current_path()
+            .ok_or(NavigationError::NoCurrentPath)?;
+        drop(nav_state);
+
+        recent_files::record(&first);
+        Ok(first)
+    }
+
+    /// Makes `path` current within the existing list, without rescanning, and returns it.
+    ///
+    /// Fails with `NavigationError::NotInList` if the path is not in the list.
+    pub fn select_by_path(&self, path: PathBuf) -> NavigationResult {
+        lock_or_recover(&self.navigation).set_current_by_path(&path)?;
         Ok(path)
     }
 
+    /// Navigates to the image at `index` of the list and returns its path.
+    pub fn navigate_to_index(&self, index: usize) -> NavigationResult {
+        let mut nav_state = lock_or_recover(&self.navigation);
+        nav_state.navigate_to_index(index)?;
+        nav_state
+            .current_path()
+            .ok_or(NavigationError::NoCurrentPath)
+    }
+
     /// Navigates to the last image in the current directory.
     pub fn navigate_to_last(&self) -> NavigationResult {
-        let mut nav_state = self.navigation.lock().unwrap();
+        let mut nav_state = lock_or_recover(&self.navigation);
         nav_state.navigate_to_last()?;
         nav_state
             .current_path()
@@ -61,8 +294,368 @@ impl NavigationService {
 
     /// Rescans the current directory and returns the new image count.
     pub fn rescan_directory(&self) -> Result<usize, NavigationError> {
-        let mut nav_state = self.navigation.lock().unwrap();
+        let mut nav_state = lock_or_recover(&self.navigation);
         nav_state.rescan_directory()?;
         Ok(nav_state.image_count())
     }
+
+    /// Returns the 1-based position of the current image and the image count.
+    ///
+    /// The position is -1 when no image is selected.
+    pub fn position(&self) -> (i32, i32) {
+        let nav_state = lock_or_recover(&self.navigation);
+        let total = nav_state.image_count() as i32;
+        let current = nav_state
+            .current_index()
+            .map(|index| index as i32 + 1)
+            .unwrap_or(-1);
+        (current, total)
+    }
+
+    /// Re-sorts the current directory listing while keeping the current image selected.
+    ///
+    /// The files are sorted without holding the navigation state, since sorting
+    /// by modification time reads the metadata of every file; call this off the
+    /// UI thread. If the list keeps changing meanwhile, it is finally sorted
+    /// while holding the state.
+    /// Returns the new 1-based position of the current image and the image count.
+    pub fn re_sort(&self, order: SortOrder) -> (i32, i32) {
+        const ATTEMPTS: usize = 3;
+
+        let sorted = (0..ATTEMPTS).any(|_| {
+            let (revision, mut files) = lock_or_recover(&self.navigation).begin_re_sort(order);
+            file_utils::sort_image_files(&mut files, order);
+            lock_or_recover(&self.navigation).finish_re_sort(revision, files)
+        });
+        if !sorted {
+            log::debug!("File list kept changing while sorting by {:?}", order);
+            lock_or_recover(&self.navigation).re_sort(order);
+        }
+        self.position()
+    }
+
+    /// Loads more files around the current one when the directory list is windowed.
+    ///
+    /// Returns the updated (current, total) indices, or `None` if the list is not windowed.
+    pub fn extend_window(&self) -> Option<(i32, i32)> {
+        let mut nav_state = lock_or_recover(&self.navigation);
+        if !nav_state.extend_window() {
+            return None;
+        }
+
+        let total = nav_state.image_count() as i32;
+        let current = nav_state
+            .current_index()
+            .map(|index| index as i32 + 1)
+            .unwrap_or(-1);
+        Some((current, total))
+    }
+
+    /// Lists all images in the current directory with their ratings.
+    ///
+    /// Files outside the window of a large directory are included. Ratings
+    /// are read through the rating index on a bounded thread pool, so only
+    /// files that are unknown or changed on disk hit XMP.
+    pub fn list_with_ratings(&self) -> Vec<(PathBuf, Option<u8>)> {
+        let files = lock_or_recover(&self.navigation).all_files_snapshot();
+        let rating_index = &self.rating_index;
+        let read_all = || {
+            files
+                .par_iter()
+                .map(|path| {
+                    let rating = rating_index::read_rating_indexed(rating_index, path);
+                    (path.clone(), rating)
+                })
+                .collect()
+        };
+
+        match RATING_READ_POOL.as_ref() {
+            Some(pool) => pool.install(read_all),
+            None => files
+                .iter()
+                .map(|path| {
+                    let rating = rating_index::read_rating_indexed(rating_index, path);
+                    (path.clone(), rating)
+                })
+                .collect(),
+        }
+    }
+
+    /// Reads the ratings of all images in the current directory into the rating index.
+    ///
+    /// Runs on the bounded rating read pool and stops early when the directory
+    /// changes or a newer prescan is started with `generation`, so `run` must be
+    /// the value returned by `generation.next()`. `on_progress` receives the
+    /// number of files read so far and the total.
+    ///
+    /// Returns the number of files read, or `None` if the prescan was cancelled.
+    pub fn prescan_ratings(
+        &self,
+        generation: &LoadGeneration,
+        run: u64,
+        on_progress: impl Fn(usize, usize) + Sync,
+    ) -> Option<usize> {
+        let (directory, files) = {
+            let nav_state = lock_or_recover(&self.navigation);
+            (
+                nav_state.get_current_directory()?,
+                nav_state.image_files_snapshot(),
+            )
+        };
+
+        let total = files.len();
+        let done = AtomicUsize::new(0);
+        let is_cancelled = || {
+            !generation.is_current(run)
+                || lock_or_recover(&self.navigation)
+                    .get_current_directory()
+                    .as_ref()
+                    != Some(&directory)
+        };
+        let read_one = |path: &PathBuf| -> Option<()> {
+            if is_cancelled() {
+                return None;
+            }
+            rating_index::read_rating_indexed(&self.rating_index, path);
+            on_progress(done.fetch_add(1, Ordering::SeqCst) + 1, total);
+            Some(())
+        };
+
+        let result = match RATING_READ_POOL.as_ref() {
+            Some(pool) => pool.install(|| files.par_iter().try_for_each(read_one)),
+            None => files.iter().try_for_each(read_one),
+        };
+
+        result.map(|()| done.load(Ordering::SeqCst))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates PNGs with the given ratings and a service with them as the list.
+    fn rated_list(ratings: &[Option<u8>]) -> (tempfile::TempDir, Vec<PathBuf>, NavigationService) {
+        let dir = tempfile::tempdir().unwrap();
+        let files: Vec<PathBuf> = ratings
+            .iter()
+            .enumerate()
+            .map(|(i, rating)| {
+                let path = dir.path().join(format!("{}.png", i));
+                image::save_buffer(&path, &[0u8; 3], 1, 1, image::ExtendedColorType::Rgb8).unwrap();
+                if let Some(rating) = rating {
+                    metadata::write_xmp_rating_scaled(
+                        &path,
+                        *rating,
+                        metadata::current_rating_scale(),
+                    )
+                    .unwrap();
+                }
+                path
+            })
+            .collect();
+
+        let mut nav_state = NavigationState::new();
+        nav_state.set_explicit_list(files.clone()).unwrap();
+        let service = NavigationService::new(
+            Arc::new(Mutex::new(nav_state)),
+            Arc::new(Mutex::new(RatingIndex::new())),
+        );
+        (dir, files, service)
+    }
+
+    #[test]
+    fn jumps_to_next_and_previous_exact_rating() {
+        let (_dir, files, service) = rated_list(&[Some(5), None, Some(3), Some(5), Some(1)]);
+
+        assert_eq!(service.next_with_rating(5).unwrap(), files[3].clone());
+        assert_eq!(service.next_with_rating(5).unwrap(), files[0].clone());
+        assert_eq!(service.prev_with_rating(1).unwrap(), files[4].clone());
+        assert_eq!(service.prev_with_rating(3).unwrap(), files[2].clone());
+    }
+
+    #[test]
+    fn missing_rating_keeps_current_image() {
+        let (_dir, files, service) = rated_list(&[Some(5), None, Some(3)]);
+
+        assert!(matches!(
+            service.next_with_rating(4),
+            Err(NavigationError::NoImageWithRating(4))
+        ));
+        assert_eq!(
+            lock_or_recover(&service.navigation).current_path(),
+            Some(files[0].clone())
+        );
+    }
+
+    /// Opens the first of the `rated_list` files as a directory instead of an explicit list.
+    fn rated_directory(
+        ratings: &[Option<u8>],
+    ) -> (tempfile::TempDir, Vec<PathBuf>, NavigationService) {
+        let (dir, files, service) = rated_list(ratings);
+        lock_or_recover(&service.navigation)
+            .update_directory(files[0].clone())
+            .unwrap();
+        (dir, files, service)
+    }
+
+    #[test]
+    fn prescan_fills_rating_index() {
+        let (_dir, files, service) = rated_directory(&[Some(5), None, Some(3)]);
+        let generation = LoadGeneration::new();
+        let progress = Mutex::new(Vec::new());
+
+        let read = service.prescan_ratings(&generation, generation.next(), |done, total| {
+            lock_or_recover(&progress).push((done, total));
+        });
+        assert_eq!(read, Some(3));
+        let mut progress = lock_or_recover(&progress).clone();
+        progress.sort();
+        assert_eq!(progress, [(1, 3), (2, 3), (3, 3)]);
+
+        let index = lock_or_recover(&service.rating_index);
+        let indexed: Vec<_> = files
+            .iter()
+            .map(|path| index.get(path, &FileStamp::read(path)))
+            .collect();
+        assert_eq!(indexed, [Some(Some(5)), Some(None), Some(Some(3))]);
+    }
+
+    #[test]
+    fn prescan_is_cancelled_by_newer_run_or_directory_change() {
+        let (dir, files, service) = rated_directory(&[Some(5), None, Some(3)]);
+        let generation = LoadGeneration::new();
+        let stale = generation.next();
+        generation.next();
+        assert_eq!(service.prescan_ratings(&generation, stale, |_, _| {}), None);
+        let stamp = FileStamp::read(&files[0]);
+        assert_eq!(
+            lock_or_recover(&service.rating_index).get(&files[0], &stamp),
+            None
+        );
+
+        let other = dir.path().join("other");
+        std::fs::create_dir(&other).unwrap();
+        std::fs::write(other.join("a.png"), b"").unwrap();
+        let read = service.prescan_ratings(&generation, generation.next(), |_, _| {
+            // The user opens another directory while the prescan runs
+            lock_or_recover(&service.navigation)
+                .update_directory(other.join("a.png"))
+                .unwrap();
+        });
+        assert_eq!(read, None);
+    }
+
+    #[test]
+    fn toggling_wrap_applies_to_the_next_navigation() {
+        let (_dir, files, service) = rated_list(&[None, None, None]);
+        let wrap = lock_or_recover(&service.navigation).wraps();
+        assert_eq!(service.toggle_wrap(), !wrap);
+        assert_eq!(service.toggle_wrap(), wrap);
+        if wrap {
+            service.toggle_wrap();
+        }
+        let revision = lock_or_recover(&service.navigation).list_revision();
+
+        // Clamped at the last image
+        service.navigate_to_last().unwrap();
+        let _ = service.next();
+        assert_eq!(
+            lock_or_recover(&service.navigation).current_path(),
+            Some(files[2].clone())
+        );
+
+        assert!(service.toggle_wrap());
+        assert_eq!(service.next().unwrap(), files[0].clone());
+        assert_eq!(
+            lock_or_recover(&service.navigation).list_revision(),
+            revision
+        );
+    }
+
+    #[test]
+    fn select_by_path_makes_listed_path_current() {
+        let (_dir, files, service) = rated_list(&[None, None, None]);
+
+        assert_eq!(service.select_by_path(files[2].clone()).unwrap(), files[2]);
+        assert_eq!(
+            lock_or_recover(&service.navigation).current_path(),
+            Some(files[2].clone())
+        );
+    }
+
+    #[test]
+    fn select_by_path_rejects_path_outside_list() {
+        let (dir, files, service) = rated_list(&[None, None]);
+
+        assert!(matches!(
+            service.select_by_path(dir.path().join("other.png")),
+            Err(NavigationError::NotInList(_))
+        ));
+        assert_eq!(
+            lock_or_recover(&service.navigation).current_path(),
+            Some(files[0].clone())
+        );
+    }
+
+    /// Creates PNGs with the given generation seeds and a service with them as the list.
+    fn seeded_list(seeds: &[Option<&str>]) -> (tempfile::TempDir, Vec<PathBuf>, NavigationService) {
+        let dir = tempfile::tempdir().unwrap();
+        let files: Vec<PathBuf> = seeds
+            .iter()
+            .enumerate()
+            .map(|(i, seed)| {
+                let path = dir.path().join(format!("{}.png", i));
+                let file = std::io::BufWriter::new(std::fs::File::create(&path).unwrap());
+                let mut encoder = png::Encoder::new(file, 1, 1);
+                encoder.set_color(png::ColorType::Grayscale);
+                if let Some(seed) = seed {
+                    let parameters =
+                        format!("a cat\nNegative prompt: blurry\nSteps: 20, Seed: {}", seed);
+                    encoder
+                        .add_text_chunk("parameters".to_string(), parameters)
+                        .unwrap();
+                }
+                let mut writer = encoder.write_header().unwrap();
+                writer.write_image_data(&[0]).unwrap();
+                writer.finish().unwrap();
+                path
+            })
+            .collect();
+
+        let mut nav_state = NavigationState::new();
+        nav_state.set_explicit_list(files.clone()).unwrap();
+        let service = NavigationService::new(
+            Arc::new(Mutex::new(nav_state)),
+            Arc::new(Mutex::new(RatingIndex::new())),
+        );
+        (dir, files, service)
+    }
+
+    #[test]
+    fn find_by_seed_selects_next_match_and_wraps() {
+        let (_dir, files, service) = seeded_list(&[Some("7"), None, Some("42"), Some("42")]);
+
+        assert_eq!(service.find_by_seed("42", |_, _| {}).unwrap(), files[2]);
+        assert_eq!(service.find_by_seed("42", |_, _| {}).unwrap(), files[3]);
+        assert_eq!(service.find_by_seed("7", |_, _| {}).unwrap(), files[0]);
+        assert_eq!(
+            lock_or_recover(&service.navigation).current_path(),
+            Some(files[0].clone())
+        );
+    }
+
+    #[test]
+    fn find_by_seed_without_match_keeps_current_image() {
+        let (_dir, files, service) = seeded_list(&[Some("7"), None, Some("42")]);
+
+        assert!(matches!(
+            service.find_by_seed("99", |_, _| {}),
+            Err(NavigationError::NoImageWithSeed(seed)) if seed == "99"
+        ));
+        assert_eq!(
+            lock_or_recover(&service.navigation).current_path(),
+            Some(files[0].clone())
+        );
+    }
 }
@@ -1,11 +1,12 @@
 //! Service for handling image rating operations.
 //!
-//! Manages XMP rating writes with duplicate write prevention and cache updates.
+//! Manages XMP rating writes with overlapping write prevention and cache updates.
 
 use crate::error::AppError;
 use crate::image_cache::ImageCache;
-use crate::metadata;
-use crate::state::NavigationState;
+use crate::metadata::{self, RatingScale};
+use crate::services::lock_or_recover;
+use crate::state::{NavigationState, XmpWriteGuard};
 use log::warn;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
@@ -17,95 +18,125 @@ pub type RatingResult = Result<RatingSuccess, AppError>;
 #[derive(Debug)]
 pub struct RatingSuccess {
     pub rating: u8,
+    /// File the rating was written to.
+    pub path: PathBuf,
 }
 
 /// Service for managing image rating operations.
 pub struct RatingService {
-    current_writing: Arc<Mutex<Option<PathBuf>>>,
+    xmp_writes: XmpWriteGuard,
     navigation: Arc<Mutex<NavigationState>>,
     cache: Arc<Mutex<ImageCache>>,
+    scale: RatingScale,
 }
 
 impl RatingService {
     /// Creates a new rating service.
-    pub fn new(navigation: Arc<Mutex<NavigationState>>, cache: Arc<Mutex<ImageCache>>) -> Self {
+    ///
+    /// `xmp_writes` must be shared with the keyword service, so rating and
+    /// keyword writes to the same file don't overlap.
+    pub fn new(
+        navigation: Arc<Mutex<NavigationState>>,
+        cache: Arc<Mutex<ImageCache>>,
+        xmp_writes: XmpWriteGuard,
+    ) -> Self {
         Self {
-            current_writing: Arc::new(Mutex::new(None)),
+            xmp_writes,
             navigation,
             cache,
+            scale: metadata::current_rating_scale(),
         }
     }
 
+    /// Returns the highest rating of the scale ratings are written with.
+    pub fn max_rating(&self) -> u8 {
+        self.scale.max_rating()
+    }
+
     /// Sets the rating for the current image.
     ///
     /// Returns an error if:
     /// - No image is currently selected
-    /// - A write is already in progress for this file
+    /// - A rating or keyword write is already in progress for this file
+    /// - The file is read-only
     /// - XMP write fails
     pub fn set_rating(&self, rating: u8) -> RatingResult {
         let path = {
-            let nav_state = self.navigation.lock().unwrap();
+            let nav_state = lock_or_recover(&self.navigation);
             nav_state.current_path()
         };
 
         let path = path.ok_or_else(|| AppError::XmpWrite("No image file selected".to_string()))?;
 
-        // Check if write is already in progress
-        if self.is_write_in_progress(&path) {
-            return Err(AppError::XmpWrite(
-                "Write already in progress for this file".to_string(),
-            ));
-        }
-
-        // Mark as writing
-        self.mark_file_as_writing(path.clone());
+        // Mark as writing, unless a write is already in progress
+        let xmp_write = self.xmp_writes.try_begin(&path).ok_or_else(|| {
+            AppError::XmpWrite("Write already in progress for this file".to_string())
+        })?;
 
         // Perform the write
-        let write_result = metadata::write_xmp_rating(&path, rating);
+        let write_result = metadata::write_xmp_rating_scaled(&path, rating, self.scale);
 
         // Clear writing lock
-        self.clear_writing_lock();
+        drop(xmp_write);
 
         // Handle result
         match write_result {
             Ok(()) => {
-                // Update navigation state
-                if let Ok(mut nav_state) = self.navigation.lock() {
-                    nav_state.set_current_rating(Some(rating));
-                }
-
-                // Update cache
-                if let Ok(mut cache) = self.cache.lock() {
-                    cache.update_rating(&path, Some(rating));
-                }
+                lock_or_recover(&self.navigation).set_current_rating(Some(rating));
+                lock_or_recover(&self.cache).update_rating(&path, Some(rating));
 
-                Ok(RatingSuccess { rating })
+                Ok(RatingSuccess { rating, path })
             }
-            Err(e) => Err(AppError::XmpWrite(e.to_string())),
+            Err(e) => Err(e),
         }
     }
 
-    /// Checks if a write operation is already in progress for the specified file.
-    fn is_write_in_progress(&self, path: &PathBuf) -> bool {
-        let writing = self.current_writing.lock().unwrap();
-        if let Some(ref writing_path) = *writing {
-            if writing_path == path {
-                warn!("XMP write already in progress for: {:?}", path);
-                return true;
-            }
-        }
-        false
-    }
+    /// Re-reads the current image's rating from disk and stores it in state and cache.
+    ///
+    /// Used after a failed write so the displayed rating matches the file.
+    /// Returns `None` if the file has no rating or it cannot be read.
+    pub fn resync_rating(&self) -> Option<u8> {
+        let path = lock_or_recover(&self.navigation).current_path()?;
 
-    /// Marks a file as being written.
-    fn mark_file_as_writing(&self, path: PathBuf) {
-        let mut writing = self.current_writing.lock().unwrap();
-        *writing = Some(path);
+        let rating = metadata::read_xmp_rating_scaled(&path, self.scale).unwrap_or_else(|e| {
+            warn!("Failed to re-read rating for {:?}: {}", path, e);
+            None
+        });
+
+        lock_or_recover(&self.navigation).set_current_rating(rating);
+        lock_or_recover(&self.cache).update_rating(&path, rating);
+
+        rating
     }
+}
 
-    /// Clears the writing lock.
-    fn clear_writing_lock(&self) {
-        let mut writing = self.current_writing.lock().unwrap();
-        *writing = None;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_write_reports_on_disk_rating() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rated.png");
+        image::save_buffer(&path, &[0u8; 12], 2, 2, image::ExtendedColorType::Rgb8).unwrap();
+        metadata::write_xmp_rating_scaled(&path, 2, RatingScale::Standard).unwrap();
+        let mut permissions = std::fs::metadata(&path).unwrap().permissions();
+        permissions.set_readonly(true);
+        std::fs::set_permissions(&path, permissions).unwrap();
+
+        let mut nav_state = NavigationState::new();
+        nav_state.update_directory(path.clone()).unwrap();
+        let mut service = RatingService::new(
+            Arc::new(Mutex::new(nav_state)),
+            Arc::new(Mutex::new(ImageCache::new(4))),
+            XmpWriteGuard::new(),
+        );
+        service.scale = RatingScale::Standard;
+
+        assert!(matches!(
+            service.set_rating(5),
+            Err(AppError::FileReadOnly(_))
+        ));
+        assert_eq!(service.resync_rating(), Some(2));
     }
 }
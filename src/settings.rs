@@ -0,0 +1,232 @@
+//! Persistent user settings.
+//!
+//! Settings are stored as JSON in the platform configuration directory
+//! and kept in memory behind a global lock for cheap access from any thread.
+
+use crate::image_cache::CacheMode;
+use crate::messages::Language;
+use crate::metadata::RatingScale;
+use crate::services::{AutoReloadMode, TargetMonitor, WatcherBackend};
+use crate::state::OpenAt;
+use crate::ui::display_mode::DisplayMode;
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+/// File name of the settings file inside the configuration directory.
+const SETTINGS_FILE_NAME: &str = "settings.json";
+
+/// Directory name used under the platform configuration directory.
+const APP_DIR_NAME: &str = "com.tenpamk2.slint-sd-image-viewer";
+
+/// User settings persisted across launches.
+///
+/// Missing fields fall back to their defaults, so older settings files keep loading.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    /// How images are scaled in the viewer.
+    pub display_mode: DisplayMode,
+    /// Whether the window starts maximized.
+    pub start_maximized: bool,
+    /// Monitor the window is moved to on startup.
+    pub target_monitor: TargetMonitor,
+    /// Whether images are converted to sRGB instead of the display profile.
+    pub force_srgb_output: bool,
+    /// File system watcher backend used for auto-reload.
+    pub watcher_backend: WatcherBackend,
+    /// Whether auto-reload jumps to new images or stays on the current one.
+    pub auto_reload_mode: AutoReloadMode,
+    /// Whether navigation wraps around at the ends of the directory.
+    pub wrap_navigation: bool,
+    /// Number of images PageUp / PageDown jump by.
+    pub page_step: usize,
+    /// Whether hidden files are left out when scanning directories.
+    pub skip_hidden_files: bool,
+    /// Which image is shown first when a directory is opened from one of its files.
+    pub open_at: OpenAt,
+    /// Number of images preloaded on each side of the current image.
+    pub preload_radius: usize,
+    /// Whether neighbors of the current image are preloaded at all.
+    pub preload_enabled: bool,
+    /// Whether the image cache has a fixed size or follows the preload window.
+    pub cache_mode: CacheMode,
+    /// Seconds until an error message is cleared, or 0 to keep it until replaced.
+    pub error_auto_hide_secs: u64,
+    /// Whether only the image is shown, without the info panel and overlays.
+    pub chrome_hidden: bool,
+    /// Whether the positive prompt is shown over the bottom of the image.
+    pub prompt_overlay: bool,
+    /// Whether dragging the image in fit mode copies the file to the clipboard.
+    pub drag_out_to_clipboard: bool,
+    /// Whether cached images keep their original file bytes for copying.
+    pub retain_encoded_bytes: bool,
+    /// strftime format of the created / modified dates in the info panel.
+    pub date_format: String,
+    /// Whether the ratings of the whole directory are read in the background after opening it.
+    pub prescan_ratings: bool,
+    /// Language of error messages.
+    pub language: Language,
+    /// Rating scale ratings are read and written with.
+    pub rating_scale: RatingScale,
+    /// Maximum number of images decoded at once for display and preloading.
+    pub max_concurrent_decodes: usize,
+    /// Directory image links in copied Markdown are relative to, or absolute when unset.
+    pub markdown_base_dir: Option<PathBuf>,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            display_mode: DisplayMode::default(),
+            start_maximized: false,
+            target_monitor: TargetMonitor::default(),
+            force_srgb_output: false,
+            watcher_backend: WatcherBackend::default(),
+            auto_reload_mode: AutoReloadMode::default(),
+            wrap_navigation: true,
+            page_step: 10,
+            skip_hidden_files: false,
+            open_at: OpenAt::default(),
+            preload_radius: 1,
+            preload_enabled: true,
+            cache_mode: CacheMode::default(),
+            error_auto_hide_secs: 5,
+            chrome_hidden: false,
+            prompt_overlay: false,
+            drag_out_to_clipboard: false,
+            retain_encoded_bytes: false,
+            date_format: crate::config::DEFAULT_DATE_FORMAT.to_string(),
+            prescan_ratings: false,
+            language: Language::default(),
+            rating_scale: RatingScale::default(),
+            max_concurrent_decodes: 4,
+            markdown_base_dir: None,
+        }
+    }
+}
+
+impl AppSettings {
+    /// Parses settings from JSON, falling back to defaults on malformed input.
+    pub fn from_json(json: &str) -> Self {
+        serde_json::from_str(json).unwrap_or_else(|e| {
+            warn!("Failed to parse settings, using defaults: {}", e);
+            Self::default()
+        })
+    }
+}
+
+static SETTINGS: Lazy<RwLock<AppSettings>> = Lazy::new(|| RwLock::new(load()));
+
+/// Returns the platform configuration directory for this application.
+pub(crate) fn config_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "macos")]
+    let base = std::env::var_os("HOME")
+        .map(|home| PathBuf::from(home).join("Library/Application Support"));
+
+    #[cfg(target_os = "windows")]
+    let base = std::env::var_os("APPDATA").map(PathBuf::from);
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+
+    base.map(|base| base.join(APP_DIR_NAME))
+}
+
+/// Returns the path of the settings file.
+fn settings_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(SETTINGS_FILE_NAME))
+}
+
+/// Loads settings from disk, returning defaults if the file is missing or invalid.
+fn load() -> AppSettings {
+    let Some(path) = settings_path() else {
+        warn!("No configuration directory available, using default settings");
+        return AppSettings::default();
+    };
+
+    match std::fs::read_to_string(&path) {
+        Ok(json) => {
+            info!("Loaded settings from {:?}", path);
+            AppSettings::from_json(&json)
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => AppSettings::default(),
+        Err(e) => {
+            warn!("Failed to read settings from {:?}: {}", path, e);
+            AppSettings::default()
+        }
+    }
+}
+
+/// Writes settings to disk.
+fn save(settings: &AppSettings) -> std::io::Result<()> {
+    let path = settings_path().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "No configuration directory available",
+        )
+    })?;
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let json = serde_json::to_string_pretty(settings).map_err(std::io::Error::other)?;
+    std::fs::write(&path, json)
+}
+
+/// Returns a snapshot of the current settings.
+pub fn current() -> AppSettings {
+    SETTINGS.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Reads from the current settings without copying them.
+///
+/// Cheaper than [`current`] for single values read often, e.g. once per file.
+pub fn read<T>(f: impl FnOnce(&AppSettings) -> T) -> T {
+    f(&SETTINGS.read().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// Applies a change to the settings and persists them.
+///
+/// Save failures are logged; the in-memory settings are updated regardless.
+pub fn update(change: impl FnOnce(&mut AppSettings)) {
+    let snapshot = {
+        let mut settings = SETTINGS.write().unwrap_or_else(|e| e.into_inner());
+        change(&mut settings);
+        settings.clone()
+    };
+
+    if let Err(e) = save(&snapshot) {
+        warn!("Failed to save settings: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_maximized_defaults_to_false() {
+        assert!(!AppSettings::default().start_maximized);
+        assert!(!AppSettings::from_json("{}").start_maximized);
+    }
+
+    #[test]
+    fn parses_start_maximized() {
+        let settings = AppSettings::from_json(r#"{"start_maximized": true}"#);
+        assert!(settings.start_maximized);
+        // Other fields keep their defaults
+        assert_eq!(settings.display_mode, DisplayMode::Fit);
+    }
+
+    #[test]
+    fn malformed_settings_fall_back_to_defaults() {
+        assert!(!AppSettings::from_json(r#"{"start_maximized": "yes"}"#).start_maximized);
+        assert!(!AppSettings::from_json("not json").start_maximized);
+    }
+}
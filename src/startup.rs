@@ -1,32 +1,118 @@
 use slint::ComponentHandle;
-use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use crate::image_cache::ImageCache;
-use crate::services::NavigationService;
-use crate::state::{AppState, NavigationState};
+use crate::cli::StartupOptions;
+use crate::error::NavigationError;
+use crate::services::{ColorTarget, NavigationService};
+use crate::state::AppState;
 use crate::ui::DisplayTracker;
 
-fn open_image_path(
+/// Loads and displays the image, then updates the directory listing in the background.
+pub(crate) fn open_image_path(
     ui: slint::Weak<crate::AppWindow>,
     path: PathBuf,
-    navigation: Arc<Mutex<NavigationState>>,
-    cache: Arc<Mutex<ImageCache>>,
+    app_state: &AppState,
+    display_tracker: DisplayTracker,
+    error_prefix: &'static str,
+) {
+    open_with_list(
+        ui,
+        path.clone(),
+        app_state,
+        display_tracker,
+        error_prefix,
+        move |nav_service| nav_service.select_image(path),
+    );
+}
+
+/// Loads and displays the first of several images, then makes them the
+/// navigation list in the background.
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn open_image_list(
+    ui: slint::Weak<crate::AppWindow>,
+    paths: Vec<PathBuf>,
+    app_state: &AppState,
+    display_tracker: DisplayTracker,
+    error_prefix: &'static str,
+) {
+    let Some(first) = paths.first().cloned() else {
+        return;
+    };
+
+    open_with_list(
+        ui,
+        first,
+        app_state,
+        display_tracker,
+        error_prefix,
+        move |nav_service| nav_service.select_images(paths),
+    );
+}
+
+/// Loads and displays `path`, then builds the navigation list with `select`
+/// in the background.
+fn open_with_list(
+    ui: slint::Weak<crate::AppWindow>,
+    path: PathBuf,
+    app_state: &AppState,
     display_tracker: DisplayTracker,
     error_prefix: &'static str,
+    select: impl FnOnce(&NavigationService) -> Result<PathBuf, NavigationError> + Send + 'static,
 ) {
     crate::ui::image_display::load_and_display_image(
         ui.clone(),
         path.clone(),
         error_prefix.to_string(),
-        navigation.clone(),
-        cache,
-        display_tracker,
+        app_state.navigation.clone(),
+        app_state.image_cache.clone(),
+        app_state.rating_index.clone(),
+        display_tracker.clone(),
+        app_state.load_generation.clone(),
     );
 
-    rayon::spawn(move || {
-        let nav_service = NavigationService::new(navigation);
-        if let Err(e) = nav_service.select_image(path) {
+    let nav_service =
+        NavigationService::new(app_state.navigation.clone(), app_state.rating_index.clone());
+    let app_state = app_state.clone();
+    rayon::spawn(move || match select(&nav_service) {
+        Ok(current) => {
+            let ui_handle = ui.clone();
+            let state = app_state.clone();
+            let _ = slint::invoke_from_event_loop(move || {
+                // The `open_at` setting may pick another image than the opened one
+                if current != path {
+                    crate::ui::image_display::load_and_display_image(
+                        ui_handle.clone(),
+                        current,
+                        error_prefix.to_string(),
+                        state.navigation.clone(),
+                        state.image_cache.clone(),
+                        state.rating_index.clone(),
+                        display_tracker.clone(),
+                        state.load_generation.clone(),
+                    );
+                }
+                crate::ui::handlers::retarget_auto_reload(&ui_handle, &state, &display_tracker);
+            });
+            crate::ui::image_display::start_rating_prescan(
+                ui.clone(),
+                app_state.navigation.clone(),
+                app_state.rating_index.clone(),
+                app_state.rating_prescan.clone(),
+            );
+            crate::ui::image_display::refresh_rating_summary(
+                ui.clone(),
+                app_state.navigation.clone(),
+                app_state.rating_index.clone(),
+            );
+            let recent = crate::recent_files::existing();
+            let _ = slint::invoke_from_event_loop(move || {
+                if let Some(ui) = ui.upgrade() {
+                    crate::ui::set_recent_files(&ui, &recent);
+                }
+            });
+        }
+        Err(e) => {
             let _ = slint::invoke_from_event_loop(move || {
                 if let Some(ui) = ui.upgrade() {
                     crate::ui::set_error_with_prefix(
@@ -40,18 +126,245 @@ fn open_image_path(
     });
 }
 
-fn startup_image_from_args() -> Option<PathBuf> {
-    std::env::args_os()
-        .skip(1)
-        .filter_map(|arg| {
-            let arg_str = arg.to_string_lossy();
-            if arg_str.starts_with('-') {
-                None
-            } else {
-                Some(PathBuf::from(arg))
+/// Files dropped onto the window, collected until the drop is complete.
+#[cfg_attr(not(any(target_os = "macos", target_os = "windows")), allow(dead_code))]
+#[derive(Default)]
+struct DropBatch {
+    paths: Vec<PathBuf>,
+}
+
+#[cfg_attr(not(any(target_os = "macos", target_os = "windows")), allow(dead_code))]
+impl DropBatch {
+    /// Adds a dropped file, skipping unsupported files and repeats.
+    fn push(&mut self, path: PathBuf) {
+        if !crate::file_utils::is_supported_image(&path) {
+            log::debug!("Ignoring dropped unsupported file: {:?}", path);
+            return;
+        }
+        if !self.paths.contains(&path) {
+            self.paths.push(path);
+        }
+    }
+
+    /// Takes the collected files in drop order, leaving the batch empty.
+    fn take(&mut self) -> Vec<PathBuf> {
+        std::mem::take(&mut self.paths)
+    }
+}
+
+/// Opens a single dropped image in its directory, or several as their own list.
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn open_dropped_files(
+    ui: slint::Weak<crate::AppWindow>,
+    mut paths: Vec<PathBuf>,
+    app_state: &AppState,
+    display_tracker: DisplayTracker,
+) {
+    match paths.len() {
+        0 => {}
+        1 => open_image_path(
+            ui,
+            paths.remove(0),
+            app_state,
+            display_tracker,
+            "Failed to load opened image",
+        ),
+        count => {
+            log::info!("Opening {} dropped images as a list", count);
+            open_image_list(
+                ui,
+                paths,
+                app_state,
+                display_tracker,
+                "Failed to load dropped images",
+            );
+        }
+    }
+}
+
+thread_local! {
+    /// Advances the slideshow given with `--slideshow`, kept alive for the whole session.
+    static SLIDESHOW_TIMER: slint::Timer = slint::Timer::default();
+}
+
+/// Returns the image to open at startup from the command line paths.
+///
+/// The first path that is a supported image, or a directory containing one, wins.
+fn startup_image(options: &StartupOptions) -> Option<PathBuf> {
+    options.paths.iter().find_map(|path| {
+        if path.is_dir() {
+            first_image_in_directory(path)
+        } else if crate::file_utils::is_supported_image(path) {
+            Some(path.clone())
+        } else {
+            log::warn!("Ignoring unsupported path: {:?}", path);
+            None
+        }
+    })
+}
+
+/// Returns the first image of a directory in its remembered sort order.
+fn first_image_in_directory(dir: &Path) -> Option<PathBuf> {
+    let skip_hidden = crate::settings::current().skip_hidden_files;
+    let mut files = match crate::file_utils::scan_directory(dir, skip_hidden) {
+        Ok(files) => files,
+        Err(e) => {
+            log::warn!("Failed to scan directory {:?}: {}", dir, e);
+            return None;
+        }
+    };
+
+    let sort_order = crate::directory_prefs::get(dir)
+        .map(|prefs| prefs.sort_order)
+        .unwrap_or_default();
+    crate::file_utils::sort_image_files(&mut files, sort_order);
+    files.into_iter().next()
+}
+
+/// Loads the startup image, converts it to sRGB and writes the pixels to
+/// `output` as PNG, without creating a window.
+///
+/// The output is independent of the display profile, so screenshots match
+/// across machines.
+pub fn render_screenshot(
+    options: &StartupOptions,
+    output: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let image =
+        startup_image(options).ok_or("--screenshot requires a supported input image path")?;
+
+    let loaded = crate::image_loader::load_first_frame(&image, ColorTarget::Srgb)?;
+    crate::services::FileOpsService::new().write_rgb8(
+        output,
+        &loaded.data,
+        loaded.width,
+        loaded.height,
+        image::ImageFormat::Png,
+    )?;
+
+    log::info!(
+        "Rendered {:?} ({}x{}) to {:?}",
+        image,
+        loaded.width,
+        loaded.height,
+        output
+    );
+    Ok(())
+}
+
+/// Maximizes the window through the winit window if it already exists.
+///
+/// Falls back to Slint's window API when the winit window is not available yet.
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn maximize_window(app: &crate::AppWindow) {
+    use i_slint_backend_winit::WinitWindowAccessor;
+
+    let applied = app
+        .window()
+        .with_winit_window(|winit_window| winit_window.set_maximized(true))
+        .is_some();
+
+    if !applied {
+        log::debug!("Winit window not available, maximizing through Slint");
+        app.window().set_maximized(true);
+    }
+}
+
+/// Maximizes the window.
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn maximize_window(app: &crate::AppWindow) {
+    app.window().set_maximized(true);
+}
+
+/// Applies persisted window settings and `--fullscreen` before the window is shown.
+fn apply_window_settings(app: &crate::AppWindow, options: &StartupOptions) {
+    if crate::settings::current().start_maximized {
+        log::info!("Starting maximized");
+        maximize_window(app);
+    }
+    if options.fullscreen {
+        log::info!("Starting fullscreen");
+        app.window().set_fullscreen(true);
+    }
+}
+
+/// Starts advancing to the next image every `interval`.
+fn start_slideshow(app: &crate::AppWindow, interval: Duration) {
+    log::info!("Starting slideshow every {:?}", interval);
+    let ui_handle = app.as_weak();
+    SLIDESHOW_TIMER.with(|timer| {
+        timer.start(slint::TimerMode::Repeated, interval, move || {
+            if let Some(ui) = ui_handle.upgrade() {
+                ui.global::<crate::Logic>().invoke_next_image();
             }
-        })
-        .find(|path| crate::file_utils::is_supported_image(path))
+        });
+    });
+}
+
+/// Moves the window onto the configured target monitor.
+///
+/// Returns `false` if the winit window does not exist yet and moving has to be retried.
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn move_to_target_monitor(window: &slint::Window) -> bool {
+    use i_slint_backend_winit::WinitWindowAccessor;
+
+    let target = crate::settings::current().target_monitor;
+    if target == crate::services::TargetMonitor::System {
+        return true;
+    }
+
+    window
+        .with_winit_window(|winit_window| place_on_monitor(winit_window, target))
+        .is_some()
+}
+
+/// Centers the winit window on the monitor resolved from `target`.
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn place_on_monitor(
+    winit_window: &i_slint_backend_winit::winit::window::Window,
+    target: crate::services::TargetMonitor,
+) {
+    use i_slint_backend_winit::winit::{dpi::PhysicalPosition, monitor::MonitorHandle};
+
+    let service = crate::services::DisplayProfileService::new();
+    let screen_id = |monitor: &MonitorHandle| {
+        let (pos, size) = (monitor.position(), monitor.size());
+        service.screen_id_from_position(
+            pos.x + size.width as i32 / 2,
+            pos.y + size.height as i32 / 2,
+        )
+    };
+
+    let monitors: Vec<(u32, MonitorHandle)> = winit_window
+        .available_monitors()
+        .filter_map(|monitor| Some((screen_id(&monitor)?, monitor)))
+        .collect();
+    let available: Vec<u32> = monitors.iter().map(|(id, _)| *id).collect();
+    let primary = winit_window.primary_monitor().as_ref().and_then(screen_id);
+
+    let Some((id, monitor)) = target
+        .resolve(&available, primary)
+        .and_then(|id| monitors.into_iter().find(|(candidate, _)| *candidate == id))
+    else {
+        log::warn!("No monitor available for {:?}", target);
+        return;
+    };
+
+    let (pos, size) = (monitor.position(), monitor.size());
+    let window_size = winit_window.outer_size();
+    let x = pos.x + (size.width.saturating_sub(window_size.width) / 2) as i32;
+    let y = pos.y + (size.height.saturating_sub(window_size.height) / 2) as i32;
+    log::info!("Moving window to monitor {} at ({}, {})", id, x, y);
+
+    // A maximized window cannot be moved, so it is re-maximized on the target monitor
+    let maximized = winit_window.is_maximized();
+    if maximized {
+        winit_window.set_maximized(false);
+    }
+    winit_window.set_outer_position(PhysicalPosition::new(x, y));
+    if maximized {
+        winit_window.set_maximized(true);
+    }
 }
 
 #[cfg(any(target_os = "macos", target_os = "windows"))]
@@ -62,12 +375,21 @@ fn setup_platform_window_hooks(
 ) {
     use i_slint_backend_winit::WinitWindowAccessor;
     use i_slint_backend_winit::{winit::event::WindowEvent, EventResult};
+    use std::cell::RefCell;
+    use std::rc::Rc;
 
     let display_tracker_clone = display_tracker.clone();
     let ui_handle = app.as_weak();
-    let navigation = app_state.navigation.clone();
-    let cache = app_state.image_cache.clone();
+    let app_state = app_state.clone();
     let window = app.window();
+    let dropped_files = Rc::new(RefCell::new(DropBatch::default()));
+    let drop_timer = slint::Timer::default();
+
+    // The winit window may only be created when the window is shown
+    let mut placement_pending = !move_to_target_monitor(window);
+    if placement_pending {
+        log::debug!("Winit window not available, moving to target monitor once shown");
+    }
 
     let initial_pos = window.position();
     let screen_id = crate::services::DisplayProfileService::new()
@@ -75,7 +397,11 @@ fn setup_platform_window_hooks(
     log::info!("Initial display screen ID: {:?}", screen_id);
     display_tracker.update_display_id(screen_id);
 
-    window.on_winit_window_event(move |_window, event| {
+    window.on_winit_window_event(move |window, event| {
+        if placement_pending {
+            placement_pending = !move_to_target_monitor(window);
+        }
+
         match event {
             WindowEvent::Moved(pos) => {
                 let prev_id = display_tracker_clone.current_display_id();
@@ -89,16 +415,27 @@ fn setup_platform_window_hooks(
                 display_tracker_clone.update_display_id(screen_id);
             }
             WindowEvent::DroppedFile(path) => {
-                if crate::file_utils::is_supported_image(path) {
-                    open_image_path(
-                        ui_handle.clone(),
-                        path.clone(),
-                        navigation.clone(),
-                        cache.clone(),
-                        display_tracker_clone.clone(),
-                        "Failed to load opened image",
-                    );
-                }
+                dropped_files.borrow_mut().push(path.clone());
+
+                // Each file of a multi-file drop arrives as its own event, so
+                // the batch is opened once no more files follow
+                let dropped_files = dropped_files.clone();
+                let ui_handle = ui_handle.clone();
+                let app_state = app_state.clone();
+                let display_tracker = display_tracker_clone.clone();
+                drop_timer.start(
+                    slint::TimerMode::SingleShot,
+                    Duration::from_millis(crate::config::DROP_COALESCE_MS),
+                    move || {
+                        let paths = dropped_files.borrow_mut().take();
+                        open_dropped_files(
+                            ui_handle.clone(),
+                            paths,
+                            &app_state,
+                            display_tracker.clone(),
+                        );
+                    },
+                );
             }
             _ => {}
         }
@@ -120,17 +457,66 @@ pub fn configure_startup_opening(
     app: &crate::AppWindow,
     app_state: &AppState,
     display_tracker: &DisplayTracker,
+    options: &StartupOptions,
 ) {
+    apply_window_settings(app, options);
     setup_platform_window_hooks(app, app_state, display_tracker);
 
-    if let Some(path) = startup_image_from_args() {
+    if let Some(interval) = options.slideshow {
+        start_slideshow(app, interval);
+    }
+
+    if let Some(path) = startup_image(options) {
         open_image_path(
             app.as_weak(),
             path,
-            app_state.navigation.clone(),
-            app_state.image_cache.clone(),
+            app_state,
             display_tracker.clone(),
             "Failed to load startup image",
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn screenshot_writes_png_of_the_input_image() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("input.png");
+        let pixels: Vec<u8> = (0..3 * 2 * 3).map(|i| i as u8 * 10).collect();
+        image::save_buffer(&input, &pixels, 3, 2, image::ExtendedColorType::Rgb8).unwrap();
+        let output = dir.path().join("out.png");
+        let options = StartupOptions {
+            paths: vec![input],
+            ..StartupOptions::default()
+        };
+
+        render_screenshot(&options, &output).unwrap();
+
+        let rendered = image::open(&output).unwrap();
+        assert_eq!(rendered.color(), image::ColorType::Rgb8);
+        assert_eq!((rendered.width(), rendered.height()), (3, 2));
+    }
+
+    #[test]
+    fn drop_batch_collects_supported_files_once_in_drop_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let [b, notes, a] = ["b.png", "notes.txt", "a.jpg"].map(|name| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, b"").unwrap();
+            path
+        });
+
+        let mut batch = DropBatch::default();
+        batch.push(b.clone());
+        batch.push(notes);
+        batch.push(a.clone());
+        batch.push(b.clone());
+        batch.push(dir.path().join("missing.png"));
+
+        assert_eq!(batch.take(), [b, a]);
+        assert!(batch.take().is_empty());
+    }
+}
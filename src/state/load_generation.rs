@@ -0,0 +1,48 @@
+//! Generation counter for discarding stale image loads.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Counter incremented on every display request.
+///
+/// A background decode captures the generation it was started with and only
+/// applies its result if no newer request has been made in the meantime.
+#[derive(Clone, Default)]
+pub struct LoadGeneration {
+    counter: Arc<AtomicU64>,
+}
+
+impl LoadGeneration {
+    /// Creates a new counter starting at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts a new generation and returns its value.
+    pub fn next(&self) -> u64 {
+        self.counter.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Returns whether `generation` is still the latest one.
+    pub fn is_current(&self, generation: u64) -> bool {
+        self.counter.load(Ordering::SeqCst) == generation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_result_is_discarded() {
+        let generation = LoadGeneration::new();
+        let first = generation.next();
+        assert!(generation.is_current(first));
+
+        // A clone shared with a background decode sees the newer request
+        let worker = generation.clone();
+        let second = generation.next();
+        assert!(!worker.is_current(first));
+        assert!(worker.is_current(second));
+    }
+}
@@ -1,31 +1,107 @@
 //! State management for the image viewer application.
 
-use crate::image_cache::ImageCache;
-use notify_debouncer_mini::{notify::PollWatcher, Debouncer};
+use crate::checksum::ChecksumIndex;
+use crate::config;
+use crate::image_cache::{self, ImageCache};
+use crate::rating_index::RatingIndex;
+use notify_debouncer_mini::{
+    notify::{PollWatcher, RecommendedWatcher, Watcher},
+    Debouncer,
+};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
+pub mod load_generation;
 pub mod navigation;
+pub mod pending_writes;
+pub mod xmp_writes;
 
-pub use navigation::NavigationState;
+pub use load_generation::LoadGeneration;
+pub use navigation::{NavigationState, OpenAt};
+pub use pending_writes::PendingWrites;
+pub use xmp_writes::XmpWriteGuard;
 
-/// Type alias for the auto-reload debouncer.
-pub type AutoReloadDebouncer = Debouncer<PollWatcher>;
+/// Debouncer for auto-reload functionality, backed by either watcher type.
+pub enum AutoReloadDebouncer {
+    /// Polls the directory periodically. Works on network filesystems.
+    Poll(Debouncer<PollWatcher>),
+    /// Uses the platform's native file system notifications.
+    Native(Debouncer<RecommendedWatcher>),
+}
+
+impl AutoReloadDebouncer {
+    /// Returns the underlying watcher.
+    pub fn watcher(&mut self) -> &mut dyn Watcher {
+        match self {
+            Self::Poll(debouncer) => debouncer.watcher(),
+            Self::Native(debouncer) => debouncer.watcher(),
+        }
+    }
+}
+
+/// Running auto-reload watcher together with the directory it watches.
+///
+/// Watching stops when this is dropped.
+pub struct AutoReloadWatcher {
+    _debouncer: AutoReloadDebouncer,
+    directory: PathBuf,
+}
+
+impl AutoReloadWatcher {
+    /// Wraps a debouncer that watches `directory`.
+    pub fn new(debouncer: AutoReloadDebouncer, directory: PathBuf) -> Self {
+        Self {
+            _debouncer: debouncer,
+            directory,
+        }
+    }
+
+    /// Returns the directory the watcher was started on.
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+}
 
 /// Application-wide state container.
+#[derive(Clone)]
 pub struct AppState {
     pub navigation: Arc<Mutex<NavigationState>>,
     /// LRU cache for decoded images.
     pub image_cache: Arc<Mutex<ImageCache>>,
-    /// Debouncer for auto-reload functionality.
-    pub auto_reload_watcher: Arc<Mutex<Option<AutoReloadDebouncer>>>,
+    /// Index of known XMP ratings.
+    pub rating_index: Arc<Mutex<RatingIndex>>,
+    /// Running auto-reload watcher, `None` while auto-reload is off.
+    pub auto_reload_watcher: Arc<Mutex<Option<AutoReloadWatcher>>>,
+    /// Generation of the latest image display request.
+    pub load_generation: LoadGeneration,
+    /// Generation of the latest directory rating prescan.
+    pub rating_prescan: LoadGeneration,
+    /// Checksums computed for displayed files.
+    pub checksums: Arc<Mutex<ChecksumIndex>>,
+    /// Rating and keyword writes that are waited for before the app exits.
+    pub pending_writes: PendingWrites,
+    /// Files whose XMP is being written, shared by rating and keyword writes.
+    pub xmp_writes: XmpWriteGuard,
 }
 
 impl AppState {
     pub fn new() -> Self {
+        let settings = crate::settings::current();
+        let cache_capacity = image_cache::capacity_for(
+            settings.cache_mode,
+            settings.preload_radius.min(config::MAX_PRELOAD_RADIUS),
+        );
+
         Self {
             navigation: Arc::new(Mutex::new(NavigationState::new())),
-            image_cache: Arc::new(Mutex::new(ImageCache::new(10))),
+            image_cache: Arc::new(Mutex::new(ImageCache::new(cache_capacity))),
+            rating_index: Arc::new(Mutex::new(RatingIndex::new())),
             auto_reload_watcher: Arc::new(Mutex::new(None)),
+            load_generation: LoadGeneration::new(),
+            rating_prescan: LoadGeneration::new(),
+            checksums: Arc::new(Mutex::new(ChecksumIndex::new())),
+            pending_writes: PendingWrites::new(),
+            xmp_writes: XmpWriteGuard::new(),
         }
     }
 }
@@ -1,17 +1,50 @@
 //! Navigation state for managing image file lists and current position.
 
+use crate::config;
+use crate::directory_prefs::{self, DirectoryPrefs};
 use crate::error::NavigationError;
-use crate::file_utils::{self, PathExt};
+use crate::file_utils::{self, PathExt, SortOrder};
+use crate::ui::display_mode::DisplayMode;
 use log::{debug, warn};
-use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 
 /// Direction for navigation through images.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 enum Direction {
+    #[default]
     Next,
     Previous,
 }
 
+/// Which image becomes current when a directory is opened from one of its files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OpenAt {
+    /// The first image in sort order.
+    First,
+    /// The last image in sort order, i.e. the newest one for generated file names
+    /// or when sorting by modification date.
+    Last,
+    /// The opened file itself.
+    #[default]
+    Selected,
+}
+
+impl OpenAt {
+    /// Returns the image to make current among the sorted `files`, given the opened file.
+    ///
+    /// Falls back to the opened file when the list is empty.
+    pub fn pick(self, files: &[PathBuf], selected: &Path) -> PathBuf {
+        let picked = match self {
+            Self::First => files.first(),
+            Self::Last => files.last(),
+            Self::Selected => None,
+        };
+        picked.cloned().unwrap_or_else(|| selected.to_path_buf())
+    }
+}
+
 /// Manages the current directory, list of image files, and current file path.
 #[derive(Default)]
 pub struct NavigationState {
@@ -19,12 +52,158 @@ pub struct NavigationState {
     image_files: Vec<PathBuf>,
     current_file_path: Option<PathBuf>,
     current_rating: Option<u8>,
+    sort_order: SortOrder,
+    /// Whether navigation wraps around at the ends of the list.
+    wrap: bool,
+    /// Full file list when the directory exceeds the soft cap. Empty otherwise.
+    all_files: Vec<PathBuf>,
+    /// Number of files kept on each side of the current file when windowed.
+    window_radius: usize,
+    /// Display mode remembered for the current directory, if any.
+    display_mode: Option<DisplayMode>,
+    /// Incremented whenever the image list changes, so views of it know to rebuild.
+    list_revision: u64,
+    /// Whether hidden files are left out of directory scans.
+    skip_hidden: bool,
+    /// Whether the list was given explicitly instead of scanned from a directory.
+    explicit_list: bool,
+    /// Which image becomes current when a directory is opened from a file.
+    open_at: OpenAt,
+    /// Direction of the last step through the list, so missing files are skipped the same way.
+    last_direction: Direction,
 }
 
 impl NavigationState {
     /// Creates a new empty navigation state.
+    ///
+    /// The wrap behavior, hidden file handling and the image opened first are
+    /// taken from the `wrap_navigation`, `skip_hidden_files` and `open_at` settings.
     pub fn new() -> Self {
-        Self::default()
+        let settings = crate::settings::current();
+        Self {
+            wrap: settings.wrap_navigation,
+            skip_hidden: settings.skip_hidden_files,
+            open_at: settings.open_at,
+            window_radius: config::DIRECTORY_WINDOW_RADIUS,
+            ..Self::default()
+        }
+    }
+
+    /// Applies the preferences remembered for `dir` when entering it.
+    ///
+    /// Keeps the current sort order for directories without stored preferences.
+    fn apply_directory_prefs(&mut self, dir: &Path) {
+        match directory_prefs::get(dir) {
+            Some(prefs) => {
+                debug!("Applying directory preferences for {:?}: {:?}", dir, prefs);
+                self.sort_order = prefs.sort_order;
+                self.display_mode = prefs.display_mode;
+            }
+            None => self.display_mode = None,
+        }
+    }
+
+    /// Remembers the current sort order and display mode for the current directory.
+    fn save_directory_prefs(&self) {
+        if let Some(dir) = &self.current_directory {
+            directory_prefs::set(
+                dir,
+                DirectoryPrefs {
+                    sort_order: self.sort_order,
+                    display_mode: self.display_mode,
+                },
+            );
+        }
+    }
+
+    /// Returns the display mode remembered for the current directory, if any.
+    pub fn directory_display_mode(&self) -> Option<DisplayMode> {
+        self.display_mode
+    }
+
+    /// Remembers the display mode for the current directory.
+    pub fn set_directory_display_mode(&mut self, mode: DisplayMode) {
+        self.display_mode = Some(mode);
+        self.save_directory_prefs();
+    }
+
+    /// Sets the file list, windowing it around `center` if it exceeds the soft cap.
+    fn set_files(&mut self, files: Vec<PathBuf>, center: Option<&PathBuf>) {
+        self.list_revision += 1;
+        if files.len() <= config::DIRECTORY_SOFT_CAP {
+            self.all_files.clear();
+            self.image_files = files;
+            return;
+        }
+
+        let center_index = center
+            .and_then(|center| files.iter().position(|p| p == center))
+            .unwrap_or(0);
+        let range = file_utils::window_range(files.len(), center_index, self.window_radius);
+        warn!(
+            "Directory has {} images (limit {}), showing {} around the current file",
+            files.len(),
+            config::DIRECTORY_SOFT_CAP,
+            range.len()
+        );
+
+        self.image_files = files[range].to_vec();
+        self.all_files = files;
+    }
+
+    /// Takes the full file list, including files outside the window.
+    fn take_all_files(&mut self) -> Vec<PathBuf> {
+        if self.all_files.is_empty() {
+            std::mem::take(&mut self.image_files)
+        } else {
+            self.image_files.clear();
+            std::mem::take(&mut self.all_files)
+        }
+    }
+
+    /// Returns whether navigation wraps around at the ends of the list.
+    pub fn wraps(&self) -> bool {
+        self.wrap
+    }
+
+    /// Sets whether navigation wraps around, effective from the next navigation.
+    pub fn set_wrap(&mut self, wrap: bool) {
+        self.wrap = wrap;
+    }
+
+    /// Returns the full file list, including files outside the window.
+    fn full_list(&self) -> &[PathBuf] {
+        if self.is_windowed() {
+            &self.all_files
+        } else {
+            &self.image_files
+        }
+    }
+
+    /// Returns whether some files of the directory are outside the window.
+    pub fn is_windowed(&self) -> bool {
+        self.image_files.len() < self.all_files.len()
+    }
+
+    /// Extends the window by `DIRECTORY_WINDOW_RADIUS` files on each side.
+    ///
+    /// Returns `false` if all files are already in the list.
+    pub fn extend_window(&mut self) -> bool {
+        if !self.is_windowed() {
+            return false;
+        }
+
+        self.window_radius = self
+            .window_radius
+            .saturating_add(config::DIRECTORY_WINDOW_RADIUS);
+        let files = self.take_all_files();
+        let current = self.current_file_path.clone();
+        self.set_files(files, current.as_ref());
+        debug!(
+            "Extended directory window to {} files",
+            self.image_files.len()
+        );
+        true
     }
 
     /// Navigates to an image in the specified direction.
@@ -44,19 +223,25 @@ impl NavigationState {
             Direction::Next => {
                 if current_index + 1 < self.image_files.len() {
                     current_index + 1
-                } else {
+                } else if self.wrap {
                     // Wrap around to the first image
                     debug!("Reached last image, wrapping to first");
                     0
+                } else {
+                    debug!("Reached last image, staying");
+                    current_index
                 }
             }
             Direction::Previous => {
                 if current_index > 0 {
                     current_index - 1
-                } else {
+                } else if self.wrap {
                     // Wrap around to the last image
                     debug!("Reached first image, wrapping to last");
                     self.image_files.len() - 1
+                } else {
+                    debug!("Reached first image, staying");
+                    current_index
                 }
             }
         };
@@ -64,6 +249,7 @@ impl NavigationState {
         let path = self.image_files[new_index].clone();
         self.current_file_path = Some(path.clone());
         self.current_rating = None;
+        self.last_direction = direction;
         debug!("Navigated to: {:?}", path);
         Ok(())
     }
@@ -78,8 +264,119 @@ impl NavigationState {
         self.navigate_to(Direction::Previous)
     }
 
+    /// Navigates `delta` images away from the current one.
+    ///
+    /// Positive values move forward, negative values back. Past the ends of the
+    /// list it wraps around when wrapping is enabled, and stops at the first /
+    /// last image otherwise.
+    pub fn navigate_by(&mut self, delta: isize) -> Result<(), NavigationError> {
+        if self.image_files.is_empty() {
+            warn!("No images available for navigation");
+            return Err(NavigationError::NoImages);
+        }
+
+        let current_path = self
+            .current_file_path
+            .as_ref()
+            .ok_or(NavigationError::NoCurrentPath)?;
+        let current_index = self.find_file_index(current_path) as isize;
+        let len = self.image_files.len() as isize;
+        let target = current_index + delta;
+
+        let new_index = if self.wrap {
+            target.rem_euclid(len)
+        } else {
+            target.clamp(0, len - 1)
+        };
+
+        let path = self.image_files[new_index as usize].clone();
+        self.current_file_path = Some(path.clone());
+        self.current_rating = None;
+        self.last_direction = if delta < 0 {
+            Direction::Previous
+        } else {
+            Direction::Next
+        };
+        debug!("Jumped {} images to: {:?}", delta, path);
+        Ok(())
+    }
+
+    /// Opens the first image of the next or previous sibling directory.
+    ///
+    /// Sibling directories without images are skipped. Wraps around past the
+    /// first / last sibling when wrapping is enabled.
+    fn navigate_to_directory(&mut self, direction: Direction) -> Result<(), NavigationError> {
+        let current_dir = self
+            .current_directory
+            .clone()
+            .ok_or(NavigationError::NoCurrentPath)?;
+
+        let siblings = file_utils::sibling_directories(&current_dir).map_err(|e| {
+            NavigationError::DirectoryScanFailed(format!("Failed to list directories: {}", e))
+        })?;
+        let current_index = siblings
+            .iter()
+            .position(|dir| dir == &current_dir)
+            .ok_or(NavigationError::NoSiblingDirectory)?;
+
+        let len = siblings.len() as isize;
+        let step = match direction {
+            Direction::Next => 1,
+            Direction::Previous => -1,
+        };
+
+        for distance in 1..len {
+            let target = current_index as isize + step * distance;
+            let index = if self.wrap {
+                target.rem_euclid(len)
+            } else if (0..len).contains(&target) {
+                target
+            } else {
+                break;
+            };
+
+            let dir = &siblings[index as usize];
+            let mut files = match file_utils::scan_directory(dir, self.skip_hidden) {
+                Ok(files) => files,
+                Err(e) => {
+                    warn!("Skipping unreadable directory {:?}: {}", dir, e);
+                    continue;
+                }
+            };
+            if files.is_empty() {
+                continue;
+            }
+
+            self.apply_directory_prefs(dir);
+            file_utils::sort_image_files(&mut files, self.sort_order);
+            let first = files[0].clone();
+            self.current_directory = Some(dir.clone());
+            self.window_radius = config::DIRECTORY_WINDOW_RADIUS;
+            self.explicit_list = false;
+            self.set_files(files, Some(&first));
+            self.current_file_path = Some(first);
+            self.current_rating = None;
+            debug!("Moved to directory: {:?}", dir);
+            return Ok(());
+        }
+
+        Err(NavigationError::NoSiblingDirectory)
+    }
+
+    /// Opens the first image of the next sibling directory.
+    pub fn navigate_next_directory(&mut self) -> Result<(), NavigationError> {
+        self.navigate_to_directory(Direction::Next)
+    }
+
+    /// Opens the first image of the previous sibling directory.
+    pub fn navigate_prev_directory(&mut self) -> Result<(), NavigationError> {
+        self.navigate_to_directory(Direction::Previous)
+    }
+
     /// Updates the directory context based on a selected file path.
-    /// Scans the parent directory and sets the current file path to the selected file.
+    /// Scans the parent directory and sets the current file path according to
+    /// the `open_at` setting: the selected file by default, or the first / last
+    /// image in sort order.
     pub fn update_directory(&mut self, file_path: PathBuf) -> Result<(), NavigationError> {
         let start = std::time::Instant::now();
         let parent = file_path.parent().ok_or_else(|| {
@@ -88,13 +385,28 @@ impl NavigationState {
         debug!("Starting directory update for: {:?}", parent);
 
         self.current_directory = Some(parent.to_path_buf());
+        self.apply_directory_prefs(parent);
 
-        let files = file_utils::scan_directory(parent).map_err(|e| {
+        let mut files = file_utils::scan_directory(parent, self.skip_hidden).map_err(|e| {
             NavigationError::DirectoryScanFailed(format!("Failed to scan directory: {}", e))
         })?;
+        // An explicitly opened hidden file stays in the list
+        if !files.contains(&file_path) && file_utils::is_supported_image(&file_path) {
+            files.push(file_path.clone());
+        }
+        file_utils::sort_image_files(&mut files, self.sort_order);
+        let current = self.open_at.pick(&files, &file_path);
+        if current != file_path {
+            debug!(
+                "Opening at {:?} ({:?}) instead of {:?}",
+                current, self.open_at, file_path
+            );
+        }
 
-        self.image_files = files;
-        self.current_file_path = Some(file_path.clone());
+        self.window_radius = config::DIRECTORY_WINDOW_RADIUS;
+        self.explicit_list = false;
+        self.set_files(files, Some(&current));
+        self.current_file_path = Some(current);
         self.current_rating = None;
 
         debug!(
@@ -105,6 +417,33 @@ impl NavigationState {
         Ok(())
     }
 
+    /// Replaces the list with the given images instead of a directory scan.
+    ///
+    /// Repeated paths are dropped and the rest sorted with the current sort
+    /// order. The first given image becomes current, and its directory is used
+    /// for sibling directory navigation. Rescans only drop files that no longer exist.
+    #[cfg_attr(not(any(target_os = "macos", target_os = "windows")), allow(dead_code))]
+    pub fn set_explicit_list(&mut self, files: Vec<PathBuf>) -> Result<(), NavigationError> {
+        let first = files.first().cloned().ok_or(NavigationError::NoImages)?;
+        let mut unique = Vec::with_capacity(files.len());
+        for file in files {
+            if !unique.contains(&file) {
+                unique.push(file);
+            }
+        }
+        file_utils::sort_image_files(&mut unique, self.sort_order);
+
+        self.current_directory = first.parent().map(Path::to_path_buf);
+        self.display_mode = None;
+        self.window_radius = config::DIRECTORY_WINDOW_RADIUS;
+        self.explicit_list = true;
+        debug!("Set explicit list of {} images", unique.len());
+        self.set_files(unique, Some(&first));
+        self.current_file_path = Some(first);
+        self.current_rating = None;
+        Ok(())
+    }
+
     /// Finds the index of a file in the image files list.
     pub fn find_file_index(&self, file_path: &PathBuf) -> usize {
         self.image_files
@@ -123,28 +462,87 @@ impl NavigationState {
         self.current_rating = rating;
     }
 
-    /// Returns the path to the next image without changing the current file path.
-    pub fn peek_next_image(&self) -> Option<PathBuf> {
+    /// Returns the path `delta` steps away from the current image without changing it.
+    ///
+    /// Positive values look ahead, negative values look back. Out-of-range offsets
+    /// wrap around when wrapping is enabled and return `None` otherwise.
+    /// Also returns `None` for an empty list or when the current file is not in the list.
+    pub fn peek_offset(&self, delta: isize) -> Option<PathBuf> {
         let current_path = self.current_file_path.as_ref()?;
-        let current_index = self.find_file_index(current_path);
+        let current_index = self.image_files.iter().position(|p| p == current_path)?;
+        let len = self.image_files.len() as isize;
+        let target = current_index as isize + delta;
 
-        if current_index + 1 < self.image_files.len() {
-            Some(self.image_files[current_index + 1].clone())
+        let index = if self.wrap {
+            target.rem_euclid(len)
+        } else if (0..len).contains(&target) {
+            target
         } else {
-            None
-        }
+            return None;
+        };
+
+        Some(self.image_files[index as usize].clone())
     }
 
-    /// Returns the path to the previous image without changing the current file path.
-    pub fn peek_prev_image(&self) -> Option<PathBuf> {
-        let current_path = self.current_file_path.as_ref()?;
-        let current_index = self.find_file_index(current_path);
+    /// Returns the other images in list order, starting after the current one.
+    ///
+    /// Always wraps around once regardless of the wrap setting, so every other
+    /// image appears exactly once. Files outside the window are included.
+    /// `backward` walks the list in reverse.
+    pub fn files_in_scan_order(&self, backward: bool) -> Vec<PathBuf> {
+        let files = self.full_list();
+        let Some(current_index) = self
+            .current_file_path
+            .as_ref()
+            .and_then(|current| files.iter().position(|p| p == current))
+        else {
+            return Vec::new();
+        };
 
-        if current_index > 0 {
-            Some(self.image_files[current_index - 1].clone())
-        } else {
-            None
+        let len = files.len();
+        (1..len)
+            .map(|distance| {
+                let index = if backward {
+                    (current_index + len - distance) % len
+                } else {
+                    (current_index + distance) % len
+                };
+                files[index].clone()
+            })
+            .collect()
+    }
+
+    /// Makes the given path current without rescanning the directory.
+    ///
+    /// A file outside the window moves the window to it. Returns
+    /// `NavigationError::NotInList` if the path is not in the directory list.
+    pub fn set_current_by_path(&mut self, path: &PathBuf) -> Result<(), NavigationError> {
+        if !self.image_files.contains(path) {
+            if !self.all_files.contains(path) {
+                return Err(NavigationError::NotInList(path.clone()));
+            }
+            let files = self.take_all_files();
+            self.set_files(files, Some(path));
         }
+
+        self.current_file_path = Some(path.clone());
+        self.current_rating = None;
+        debug!("Selected: {}", path.format_for_log());
+        Ok(())
+    }
+
+    /// Makes the image at `index` of the list current.
+    pub fn navigate_to_index(&mut self, index: usize) -> Result<(), NavigationError> {
+        let path = self
+            .image_files
+            .get(index)
+            .cloned()
+            .ok_or(NavigationError::IndexOutOfRange(index))?;
+
+        debug!("Navigated to index {}: {}", index, path.format_for_log());
+        self.current_file_path = Some(path);
+        self.current_rating = None;
+        Ok(())
     }
 
     /// Returns the current directory path.
@@ -167,28 +565,514 @@ impl NavigationState {
         Ok(())
     }
 
+    /// Clears the current image, e.g. after the view was closed or the list became empty.
+    ///
+    /// The list itself is kept, so rescans still report new images.
+    pub fn clear_current(&mut self) {
+        self.current_file_path = None;
+        self.current_rating = None;
+    }
+
+    /// Removes a file that no longer exists and selects the image that took its place.
+    ///
+    /// The image after it is selected, or the one before it when the last step
+    /// through the list went backwards. Past the ends of the list it wraps
+    /// around when wrapping is enabled, and stops at the first / last image
+    /// otherwise. Returns the newly selected path.
+    pub fn remove_missing(&mut self, path: &Path) -> Result<PathBuf, NavigationError> {
+        let index = self
+            .image_files
+            .iter()
+            .position(|p| p == path)
+            .ok_or_else(|| NavigationError::NotInList(path.to_path_buf()))?;
+
+        self.image_files.remove(index);
+        self.all_files.retain(|p| p != path);
+        self.list_revision += 1;
+        debug!("Removed missing file: {}", path.format_for_log());
+
+        if self.image_files.is_empty() {
+            self.current_file_path = None;
+            return Err(NavigationError::NoImages);
+        }
+
+        let len = self.image_files.len();
+        let new_index = match self.last_direction {
+            Direction::Next if index < len => index,
+            Direction::Next if self.wrap => 0,
+            Direction::Next => len - 1,
+            Direction::Previous => match index.checked_sub(1) {
+                Some(previous) => previous,
+                None if self.wrap => len - 1,
+                None => 0,
+            },
+        };
+        let next = self.image_files[new_index].clone();
+        self.current_file_path = Some(next.clone());
+        self.current_rating = None;
+        Ok(next)
+    }
+
     /// Rescans the current directory.
+    ///
+    /// An explicit list is not replaced by the directory; only files that no
+    /// longer exist are dropped from it.
     pub fn rescan_directory(&mut self) -> Result<(), NavigationError> {
+        if self.explicit_list {
+            let mut files = self.take_all_files();
+            files.retain(|path| path.exists());
+            debug!("Explicit list rechecked: {} files remain", files.len());
+            let current = self.current_file_path.clone();
+            self.set_files(files, current.as_ref());
+            return Ok(());
+        }
+
         let current_dir = self.current_directory.as_ref().ok_or_else(|| {
             NavigationError::DirectoryScanFailed("No current directory to rescan".to_string())
         })?;
 
-        let new_files = file_utils::scan_directory(current_dir).map_err(|e| {
-            NavigationError::DirectoryScanFailed(format!("Failed to rescan directory: {}", e))
-        })?;
+        let mut new_files =
+            file_utils::scan_directory(current_dir, self.skip_hidden).map_err(|e| {
+                NavigationError::DirectoryScanFailed(format!("Failed to rescan directory: {}", e))
+            })?;
+        file_utils::sort_image_files(&mut new_files, self.sort_order);
 
         debug!(
             "Directory rescanned: {} -> {} files",
             self.image_files.len(),
             new_files.len()
         );
-        self.image_files = new_files;
+        let current = self.current_file_path.clone();
+        self.set_files(new_files, current.as_ref());
 
         Ok(())
     }
 
+    /// Re-sorts the image file list with the given order.
+    ///
+    /// The current file path is left untouched, so the current image stays
+    /// selected and `find_file_index` reports its new position.
+    pub fn re_sort(&mut self, order: SortOrder) {
+        let (revision, mut files) = self.begin_re_sort(order);
+        file_utils::sort_image_files(&mut files, order);
+        self.finish_re_sort(revision, files);
+    }
+
+    /// Switches to `order` and returns the list revision and the files to sort.
+    ///
+    /// Sorting by modification time reads the metadata of every file, so
+    /// callers can sort the files without holding the state and pass them
+    /// to [`NavigationState::finish_re_sort`].
+    pub fn begin_re_sort(&mut self, order: SortOrder) -> (u64, Vec<PathBuf>) {
+        self.sort_order = order;
+        self.save_directory_prefs();
+        (self.list_revision, self.all_files_snapshot())
+    }
+
+    /// Replaces the file list with `files` sorted after [`NavigationState::begin_re_sort`].
+    ///
+    /// Returns `false` and keeps the list if it changed since `revision`.
+    pub fn finish_re_sort(&mut self, revision: u64, files: Vec<PathBuf>) -> bool {
+        if revision != self.list_revision {
+            return false;
+        }
+        debug!("Re-sorted {} files by {:?}", files.len(), self.sort_order);
+
+        let current = self.current_file_path.clone();
+        self.set_files(files, current.as_ref());
+        true
+    }
+
     /// Returns the number of images in the current directory.
     pub fn image_count(&self) -> usize {
         self.image_files.len()
     }
+
+    /// Returns whether the current directory has no images.
+    pub fn is_empty(&self) -> bool {
+        self.image_files.is_empty()
+    }
+
+    /// Returns the 0-based index of the current image in the list.
+    ///
+    /// Returns `None` when no image is selected or it is not in the list.
+    pub fn current_index(&self) -> Option<usize> {
+        let current_path = self.current_file_path.as_ref()?;
+        self.image_files.iter().position(|p| p == current_path)
+    }
+
+    /// Returns a copy of the current image file list.
+    ///
+    /// The snapshot is independent of later navigation or rescans. Only the
+    /// window is included, see [`NavigationState::all_files_snapshot`].
+    pub fn image_files_snapshot(&self) -> Vec<PathBuf> {
+        self.image_files.clone()
+    }
+
+    /// Returns a copy of the full file list, including files outside the window.
+    pub fn all_files_snapshot(&self) -> Vec<PathBuf> {
+        self.full_list().to_vec()
+    }
+
+    /// Returns the file names of the image list, in list order.
+    pub fn file_names_snapshot(&self) -> Vec<String> {
+        self.image_files
+            .iter()
+            .map(|path| {
+                path.file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    /// Returns the revision of the image list, which changes whenever the list does.
+    pub fn list_revision(&self) -> u64 {
+        self.list_revision
+    }
+
+    /// Returns a snapshot of the image file list as `(index, path)` pairs.
+    ///
+    /// Intended for batch operations that walk the directory in list order.
+    /// Like [`NavigationState::image_files_snapshot`], later navigation does
+    /// not affect the returned iterator.
+    pub fn indexed_files(&self) -> impl Iterator<Item = (usize, PathBuf)> + use<> {
+        self.image_files_snapshot().into_iter().enumerate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    fn paths(names: &[&str]) -> Vec<PathBuf> {
+        names.iter().map(PathBuf::from).collect()
+    }
+
+    fn state(names: &[&str], current: &str, wrap: bool) -> NavigationState {
+        NavigationState {
+            image_files: paths(names),
+            current_file_path: Some(PathBuf::from(current)),
+            wrap,
+            window_radius: config::DIRECTORY_WINDOW_RADIUS,
+            ..NavigationState::default()
+        }
+    }
+
+    /// Creates `names` in a temp dir, each one minute older than the previous.
+    fn files_by_age(names: &[&str]) -> (tempfile::TempDir, Vec<PathBuf>) {
+        let dir = tempfile::tempdir().unwrap();
+        let newest = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let files = names
+            .iter()
+            .enumerate()
+            .map(|(age, name)| {
+                let path = dir.path().join(name);
+                let file = std::fs::File::create(&path).unwrap();
+                file.set_modified(newest - Duration::from_secs(age as u64 * 60))
+                    .unwrap();
+                path
+            })
+            .collect();
+        (dir, files)
+    }
+
+    #[test]
+    fn re_sort_keeps_current_file_selected() {
+        let (_dir, files) = files_by_age(&["a.png", "b.png", "c.png"]);
+        let mut nav = NavigationState {
+            image_files: files.clone(),
+            current_file_path: Some(files[0].clone()),
+            ..NavigationState::default()
+        };
+
+        nav.re_sort(SortOrder::ModifiedDate);
+        assert_eq!(
+            nav.image_files_snapshot(),
+            [files[2].clone(), files[1].clone(), files[0].clone()]
+        );
+        assert_eq!(nav.current_path(), Some(files[0].clone()));
+        assert_eq!(nav.current_index(), Some(2));
+
+        nav.re_sort(SortOrder::Name);
+        assert_eq!(nav.image_files_snapshot(), files);
+        assert_eq!(nav.current_index(), Some(0));
+    }
+
+    #[test]
+    fn re_sort_result_is_dropped_when_list_changed() {
+        let (_dir, files) = files_by_age(&["a.png", "b.png", "c.png"]);
+        let mut nav = NavigationState {
+            image_files: files.clone(),
+            current_file_path: Some(files[0].clone()),
+            ..NavigationState::default()
+        };
+
+        let (revision, mut sorted) = nav.begin_re_sort(SortOrder::ModifiedDate);
+        file_utils::sort_image_files(&mut sorted, SortOrder::ModifiedDate);
+        nav.remove_missing(&files[1]).unwrap();
+
+        assert!(!nav.finish_re_sort(revision, sorted));
+        assert_eq!(
+            nav.image_files_snapshot(),
+            [files[0].clone(), files[2].clone()]
+        );
+    }
+
+    #[test]
+    fn indexed_files_snapshot_is_independent_of_navigation() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["c.png", "a.png", "b.png"] {
+            std::fs::write(dir.path().join(name), b"").unwrap();
+        }
+        let mut nav = NavigationState::default();
+        nav.update_directory(dir.path().join("b.png")).unwrap();
+        let mut scanned = file_utils::scan_directory(dir.path(), false).unwrap();
+        file_utils::sort_image_files(&mut scanned, SortOrder::Name);
+
+        let indexed = nav.indexed_files();
+        nav.navigate_next().unwrap();
+        nav.remove_missing(&dir.path().join("a.png")).unwrap();
+
+        let expected: Vec<(usize, PathBuf)> = scanned.into_iter().enumerate().collect();
+        assert_eq!(expected.len(), 3);
+        assert_eq!(indexed.collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn peek_offset_with_wrap() {
+        let nav = state(&["a", "b", "c"], "b", true);
+        assert_eq!(nav.peek_offset(1), Some(PathBuf::from("c")));
+        assert_eq!(nav.peek_offset(-1), Some(PathBuf::from("a")));
+        assert_eq!(nav.peek_offset(2), Some(PathBuf::from("a")));
+        assert_eq!(nav.peek_offset(-2), Some(PathBuf::from("c")));
+        assert_eq!(nav.peek_offset(0), Some(PathBuf::from("b")));
+        // Peeking does not move the current image
+        assert_eq!(nav.current_path(), Some(PathBuf::from("b")));
+    }
+
+    #[test]
+    fn peek_offset_without_wrap() {
+        let nav = state(&["a", "b", "c"], "b", false);
+        assert_eq!(nav.peek_offset(1), Some(PathBuf::from("c")));
+        assert_eq!(nav.peek_offset(-1), Some(PathBuf::from("a")));
+        assert_eq!(nav.peek_offset(2), None);
+        assert_eq!(nav.peek_offset(-2), None);
+    }
+
+    #[test]
+    fn peek_offset_needs_current_file_in_list() {
+        assert_eq!(state(&[], "a", true).peek_offset(1), None);
+        assert_eq!(state(&["a", "b"], "x", true).peek_offset(1), None);
+    }
+
+    #[test]
+    fn scan_order_includes_files_outside_window() {
+        let mut nav = state(&["b", "c"], "b", false);
+        nav.all_files = paths(&["a", "b", "c", "d"]);
+        assert_eq!(nav.files_in_scan_order(false), paths(&["c", "d", "a"]));
+        assert_eq!(nav.files_in_scan_order(true), paths(&["a", "d", "c"]));
+    }
+
+    #[test]
+    fn selecting_file_outside_window_moves_window() {
+        let mut nav = state(&["b", "c"], "b", false);
+        nav.all_files = paths(&["a", "b", "c", "d"]);
+        nav.set_current_by_path(&PathBuf::from("d")).unwrap();
+        assert_eq!(nav.current_path(), Some(PathBuf::from("d")));
+        assert_eq!(nav.current_index(), Some(3));
+        assert!(matches!(
+            nav.set_current_by_path(&PathBuf::from("e")),
+            Err(NavigationError::NotInList(_))
+        ));
+    }
+
+    #[test]
+    fn directory_navigation_skips_directories_without_images() {
+        let root = tempfile::tempdir().unwrap();
+        for (dir, file) in [("a", Some("1.png")), ("b", None), ("c", Some("2.png"))] {
+            std::fs::create_dir(root.path().join(dir)).unwrap();
+            if let Some(file) = file {
+                std::fs::write(root.path().join(dir).join(file), b"").unwrap();
+            }
+        }
+        let mut nav = NavigationState::default();
+        nav.update_directory(root.path().join("a").join("1.png"))
+            .unwrap();
+
+        nav.navigate_next_directory().unwrap();
+        assert_eq!(
+            nav.current_path(),
+            Some(root.path().join("c").join("2.png"))
+        );
+        assert!(matches!(
+            nav.navigate_next_directory(),
+            Err(NavigationError::NoSiblingDirectory)
+        ));
+
+        nav.set_wrap(true);
+        nav.navigate_next_directory().unwrap();
+        assert_eq!(
+            nav.current_path(),
+            Some(root.path().join("a").join("1.png"))
+        );
+    }
+
+    #[test]
+    fn empty_state_has_no_current_index() {
+        let nav = NavigationState::default();
+        assert!(nav.is_empty());
+        assert_eq!(nav.current_index(), None);
+
+        let nav = state(&["a", "b"], "x", false);
+        assert!(!nav.is_empty());
+        assert_eq!(nav.current_index(), None);
+
+        let nav = state(&["a", "b"], "b", false);
+        assert_eq!(nav.current_index(), Some(1));
+    }
+
+    #[test]
+    fn remove_missing_selects_following_file() {
+        let mut nav = state(&["a", "b", "c"], "b", false);
+        assert_eq!(
+            nav.remove_missing(Path::new("b")).unwrap(),
+            PathBuf::from("c")
+        );
+        assert_eq!(nav.image_files_snapshot(), paths(&["a", "c"]));
+        assert_eq!(nav.current_index(), Some(1));
+
+        assert_eq!(
+            nav.remove_missing(Path::new("c")).unwrap(),
+            PathBuf::from("a")
+        );
+        assert!(matches!(
+            nav.remove_missing(Path::new("a")),
+            Err(NavigationError::NoImages)
+        ));
+        assert_eq!(nav.current_path(), None);
+    }
+
+    #[test]
+    fn remove_missing_follows_backward_navigation() {
+        let mut nav = state(&["a", "b", "c", "d"], "d", false);
+        nav.navigate_prev().unwrap();
+        assert_eq!(
+            nav.remove_missing(Path::new("c")).unwrap(),
+            PathBuf::from("b")
+        );
+        assert_eq!(nav.image_files_snapshot(), paths(&["a", "b", "d"]));
+
+        nav.navigate_by(-1).unwrap();
+        assert_eq!(
+            nav.remove_missing(Path::new("a")).unwrap(),
+            PathBuf::from("b")
+        );
+    }
+
+    #[test]
+    fn remove_missing_wraps_at_list_ends() {
+        let mut nav = state(&["a", "b", "c"], "b", true);
+        nav.navigate_prev().unwrap();
+        assert_eq!(
+            nav.remove_missing(Path::new("a")).unwrap(),
+            PathBuf::from("c")
+        );
+
+        nav.navigate_next().unwrap();
+        assert_eq!(nav.current_path(), Some(PathBuf::from("b")));
+        nav.navigate_next().unwrap();
+        assert_eq!(
+            nav.remove_missing(Path::new("c")).unwrap(),
+            PathBuf::from("b")
+        );
+    }
+
+    #[test]
+    fn file_list_snapshot_follows_rescan() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["b.png", "d.png"] {
+            std::fs::write(dir.path().join(name), b"").unwrap();
+        }
+        let mut nav = NavigationState::default();
+        nav.update_directory(dir.path().join("d.png")).unwrap();
+        assert_eq!(nav.file_names_snapshot(), ["b.png", "d.png"]);
+        let revision = nav.list_revision();
+
+        std::fs::write(dir.path().join("c.png"), b"").unwrap();
+        nav.rescan_directory().unwrap();
+        assert_eq!(nav.file_names_snapshot(), ["b.png", "c.png", "d.png"]);
+        assert_ne!(nav.list_revision(), revision);
+        assert_eq!(nav.current_index(), Some(2));
+
+        nav.navigate_to_index(1).unwrap();
+        assert_eq!(nav.current_path(), Some(dir.path().join("c.png")));
+        assert!(matches!(
+            nav.navigate_to_index(3),
+            Err(NavigationError::IndexOutOfRange(3))
+        ));
+    }
+
+    #[test]
+    fn open_at_picks_from_sorted_list() {
+        let files = paths(&["a", "b", "c"]);
+        let selected = Path::new("b");
+        assert_eq!(OpenAt::First.pick(&files, selected), PathBuf::from("a"));
+        assert_eq!(OpenAt::Last.pick(&files, selected), PathBuf::from("c"));
+        assert_eq!(OpenAt::Selected.pick(&files, selected), PathBuf::from("b"));
+        assert_eq!(OpenAt::Last.pick(&[], selected), PathBuf::from("b"));
+    }
+
+    #[test]
+    fn open_at_last_picks_newest_by_modified_date() {
+        let (_dir, files) = files_by_age(&["a.png", "b.png", "c.png"]);
+        let mut nav = NavigationState {
+            image_files: files.clone(),
+            current_file_path: Some(files[1].clone()),
+            ..NavigationState::default()
+        };
+        nav.re_sort(SortOrder::ModifiedDate);
+
+        let sorted = nav.image_files_snapshot();
+        assert_eq!(OpenAt::Last.pick(&sorted, &files[1]), files[0]);
+        assert_eq!(OpenAt::First.pick(&sorted, &files[1]), files[2]);
+    }
+
+    #[test]
+    fn navigate_by_within_bounds() {
+        let names = ["a", "b", "c", "d", "e"];
+        let mut nav = state(&names, "a", false);
+        nav.navigate_by(3).unwrap();
+        assert_eq!(nav.current_path(), Some(PathBuf::from("d")));
+        nav.navigate_by(-2).unwrap();
+        assert_eq!(nav.current_path(), Some(PathBuf::from("b")));
+    }
+
+    #[test]
+    fn navigate_by_wraps_around() {
+        let mut nav = state(&["a", "b", "c"], "b", true);
+        nav.navigate_by(4).unwrap();
+        assert_eq!(nav.current_path(), Some(PathBuf::from("c")));
+        nav.navigate_by(-5).unwrap();
+        assert_eq!(nav.current_path(), Some(PathBuf::from("a")));
+    }
+
+    #[test]
+    fn navigate_by_stops_at_ends_without_wrap() {
+        let mut nav = state(&["a", "b", "c"], "b", false);
+        nav.navigate_by(10).unwrap();
+        assert_eq!(nav.current_path(), Some(PathBuf::from("c")));
+        nav.navigate_by(-10).unwrap();
+        assert_eq!(nav.current_path(), Some(PathBuf::from("a")));
+    }
+
+    #[test]
+    fn clear_current_keeps_list() {
+        let mut nav = state(&["a", "b"], "b", false);
+        nav.clear_current();
+        assert_eq!(nav.current_path(), None);
+        assert_eq!(nav.current_index(), None);
+        assert_eq!(nav.image_files_snapshot(), paths(&["a", "b"]));
+    }
 }
@@ -0,0 +1,101 @@
+//! Tracking of file writes that must finish before the app exits.
+
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// Number of writes started but not yet finished.
+///
+/// A write is registered with [`PendingWrites::begin`] before it is queued on
+/// a background thread, so writes waiting for a thread are counted too.
+#[derive(Clone, Default)]
+pub struct PendingWrites {
+    inner: Arc<(Mutex<usize>, Condvar)>,
+}
+
+/// Marks a write as finished when dropped.
+pub struct PendingWrite {
+    writes: PendingWrites,
+}
+
+impl PendingWrites {
+    /// Creates a tracker without pending writes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a write, which stays pending until the returned guard is dropped.
+    pub fn begin(&self) -> PendingWrite {
+        let (count, _) = &*self.inner;
+        *count.lock().unwrap_or_else(|e| e.into_inner()) += 1;
+        PendingWrite {
+            writes: self.clone(),
+        }
+    }
+
+    /// Waits until no write is pending, for at most `timeout`.
+    ///
+    /// Returns the number of writes still pending, which is 0 unless the wait timed out.
+    pub fn wait(&self, timeout: Duration) -> usize {
+        let (count, finished) = &*self.inner;
+        let deadline = Instant::now() + timeout;
+        let mut pending = count.lock().unwrap_or_else(|e| e.into_inner());
+
+        while *pending > 0 {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            pending = finished
+                .wait_timeout(pending, remaining)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+        *pending
+    }
+}
+
+impl Drop for PendingWrite {
+    fn drop(&mut self) {
+        let (count, finished) = &*self.writes.inner;
+        let mut pending = count.lock().unwrap_or_else(|e| e.into_inner());
+        *pending = pending.saturating_sub(1);
+        finished.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wait_returns_when_pending_write_finishes() {
+        let writes = PendingWrites::new();
+        let write = writes.begin();
+
+        let writer = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            drop(write);
+        });
+
+        assert_eq!(writes.wait(Duration::from_secs(5)), 0);
+        writer.join().unwrap();
+    }
+
+    #[test]
+    fn wait_times_out_with_write_still_pending() {
+        let writes = PendingWrites::new();
+        let _write = writes.begin();
+
+        let started = Instant::now();
+        assert_eq!(writes.wait(Duration::from_millis(50)), 1);
+        assert!(started.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn wait_without_pending_writes_returns_at_once() {
+        let writes = PendingWrites::new();
+        drop(writes.begin());
+
+        assert_eq!(writes.wait(Duration::ZERO), 0);
+    }
+}
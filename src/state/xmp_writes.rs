@@ -0,0 +1,52 @@
+//! Per-file guard against overlapping XMP writes.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Files whose XMP packet is being rewritten.
+///
+/// Rating and keyword writes both read, modify and write the whole packet,
+/// so two writes to the same file at once would drop one of the changes.
+#[derive(Clone, Default)]
+pub struct XmpWriteGuard {
+    writing: Arc<Mutex<HashSet<PathBuf>>>,
+}
+
+/// Marks the XMP write of a file as finished when dropped.
+pub struct XmpWrite {
+    guard: XmpWriteGuard,
+    path: PathBuf,
+}
+
+impl XmpWriteGuard {
+    /// Creates a guard without running writes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks `path` as being written until the returned token is dropped.
+    ///
+    /// Returns `None` if a write to the same file is already running.
+    pub fn try_begin(&self, path: &Path) -> Option<XmpWrite> {
+        let mut writing = self.writing.lock().unwrap_or_else(|e| e.into_inner());
+        if !writing.insert(path.to_path_buf()) {
+            log::warn!("XMP write already in progress for: {:?}", path);
+            return None;
+        }
+        Some(XmpWrite {
+            guard: self.clone(),
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl Drop for XmpWrite {
+    fn drop(&mut self) {
+        self.guard
+            .writing
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.path);
+    }
+}
@@ -0,0 +1,103 @@
+//! Image display mode and scale computation.
+
+use serde::{Deserialize, Serialize};
+
+/// How the viewer scales images.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayMode {
+    /// Scale the image to fit inside the viewport.
+    #[default]
+    Fit,
+    /// Show the image at 100% (one image pixel per physical pixel).
+    Actual,
+}
+
+impl DisplayMode {
+    /// Returns the other display mode.
+    pub fn toggled(self) -> Self {
+        match self {
+            DisplayMode::Fit => DisplayMode::Actual,
+            DisplayMode::Actual => DisplayMode::Fit,
+        }
+    }
+}
+
+/// Computes the scale factor applied to image pixels for display.
+///
+/// `image_dims` is in image pixels and `viewport_dims` in physical pixels.
+/// Returns `1.0` for degenerate sizes so the caller never divides by zero.
+pub fn compute_display_scale(
+    mode: DisplayMode,
+    image_dims: (u32, u32),
+    viewport_dims: (f32, f32),
+) -> f32 {
+    let (image_width, image_height) = image_dims;
+    let (viewport_width, viewport_height) = viewport_dims;
+
+    match mode {
+        DisplayMode::Actual => 1.0,
+        DisplayMode::Fit => {
+            if image_width == 0
+                || image_height == 0
+                || viewport_width <= 0.0
+                || viewport_height <= 0.0
+            {
+                return 1.0;
+            }
+
+            let scale_x = viewport_width / image_width as f32;
+            let scale_y = viewport_height / image_height as f32;
+            scale_x.min(scale_y)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit_scales_to_viewport() {
+        // Larger image shrinks to the limiting side
+        assert_eq!(
+            compute_display_scale(DisplayMode::Fit, (2000, 1000), (1000.0, 800.0)),
+            0.5
+        );
+        // Smaller image grows to fill the viewport
+        assert_eq!(
+            compute_display_scale(DisplayMode::Fit, (100, 200), (800.0, 800.0)),
+            4.0
+        );
+    }
+
+    #[test]
+    fn actual_keeps_image_pixels() {
+        assert_eq!(
+            compute_display_scale(DisplayMode::Actual, (2000, 1000), (1000.0, 800.0)),
+            1.0
+        );
+        assert_eq!(
+            compute_display_scale(DisplayMode::Actual, (100, 200), (800.0, 800.0)),
+            1.0
+        );
+    }
+
+    #[test]
+    fn degenerate_sizes_use_unit_scale() {
+        assert_eq!(
+            compute_display_scale(DisplayMode::Fit, (0, 100), (800.0, 800.0)),
+            1.0
+        );
+        assert_eq!(
+            compute_display_scale(DisplayMode::Fit, (100, 100), (0.0, 800.0)),
+            1.0
+        );
+    }
+
+    #[test]
+    fn toggles_between_modes() {
+        assert_eq!(DisplayMode::Fit.toggled(), DisplayMode::Actual);
+        assert_eq!(DisplayMode::Actual.toggled(), DisplayMode::Fit);
+    }
+}
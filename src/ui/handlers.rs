@@ -3,29 +3,75 @@
 //! Sets up all Logic callbacks (select_image, next_image, prev_image, etc.)
 //! using the appropriate threading model for each operation type.
 
-use crate::services::{AutoReloadService, ClipboardService, NavigationService, RatingService};
-use crate::state::AppState;
-use crate::ui::image_display::load_and_display_image;
+use crate::file_utils::SortOrder;
+use crate::services::{
+    AutoReloadEvent, AutoReloadMode, AutoReloadService, ClipboardService, ExportService,
+    FileOpsService, KeywordService, NavigationService, RatingService, auto_reload_service,
+    lock_or_recover,
+};
+use crate::state::{AppState, AutoReloadWatcher};
+use crate::ui::display_mode::{DisplayMode, compute_display_scale};
+use crate::ui::image_display::{
+    close_image, current_loaded_image, load_and_display_image, with_current_loaded,
+};
 use rfd::AsyncFileDialog;
 use slint::ComponentHandle;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
-/// Creates a rating handler closure for the specified rating value.
+/// Creates the rating handler closure, which takes the rating to set.
+///
+/// Ratings above the maximum of the rating scale are ignored.
 fn create_rating_handler(
     ui_handle: slint::Weak<crate::AppWindow>,
+    app_state: AppState,
     rating_service: Arc<RatingService>,
-    rating: u8,
-) -> impl Fn() {
-    move || {
+) -> impl Fn(i32) {
+    move |rating| {
+        let Some(rating) = u8::try_from(rating)
+            .ok()
+            .filter(|&rating| rating <= rating_service.max_rating())
+        else {
+            log::warn!("Rating {} is out of the rating scale", rating);
+            return;
+        };
+
         if let Some(ui) = ui_handle.upgrade() {
+            let viewer_state = ui.global::<crate::ViewerState>();
+            if !viewer_state.get_rating_writable() {
+                log::warn!("Rating is not supported for the current file format");
+                crate::ui::set_error_message(&ui, "Rating is not supported for this file format");
+                return;
+            }
             crate::ui::set_rating_info(&ui, -1, true);
         }
 
         let ui_handle_clone = ui_handle.clone();
         let rating_service_clone = rating_service.clone();
+        let app_state = app_state.clone();
+        // Registered before queuing, so closing right after rating still waits for it
+        let pending_write = app_state.pending_writes.begin();
 
         rayon::spawn(move || {
-            let result = rating_service_clone.set_rating(rating);
+            let result = rating_service_clone
+                .set_rating(rating)
+                // Re-read the on-disk rating so the UI does not drift from the file
+                .map_err(|e| (e, rating_service_clone.resync_rating()));
+            drop(pending_write);
+            match &result {
+                Ok(success) => crate::ui::image_display::update_rating_summary(
+                    ui_handle_clone.clone(),
+                    app_state.navigation.clone(),
+                    app_state.rating_index.clone(),
+                    success.path.clone(),
+                    Some(success.rating),
+                ),
+                Err(_) => crate::ui::image_display::refresh_rating_summary(
+                    ui_handle_clone.clone(),
+                    app_state.navigation.clone(),
+                    app_state.rating_index.clone(),
+                ),
+            }
 
             let _ = slint::invoke_from_event_loop(move || {
                 if let Some(ui) = ui_handle_clone.upgrade() {
@@ -35,9 +81,10 @@ fn create_rating_handler(
                             ui.global::<crate::ViewerState>()
                                 .set_error_message("".into());
                         }
-                        Err(e) => {
-                            ui.global::<crate::ViewerState>()
-                                .set_error_message(e.to_string().into());
+                        Err((e, on_disk_rating)) => {
+                            let rating_i32 = on_disk_rating.map(|r| r as i32).unwrap_or(-1);
+                            crate::ui::set_rating_info(&ui, rating_i32, false);
+                            crate::ui::set_error_message(&ui, e.to_string());
                         }
                     }
                 }
@@ -52,78 +99,104 @@ fn setup_file_selection_handler(
     app_state: &AppState,
     display_tracker: &crate::ui::DisplayTracker,
 ) {
-    let navigation_service = Arc::new(NavigationService::new(app_state.navigation.clone()));
-
     ui.global::<crate::Logic>().on_select_image({
         let ui_handle = ui.as_weak();
-        let state = app_state.navigation.clone();
-        let cache = app_state.image_cache.clone();
-        let nav_service = navigation_service.clone();
+        let app_state = app_state.clone();
         let display_tracker = display_tracker.clone();
         move || {
             let ui_handle = ui_handle.clone();
-            let state = state.clone();
-            let cache = cache.clone();
-            let nav_service = nav_service.clone();
+            let app_state = app_state.clone();
             let display_tracker = display_tracker.clone();
             let _ = slint::spawn_local(async move {
                 let Some(file_handle) = AsyncFileDialog::new().pick_file().await else {
                     if let Some(ui) = ui_handle.upgrade() {
-                        ui.global::<crate::ViewerState>()
-                            .set_error_message("No file selected".into());
+                        crate::ui::set_error_message(&ui, "No file selected");
                     }
                     return;
                 };
 
-                let path = file_handle.path().to_path_buf();
-
-                // Load and display the selected image immediately
-                load_and_display_image(
-                    ui_handle.clone(),
-                    path.clone(),
-                    "Failed to load image".to_string(),
-                    state.clone(),
-                    cache.clone(),
-                    display_tracker.clone(),
+                crate::startup::open_image_path(
+                    ui_handle,
+                    file_handle.path().to_path_buf(),
+                    &app_state,
+                    display_tracker,
+                    "Failed to load image",
                 );
-
-                // Update directory in background
-                let ui_handle_clone = ui_handle.clone();
-                rayon::spawn(move || {
-                    let result = nav_service.select_image(path);
-
-                    if let Err(e) = result {
-                        let _ = slint::invoke_from_event_loop(move || {
-                            if let Some(ui) = ui_handle_clone.upgrade() {
-                                crate::ui::set_error_with_prefix(
-                                    &ui,
-                                    "Failed to update directory",
-                                    e.to_string(),
-                                );
-                            }
-                        });
-                    }
-                });
             });
         }
     });
 }
 
+/// Sets up the recently opened files menu.
+fn setup_recent_files_handler(
+    ui: &crate::AppWindow,
+    app_state: &AppState,
+    display_tracker: &crate::ui::DisplayTracker,
+) {
+    crate::ui::set_recent_files_from_disk(&ui.as_weak());
+
+    ui.global::<crate::Logic>().on_open_recent({
+        let ui_handle = ui.as_weak();
+        let app_state = app_state.clone();
+        let display_tracker = display_tracker.clone();
+        move |path| {
+            let path = std::path::PathBuf::from(path.as_str());
+            if !path.is_file() {
+                log::warn!("Recent file no longer exists: {:?}", path);
+                crate::ui::set_ui_error(&ui_handle, "File no longer exists");
+                crate::ui::set_recent_files_from_disk(&ui_handle);
+                return;
+            }
+
+            crate::startup::open_image_path(
+                ui_handle.clone(),
+                path,
+                &app_state,
+                display_tracker.clone(),
+                "Failed to load recent file",
+            );
+        }
+    });
+}
+
+/// Sets up the handler for closing the current image.
+///
+/// The image list is kept, so next and previous still open images from it.
+fn setup_close_image_handler(ui: &crate::AppWindow, app_state: &AppState) {
+    ui.global::<crate::Logic>().on_close_image({
+        let ui_handle = ui.as_weak();
+        let state = app_state.navigation.clone();
+        let load_generation = app_state.load_generation.clone();
+        move || {
+            let Some(ui) = ui_handle.upgrade() else {
+                return;
+            };
+            log::info!("Closing the current image");
+            close_image(&ui, &state, &load_generation);
+        }
+    });
+}
+
 /// Sets up the navigation handlers (next and previous image).
 fn setup_navigation_handlers(
     ui: &crate::AppWindow,
     app_state: &AppState,
     display_tracker: &crate::ui::DisplayTracker,
 ) {
-    let navigation_service = Arc::new(NavigationService::new(app_state.navigation.clone()));
+    let navigation_service = Arc::new(NavigationService::new(
+        app_state.navigation.clone(),
+        app_state.rating_index.clone(),
+    ));
 
     ui.global::<crate::Logic>().on_next_image({
         let ui_handle = ui.as_weak();
         let state = app_state.navigation.clone();
         let cache = app_state.image_cache.clone();
+        let rating_index = app_state.rating_index.clone();
         let watcher_ref = app_state.auto_reload_watcher.clone();
         let nav_service = navigation_service.clone();
         let display_tracker = display_tracker.clone();
+        let load_generation = app_state.load_generation.clone();
         move || {
             // Stop auto-reload on manual navigation
             stop_auto_reload_internal(&ui_handle, &watcher_ref);
@@ -138,7 +211,9 @@ fn setup_navigation_handlers(
                         "Failed to load next image".to_string(),
                         state.clone(),
                         cache.clone(),
+                        rating_index.clone(),
                         display_tracker.clone(),
+                        load_generation.clone(),
                     );
                 }
                 Err(e) => {
@@ -154,9 +229,11 @@ fn setup_navigation_handlers(
         let ui_handle = ui.as_weak();
         let state = app_state.navigation.clone();
         let cache = app_state.image_cache.clone();
+        let rating_index = app_state.rating_index.clone();
         let watcher_ref = app_state.auto_reload_watcher.clone();
         let nav_service = navigation_service.clone();
         let display_tracker = display_tracker.clone();
+        let load_generation = app_state.load_generation.clone();
         move || {
             // Stop auto-reload on manual navigation
             stop_auto_reload_internal(&ui_handle, &watcher_ref);
@@ -171,7 +248,79 @@ fn setup_navigation_handlers(
                         "Failed to load previous image".to_string(),
                         state.clone(),
                         cache.clone(),
+                        rating_index.clone(),
+                        display_tracker.clone(),
+                        load_generation.clone(),
+                    );
+                }
+                Err(e) => {
+                    if let Some(ui) = ui_handle.upgrade() {
+                        crate::ui::set_error_with_prefix(&ui, "Navigation failed", e.to_string());
+                    }
+                }
+            }
+        }
+    });
+
+    ui.global::<crate::Logic>().on_navigate_to_index({
+        let ui_handle = ui.as_weak();
+        let state = app_state.navigation.clone();
+        let cache = app_state.image_cache.clone();
+        let rating_index = app_state.rating_index.clone();
+        let watcher_ref = app_state.auto_reload_watcher.clone();
+        let nav_service = navigation_service.clone();
+        let display_tracker = display_tracker.clone();
+        let load_generation = app_state.load_generation.clone();
+        move |index| {
+            // Stop auto-reload on manual navigation
+            stop_auto_reload_internal(&ui_handle, &watcher_ref);
+
+            match nav_service.navigate_to_index(index.max(0) as usize) {
+                Ok(path) => {
+                    load_and_display_image(
+                        ui_handle.clone(),
+                        path,
+                        "Failed to load selected image".to_string(),
+                        state.clone(),
+                        cache.clone(),
+                        rating_index.clone(),
+                        display_tracker.clone(),
+                        load_generation.clone(),
+                    );
+                }
+                Err(e) => {
+                    if let Some(ui) = ui_handle.upgrade() {
+                        crate::ui::set_error_with_prefix(&ui, "Navigation failed", e.to_string());
+                    }
+                }
+            }
+        }
+    });
+
+    ui.global::<crate::Logic>().on_select_from_list({
+        let ui_handle = ui.as_weak();
+        let state = app_state.navigation.clone();
+        let cache = app_state.image_cache.clone();
+        let rating_index = app_state.rating_index.clone();
+        let watcher_ref = app_state.auto_reload_watcher.clone();
+        let nav_service = navigation_service.clone();
+        let display_tracker = display_tracker.clone();
+        let load_generation = app_state.load_generation.clone();
+        move |path| {
+            // Stop auto-reload on manual navigation
+            stop_auto_reload_internal(&ui_handle, &watcher_ref);
+
+            match nav_service.select_by_path(PathBuf::from(path.as_str())) {
+                Ok(path) => {
+                    load_and_display_image(
+                        ui_handle.clone(),
+                        path,
+                        "Failed to load selected image".to_string(),
+                        state.clone(),
+                        cache.clone(),
+                        rating_index.clone(),
                         display_tracker.clone(),
+                        load_generation.clone(),
                     );
                 }
                 Err(e) => {
@@ -182,12 +331,278 @@ fn setup_navigation_handlers(
             }
         }
     });
+
+    ui.global::<crate::Logic>()
+        .on_next_directory(create_directory_handler(
+            ui.as_weak(),
+            app_state.clone(),
+            navigation_service.clone(),
+            display_tracker.clone(),
+            NavigationService::next_directory,
+        ));
+
+    ui.global::<crate::Logic>()
+        .on_prev_directory(create_directory_handler(
+            ui.as_weak(),
+            app_state.clone(),
+            navigation_service.clone(),
+            display_tracker.clone(),
+            NavigationService::previous_directory,
+        ));
+
+    ui.global::<crate::Logic>()
+        .on_page_next(create_page_handler(
+            ui.as_weak(),
+            app_state.clone(),
+            navigation_service.clone(),
+            display_tracker.clone(),
+            1,
+        ));
+
+    ui.global::<crate::Logic>()
+        .on_page_prev(create_page_handler(
+            ui.as_weak(),
+            app_state.clone(),
+            navigation_service.clone(),
+            display_tracker.clone(),
+            -1,
+        ));
+
+    ui.global::<crate::Logic>()
+        .on_next_with_rating(create_rating_jump_handler(
+            ui.as_weak(),
+            app_state.clone(),
+            navigation_service.clone(),
+            display_tracker.clone(),
+            NavigationService::next_with_rating,
+        ));
+
+    ui.global::<crate::Logic>()
+        .on_prev_with_rating(create_rating_jump_handler(
+            ui.as_weak(),
+            app_state.clone(),
+            navigation_service,
+            display_tracker.clone(),
+            NavigationService::prev_with_rating,
+        ));
+}
+
+/// Creates a handler closure that jumps to an image with a given rating using `jump`.
+///
+/// Ratings may need to be read from disk, so the scan runs in the background.
+fn create_rating_jump_handler(
+    ui_handle: slint::Weak<crate::AppWindow>,
+    app_state: AppState,
+    navigation_service: Arc<NavigationService>,
+    display_tracker: crate::ui::DisplayTracker,
+    jump: fn(&NavigationService, u8) -> crate::services::navigation_service::NavigationResult,
+) -> impl Fn(i32) {
+    move |rating| {
+        let Ok(rating) = u8::try_from(rating) else {
+            log::warn!("Invalid rating for jump: {}", rating);
+            return;
+        };
+
+        // Stop auto-reload on manual navigation
+        stop_auto_reload_internal(&ui_handle, &app_state.auto_reload_watcher);
+
+        let ui_handle = ui_handle.clone();
+        let app_state = app_state.clone();
+        let navigation_service = navigation_service.clone();
+        let display_tracker = display_tracker.clone();
+
+        rayon::spawn(move || {
+            let result = jump(&navigation_service, rating);
+
+            let _ = slint::invoke_from_event_loop(move || match result {
+                Ok(path) => {
+                    load_and_display_image(
+                        ui_handle,
+                        path,
+                        "Failed to load image".to_string(),
+                        app_state.navigation.clone(),
+                        app_state.image_cache.clone(),
+                        app_state.rating_index.clone(),
+                        display_tracker,
+                        app_state.load_generation.clone(),
+                    );
+                }
+                Err(e) => {
+                    if let Some(ui) = ui_handle.upgrade() {
+                        crate::ui::set_error_with_prefix(&ui, "Rating jump failed", e.to_string());
+                    }
+                }
+            });
+        });
+    }
+}
+
+/// Sets up the handler for jumping to the PNG image generated with a given seed.
+///
+/// Every PNG of the list may need to be read, so the search runs in the background
+/// and reports its progress to the status message.
+fn setup_seed_search_handler(
+    ui: &crate::AppWindow,
+    app_state: &AppState,
+    display_tracker: &crate::ui::DisplayTracker,
+) {
+    ui.global::<crate::Logic>().on_find_seed({
+        let ui_handle = ui.as_weak();
+        let app_state = app_state.clone();
+        let display_tracker = display_tracker.clone();
+
+        move |seed| {
+            let seed = seed.trim().to_string();
+            if seed.is_empty() {
+                return;
+            }
+
+            // Stop auto-reload on manual navigation
+            stop_auto_reload_internal(&ui_handle, &app_state.auto_reload_watcher);
+
+            let ui_handle = ui_handle.clone();
+            let app_state = app_state.clone();
+            let display_tracker = display_tracker.clone();
+
+            rayon::spawn(move || {
+                let navigation_service = NavigationService::new(
+                    app_state.navigation.clone(),
+                    app_state.rating_index.clone(),
+                );
+                let report = |done: usize, total: usize| {
+                    if done.is_multiple_of(crate::config::SEED_SEARCH_PROGRESS_STEP) {
+                        crate::ui::set_ui_status(
+                            &ui_handle,
+                            format!("Searching for seed {}: {}/{}", seed, done, total),
+                        );
+                    }
+                };
+                let result = navigation_service.find_by_seed(&seed, report);
+
+                let _ = slint::invoke_from_event_loop(move || match result {
+                    Ok(path) => {
+                        if let Some(ui) = ui_handle.upgrade() {
+                            ui.global::<crate::ViewerState>()
+                                .set_status_message(format!("Found seed {}", seed).into());
+                        }
+                        load_and_display_image(
+                            ui_handle,
+                            path,
+                            "Failed to load image".to_string(),
+                            app_state.navigation.clone(),
+                            app_state.image_cache.clone(),
+                            app_state.rating_index.clone(),
+                            display_tracker,
+                            app_state.load_generation.clone(),
+                        );
+                    }
+                    Err(e) => {
+                        if let Some(ui) = ui_handle.upgrade() {
+                            ui.global::<crate::ViewerState>()
+                                .set_status_message("".into());
+                            crate::ui::set_error_with_prefix(
+                                &ui,
+                                "Seed search failed",
+                                e.to_string(),
+                            );
+                        }
+                    }
+                });
+            });
+        }
+    });
+}
+
+/// Creates a handler closure that moves to a sibling directory with `navigate`.
+fn create_directory_handler(
+    ui_handle: slint::Weak<crate::AppWindow>,
+    app_state: AppState,
+    navigation_service: Arc<NavigationService>,
+    display_tracker: crate::ui::DisplayTracker,
+    navigate: fn(&NavigationService) -> crate::services::navigation_service::NavigationResult,
+) -> impl Fn() {
+    move || {
+        // Stop auto-reload on manual navigation
+        stop_auto_reload_internal(&ui_handle, &app_state.auto_reload_watcher);
+
+        match navigate(&navigation_service) {
+            Ok(path) => {
+                load_and_display_image(
+                    ui_handle.clone(),
+                    path,
+                    "Failed to load image in directory".to_string(),
+                    app_state.navigation.clone(),
+                    app_state.image_cache.clone(),
+                    app_state.rating_index.clone(),
+                    display_tracker.clone(),
+                    app_state.load_generation.clone(),
+                );
+                crate::ui::image_display::start_rating_prescan(
+                    ui_handle.clone(),
+                    app_state.navigation.clone(),
+                    app_state.rating_index.clone(),
+                    app_state.rating_prescan.clone(),
+                );
+                crate::ui::image_display::refresh_rating_summary(
+                    ui_handle.clone(),
+                    app_state.navigation.clone(),
+                    app_state.rating_index.clone(),
+                );
+            }
+            Err(e) => {
+                if let Some(ui) = ui_handle.upgrade() {
+                    crate::ui::set_error_with_prefix(
+                        &ui,
+                        "Directory navigation failed",
+                        e.to_string(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Creates a handler closure that jumps `page_step` images in `direction` (1 or -1).
+///
+/// The step is read from the settings on each jump.
+fn create_page_handler(
+    ui_handle: slint::Weak<crate::AppWindow>,
+    app_state: AppState,
+    navigation_service: Arc<NavigationService>,
+    display_tracker: crate::ui::DisplayTracker,
+    direction: isize,
+) -> impl Fn() {
+    move || {
+        // Stop auto-reload on manual navigation
+        stop_auto_reload_internal(&ui_handle, &app_state.auto_reload_watcher);
+
+        let step = crate::settings::current().page_step.max(1) as isize;
+        match navigation_service.jump(direction * step) {
+            Ok(path) => {
+                load_and_display_image(
+                    ui_handle.clone(),
+                    path,
+                    "Failed to load image".to_string(),
+                    app_state.navigation.clone(),
+                    app_state.image_cache.clone(),
+                    app_state.rating_index.clone(),
+                    display_tracker.clone(),
+                    app_state.load_generation.clone(),
+                );
+            }
+            Err(e) => {
+                if let Some(ui) = ui_handle.upgrade() {
+                    crate::ui::set_error_with_prefix(&ui, "Navigation failed", e.to_string());
+                }
+            }
+        }
+    }
 }
 
 /// Internal helper to stop the auto-reload watcher.
 fn stop_auto_reload_internal(
     ui_handle: &slint::Weak<crate::AppWindow>,
-    watcher_ref: &Arc<Mutex<Option<crate::state::AutoReloadDebouncer>>>,
+    watcher_ref: &Arc<Mutex<Option<AutoReloadWatcher>>>,
 ) {
     if let Ok(mut watcher_lock) = watcher_ref.lock() {
         if watcher_lock.take().is_some() {
@@ -203,13 +618,14 @@ fn stop_auto_reload_internal(
 /// Internal helper to start the auto-reload watcher.
 fn start_auto_reload_internal(
     ui_handle: &slint::Weak<crate::AppWindow>,
-    state: &Arc<Mutex<crate::state::NavigationState>>,
-    cache: &Arc<Mutex<crate::image_cache::ImageCache>>,
-    watcher_ref: &Arc<Mutex<Option<crate::state::AutoReloadDebouncer>>>,
+    app_state: &AppState,
     navigation_service: &Arc<NavigationService>,
     reload_service: &Arc<AutoReloadService>,
     display_tracker: &crate::ui::DisplayTracker,
 ) {
+    let state = &app_state.navigation;
+    let cache = &app_state.image_cache;
+
     // First, rescan directory to get the latest file list
     if let Err(e) = navigation_service.rescan_directory() {
         if let Some(ui) = ui_handle.upgrade() {
@@ -218,18 +634,25 @@ fn start_auto_reload_internal(
         return;
     }
 
-    // Then navigate to the last image with the updated list
-    let result = reload_service.navigate_to_last();
+    // Then navigate to the last image with the updated list, unless staying on the current one
+    let mode = crate::settings::current().auto_reload_mode;
+    let result = match mode {
+        AutoReloadMode::FollowNewest => reload_service.navigate_to_last().map(Some),
+        AutoReloadMode::StayOnCurrent => Ok(None),
+    };
 
     match result {
-        Ok(path) => {
+        Ok(None) => {}
+        Ok(Some(path)) => {
             load_and_display_image(
                 ui_handle.clone(),
                 path,
                 "Failed to load last image".to_string(),
                 state.clone(),
                 cache.clone(),
+                app_state.rating_index.clone(),
                 display_tracker.clone(),
+                app_state.load_generation.clone(),
             );
         }
         Err(e) => {
@@ -244,22 +667,61 @@ fn start_auto_reload_internal(
         }
     }
 
+    watch_current_directory(ui_handle, app_state, reload_service, display_tracker);
+}
+
+/// Starts the auto-reload watcher on the current directory and marks auto-reload active.
+fn watch_current_directory(
+    ui_handle: &slint::Weak<crate::AppWindow>,
+    app_state: &AppState,
+    reload_service: &AutoReloadService,
+    display_tracker: &crate::ui::DisplayTracker,
+) {
+    let state = &app_state.navigation;
+    let cache = &app_state.image_cache;
+    let watcher_ref = &app_state.auto_reload_watcher;
+
     // Start watching for changes
     let ui_weak = ui_handle.clone();
     let state_clone = state.clone();
     let cache_clone = cache.clone();
+    let rating_index = app_state.rating_index.clone();
     let display_tracker_clone = display_tracker.clone();
+    let load_generation = app_state.load_generation.clone();
 
-    let watcher_result = reload_service.start_watching(state_clone.clone(), move |path| {
-        load_and_display_image(
-            ui_weak.clone(),
-            path,
-            "Auto-reload failed".to_string(),
-            state_clone.clone(),
-            cache_clone.clone(),
-            display_tracker_clone.clone(),
-        );
-    });
+    let watcher_result =
+        reload_service.start_watching(state_clone.clone(), move |event| match event {
+            AutoReloadEvent::Newest(path) => {
+                load_and_display_image(
+                    ui_weak.clone(),
+                    path,
+                    "Auto-reload failed".to_string(),
+                    state_clone.clone(),
+                    cache_clone.clone(),
+                    rating_index.clone(),
+                    display_tracker_clone.clone(),
+                    load_generation.clone(),
+                );
+            }
+            AutoReloadEvent::Rescanned { current, total } => {
+                if let Some(ui) = ui_weak.upgrade() {
+                    crate::ui::set_navigation_info(&ui, current, total, true);
+                    crate::ui::sync_file_list(&ui, &lock_or_recover(&state_clone));
+                }
+                crate::ui::image_display::refresh_rating_summary(
+                    ui_weak.clone(),
+                    state_clone.clone(),
+                    rating_index.clone(),
+                );
+            }
+            AutoReloadEvent::Emptied => {
+                if let Some(ui) = ui_weak.upgrade() {
+                    close_image(&ui, &state_clone, &load_generation);
+                    ui.global::<crate::ViewerState>()
+                        .set_status_message("No images left".into());
+                }
+            }
+        });
 
     match watcher_result {
         Ok(watcher) => {
@@ -281,20 +743,57 @@ fn start_auto_reload_internal(
     }
 }
 
+/// Moves a running auto-reload watcher to the current directory after
+/// another directory was opened.
+///
+/// Does nothing when auto-reload is off or already watches that directory.
+pub(crate) fn retarget_auto_reload(
+    ui_handle: &slint::Weak<crate::AppWindow>,
+    app_state: &AppState,
+    display_tracker: &crate::ui::DisplayTracker,
+) {
+    let current = lock_or_recover(&app_state.navigation).get_current_directory();
+    {
+        let mut watcher = lock_or_recover(&app_state.auto_reload_watcher);
+        let watched = watcher.as_ref().map(AutoReloadWatcher::directory);
+        if !auto_reload_service::needs_retarget(watched, current.as_deref()) {
+            return;
+        }
+        // Dropping the debouncer stops the old watcher
+        *watcher = None;
+    }
+    log::info!("Moving auto-reload to the opened directory: {:?}", current);
+
+    let reload_service = AutoReloadService::new(NavigationService::new(
+        app_state.navigation.clone(),
+        app_state.rating_index.clone(),
+    ));
+    watch_current_directory(ui_handle, app_state, &reload_service, display_tracker);
+
+    // The old watcher is gone, so a failed start leaves auto-reload off
+    let stopped = lock_or_recover(&app_state.auto_reload_watcher).is_none();
+    if let Some(ui) = ui_handle.upgrade().filter(|_| stopped) {
+        let current = ui.global::<crate::ViewerState>().get_current_index();
+        let total = ui.global::<crate::ViewerState>().get_total_index();
+        crate::ui::set_navigation_info(&ui, current, total, false);
+    }
+}
+
 /// Sets up the auto-reload handlers.
 fn setup_auto_reload_handlers(
     ui: &crate::AppWindow,
     app_state: &AppState,
     display_tracker: &crate::ui::DisplayTracker,
 ) {
-    let navigation_service = Arc::new(NavigationService::new(app_state.navigation.clone()));
+    let navigation_service = Arc::new(NavigationService::new(
+        app_state.navigation.clone(),
+        app_state.rating_index.clone(),
+    ));
     let reload_service = Arc::new(AutoReloadService::new((*navigation_service).clone()));
 
     ui.global::<crate::Logic>().on_start_auto_reload({
         let ui_handle = ui.as_weak();
-        let state = app_state.navigation.clone();
-        let cache = app_state.image_cache.clone();
-        let watcher_ref = app_state.auto_reload_watcher.clone();
+        let app_state = app_state.clone();
         let navigation_service = navigation_service.clone();
         let reload_service = reload_service.clone();
         let display_tracker = display_tracker.clone();
@@ -302,9 +801,7 @@ fn setup_auto_reload_handlers(
         move || {
             start_auto_reload_internal(
                 &ui_handle,
-                &state,
-                &cache,
-                &watcher_ref,
+                &app_state,
                 &navigation_service,
                 &reload_service,
                 &display_tracker,
@@ -320,27 +817,124 @@ fn setup_auto_reload_handlers(
             stop_auto_reload_internal(&ui_handle, &watcher_ref);
         }
     });
-}
 
-/// Sets up the rating handlers (rate-0 through rate-5).
-fn setup_rating_handlers(ui: &crate::AppWindow, app_state: &AppState) {
-    let rating_service = Arc::new(RatingService::new(
-        app_state.navigation.clone(),
+    ui.global::<crate::ViewerState>().set_auto_reload_follow(
+        crate::settings::current().auto_reload_mode == AutoReloadMode::FollowNewest,
+    );
+
+    ui.global::<crate::Logic>().on_toggle_auto_reload_follow({
+        let ui_handle = ui.as_weak();
+        move || {
+            let Some(ui) = ui_handle.upgrade() else {
+                return;
+            };
+
+            let current = crate::settings::current().auto_reload_mode;
+            let next = current.toggled();
+            log::info!("Auto-reload mode: {:?} -> {:?}", current, next);
+
+            crate::settings::update(|settings| settings.auto_reload_mode = next);
+            ui.global::<crate::ViewerState>()
+                .set_auto_reload_follow(next == AutoReloadMode::FollowNewest);
+        }
+    });
+}
+
+/// Sets up the rating handler and the maximum rating shown in the UI.
+fn setup_rating_handlers(ui: &crate::AppWindow, app_state: &AppState) {
+    let rating_service = Arc::new(RatingService::new(
+        app_state.navigation.clone(),
         app_state.image_cache.clone(),
+        app_state.xmp_writes.clone(),
     ));
 
-    for rating in 0..=5 {
-        let handler = create_rating_handler(ui.as_weak(), rating_service.clone(), rating);
+    ui.global::<crate::ViewerState>()
+        .set_max_rating(rating_service.max_rating() as i32);
+    ui.global::<crate::Logic>().on_rate(create_rating_handler(
+        ui.as_weak(),
+        app_state.clone(),
+        rating_service,
+    ));
+}
 
-        match rating {
-            0 => ui.global::<crate::Logic>().on_rate_0(handler),
-            1 => ui.global::<crate::Logic>().on_rate_1(handler),
-            2 => ui.global::<crate::Logic>().on_rate_2(handler),
-            3 => ui.global::<crate::Logic>().on_rate_3(handler),
-            4 => ui.global::<crate::Logic>().on_rate_4(handler),
-            5 => ui.global::<crate::Logic>().on_rate_5(handler),
-            _ => unreachable!(),
-        }
+/// Creates a keyword edit handler that runs the given write in the background.
+fn create_keyword_handler(
+    ui_handle: slint::Weak<crate::AppWindow>,
+    app_state: AppState,
+    keyword_service: Arc<KeywordService>,
+    edit: fn(&KeywordService, &str) -> crate::error::Result<Vec<String>>,
+) -> impl Fn(slint::SharedString) {
+    move |keyword| {
+        let ui_handle = ui_handle.clone();
+        let keyword_service = keyword_service.clone();
+        // Registered before queuing, so closing right after an edit still waits for it
+        let pending_write = app_state.pending_writes.begin();
+
+        rayon::spawn(move || {
+            let result = edit(&keyword_service, keyword.as_str());
+            drop(pending_write);
+
+            let _ = slint::invoke_from_event_loop(move || {
+                if let Some(ui) = ui_handle.upgrade() {
+                    match result {
+                        Ok(keywords) => {
+                            crate::ui::set_keywords(&ui, &keywords);
+                            ui.global::<crate::ViewerState>()
+                                .set_error_message("".into());
+                        }
+                        Err(e) => {
+                            log::error!("Failed to update keywords: {}", e);
+                            crate::ui::set_error_message(&ui, e.to_string());
+                        }
+                    }
+                }
+            });
+        });
+    }
+}
+
+/// Sets up the keyword handlers (add-keyword, remove-keyword).
+fn setup_keyword_handlers(ui: &crate::AppWindow, app_state: &AppState) {
+    let keyword_service = Arc::new(KeywordService::new(
+        app_state.navigation.clone(),
+        app_state.image_cache.clone(),
+        app_state.xmp_writes.clone(),
+    ));
+
+    ui.global::<crate::Logic>()
+        .on_add_keyword(create_keyword_handler(
+            ui.as_weak(),
+            app_state.clone(),
+            keyword_service.clone(),
+            KeywordService::add_keyword,
+        ));
+    ui.global::<crate::Logic>()
+        .on_remove_keyword(create_keyword_handler(
+            ui.as_weak(),
+            app_state.clone(),
+            keyword_service,
+            KeywordService::remove_keyword,
+        ));
+}
+
+/// How a drag from the image area hands the current file to other apps.
+#[derive(Debug, PartialEq, Eq)]
+enum DragOut {
+    /// The drag does nothing.
+    Ignore,
+    /// The file is copied to the clipboard, to be pasted into the target app.
+    Clipboard(PathBuf),
+}
+
+/// Picks how a drag-out of `current` is carried out.
+///
+/// Slint cannot start a native OS drag session on any platform (macOS, Windows
+/// or Linux), so the clipboard is the only way. It replaces the clipboard
+/// contents, so it is used only when the `drag_out_to_clipboard` setting is on.
+fn drag_out_method(enabled: bool, current: Option<PathBuf>) -> DragOut {
+    match current {
+        Some(path) if enabled => DragOut::Clipboard(path),
+        _ => DragOut::Ignore,
     }
 }
 
@@ -361,7 +955,7 @@ fn setup_clipboard_handler(ui: &crate::AppWindow, app_state: &AppState) {
             rayon::spawn(move || {
                 // Get current file path
                 let current_path = {
-                    let nav = navigation.lock().unwrap();
+                    let nav = lock_or_recover(&navigation);
                     nav.current_path()
                 };
 
@@ -370,6 +964,7 @@ fn setup_clipboard_handler(ui: &crate::AppWindow, app_state: &AppState) {
                     match clipboard_service.copy_files(paths) {
                         Ok(_) => {
                             log::info!("File copied to clipboard successfully");
+                            crate::ui::set_ui_status(&ui_handle, "Copied to clipboard");
                         }
                         Err(e) => {
                             log::error!("Failed to copy file to clipboard: {}", e);
@@ -378,12 +973,947 @@ fn setup_clipboard_handler(ui: &crate::AppWindow, app_state: &AppState) {
                     }
                 } else {
                     log::warn!("No file to copy");
+                    crate::ui::set_ui_status(&ui_handle, "No image to copy");
+                }
+            });
+        }
+    });
+
+    let drag_out_enabled = crate::settings::current().drag_out_to_clipboard;
+    ui.global::<crate::ViewerState>()
+        .set_drag_out_enabled(drag_out_enabled);
+    ui.global::<crate::Logic>().on_drag_out_current_file({
+        let ui_handle = ui.as_weak();
+        let clipboard_service = clipboard_service.clone();
+        let navigation = app_state.navigation.clone();
+
+        move || {
+            let current = lock_or_recover(&navigation).current_path();
+            let path = match drag_out_method(drag_out_enabled, current) {
+                DragOut::Clipboard(path) => path,
+                DragOut::Ignore => return,
+            };
+
+            let clipboard_service = clipboard_service.clone();
+            let ui_handle = ui_handle.clone();
+
+            rayon::spawn(move || match clipboard_service.copy_files(vec![path]) {
+                Ok(_) => {
+                    log::info!("Drag-out fell back to clipboard copy");
+                    crate::ui::set_ui_status(
+                        &ui_handle,
+                        "Copied to clipboard for pasting into the target app",
+                    );
+                }
+                Err(e) => {
+                    log::error!("Failed to copy file for drag-out: {}", e);
+                    crate::ui::set_ui_error(&ui_handle, format!("Failed to copy: {}", e));
+                }
+            });
+        }
+    });
+
+    ui.global::<crate::Logic>().on_copy_all_metadata({
+        let ui_handle = ui.as_weak();
+        let clipboard_service = clipboard_service.clone();
+        let navigation = app_state.navigation.clone();
+        let cache = app_state.image_cache.clone();
+
+        move || {
+            let Some(text) = with_current_loaded(
+                &navigation,
+                &cache,
+                crate::ui::image_display::format_metadata_text,
+            ) else {
+                log::warn!("No image metadata to copy");
+                return;
+            };
+
+            let clipboard_service = clipboard_service.clone();
+            let ui_handle = ui_handle.clone();
+
+            rayon::spawn(move || match clipboard_service.copy_text(&text) {
+                Ok(_) => {
+                    log::info!("Metadata copied to clipboard successfully");
+                    crate::ui::set_ui_status(&ui_handle, "Metadata copied to clipboard");
+                }
+                Err(e) => {
+                    log::error!("Failed to copy metadata to clipboard: {}", e);
+                    crate::ui::set_ui_error(&ui_handle, format!("Failed to copy: {}", e));
+                }
+            });
+        }
+    });
+
+    ui.global::<crate::Logic>().on_copy_markdown({
+        let ui_handle = ui.as_weak();
+        let clipboard_service = clipboard_service.clone();
+        let navigation = app_state.navigation.clone();
+        let cache = app_state.image_cache.clone();
+
+        move || {
+            let base = crate::settings::current().markdown_base_dir;
+            let path = lock_or_recover(&navigation).current_path();
+            let text = path.and_then(|path| {
+                with_current_loaded(&navigation, &cache, |loaded| {
+                    crate::ui::image_display::format_markdown(loaded, &path, base.as_deref())
+                })
+            });
+            let Some(text) = text else {
+                log::warn!("No image to copy as Markdown");
+                return;
+            };
+
+            let clipboard_service = clipboard_service.clone();
+            let ui_handle = ui_handle.clone();
+
+            rayon::spawn(move || match clipboard_service.copy_text(&text) {
+                Ok(_) => {
+                    log::info!("Markdown copied to clipboard successfully");
+                    crate::ui::set_ui_status(&ui_handle, "Markdown copied to clipboard");
+                }
+                Err(e) => {
+                    log::error!("Failed to copy Markdown to clipboard: {}", e);
+                    crate::ui::set_ui_error(&ui_handle, format!("Failed to copy: {}", e));
+                }
+            });
+        }
+    });
+
+    ui.global::<crate::Logic>().on_copy_dimensions({
+        let ui_handle = ui.as_weak();
+        let clipboard_service = clipboard_service.clone();
+        let navigation = app_state.navigation.clone();
+        let cache = app_state.image_cache.clone();
+
+        move || {
+            let Some(text) = with_current_loaded(
+                &navigation,
+                &cache,
+                crate::ui::image_display::format_dimensions,
+            ) else {
+                log::warn!("No image dimensions to copy");
+                return;
+            };
+
+            let clipboard_service = clipboard_service.clone();
+            let ui_handle = ui_handle.clone();
+
+            rayon::spawn(move || match clipboard_service.copy_text(&text) {
+                Ok(_) => {
+                    log::info!("Dimensions copied to clipboard successfully");
+                    crate::ui::set_ui_status(&ui_handle, "Dimensions copied to clipboard");
+                }
+                Err(e) => {
+                    log::error!("Failed to copy dimensions to clipboard: {}", e);
+                    crate::ui::set_ui_error(&ui_handle, format!("Failed to copy: {}", e));
+                }
+            });
+        }
+    });
+
+    // Apps that only accept files get the displayed pixels as a staged PNG file.
+    ui.global::<crate::Logic>().on_copy_image_as_png_file({
+        let ui_handle = ui.as_weak();
+        let clipboard_service = clipboard_service.clone();
+        let navigation = app_state.navigation.clone();
+        let cache = app_state.image_cache.clone();
+
+        move || {
+            let Some(loaded) = current_loaded_image(&navigation, &cache) else {
+                log::warn!("No image to copy as PNG file");
+                return;
+            };
+
+            let clipboard_service = clipboard_service.clone();
+            let ui_handle = ui_handle.clone();
+
+            rayon::spawn(move || {
+                let staged = FileOpsService::new().stage_temp_copy(
+                    &loaded.data,
+                    loaded.width,
+                    loaded.height,
+                    image::ImageFormat::Png,
+                );
+                let result = match staged {
+                    Ok(path) => clipboard_service
+                        .copy_files(vec![path])
+                        .map_err(|e| e.to_string()),
+                    Err(e) => Err(format!("Failed to stage PNG file: {}", e)),
+                };
+
+                match result {
+                    Ok(()) => {
+                        log::info!("Staged PNG file copied to clipboard successfully");
+                        crate::ui::set_ui_status(&ui_handle, "Image copied as PNG file");
+                    }
+                    Err(e) => {
+                        log::error!("Failed to copy image as PNG file: {}", e);
+                        crate::ui::set_ui_error(&ui_handle, format!("Failed to copy: {}", e));
+                    }
+                }
+            });
+        }
+    });
+
+    // Original file bytes keep embedded metadata that a decoded bitmap would lose.
+    ui.global::<crate::Logic>().on_copy_image_encoded({
+        let ui_handle = ui.as_weak();
+        let clipboard_service = clipboard_service.clone();
+        let navigation = app_state.navigation.clone();
+        let cache = app_state.image_cache.clone();
+
+        move || {
+            let Some(path) = lock_or_recover(&navigation).current_path() else {
+                log::warn!("No image to copy");
+                return;
+            };
+            let retained =
+                with_current_loaded(&navigation, &cache, |l| l.encoded.clone()).flatten();
+
+            let clipboard_service = clipboard_service.clone();
+            let ui_handle = ui_handle.clone();
+
+            rayon::spawn(move || {
+                let encoded = match retained {
+                    Some(encoded) => Ok(encoded),
+                    None => crate::image_loader::EncodedImage::read(&path),
+                };
+                let result = encoded.map_err(|e| e.to_string()).and_then(|encoded| {
+                    clipboard_service
+                        .copy_encoded(&encoded.bytes, encoded.mime)
+                        .map(|_| encoded.mime)
+                        .map_err(|e| e.to_string())
+                });
+
+                match result {
+                    Ok(mime) => {
+                        log::info!("Encoded image copied to clipboard successfully");
+                        crate::ui::set_ui_status(&ui_handle, format!("Copied {} data", mime));
+                    }
+                    Err(e) => {
+                        log::error!("Failed to copy encoded image: {}", e);
+                        crate::ui::set_ui_error(&ui_handle, format!("Failed to copy: {}", e));
+                    }
+                }
+            });
+        }
+    });
+
+    ui.global::<crate::Logic>().on_copy_seed({
+        let ui_handle = ui.as_weak();
+        let clipboard_service = clipboard_service.clone();
+        let navigation = app_state.navigation.clone();
+        let cache = app_state.image_cache.clone();
+
+        move || {
+            let seed =
+                with_current_loaded(&navigation, &cache, crate::ui::image_display::seed_text)
+                    .flatten();
+
+            let Some(seed) = seed else {
+                log::warn!("No seed to copy");
+                crate::ui::set_ui_status(&ui_handle, "No seed in this image");
+                return;
+            };
+
+            let clipboard_service = clipboard_service.clone();
+            let ui_handle = ui_handle.clone();
+
+            rayon::spawn(move || match clipboard_service.copy_text(&seed) {
+                Ok(_) => {
+                    log::info!("Seed copied to clipboard successfully");
+                    crate::ui::set_ui_status(
+                        &ui_handle,
+                        format!("Seed {} copied to clipboard", seed),
+                    );
+                }
+                Err(e) => {
+                    log::error!("Failed to copy seed to clipboard: {}", e);
+                    crate::ui::set_ui_error(&ui_handle, format!("Failed to copy: {}", e));
+                }
+            });
+        }
+    });
+
+    ui.global::<crate::Logic>().on_copy_parameters_json({
+        let ui_handle = ui.as_weak();
+        let clipboard_service = clipboard_service.clone();
+        let navigation = app_state.navigation.clone();
+        let cache = app_state.image_cache.clone();
+
+        move || {
+            let json = with_current_loaded(
+                &navigation,
+                &cache,
+                crate::ui::image_display::parameters_json,
+            )
+            .flatten();
+
+            let Some(json) = json else {
+                log::warn!("No parameters to copy as JSON");
+                crate::ui::set_ui_status(&ui_handle, "No generation parameters in this image");
+                return;
+            };
+
+            let clipboard_service = clipboard_service.clone();
+            let ui_handle = ui_handle.clone();
+
+            rayon::spawn(move || match clipboard_service.copy_text(&json) {
+                Ok(_) => {
+                    log::info!("Parameters JSON copied to clipboard successfully");
+                    crate::ui::set_ui_status(&ui_handle, "Parameters copied to clipboard as JSON");
+                }
+                Err(e) => {
+                    log::error!("Failed to copy parameters JSON to clipboard: {}", e);
+                    crate::ui::set_ui_error(&ui_handle, format!("Failed to copy: {}", e));
+                }
+            });
+        }
+    });
+
+    ui.global::<crate::Logic>().on_copy_filename({
+        let ui_handle = ui.as_weak();
+        let clipboard_service = clipboard_service.clone();
+        let navigation = app_state.navigation.clone();
+        let cache = app_state.image_cache.clone();
+
+        move || {
+            let file_name = with_current_loaded(
+                &navigation,
+                &cache,
+                crate::ui::image_display::file_name_text,
+            )
+            .flatten();
+
+            let Some(file_name) = file_name else {
+                log::warn!("No file name to copy");
+                crate::ui::set_ui_status(&ui_handle, "No image loaded");
+                return;
+            };
+
+            let clipboard_service = clipboard_service.clone();
+            let ui_handle = ui_handle.clone();
+
+            rayon::spawn(move || match clipboard_service.copy_text(&file_name) {
+                Ok(_) => {
+                    log::info!("File name copied to clipboard successfully");
+                    crate::ui::set_ui_status(
+                        &ui_handle,
+                        format!("{} copied to clipboard", file_name),
+                    );
+                }
+                Err(e) => {
+                    log::error!("Failed to copy file name to clipboard: {}", e);
+                    crate::ui::set_ui_error(&ui_handle, format!("Failed to copy: {}", e));
+                }
+            });
+        }
+    });
+}
+
+/// Sets up the handlers for computing and copying the current file's SHA-256.
+fn setup_checksum_handler(ui: &crate::AppWindow, app_state: &AppState) {
+    ui.global::<crate::Logic>().on_show_hash({
+        let ui_handle = ui.as_weak();
+        let navigation = app_state.navigation.clone();
+        let checksums = app_state.checksums.clone();
+
+        move || {
+            let Some(path) = lock_or_recover(&navigation).current_path() else {
+                log::warn!("No file to compute the checksum of");
+                crate::ui::set_ui_status(&ui_handle, "No image loaded");
+                return;
+            };
+
+            if let Some(ui) = ui_handle.upgrade() {
+                ui.global::<crate::ViewerState>()
+                    .set_status_message("Computing SHA-256...".into());
+            }
+
+            let ui_handle = ui_handle.clone();
+            let navigation = navigation.clone();
+            let checksums = checksums.clone();
+
+            rayon::spawn(move || {
+                let checksum = match crate::checksum::sha256_indexed(&checksums, &path) {
+                    Ok(checksum) => checksum,
+                    Err(e) => {
+                        log::error!("Failed to compute SHA-256 of {:?}: {}", path, e);
+                        crate::ui::set_ui_error(
+                            &ui_handle,
+                            format!("Failed to compute SHA-256: {}", e),
+                        );
+                        return;
+                    }
+                };
+
+                log::info!("SHA-256 of {:?}: {}", path, checksum);
+                let _ = slint::invoke_from_event_loop(move || {
+                    // The user may have moved on while a large file was hashed
+                    if lock_or_recover(&navigation).current_path().as_ref() != Some(&path) {
+                        return;
+                    }
+                    if let Some(ui) = ui_handle.upgrade() {
+                        let viewer_state = ui.global::<crate::ViewerState>();
+                        viewer_state.set_file_hash(checksum.into());
+                        viewer_state.set_status_message("SHA-256 computed".into());
+                    }
+                });
+            });
+        }
+    });
+
+    ui.global::<crate::Logic>().on_copy_hash({
+        let ui_handle = ui.as_weak();
+        let clipboard_service = Arc::new(ClipboardService::new());
+
+        move || {
+            let Some(ui) = ui_handle.upgrade() else {
+                return;
+            };
+            let checksum = ui
+                .global::<crate::ViewerState>()
+                .get_file_hash()
+                .to_string();
+            if checksum.is_empty() {
+                log::warn!("No checksum to copy");
+                return;
+            }
+
+            let clipboard_service = clipboard_service.clone();
+            let ui_handle = ui_handle.clone();
+
+            rayon::spawn(move || match clipboard_service.copy_text(&checksum) {
+                Ok(_) => {
+                    log::info!("SHA-256 copied to clipboard successfully");
+                    crate::ui::set_ui_status(&ui_handle, "SHA-256 copied to clipboard");
+                }
+                Err(e) => {
+                    log::error!("Failed to copy SHA-256 to clipboard: {}", e);
+                    crate::ui::set_ui_error(&ui_handle, format!("Failed to copy: {}", e));
                 }
             });
         }
     });
 }
 
+/// Sets up the handler for looking up the current image's model online.
+fn setup_model_lookup_handler(ui: &crate::AppWindow, app_state: &AppState) {
+    ui.global::<crate::Logic>().on_lookup_model({
+        let ui_handle = ui.as_weak();
+        let navigation = app_state.navigation.clone();
+        let cache = app_state.image_cache.clone();
+
+        move || {
+            let url = with_current_loaded(&navigation, &cache, |loaded| {
+                loaded
+                    .sd_parameters
+                    .as_ref()
+                    .and_then(crate::links::model_lookup_url)
+            })
+            .flatten();
+
+            let Some(url) = url else {
+                log::warn!("No model hash available for lookup");
+                crate::ui::set_ui_status(&ui_handle, "No model hash in this image");
+                return;
+            };
+
+            log::info!("Opening model lookup: {}", url);
+            if let Err(e) = open::that(&url) {
+                crate::ui::set_ui_error(&ui_handle, format!("Failed to open browser: {}", e));
+            }
+        }
+    });
+}
+
+/// Sets up the handler for pinning the current prompt to diff other images against.
+fn setup_prompt_pin_handler(ui: &crate::AppWindow, app_state: &AppState) {
+    ui.global::<crate::Logic>().on_toggle_prompt_pin({
+        let ui_handle = ui.as_weak();
+        let navigation = app_state.navigation.clone();
+        let cache = app_state.image_cache.clone();
+
+        move || {
+            let Some(ui) = ui_handle.upgrade() else {
+                return;
+            };
+
+            let status = with_current_loaded(&navigation, &cache, |loaded| {
+                crate::ui::image_display::toggle_prompt_pin(&ui, Some(loaded))
+            })
+            .unwrap_or_else(|| crate::ui::image_display::toggle_prompt_pin(&ui, None));
+            log::info!("{}", status);
+            ui.global::<crate::ViewerState>()
+                .set_status_message(status.into());
+        }
+    });
+}
+
+/// Sets up the handler for exporting ratings of the current directory as CSV.
+fn setup_export_handler(ui: &crate::AppWindow, app_state: &AppState) {
+    let navigation_service = Arc::new(NavigationService::new(
+        app_state.navigation.clone(),
+        app_state.rating_index.clone(),
+    ));
+    let export_service = Arc::new(ExportService::new());
+
+    ui.global::<crate::Logic>().on_export_ratings_csv({
+        let ui_handle = ui.as_weak();
+        move || {
+            let ui_handle = ui_handle.clone();
+            let navigation_service = navigation_service.clone();
+            let export_service = export_service.clone();
+            let _ = slint::spawn_local(async move {
+                let Some(file_handle) = AsyncFileDialog::new()
+                    .add_filter("CSV", &["csv"])
+                    .set_file_name("ratings.csv")
+                    .save_file()
+                    .await
+                else {
+                    return;
+                };
+
+                let dest = file_handle.path().to_path_buf();
+
+                // Read ratings and write the file in background
+                rayon::spawn(move || {
+                    let entries = navigation_service.list_with_ratings();
+                    match export_service.write_ratings_csv(&dest, &entries) {
+                        Ok(()) => {
+                            log::info!("Exported {} ratings to {:?}", entries.len(), dest);
+                        }
+                        Err(e) => {
+                            log::error!("Failed to export ratings: {}", e);
+                            crate::ui::set_ui_error(
+                                &ui_handle,
+                                format!("Failed to export ratings: {}", e),
+                            );
+                        }
+                    }
+                });
+            });
+        }
+    });
+}
+
+/// Sets up the handler for saving the current image as a PNG copy with its metadata.
+fn setup_save_copy_handler(ui: &crate::AppWindow, app_state: &AppState) {
+    ui.global::<crate::Logic>().on_save_copy_with_metadata({
+        let ui_handle = ui.as_weak();
+        let navigation = app_state.navigation.clone();
+        move || {
+            let Some(src) = lock_or_recover(&navigation).current_path() else {
+                log::warn!("No image to save");
+                return;
+            };
+
+            let ui_handle = ui_handle.clone();
+            let _ = slint::spawn_local(async move {
+                let stem = src
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "image".to_string());
+                let Some(file_handle) = AsyncFileDialog::new()
+                    .add_filter("PNG", &["png"])
+                    .set_file_name(format!("{}-copy.png", stem))
+                    .save_file()
+                    .await
+                else {
+                    return;
+                };
+
+                let dest = file_handle.path().to_path_buf();
+
+                // Encode and write the file in background
+                rayon::spawn(
+                    move || match FileOpsService::new().save_with_metadata(&src, &dest) {
+                        Ok(()) => {
+                            log::info!("Saved copy with metadata to {:?}", dest);
+                            crate::ui::set_ui_status(&ui_handle, "Saved copy with metadata");
+                        }
+                        Err(e) => {
+                            log::error!("Failed to save copy: {}", e);
+                            crate::ui::set_ui_error(
+                                &ui_handle,
+                                format!("Failed to save copy: {}", e),
+                            );
+                        }
+                    },
+                );
+            });
+        }
+    });
+}
+
+/// Sets up the handler for rotating the current file and saving it in place.
+fn setup_rotate_save_handler(
+    ui: &crate::AppWindow,
+    app_state: &AppState,
+    display_tracker: &crate::ui::DisplayTracker,
+) {
+    ui.global::<crate::Logic>().on_rotate_save({
+        let ui_handle = ui.as_weak();
+        let app_state = app_state.clone();
+        let display_tracker = display_tracker.clone();
+        move |quarter_turns| {
+            let Some(path) = lock_or_recover(&app_state.navigation).current_path() else {
+                log::warn!("No image to rotate");
+                return;
+            };
+
+            let ui_handle = ui_handle.clone();
+            let app_state = app_state.clone();
+            let display_tracker = display_tracker.clone();
+
+            rayon::spawn(move || {
+                let result = FileOpsService::new().rotate_and_save(&path, quarter_turns);
+
+                let _ = slint::invoke_from_event_loop(move || match result {
+                    Ok(()) => {
+                        lock_or_recover(&app_state.image_cache).remove(&path);
+                        load_and_display_image(
+                            ui_handle.clone(),
+                            path,
+                            "Failed to reload rotated image".to_string(),
+                            app_state.navigation.clone(),
+                            app_state.image_cache.clone(),
+                            app_state.rating_index.clone(),
+                            display_tracker,
+                            app_state.load_generation.clone(),
+                        );
+                        if let Some(ui) = ui_handle.upgrade() {
+                            ui.global::<crate::ViewerState>()
+                                .set_status_message("Rotated and saved".into());
+                        }
+                    }
+                    Err(e) => {
+                        log::error!("Failed to rotate image: {}", e);
+                        if let Some(ui) = ui_handle.upgrade() {
+                            crate::ui::set_error_with_prefix(
+                                &ui,
+                                "Failed to rotate",
+                                e.to_string(),
+                            );
+                        }
+                    }
+                });
+            });
+        }
+    });
+}
+
+/// Sets up the handler for refreshing the display profile.
+///
+/// Decoded images in the cache were color-managed for the old profile,
+/// so the cache is cleared and the current image is decoded again. The
+/// display profile is read on every decode, so the new decode picks it up.
+fn setup_display_profile_handler(
+    ui: &crate::AppWindow,
+    app_state: &AppState,
+    display_tracker: &crate::ui::DisplayTracker,
+) {
+    ui.global::<crate::Logic>().on_refresh_display_profile({
+        let ui_handle = ui.as_weak();
+        let state = app_state.navigation.clone();
+        let cache = app_state.image_cache.clone();
+        let rating_index = app_state.rating_index.clone();
+        let display_tracker = display_tracker.clone();
+        let load_generation = app_state.load_generation.clone();
+
+        move || {
+            let current_path =
+                crate::ui::image_display::invalidate_color_managed_images(&state, &cache);
+            if let Some(path) = current_path {
+                load_and_display_image(
+                    ui_handle.clone(),
+                    path,
+                    "Failed to reload image".to_string(),
+                    state.clone(),
+                    cache.clone(),
+                    rating_index.clone(),
+                    display_tracker.clone(),
+                    load_generation.clone(),
+                );
+            }
+        }
+    });
+}
+
+/// Creates a sort handler closure for the specified sort order.
+fn create_sort_handler(
+    ui_handle: slint::Weak<crate::AppWindow>,
+    navigation_service: Arc<NavigationService>,
+    state: Arc<Mutex<crate::state::NavigationState>>,
+    order: SortOrder,
+) -> impl Fn() {
+    move || {
+        let ui_handle = ui_handle.clone();
+        let navigation_service = navigation_service.clone();
+        let state = state.clone();
+
+        // Sorting by date stats every file, so keep it off the UI thread
+        rayon::spawn(move || {
+            let (current, total) = navigation_service.re_sort(order);
+            let _ = slint::invoke_from_event_loop(move || {
+                if let Some(ui) = ui_handle.upgrade() {
+                    let auto_reload = ui.global::<crate::ViewerState>().get_auto_reload_active();
+                    crate::ui::set_navigation_info(&ui, current, total, auto_reload);
+                    crate::ui::sync_file_list(&ui, &lock_or_recover(&state));
+                }
+            });
+        });
+    }
+}
+
+/// Sets up the sort order handlers.
+fn setup_sort_handlers(ui: &crate::AppWindow, app_state: &AppState) {
+    let navigation_service = Arc::new(NavigationService::new(
+        app_state.navigation.clone(),
+        app_state.rating_index.clone(),
+    ));
+
+    ui.global::<crate::Logic>()
+        .on_sort_by_name(create_sort_handler(
+            ui.as_weak(),
+            navigation_service.clone(),
+            app_state.navigation.clone(),
+            SortOrder::Name,
+        ));
+    ui.global::<crate::Logic>()
+        .on_sort_by_modified_date(create_sort_handler(
+            ui.as_weak(),
+            navigation_service,
+            app_state.navigation.clone(),
+            SortOrder::ModifiedDate,
+        ));
+}
+
+/// Sets up the handler for loading more files of a windowed directory.
+fn setup_directory_window_handler(ui: &crate::AppWindow, app_state: &AppState) {
+    let navigation_service =
+        NavigationService::new(app_state.navigation.clone(), app_state.rating_index.clone());
+
+    ui.global::<crate::Logic>().on_load_more_files({
+        let ui_handle = ui.as_weak();
+        let navigation = app_state.navigation.clone();
+
+        move || {
+            let Some(ui) = ui_handle.upgrade() else {
+                return;
+            };
+
+            if let Some((current, total)) = navigation_service.extend_window() {
+                let auto_reload = ui.global::<crate::ViewerState>().get_auto_reload_active();
+                crate::ui::set_navigation_info(&ui, current, total, auto_reload);
+                crate::ui::sync_file_list(&ui, &lock_or_recover(&navigation));
+            }
+
+            let windowed = lock_or_recover(&navigation).is_windowed();
+            ui.global::<crate::ViewerState>()
+                .set_directory_windowed(windowed);
+        }
+    });
+}
+
+/// Sets up the handler that redraws the image with the preview filters.
+fn setup_preview_filter_handler(ui: &crate::AppWindow, app_state: &AppState) {
+    ui.global::<crate::Logic>().on_recompute_preview({
+        let ui_handle = ui.as_weak();
+        let navigation = app_state.navigation.clone();
+        let cache = app_state.image_cache.clone();
+
+        move || {
+            let Some(ui) = ui_handle.upgrade() else {
+                return;
+            };
+            crate::ui::image_display::refresh_current_preview(&ui, &navigation, &cache);
+        }
+    });
+}
+
+/// Sets up the display mode (fit / actual size) handlers.
+///
+/// Applies the persisted display mode and saves it whenever it is toggled,
+/// both globally and for the current directory.
+fn setup_display_mode_handlers(ui: &crate::AppWindow, app_state: &AppState) {
+    let display_mode = crate::settings::current().display_mode;
+    ui.global::<crate::ViewerState>()
+        .set_display_mode_actual(display_mode == DisplayMode::Actual);
+
+    ui.global::<crate::Logic>().on_toggle_display_mode({
+        let ui_handle = ui.as_weak();
+        let state = app_state.navigation.clone();
+        move || {
+            let Some(ui) = ui_handle.upgrade() else {
+                return;
+            };
+
+            let viewer_state = ui.global::<crate::ViewerState>();
+            let current = if viewer_state.get_display_mode_actual() {
+                DisplayMode::Actual
+            } else {
+                DisplayMode::Fit
+            };
+            let next = current.toggled();
+            log::info!("Display mode: {:?} -> {:?}", current, next);
+
+            viewer_state.set_display_mode_actual(next == DisplayMode::Actual);
+            crate::settings::update(|settings| settings.display_mode = next);
+            lock_or_recover(&state).set_directory_display_mode(next);
+        }
+    });
+
+    ui.global::<crate::Logic>().on_display_scale(
+        |actual, image_width, image_height, viewport_width, viewport_height| {
+            let mode = if actual {
+                DisplayMode::Actual
+            } else {
+                DisplayMode::Fit
+            };
+            compute_display_scale(
+                mode,
+                (image_width.max(0) as u32, image_height.max(0) as u32),
+                (viewport_width, viewport_height),
+            )
+        },
+    );
+}
+
+/// Sets up the handler for hiding all chrome around the image.
+///
+/// Applies the persisted state and saves it whenever it is toggled.
+/// Prompt panels are not updated while hidden, so they are refreshed on reveal.
+fn setup_chrome_handler(ui: &crate::AppWindow, app_state: &AppState) {
+    ui.global::<crate::ViewerState>()
+        .set_chrome_hidden(crate::settings::current().chrome_hidden);
+
+    ui.global::<crate::Logic>().on_toggle_chrome({
+        let ui_handle = ui.as_weak();
+        let state = app_state.navigation.clone();
+        let cache = app_state.image_cache.clone();
+        move || {
+            let Some(ui) = ui_handle.upgrade() else {
+                return;
+            };
+
+            let viewer_state = ui.global::<crate::ViewerState>();
+            let hidden = !viewer_state.get_chrome_hidden();
+            log::info!("Chrome hidden: {}", hidden);
+
+            viewer_state.set_chrome_hidden(hidden);
+            crate::settings::update(|settings| settings.chrome_hidden = hidden);
+
+            if !hidden && crate::ui::image_display::take_stale_prompt_panel() {
+                with_current_loaded(&state, &cache, |loaded| {
+                    crate::ui::image_display::update_prompt_panel(&ui, loaded)
+                });
+            }
+        }
+    });
+}
+
+/// Sets up the handler for showing or hiding the prompt overlay on the image.
+///
+/// Saves the new state as the `prompt_overlay` setting whenever it is toggled.
+fn setup_prompt_overlay_handler(ui: &crate::AppWindow) {
+    ui.global::<crate::ViewerState>()
+        .set_prompt_overlay(crate::settings::current().prompt_overlay);
+
+    ui.global::<crate::Logic>().on_toggle_prompt_overlay({
+        let ui_handle = ui.as_weak();
+        move || {
+            let Some(ui) = ui_handle.upgrade() else {
+                return;
+            };
+
+            let viewer_state = ui.global::<crate::ViewerState>();
+            let shown = !viewer_state.get_prompt_overlay();
+            log::info!("Prompt overlay shown: {}", shown);
+
+            viewer_state.set_prompt_overlay(shown);
+            crate::settings::update(|settings| settings.prompt_overlay = shown);
+        }
+    });
+}
+
+/// Sets up the handler for switching between wrapping and clamping at the list ends.
+///
+/// Saves the new mode as the `wrap_navigation` setting whenever it is toggled.
+fn setup_wrap_handler(ui: &crate::AppWindow, app_state: &AppState) {
+    ui.global::<crate::ViewerState>()
+        .set_wrap_navigation(crate::settings::current().wrap_navigation);
+
+    ui.global::<crate::Logic>().on_toggle_wrap({
+        let ui_handle = ui.as_weak();
+        let navigation_service =
+            NavigationService::new(app_state.navigation.clone(), app_state.rating_index.clone());
+        move || {
+            let Some(ui) = ui_handle.upgrade() else {
+                return;
+            };
+
+            let wrap = navigation_service.toggle_wrap();
+            log::info!("Wrap navigation: {}", wrap);
+
+            crate::settings::update(|settings| settings.wrap_navigation = wrap);
+            let viewer_state = ui.global::<crate::ViewerState>();
+            viewer_state.set_wrap_navigation(wrap);
+            viewer_state.set_status_message(
+                if wrap {
+                    "Navigation wraps at the ends"
+                } else {
+                    "Navigation stops at the ends"
+                }
+                .into(),
+            );
+        }
+    });
+}
+
+/// Sets up the handler for decoding the whole current directory into the cache.
+fn setup_preload_all_handler(
+    ui: &crate::AppWindow,
+    app_state: &AppState,
+    display_tracker: &crate::ui::DisplayTracker,
+) {
+    // Shared by all runs, so starting a new preload cancels the previous one
+    let generation = crate::state::LoadGeneration::new();
+
+    ui.global::<crate::Logic>().on_preload_all({
+        let ui_handle = ui.as_weak();
+        let state = app_state.navigation.clone();
+        let cache = app_state.image_cache.clone();
+        let rating_index = app_state.rating_index.clone();
+        let display_tracker = display_tracker.clone();
+        move || {
+            log::info!("Preloading all images in directory");
+            crate::ui::image_display::preload_all_images(
+                ui_handle.clone(),
+                state.clone(),
+                cache.clone(),
+                rating_index.clone(),
+                display_tracker.clone(),
+                generation.clone(),
+            );
+        }
+    });
+}
+
+/// Sets up the handler for showing recent log messages.
+fn setup_log_handler(ui: &crate::AppWindow) {
+    ui.global::<crate::Logic>().on_show_logs({
+        let ui_handle = ui.as_weak();
+        move || {
+            if let Some(ui) = ui_handle.upgrade() {
+                let logs = crate::log_buffer::recent_logs().join("\n");
+                ui.global::<crate::ViewerState>().set_log_text(logs.into());
+            }
+        }
+    });
+}
+
 /// Sets up all UI event handlers for the application.
 ///
 /// Takes the UI handle and shared application state, then registers
@@ -394,8 +1924,44 @@ pub fn setup_handlers(
     display_tracker: crate::ui::DisplayTracker,
 ) {
     setup_file_selection_handler(ui, &app_state, &display_tracker);
+    setup_recent_files_handler(ui, &app_state, &display_tracker);
+    setup_close_image_handler(ui, &app_state);
     setup_navigation_handlers(ui, &app_state, &display_tracker);
     setup_auto_reload_handlers(ui, &app_state, &display_tracker);
     setup_rating_handlers(ui, &app_state);
+    setup_keyword_handlers(ui, &app_state);
     setup_clipboard_handler(ui, &app_state);
+    setup_checksum_handler(ui, &app_state);
+    setup_seed_search_handler(ui, &app_state, &display_tracker);
+    setup_export_handler(ui, &app_state);
+    setup_save_copy_handler(ui, &app_state);
+    setup_rotate_save_handler(ui, &app_state, &display_tracker);
+    setup_model_lookup_handler(ui, &app_state);
+    setup_prompt_pin_handler(ui, &app_state);
+    setup_display_profile_handler(ui, &app_state, &display_tracker);
+    setup_preload_all_handler(ui, &app_state, &display_tracker);
+    setup_sort_handlers(ui, &app_state);
+    setup_directory_window_handler(ui, &app_state);
+    setup_display_mode_handlers(ui, &app_state);
+    setup_chrome_handler(ui, &app_state);
+    setup_prompt_overlay_handler(ui);
+    setup_wrap_handler(ui, &app_state);
+    setup_preview_filter_handler(ui, &app_state);
+    setup_log_handler(ui);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drag_out_falls_back_to_clipboard_only_when_enabled() {
+        let path = PathBuf::from("a.png");
+        assert_eq!(
+            drag_out_method(true, Some(path.clone())),
+            DragOut::Clipboard(path.clone())
+        );
+        assert_eq!(drag_out_method(false, Some(path)), DragOut::Ignore);
+        assert_eq!(drag_out_method(true, None), DragOut::Ignore);
+    }
 }
@@ -1,26 +1,147 @@
 //! Image loading and display logic.
 //!
-//! Uses `rayon::spawn` for CPU-intensive image decoding operations,
-//! then `slint::invoke_from_event_loop` to update UI from the background thread.
+//! Decodes images on a dedicated rayon pool bounded by the `max_concurrent_decodes`
+//! setting, then uses `slint::invoke_from_event_loop` to update UI from the background thread.
 
 use crate::{
+    config,
+    error::{AppError, NavigationError},
     image_cache::ImageCache,
     image_loader,
-    metadata::{SdParameters, SdTag},
-    state::NavigationState,
+    metadata::{self, PromptDiff, SdParameters, SdTag, TagDiff},
+    rating_index::RatingIndex,
+    services::{NavigationService, lock_or_recover},
+    state::{LoadGeneration, NavigationState},
+    ui::display_mode::DisplayMode,
 };
+use once_cell::sync::Lazy;
+use rayon::prelude::*;
 use slint::ComponentHandle;
-use std::path::PathBuf;
+use std::cell::{Cell, RefCell};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+thread_local! {
+    /// Incremented whenever a new image is displayed, so stale animation timers stop.
+    static ANIMATION_GENERATION: Cell<u64> = const { Cell::new(0) };
+    /// File name and SD parameters of the image pinned for prompt diffs.
+    static PINNED_PROMPT: RefCell<Option<(String, SdParameters)>> = const { RefCell::new(None) };
+    /// Path and alpha values of the image shown with the alpha preview.
+    /// Kept only while that preview is on, so cached images don't carry an extra plane.
+    static ALPHA_PLANE: RefCell<Option<(PathBuf, Vec<u8>)>> = const { RefCell::new(None) };
+    /// Whether the prompt panel was skipped while chrome was hidden.
+    static PROMPT_PANEL: RefCell<PromptPanelSync> = RefCell::new(PromptPanelSync::default());
+}
+
+/// Tracks whether the prompt panel is out of date because chrome was hidden.
+#[derive(Debug, Default)]
+struct PromptPanelSync {
+    stale: bool,
+}
+
+impl PromptPanelSync {
+    /// Returns whether the panel is updated for a newly displayed image.
+    ///
+    /// The update is skipped while chrome is hidden and the panel is marked stale.
+    fn on_display(&mut self, chrome_hidden: bool) -> bool {
+        self.stale = chrome_hidden;
+        !chrome_hidden
+    }
+
+    /// Returns whether the panel has to be refreshed when chrome is shown again.
+    fn on_reveal(&mut self) -> bool {
+        std::mem::take(&mut self.stale)
+    }
+}
+
+/// Returns whether the prompt panel missed an update while chrome was hidden.
+///
+/// Clears the flag, so the caller is expected to refresh the panel.
+pub fn take_stale_prompt_panel() -> bool {
+    PROMPT_PANEL.with(|panel| panel.borrow_mut().on_reveal())
+}
+
+/// Thread pool for displaying and preloading images, including `Preload all`.
+///
+/// Limits how many images are decoded at once, so a directory of huge images
+/// can't spike memory. Sized from `max_concurrent_decodes` on first use.
+static DECODE_POOL: Lazy<Option<rayon::ThreadPool>> =
+    Lazy::new(|| build_decode_pool(crate::settings::current().max_concurrent_decodes));
+
+/// Builds a pool running at most `max_decodes` tasks at once (at least one).
+fn build_decode_pool(max_decodes: usize) -> Option<rayon::ThreadPool> {
+    let threads = max_decodes.max(1);
+    match rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .thread_name(|index| format!("decode-{}", index))
+        .build()
+    {
+        Ok(pool) => {
+            log::info!("Decoding up to {} images at once", threads);
+            Some(pool)
+        }
+        Err(e) => {
+            log::warn!("Failed to build decode pool, using the global pool: {}", e);
+            None
+        }
+    }
+}
+
+/// Runs an image decode task on the bounded decode pool.
+fn spawn_decode(task: impl FnOnce() + Send + 'static) {
+    match DECODE_POOL.as_ref() {
+        Some(pool) => pool.spawn(task),
+        None => rayon::spawn(task),
+    }
+}
+
+/// Where the displayed image data came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageSource {
+    /// Served from the image cache, e.g. after preloading.
+    Cache,
+    /// Decoded from disk on demand.
+    Disk,
+}
+
+impl ImageSource {
+    /// Short label shown next to the image position.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Cache => "cached",
+            Self::Disk => "loaded",
+        }
+    }
+}
+
+/// Looks up an image to display in the cache.
+///
+/// Returns the image with [`ImageSource::Cache`] on a hit, and
+/// [`ImageSource::Disk`] without an image when it has to be decoded.
+fn lookup_for_display(
+    cache: &Mutex<ImageCache>,
+    path: &PathBuf,
+) -> (ImageSource, Option<image_loader::LoadedImageData>) {
+    match lock_or_recover(cache).get(path) {
+        Some(loaded) => (ImageSource::Cache, Some(loaded)),
+        None => (ImageSource::Disk, None),
+    }
+}
 
 /// Updates the UI with successfully loaded image data.
 fn update_ui_with_image(
     ui: &crate::AppWindow,
     loaded: &image_loader::LoadedImageData,
     state: &Arc<Mutex<NavigationState>>,
+    source: ImageSource,
 ) {
-    let image = image_loader::create_slint_image(&loaded.data, loaded.width, loaded.height);
-    update_ui_state(ui, image, loaded, state);
+    match image_loader::create_slint_image(&loaded.data, loaded.width, loaded.height) {
+        Ok(image) => update_ui_state(ui, image, loaded, state, source),
+        Err(e) => update_ui_with_error(ui, "Failed to display image", e.to_string()),
+    }
 }
 
 /// Updates the UI with an error message.
@@ -34,11 +155,26 @@ fn update_ui_state(
     image: slint::Image,
     loaded: &image_loader::LoadedImageData,
     state: &Arc<Mutex<NavigationState>>,
+    source: ImageSource,
 ) {
     ui.global::<crate::ViewerState>().set_dynamic_image(image);
+    ui.global::<crate::ViewerState>()
+        .set_image_source(source.label().into());
+    ui.global::<crate::ViewerState>()
+        .set_raw_preview(loaded.raw_preview);
     ui.global::<crate::ViewerState>().set_image_loaded(true);
+    // Preview filters apply to the image they were enabled on only
+    ui.global::<crate::ViewerState>().set_preview_invert(false);
+    ui.global::<crate::ViewerState>()
+        .set_preview_grayscale(false);
+    ui.global::<crate::ViewerState>().set_preview_alpha(false);
+    ALPHA_PLANE.with(|plane| plane.borrow_mut().take());
+    start_animation(ui, loaded);
     ui.global::<crate::ViewerState>()
         .set_error_message("".into());
+    ui.global::<crate::ViewerState>()
+        .set_color_warning(loaded.color_management.ui_message().into());
+    crate::ui::set_keywords(ui, &loaded.keywords);
 
     let rating_i32 = loaded.rating.map(|r| r as i32).unwrap_or(-1);
     crate::ui::set_rating_info(ui, rating_i32, false);
@@ -46,13 +182,29 @@ fn update_ui_state(
     // Set navigation information
     if let Ok(nav_state) = state.lock() {
         let total = nav_state.image_count() as i32;
-        let current = if let Some(path) = nav_state.current_path() {
-            (nav_state.find_file_index(&path) + 1) as i32 // 1-based index
-        } else {
-            -1
-        };
+        let current = nav_state
+            .current_index()
+            .map(|index| index as i32 + 1) // 1-based index
+            .unwrap_or(-1);
         let auto_reload = ui.global::<crate::ViewerState>().get_auto_reload_active();
         crate::ui::set_navigation_info(ui, current, total, auto_reload);
+        crate::ui::sync_file_list(ui, &nav_state);
+
+        let rating_writable = nav_state
+            .current_path()
+            .map(|path| metadata::supports_rating_write(&path))
+            .unwrap_or(false);
+        ui.global::<crate::ViewerState>()
+            .set_rating_writable(rating_writable);
+        ui.global::<crate::ViewerState>()
+            .set_directory_windowed(nav_state.is_windowed());
+
+        // Directories with a remembered display mode override the global one
+        let display_mode = nav_state
+            .directory_display_mode()
+            .unwrap_or_else(|| crate::settings::current().display_mode);
+        ui.global::<crate::ViewerState>()
+            .set_display_mode_actual(display_mode == DisplayMode::Actual);
     }
 
     // Set basic file information
@@ -65,46 +217,550 @@ fn update_ui_state(
         &loaded.created_date,
         &loaded.modified_date,
     );
+    ui.global::<crate::ViewerState>().set_file_hash("".into());
+
+    // Prompt formatting is skipped while chrome is hidden and redone on reveal
+    let chrome_hidden = ui.global::<crate::ViewerState>().get_chrome_hidden();
+    if PROMPT_PANEL.with(|panel| panel.borrow_mut().on_display(chrome_hidden)) {
+        update_prompt_panel(ui, loaded);
+    }
 
-    // Update SD parameters
+    if let Ok(mut nav_state) = state.lock() {
+        nav_state.set_current_rating(loaded.rating);
+    }
+
+    crate::ui::ready_signal::notify_image_displayed();
+}
+
+/// Updates the prompt and generation settings panels for the loaded image.
+pub fn update_prompt_panel(ui: &crate::AppWindow, loaded: &image_loader::LoadedImageData) {
     if let Some(params) = &loaded.sd_parameters {
         // Format positive tags
-        let positive_prompt = format_tags(&params.positive_sd_tags);
+        let positive_prompt =
+            format_tags_capped(&params.positive_sd_tags, config::MAX_DISPLAYED_TAGS);
 
         // Format negative tags
-        let negative_prompt = format_tags(&params.negative_sd_tags);
+        let negative_prompt =
+            format_tags_capped(&params.negative_sd_tags, config::MAX_DISPLAYED_TAGS);
 
         // Format other parameters as key-value pairs
         let sd_params = format_sd_parameters(params);
 
+        let overlay_text = wrap_overlay_text(
+            &positive_prompt,
+            config::PROMPT_OVERLAY_MAX_CHARS,
+            config::PROMPT_OVERLAY_LINE_WIDTH,
+        );
+
         crate::ui::set_prompts_and_parameters(ui, &positive_prompt, &negative_prompt, sd_params);
+        let viewer_state = ui.global::<crate::ViewerState>();
+        viewer_state.set_prompt_overlay_text(overlay_text.into());
+        viewer_state.set_model_lookup_available(crate::links::model_lookup_url(params).is_some());
     } else {
         // Clear SD parameters
         crate::ui::clear_prompts_and_parameters(ui);
+        let viewer_state = ui.global::<crate::ViewerState>();
+        viewer_state.set_prompt_overlay_text("".into());
+        viewer_state.set_model_lookup_available(false);
     }
 
-    if let Ok(mut nav_state) = state.lock() {
-        nav_state.set_current_rating(loaded.rating);
+    crate::ui::set_sd_parse_error(ui, loaded.sd_parse_error.as_ref());
+    update_prompt_diff(ui, loaded.sd_parameters.as_ref());
+}
+
+/// Pins the prompt of `loaded` for diffing, or unpins the current pin.
+///
+/// Returns the status message to show.
+pub fn toggle_prompt_pin(
+    ui: &crate::AppWindow,
+    loaded: Option<&image_loader::LoadedImageData>,
+) -> String {
+    let was_pinned = PINNED_PROMPT
+        .with(|pinned| pinned.borrow_mut().take())
+        .is_some();
+    if was_pinned {
+        crate::ui::set_prompt_diff(ui, "", vec![]);
+        return "Prompt unpinned".to_string();
     }
+
+    let Some((loaded, params)) =
+        loaded.and_then(|loaded| Some((loaded, loaded.sd_parameters.as_ref()?)))
+    else {
+        return "No SD parameters to pin".to_string();
+    };
+
+    PINNED_PROMPT.with(|pinned| {
+        *pinned.borrow_mut() = Some((loaded.file_name.clone(), params.clone()));
+    });
+    update_prompt_diff(ui, Some(params));
+    format!("Pinned prompt of {}", loaded.file_name)
 }
 
-/// Formats SD tags into a comma-separated string with weights.
-fn format_tags(tags: &[SdTag]) -> String {
-    tags.iter()
-        .map(|tag| {
-            if let Some(weight) = tag.weight {
-                format!("({}:{})", tag.name, weight)
-            } else {
-                tag.name.clone()
+/// Shows the diff between the pinned prompt and `current`, if a prompt is pinned.
+fn update_prompt_diff(ui: &crate::AppWindow, current: Option<&SdParameters>) {
+    PINNED_PROMPT.with(|pinned| {
+        let pinned = pinned.borrow();
+        let Some((file_name, pinned_params)) = pinned.as_ref() else {
+            return;
+        };
+        let rows = current
+            .map(|params| format_prompt_diff(&metadata::diff_prompts(pinned_params, params)))
+            .unwrap_or_else(|| vec![("~".into(), "No SD parameters in this image".into())]);
+        crate::ui::set_prompt_diff(ui, file_name, rows);
+    });
+}
+
+/// Formats a prompt diff into rows keyed by `+` (added), `-` (removed) or `~` (changed).
+fn format_prompt_diff(diff: &PromptDiff) -> Vec<(slint::SharedString, slint::SharedString)> {
+    let mut rows = Vec::new();
+    push_tag_diff_rows(&mut rows, &diff.positive, "");
+    push_tag_diff_rows(&mut rows, &diff.negative, "Negative: ");
+
+    let or_none = |value: &Option<String>| value.clone().unwrap_or_else(|| "(none)".into());
+    rows.extend(diff.fields.iter().map(|change| {
+        (
+            "~".into(),
+            format!(
+                "{}: {} → {}",
+                change.field,
+                or_none(&change.before),
+                or_none(&change.after)
+            )
+            .into(),
+        )
+    }));
+    rows
+}
+
+/// Appends the rows of one prompt's tag diff, prefixing each tag with `prefix`.
+fn push_tag_diff_rows(
+    rows: &mut Vec<(slint::SharedString, slint::SharedString)>,
+    diff: &TagDiff,
+    prefix: &str,
+) {
+    let weight = |w: Option<f32>| w.map_or_else(|| "1".to_string(), |w| w.to_string());
+
+    rows.extend(
+        diff.added
+            .iter()
+            .map(|tag| ("+".into(), format!("{}{}", prefix, format_tag(tag)).into())),
+    );
+    rows.extend(
+        diff.removed
+            .iter()
+            .map(|tag| ("-".into(), format!("{}{}", prefix, format_tag(tag)).into())),
+    );
+    rows.extend(diff.reweighted.iter().map(|change| {
+        (
+            "~".into(),
+            format!(
+                "{}{}: {} → {}",
+                prefix,
+                change.name,
+                weight(change.before),
+                weight(change.after)
+            )
+            .into(),
+        )
+    }));
+}
+
+/// Shows the current image with the preview filters selected in the UI.
+///
+/// The alpha values are read from the file in the background the first time the
+/// alpha preview is turned on for an image, and the preview is refreshed once
+/// they arrive. They are dropped when the preview is turned off.
+pub fn refresh_current_preview(
+    ui: &crate::AppWindow,
+    state: &Arc<Mutex<NavigationState>>,
+    cache: &Arc<Mutex<ImageCache>>,
+) {
+    let Some(path) = lock_or_recover(state).current_path() else {
+        log::warn!("No image to apply preview filters to");
+        return;
+    };
+    let alpha_preview = ui.global::<crate::ViewerState>().get_preview_alpha();
+
+    let pending = ALPHA_PLANE.with(|plane| {
+        let mut plane = plane.borrow_mut();
+        if !alpha_preview || plane.as_ref().is_some_and(|(p, _)| *p != path) {
+            *plane = None;
+        }
+        let alpha = plane.as_ref().map(|(_, alpha)| alpha.as_slice());
+
+        with_current_loaded(state, cache, |loaded| {
+            let pending = alpha_preview && loaded.has_alpha && alpha.is_none();
+            if !pending {
+                refresh_preview(ui, loaded, alpha);
             }
+            pending
         })
+    });
+
+    match pending {
+        Some(true) => read_alpha_plane(ui.as_weak(), path),
+        Some(false) => {}
+        None => log::warn!("No image to apply preview filters to"),
+    }
+}
+
+/// Reads the alpha values of `path` in the background, then refreshes the preview.
+///
+/// The result is dropped if the alpha preview was turned off meanwhile.
+fn read_alpha_plane(ui_handle: slint::Weak<crate::AppWindow>, path: PathBuf) {
+    spawn_decode(move || {
+        let alpha = image_loader::load_alpha(&path);
+
+        let _ = slint::invoke_from_event_loop(move || {
+            let Some(ui) = ui_handle.upgrade() else {
+                return;
+            };
+            if !ui.global::<crate::ViewerState>().get_preview_alpha() {
+                return;
+            }
+
+            match alpha {
+                Ok(Some(alpha)) => {
+                    ALPHA_PLANE.with(|plane| *plane.borrow_mut() = Some((path, alpha)));
+                    ui.global::<crate::Logic>().invoke_recompute_preview();
+                }
+                Ok(None) => log::warn!("No alpha channel after reading {:?} again", path),
+                Err(e) => update_ui_with_error(&ui, "Failed to read alpha channel", e.to_string()),
+            }
+        });
+    });
+}
+
+/// Shows the image with the invert / grayscale / alpha preview filters selected in the UI.
+///
+/// Filters are applied to a copy of the decoded pixels, never to the file.
+/// Animations pause while a filter is active and resume when all are off.
+/// `alpha_plane` holds the alpha values for the alpha preview; without them it shows plain white.
+fn refresh_preview(
+    ui: &crate::AppWindow,
+    loaded: &image_loader::LoadedImageData,
+    alpha_plane: Option<&[u8]>,
+) {
+    let viewer_state = ui.global::<crate::ViewerState>();
+    let invert = viewer_state.get_preview_invert();
+    let grayscale = viewer_state.get_preview_grayscale();
+    let alpha = viewer_state.get_preview_alpha();
+
+    if alpha && !loaded.has_alpha {
+        viewer_state.set_status_message("No alpha channel (fully opaque)".into());
+    }
+
+    if !invert && !grayscale && !alpha {
+        match image_loader::create_slint_image(&loaded.data, loaded.width, loaded.height) {
+            Ok(image) => viewer_state.set_dynamic_image(image),
+            Err(e) => {
+                update_ui_with_error(ui, "Failed to display image", e.to_string());
+                return;
+            }
+        }
+        start_animation(ui, loaded);
+        return;
+    }
+
+    stop_animation();
+    let data = if alpha {
+        image_loader::alpha_to_gray(alpha_plane, loaded.width, loaded.height)
+    } else if grayscale {
+        image_loader::to_grayscale_rgb8(&loaded.data)
+    } else {
+        loaded.data.clone()
+    };
+    let data = if invert {
+        image_loader::invert_rgb8(&data)
+    } else {
+        data
+    };
+    match image_loader::create_slint_image(&data, loaded.width, loaded.height) {
+        Ok(image) => viewer_state.set_dynamic_image(image),
+        Err(e) => update_ui_with_error(ui, "Failed to apply preview filter", e.to_string()),
+    }
+}
+
+/// Stops the running animation, if any.
+fn stop_animation() -> u64 {
+    ANIMATION_GENERATION.with(|g| {
+        g.set(g.get() + 1);
+        g.get()
+    })
+}
+
+/// Starts playing the frames of an animated image, replacing any running animation.
+///
+/// Still images only stop the previous animation.
+fn start_animation(ui: &crate::AppWindow, loaded: &image_loader::LoadedImageData) {
+    let generation = stop_animation();
+
+    if loaded.frames.len() < 2 {
+        return;
+    }
+
+    // The images share the decoded pixels, so this copies no frame data
+    let frames: Rc<Vec<(slint::Image, Duration)>> = Rc::new(
+        loaded
+            .frames
+            .iter()
+            .map(|frame| {
+                (
+                    slint::Image::from_rgb8(frame.pixels.clone()),
+                    frame_delay(frame.delay_ms),
+                )
+            })
+            .collect(),
+    );
+
+    schedule_next_frame(ui.as_weak(), frames, 0, generation);
+}
+
+/// Schedules the frame after `index` to be shown once the current frame's delay elapses.
+fn schedule_next_frame(
+    ui: slint::Weak<crate::AppWindow>,
+    frames: Rc<Vec<(slint::Image, Duration)>>,
+    index: usize,
+    generation: u64,
+) {
+    let delay = frames[index].1;
+    slint::Timer::single_shot(delay, move || {
+        if ANIMATION_GENERATION.with(|g| g.get()) != generation {
+            return;
+        }
+        let Some(ui) = ui.upgrade() else {
+            return;
+        };
+
+        let next = (index + 1) % frames.len();
+        ui.global::<crate::ViewerState>()
+            .set_dynamic_image(frames[next].0.clone());
+        schedule_next_frame(ui.as_weak(), frames, next, generation);
+    });
+}
+
+/// Converts a frame delay, substituting the default for missing or tiny delays.
+fn frame_delay(delay_ms: u32) -> Duration {
+    if delay_ms < 10 {
+        Duration::from_millis(crate::config::DEFAULT_FRAME_DELAY_MS)
+    } else {
+        Duration::from_millis(delay_ms as u64)
+    }
+}
+
+/// Returns the seed of an image, if its SD parameters contain one.
+pub fn seed_text(loaded: &image_loader::LoadedImageData) -> Option<String> {
+    loaded
+        .sd_parameters
+        .as_ref()
+        .and_then(|params| params.seed.clone())
+        .filter(|seed| !seed.is_empty())
+}
+
+/// Returns the file name of an image without its directory, if it has one.
+pub fn file_name_text(loaded: &image_loader::LoadedImageData) -> Option<String> {
+    Some(loaded.file_name.clone()).filter(|file_name| !file_name.is_empty())
+}
+
+/// Serializes the parsed SD parameters of an image as pretty-printed JSON.
+///
+/// Returns `None` for images without parsed parameters.
+pub fn parameters_json(loaded: &image_loader::LoadedImageData) -> Option<String> {
+    let params = loaded.sd_parameters.as_ref()?;
+    serde_json::to_string_pretty(params)
+        .map_err(|e| log::error!("Failed to serialize SD parameters: {}", e))
+        .ok()
+}
+
+/// Formats the pixel dimensions and file size of an image, e.g. "3840×2160, 11.8 MB".
+pub fn format_dimensions(loaded: &image_loader::LoadedImageData) -> String {
+    format!(
+        "{}×{}, {}",
+        loaded.width,
+        loaded.height,
+        image_loader::format_file_size_short(loaded.file_size)
+    )
+}
+
+/// Formats all visible metadata of an image as a block of labeled lines.
+///
+/// Each line is `Label: value`, so the output stays stable and greppable.
+pub fn format_metadata_text(loaded: &image_loader::LoadedImageData) -> String {
+    let rating = loaded
+        .rating
+        .map(|r| r.to_string())
+        .unwrap_or_else(|| "None".to_string());
+
+    let mut lines = vec![
+        format!("Filename: {}", loaded.file_name),
+        format!("Resolution: {} x {}", loaded.width, loaded.height),
+        format!("Size: {}", loaded.file_size_formatted),
+        format!("Created: {}", loaded.created_date),
+        format!("Modified: {}", loaded.modified_date),
+        format!("Rating: {}", rating),
+    ];
+
+    if !loaded.keywords.is_empty() {
+        lines.push(format!("Keywords: {}", loaded.keywords.join(", ")));
+    }
+
+    if let Some(params) = &loaded.sd_parameters {
+        lines.push(format!(
+            "Positive prompt: {}",
+            format_tags(&params.positive_sd_tags)
+        ));
+        lines.push(format!(
+            "Negative prompt: {}",
+            format_tags(&params.negative_sd_tags)
+        ));
+        for (key, value) in format_sd_parameters(params) {
+            lines.push(format!("{}: {}", key, value));
+        }
+    } else if let Some(failure) = &loaded.sd_parse_error {
+        lines.push(format!("Parameters (unparsed): {}", failure.raw));
+    }
+
+    lines.join("\n")
+}
+
+/// Formats an image as Markdown for posting: an image link followed by its
+/// generation parameters in a fenced code block.
+///
+/// The link is relative to `base` when the image is inside it, and absolute
+/// otherwise. The code block holds the parameters text as written by the tool,
+/// and is left out for images without parameters.
+pub fn format_markdown(
+    loaded: &image_loader::LoadedImageData,
+    path: &Path,
+    base: Option<&Path>,
+) -> String {
+    let link = base
+        .and_then(|base| path.strip_prefix(base).ok())
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace(std::path::MAIN_SEPARATOR, "/");
+    let alt = loaded.file_name.replace('[', "\\[").replace(']', "\\]");
+    // Angle brackets allow spaces and parentheses in the link
+    let mut markdown = format!("![{}](<{}>)", alt, link);
+
+    let parameters = loaded
+        .sd_parameters
+        .as_ref()
+        .map(|params| params.raw.as_str())
+        .or_else(|| {
+            loaded
+                .sd_parse_error
+                .as_ref()
+                .map(|failure| failure.raw.as_str())
+        })
+        .map(str::trim)
+        .filter(|raw| !raw.is_empty());
+
+    if let Some(parameters) = parameters {
+        // The fence must be longer than any run of backticks in the text
+        let longest_run = parameters
+            .split(|c| c != '`')
+            .map(str::len)
+            .max()
+            .unwrap_or(0);
+        let fence = "`".repeat((longest_run + 1).max(3));
+        markdown.push_str(&format!("\n\n{}text\n{}\n{}", fence, parameters, fence));
+    }
+
+    markdown
+}
+
+/// Formats SD tags into a comma-separated string with weights.
+///
+/// Formats every tag, so it is meant for copy operations rather than display.
+pub fn format_tags(tags: &[SdTag]) -> String {
+    tags.iter().map(format_tag).collect::<Vec<_>>().join(", ")
+}
+
+/// Formats at most `limit` SD tags, appending "… +N more" for the rest.
+///
+/// Keeps navigation responsive for prompts with thousands of tags.
+fn format_tags_capped(tags: &[SdTag], limit: usize) -> String {
+    let formatted = tags[..tags.len().min(limit)]
+        .iter()
+        .map(format_tag)
         .collect::<Vec<_>>()
-        .join(", ")
+        .join(", ");
+
+    match tags.len().saturating_sub(limit) {
+        0 => formatted,
+        remaining => format!("{} … +{} more", formatted, remaining),
+    }
+}
+
+/// Shortens `text` to at most `max_chars` characters and wraps it into lines
+/// of at most `line_width` characters, for the prompt overlay on the image.
+///
+/// Lines break only between words, and shortened text ends at a word with "…",
+/// which counts toward `max_chars`. Words longer than a line are kept whole on
+/// their own line; a first word that does not fit is cut.
+pub fn wrap_overlay_text(text: &str, max_chars: usize, line_width: usize) -> String {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let full_len = words.iter().map(|word| word.chars().count()).sum::<usize>()
+        + words.len().saturating_sub(1);
+    // Shortened text keeps one character for the ellipsis
+    let budget = if full_len > max_chars {
+        max_chars.saturating_sub(1)
+    } else {
+        max_chars
+    };
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    let mut line_len = 0;
+    let mut used = 0;
+    let mut truncated = false;
+
+    for word in words {
+        let word_len = word.chars().count();
+        let separator = usize::from(used > 0);
+        if used + separator + word_len > budget {
+            truncated = true;
+            if used == 0 {
+                line = word.chars().take(budget).collect();
+            }
+            break;
+        }
+        used += separator + word_len;
+
+        if line_len > 0 && line_len + 1 + word_len > line_width {
+            lines.push(std::mem::take(&mut line));
+            line_len = 0;
+        }
+        if line_len > 0 {
+            line.push(' ');
+            line_len += 1;
+        }
+        line.push_str(word);
+        line_len += word_len;
+    }
+
+    if truncated {
+        line.push('…');
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+/// Formats a single SD tag with its weight.
+fn format_tag(tag: &SdTag) -> String {
+    if let Some(weight) = tag.weight {
+        format!("({}:{})", tag.name, weight)
+    } else {
+        tag.name.clone()
+    }
 }
 
 /// Formats SD parameters into key-value pairs for the table.
 fn format_sd_parameters(params: &SdParameters) -> Vec<(slint::SharedString, slint::SharedString)> {
-    let mut result = Vec::new();
+    let mut result = vec![("Source".into(), params.source.label().into())];
 
     if let Some(ref steps) = params.steps {
         result.push(("Steps".into(), steps.clone().into()));
@@ -127,6 +783,9 @@ fn format_sd_parameters(params: &SdParameters) -> Vec<(slint::SharedString, slin
     if let Some(ref model) = params.model {
         result.push(("Model".into(), model.clone().into()));
     }
+    if let Some(ref model_hash) = params.model_hash {
+        result.push(("Model hash".into(), model_hash.clone().into()));
+    }
     if let Some(ref denoising_strength) = params.denoising_strength {
         result.push((
             "Denoising strength".into(),
@@ -136,10 +795,41 @@ fn format_sd_parameters(params: &SdParameters) -> Vec<(slint::SharedString, slin
     if let Some(ref clip_skip) = params.clip_skip {
         result.push(("Clip skip".into(), clip_skip.clone().into()));
     }
+    if let Some(ref vae) = params.vae {
+        result.push(("VAE".into(), vae.clone().into()));
+    }
+    if !params.loras.is_empty() {
+        result.push(("LoRA".into(), params.loras.join(", ").into()));
+    }
 
     result
 }
 
+/// Reduces the image dimensions to the simplest aspect ratio (e.g. 1920x1080 -> 16:9).
+///
+/// Returns `(0, 0)` if either dimension is zero.
+pub fn aspect_ratio(width: u32, height: u32) -> (u32, u32) {
+    if width == 0 || height == 0 {
+        return (0, 0);
+    }
+
+    let divisor = gcd(width, height);
+    (width / divisor, height / divisor)
+}
+
+/// Returns the greatest common divisor using the Euclidean algorithm.
+fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Returns the pixel count in megapixels.
+pub fn megapixels(width: u32, height: u32) -> f32 {
+    (width as u64 * height as u64) as f32 / 1_000_000.0
+}
+
 /// Helper function to load an image in a background thread and update UI.
 ///
 /// This function:
@@ -147,30 +837,28 @@ fn format_sd_parameters(params: &SdParameters) -> Vec<(slint::SharedString, slin
 /// 2. If cache miss, spawns a rayon thread to decode the image (CPU-intensive)
 /// 3. Uses invoke_from_event_loop to return to the UI thread
 /// 4. Updates ViewerState with the loaded image or error message
+#[allow(clippy::too_many_arguments)]
 pub fn load_and_display_image(
     ui: slint::Weak<crate::AppWindow>,
     path: PathBuf,
     error_prefix: String,
     state: Arc<Mutex<NavigationState>>,
     cache: Arc<Mutex<ImageCache>>,
+    rating_index: Arc<Mutex<RatingIndex>>,
     display_tracker: crate::ui::DisplayTracker,
+    load_generation: LoadGeneration,
 ) {
-    // Check cache first
-    let cached = cache.lock().ok().and_then(|mut c| c.get(&path));
+    // Newer requests make results of older in-flight loads stale
+    let generation = load_generation.next();
 
-    if let Some(cached_image) = cached {
+    // Check cache first
+    if let (ImageSource::Cache, Some(cached_image)) = lookup_for_display(&cache, &path) {
         // Cache hit - display immediately
         if let Some(ui) = ui.upgrade() {
-            let image = image_loader::create_slint_image(
-                &cached_image.data,
-                cached_image.width,
-                cached_image.height,
-            );
-
-            update_ui_state(&ui, image, &cached_image, &state);
+            update_ui_with_image(&ui, &cached_image, &state, ImageSource::Cache);
 
             // Trigger preload even on cache hit
-            preload_adjacent_images(state, cache, display_tracker);
+            preload_adjacent_images(state, cache, rating_index, display_tracker);
         }
         return;
     }
@@ -179,10 +867,9 @@ pub fn load_and_display_image(
     let cache_clone = cache.clone();
     let state_clone = state.clone();
     let display_tracker_clone = display_tracker.clone();
-    rayon::spawn(move || {
+    spawn_decode(move || {
         let screen_id = display_tracker_clone.current_display_id();
-        let result = image_loader::load_image_with_metadata(&path, screen_id)
-            .map_err(|e| format!("Failed to load image: {}", e));
+        let result = image_loader::load_image_with_metadata(&path, screen_id, &rating_index);
 
         let _ = slint::invoke_from_event_loop(move || {
             if let Some(ui) = ui.upgrade() {
@@ -197,78 +884,919 @@ pub fn load_and_display_image(
                             _ => None,
                         };
 
+                        if !load_generation.is_current(generation) {
+                            log::debug!("Discarding stale image load: {:?}", path);
+                            return;
+                        }
+
                         if let Some(cached) = cached_ref {
-                            update_ui_with_image(&ui, &cached, &state_clone);
+                            update_ui_with_image(&ui, &cached, &state_clone, ImageSource::Disk);
                         }
 
                         // Trigger preload after successful display
-                        preload_adjacent_images(state_clone, cache_clone, display_tracker_clone);
+                        preload_adjacent_images(
+                            state_clone,
+                            cache_clone,
+                            rating_index,
+                            display_tracker_clone,
+                        );
+                    }
+                    Err(_) if !load_generation.is_current(generation) => {
+                        log::debug!("Discarding stale image load error: {:?}", path);
+                    }
+                    Err(AppError::FileNotFound(_)) if is_current(&state_clone, &path) => {
+                        skip_missing_image(
+                            ui.as_weak(),
+                            path,
+                            error_prefix,
+                            state_clone,
+                            cache_clone,
+                            rating_index,
+                            display_tracker_clone,
+                            load_generation,
+                        );
+                    }
+                    Err(error) => {
+                        if error.is_transient() {
+                            // Not cached, so the next change event or navigation retries
+                            log::warn!("Image incomplete, will retry later: {:?}", path);
+                        }
+                        update_ui_with_error(
+                            &ui,
+                            &error_prefix,
+                            format!("Failed to load image: {}", error),
+                        );
                     }
-                    Err(error) => update_ui_with_error(&ui, &error_prefix, error),
                 }
             }
         });
     });
 }
 
+/// Returns whether `path` is the currently selected image.
+fn is_current(state: &Arc<Mutex<NavigationState>>, path: &PathBuf) -> bool {
+    lock_or_recover(state).current_path().as_ref() == Some(path)
+}
+
+/// Runs `f` on the cached data of the currently selected image and returns its result.
+///
+/// Features that need the current pixels or metadata go through this instead of
+/// reaching into the cache. `f` runs under the cache lock, so it should take only
+/// what it needs instead of cloning the whole image. Nothing is decoded: `None`
+/// is returned on a cache miss or when no image is selected.
+pub fn with_current_loaded<R>(
+    state: &Arc<Mutex<NavigationState>>,
+    cache: &Arc<Mutex<ImageCache>>,
+    f: impl FnOnce(&image_loader::LoadedImageData) -> R,
+) -> Option<R> {
+    let path = lock_or_recover(state).current_path()?;
+    lock_or_recover(cache).peek(&path).map(f)
+}
+
+/// Returns a copy of the cached data of the currently selected image.
+///
+/// For features that hand the whole image, such as its raw pixels, to another
+/// thread. Use [`with_current_loaded`] to take only a part of it. Nothing is
+/// decoded: `None` is returned on a cache miss or when no image is selected.
+pub fn current_loaded_image(
+    state: &Arc<Mutex<NavigationState>>,
+    cache: &Arc<Mutex<ImageCache>>,
+) -> Option<image_loader::LoadedImageData> {
+    with_current_loaded(state, cache, Clone::clone)
+}
+
+/// Closes the current image, leaving the view empty.
+///
+/// Stops any running animation and makes in-flight loads stale, so nothing
+/// replaces the empty view until the next image is opened.
+pub fn close_image(
+    ui: &crate::AppWindow,
+    state: &Arc<Mutex<NavigationState>>,
+    load_generation: &LoadGeneration,
+) {
+    stop_animation();
+    ALPHA_PLANE.with(|plane| plane.borrow_mut().take());
+    load_generation.next();
+    crate::ui::clear_display(ui, &mut lock_or_recover(state));
+}
+
+/// Drops an image deleted since the directory scan and shows the one that took its place.
+///
+/// The view is closed when no image is left.
+#[allow(clippy::too_many_arguments)]
+fn skip_missing_image(
+    ui: slint::Weak<crate::AppWindow>,
+    path: PathBuf,
+    error_prefix: String,
+    state: Arc<Mutex<NavigationState>>,
+    cache: Arc<Mutex<ImageCache>>,
+    rating_index: Arc<Mutex<RatingIndex>>,
+    display_tracker: crate::ui::DisplayTracker,
+    load_generation: LoadGeneration,
+) {
+    let result = lock_or_recover(&state).remove_missing(&path);
+    let Some(ui_strong) = ui.upgrade() else {
+        return;
+    };
+
+    match result {
+        Ok(next) => {
+            log::warn!("Skipping deleted image: {:?}", path);
+            let file_name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            ui_strong
+                .global::<crate::ViewerState>()
+                .set_status_message(format!("Skipped missing file: {}", file_name).into());
+            load_and_display_image(
+                ui,
+                next,
+                error_prefix,
+                state,
+                cache,
+                rating_index,
+                display_tracker,
+                load_generation,
+            );
+        }
+        Err(NavigationError::NoImages) => {
+            log::warn!("Last image was deleted: {:?}", path);
+            close_image(&ui_strong, &state, &load_generation);
+            ui_strong
+                .global::<crate::ViewerState>()
+                .set_status_message("No images left".into());
+        }
+        Err(e) => {
+            update_ui_with_error(&ui_strong, &error_prefix, e.to_string());
+        }
+    }
+}
+
 /// Preloads adjacent images (next and previous) in the background.
+///
+/// Looks `preload_radius` images ahead and behind, nearest first.
+/// Does nothing when the `preload_enabled` setting is off.
 fn preload_adjacent_images(
     state: Arc<Mutex<NavigationState>>,
     cache: Arc<Mutex<ImageCache>>,
+    rating_index: Arc<Mutex<RatingIndex>>,
     display_tracker: crate::ui::DisplayTracker,
 ) {
-    let (next_path, prev_path) = {
-        match state.lock() {
-            Ok(nav_state) => (nav_state.peek_next_image(), nav_state.peek_prev_image()),
-            _ => {
-                return;
+    preload_adjacent_with(
+        &crate::settings::current(),
+        state,
+        cache,
+        rating_index,
+        display_tracker,
+    );
+}
+
+/// Preloads adjacent images as [`preload_adjacent_images`] does, with the given settings.
+fn preload_adjacent_with(
+    settings: &crate::settings::AppSettings,
+    state: Arc<Mutex<NavigationState>>,
+    cache: Arc<Mutex<ImageCache>>,
+    rating_index: Arc<Mutex<RatingIndex>>,
+    display_tracker: crate::ui::DisplayTracker,
+) {
+    // On slow disks neighbor decodes compete with the image being shown
+    if !settings.preload_enabled {
+        log::debug!("Preloading is disabled");
+        return;
+    }
+    let radius = settings.preload_radius.min(config::MAX_PRELOAD_RADIUS);
+
+    // Keep the whole preload window cached even if the settings changed
+    let capacity = crate::image_cache::capacity_for(settings.cache_mode, radius);
+    lock_or_recover(&cache).resize(capacity);
+    let radius = radius as isize;
+
+    let paths: Vec<PathBuf> = {
+        let nav_state = lock_or_recover(&state);
+        let current = nav_state.current_path();
+        let mut paths: Vec<PathBuf> = Vec::new();
+        for path in (1..=radius)
+            .flat_map(|distance| [distance, -distance])
+            .filter_map(|delta| nav_state.peek_offset(delta))
+        {
+            if Some(&path) != current.as_ref() && !paths.contains(&path) {
+                paths.push(path);
             }
         }
+        paths
     };
 
-    // Preload next image if not in cache
-    if let Some(path) = next_path {
-        let should_load = cache
-            .lock()
-            .ok()
-            .map(|mut c| !c.contains(&path))
-            .unwrap_or(false);
+    // Preload each neighbor if not in cache
+    for path in paths {
+        if lock_or_recover(&cache).peek(&path).is_some() {
+            continue;
+        }
 
-        if should_load {
-            let cache_clone = cache.clone();
-            let display_tracker_clone = display_tracker.clone();
-            rayon::spawn(move || {
-                let screen_id = display_tracker_clone.current_display_id();
-                // Silently ignore errors during preload
-                if let Ok(loaded) = image_loader::load_image_with_metadata(&path, screen_id) {
-                    if let Ok(mut cache) = cache_clone.lock() {
-                        cache.put(path, loaded);
-                    }
-                }
-            });
+        let cache_clone = cache.clone();
+        let rating_index = rating_index.clone();
+        let display_tracker_clone = display_tracker.clone();
+        spawn_decode(move || {
+            let screen_id = display_tracker_clone.current_display_id();
+            // Silently ignore errors during preload
+            if let Ok(loaded) =
+                image_loader::load_image_with_metadata(&path, screen_id, &rating_index)
+            {
+                lock_or_recover(&cache_clone).put(path, loaded);
+            }
+        });
+    }
+}
+
+/// Reads the ratings of the whole current directory into the rating index in the
+/// background, if the `prescan_ratings` setting is enabled.
+///
+/// Navigation is not blocked. The prescan is cancelled when the directory
+/// changes or another prescan is started with `generation`.
+/// Progress is reported to the `rating-prescan-progress` property.
+pub fn start_rating_prescan(
+    ui_handle: slint::Weak<crate::AppWindow>,
+    state: Arc<Mutex<NavigationState>>,
+    rating_index: Arc<Mutex<RatingIndex>>,
+    generation: LoadGeneration,
+) {
+    if !crate::settings::current().prescan_ratings {
+        return;
+    }
+
+    let run = generation.next();
+    rayon::spawn(move || {
+        let service = NavigationService::new(state, rating_index);
+        let report = |done: usize, total: usize| {
+            if done.is_multiple_of(config::RATING_PRESCAN_PROGRESS_STEP) {
+                crate::ui::set_ui_rating_prescan_progress(
+                    &ui_handle,
+                    format!("{}/{}", done, total),
+                );
+            }
+        };
+
+        let progress = match service.prescan_ratings(&generation, run, report) {
+            Some(read) => format!("{} done", read),
+            // A newer prescan reports its own progress
+            None if !generation.is_current(run) => return,
+            None => "Cancelled".to_string(),
+        };
+        log::info!("Rating prescan finished: {}", progress);
+        crate::ui::set_ui_rating_prescan_progress(&ui_handle, progress);
+        crate::ui::set_ui_rating_summary(&ui_handle, service.rating_summary().label());
+    });
+}
+
+/// Recomputes the rating summary of the current list in the background.
+///
+/// Only ratings already in the index are counted, see `NavigationService::rating_summary`.
+pub fn refresh_rating_summary(
+    ui_handle: slint::Weak<crate::AppWindow>,
+    state: Arc<Mutex<NavigationState>>,
+    rating_index: Arc<Mutex<RatingIndex>>,
+) {
+    rayon::spawn(move || {
+        let summary = NavigationService::new(state, rating_index).rating_summary();
+        crate::ui::set_ui_rating_summary(&ui_handle, summary.label());
+    });
+}
+
+/// Drops the cached images, which were converted for the old display profile.
+///
+/// Returns the current image, which has to be decoded again.
+pub fn invalidate_color_managed_images(
+    state: &Mutex<NavigationState>,
+    cache: &Mutex<ImageCache>,
+) -> Option<PathBuf> {
+    lock_or_recover(cache).clear();
+    lock_or_recover(state).current_path()
+}
+
+/// Updates the rating summary in the background after the rating of `path` was set.
+///
+/// Only the changed image is counted again, see `NavigationService::update_rating_summary`.
+pub fn update_rating_summary(
+    ui_handle: slint::Weak<crate::AppWindow>,
+    state: Arc<Mutex<NavigationState>>,
+    rating_index: Arc<Mutex<RatingIndex>>,
+    path: PathBuf,
+    rating: Option<u8>,
+) {
+    rayon::spawn(move || {
+        let summary =
+            NavigationService::new(state, rating_index).update_rating_summary(&path, rating);
+        crate::ui::set_ui_rating_summary(&ui_handle, summary.label());
+    });
+}
+
+/// Why preloading the whole directory stopped early.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PreloadStop {
+    /// The cache has no room left without evicting images.
+    CacheFull,
+    /// The directory changed or a newer preload was started.
+    Cancelled,
+}
+
+/// Decodes every image of the current directory into the cache.
+///
+/// Images nearest to the current one are loaded first. Stops once the cache
+/// is full instead of evicting images, and is cancelled
+/// when the directory changes or another preload is started with `generation`.
+/// Progress is reported to the `preload-progress` property.
+pub fn preload_all_images(
+    ui_handle: slint::Weak<crate::AppWindow>,
+    state: Arc<Mutex<NavigationState>>,
+    cache: Arc<Mutex<ImageCache>>,
+    rating_index: Arc<Mutex<RatingIndex>>,
+    display_tracker: crate::ui::DisplayTracker,
+    generation: LoadGeneration,
+) {
+    let run = generation.next();
+    let (directory, current, mut indexed) = {
+        let nav_state = lock_or_recover(&state);
+        (
+            nav_state.get_current_directory(),
+            nav_state.current_index(),
+            nav_state.indexed_files().collect::<Vec<_>>(),
+        )
+    };
+    // Nearest images first, so a full cache holds the neighbors of the current image
+    if let Some(current) = current {
+        indexed.sort_by_key(|(index, _)| index.abs_diff(current));
+    }
+    let files: Vec<PathBuf> = indexed.into_iter().map(|(_, path)| path).collect();
+    let Some(directory) = directory else {
+        log::warn!("No directory to preload");
+        return;
+    };
+
+    rayon::spawn(move || {
+        let total = files.len();
+        let done = AtomicUsize::new(0);
+        let is_cancelled = || preload_outdated(&generation, run, &state, &directory);
+
+        let preload_one = |path: &PathBuf| -> Result<(), PreloadStop> {
+            preload_into(&cache, path, is_cancelled, || {
+                let screen_id = display_tracker.current_display_id();
+                image_loader::load_image_with_metadata(path, screen_id, &rating_index)
+            })?;
+
+            let done = done.fetch_add(1, Ordering::SeqCst) + 1;
+            crate::ui::set_ui_preload_progress(&ui_handle, format!("{}/{}", done, total));
+            Ok(())
+        };
+
+        // Shares the decode pool with navigation, so max_concurrent_decodes bounds both
+        let result = match DECODE_POOL.as_ref() {
+            Some(pool) => pool.install(|| files.par_iter().try_for_each(preload_one)),
+            None => files.iter().try_for_each(preload_one),
+        };
+
+        let done = done.load(Ordering::SeqCst);
+        let progress = match result {
+            Ok(()) => format!("{}/{} done", done, total),
+            Err(PreloadStop::CacheFull) => format!("{}/{} (cache full)", done, total),
+            // A newer preload reports its own progress
+            Err(PreloadStop::Cancelled) if !generation.is_current(run) => return,
+            Err(PreloadStop::Cancelled) => format!("{}/{} (cancelled)", done, total),
+        };
+        log::info!("Preload of {:?} finished: {}", directory, progress);
+        crate::ui::set_ui_preload_progress(&ui_handle, progress);
+    });
+}
+
+/// Returns whether a preload of `directory` started as `run` should stop.
+///
+/// That is the case once another preload started or another directory was opened.
+fn preload_outdated(
+    generation: &LoadGeneration,
+    run: u64,
+    state: &Mutex<NavigationState>,
+    directory: &PathBuf,
+) -> bool {
+    !generation.is_current(run)
+        || lock_or_recover(state).get_current_directory().as_ref() != Some(directory)
+}
+
+/// Decodes one image of a whole-directory preload into the cache with `load`.
+///
+/// Already cached images are left alone. Fails instead of evicting an image
+/// when the cache is full, and drops the decoded image if cancelled meanwhile.
+/// Unloadable images are skipped.
+fn preload_into(
+    cache: &Mutex<ImageCache>,
+    path: &PathBuf,
+    is_cancelled: impl Fn() -> bool,
+    load: impl FnOnce() -> crate::error::Result<image_loader::LoadedImageData>,
+) -> Result<(), PreloadStop> {
+    if is_cancelled() {
+        return Err(PreloadStop::Cancelled);
+    }
+
+    {
+        let cache = lock_or_recover(cache);
+        if cache.peek(path).is_some() {
+            return Ok(());
+        }
+        if cache.is_full() {
+            return Err(PreloadStop::CacheFull);
         }
     }
 
-    // Preload previous image if not in cache
-    if let Some(path) = prev_path {
-        let should_load = cache
-            .lock()
-            .ok()
-            .map(|mut c| !c.contains(&path))
-            .unwrap_or(false);
+    match load() {
+        Ok(loaded) => {
+            if is_cancelled() {
+                return Err(PreloadStop::Cancelled);
+            }
+            // Checked again, other workers may have filled the cache meanwhile
+            let mut cache = lock_or_recover(cache);
+            if cache.is_full() {
+                return Err(PreloadStop::CacheFull);
+            }
+            cache.put(path.clone(), loaded);
+        }
+        Err(e) => log::debug!("Skipping unloadable image during preload: {}", e),
+    }
+    Ok(())
+}
 
-        if should_load {
-            let cache_clone = cache.clone();
-            let display_tracker_clone = display_tracker.clone();
-            rayon::spawn(move || {
-                let screen_id = display_tracker_clone.current_display_id();
-                // Silently ignore errors during preload
-                if let Ok(loaded) = image_loader::load_image_with_metadata(&path, screen_id) {
-                    if let Ok(mut cache) = cache_clone.lock() {
-                        cache.put(path, loaded);
-                    }
-                }
-            });
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_refresh_clears_cache() {
+        let path = PathBuf::from("a.png");
+        let state = Mutex::new(NavigationState::default());
+        lock_or_recover(&state)
+            .set_explicit_list(vec![path.clone()])
+            .unwrap();
+        let cache = Mutex::new(ImageCache::new(4));
+        lock_or_recover(&cache).put(
+            path.clone(),
+            image_loader::LoadedImageData::blank("a.png", 1, 1),
+        );
+
+        assert_eq!(
+            invalidate_color_managed_images(&state, &cache),
+            Some(path.clone())
+        );
+        assert!(lock_or_recover(&cache).peek(&path).is_none());
+    }
+
+    #[test]
+    fn metadata_text_has_labeled_lines() {
+        let mut loaded = image_loader::LoadedImageData::blank("cat.png", 832, 1216);
+        loaded.rating = Some(4);
+        loaded.keywords = vec!["cat".to_string(), "night".to_string()];
+        loaded.sd_parameters = Some(SdParameters {
+            positive_sd_tags: vec![SdTag {
+                name: "a cat".to_string(),
+                weight: None,
+            }],
+            negative_sd_tags: vec![SdTag {
+                name: "blurry".to_string(),
+                weight: None,
+            }],
+            steps: Some("28".to_string()),
+            seed: Some("42".to_string()),
+            ..SdParameters::default()
+        });
+
+        let text = format_metadata_text(&loaded);
+        for line in [
+            "Filename: cat.png",
+            "Resolution: 832 x 1216",
+            "Rating: 4",
+            "Keywords: cat, night",
+            "Positive prompt: a cat",
+            "Negative prompt: blurry",
+            "Steps: 28",
+            "Seed: 42",
+        ] {
+            assert!(text.lines().any(|l| l == line), "{}", line);
         }
+        assert!(!text.contains("Parameters (unparsed)"));
+    }
+
+    #[test]
+    fn seed_text_present_and_absent() {
+        let mut loaded = image_loader::LoadedImageData::blank("a.png", 1, 1);
+        assert_eq!(seed_text(&loaded), None);
+
+        loaded.sd_parameters = Some(SdParameters::default());
+        assert_eq!(seed_text(&loaded), None);
+
+        loaded.sd_parameters = Some(SdParameters {
+            seed: Some("1234567890".to_string()),
+            ..SdParameters::default()
+        });
+        assert_eq!(seed_text(&loaded).as_deref(), Some("1234567890"));
+
+        loaded.sd_parameters = Some(SdParameters {
+            seed: Some(String::new()),
+            ..SdParameters::default()
+        });
+        assert_eq!(seed_text(&loaded), None);
+    }
+
+    fn tags(count: usize) -> Vec<SdTag> {
+        (0..count)
+            .map(|i| SdTag {
+                name: format!("tag{}", i),
+                weight: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tags_under_cap_are_all_formatted() {
+        assert_eq!(format_tags_capped(&tags(3), 3), "tag0, tag1, tag2");
+        assert_eq!(format_tags_capped(&tags(3), 3), format_tags(&tags(3)));
+    }
+
+    #[test]
+    fn tags_over_cap_report_remaining_count() {
+        let text = format_tags_capped(&tags(10_000), 2);
+        assert_eq!(text, "tag0, tag1 … +9998 more");
+    }
+
+    #[test]
+    fn aspect_ratio_reduces_by_gcd() {
+        assert_eq!(aspect_ratio(1920, 1080), (16, 9));
+        assert_eq!(aspect_ratio(832, 1216), (13, 19));
+        assert_eq!(aspect_ratio(1024, 1024), (1, 1));
+        assert_eq!(aspect_ratio(1021, 1019), (1021, 1019));
+    }
+
+    #[test]
+    fn aspect_ratio_guards_zero_dimensions() {
+        assert_eq!(aspect_ratio(0, 1080), (0, 0));
+        assert_eq!(aspect_ratio(1920, 0), (0, 0));
+        assert_eq!(megapixels(0, 1080), 0.0);
+    }
+
+    #[test]
+    fn megapixels_counts_pixels() {
+        assert_eq!(megapixels(1000, 1000), 1.0);
+        assert!((megapixels(832, 1216) - 1.011712).abs() < 1e-6);
+    }
+
+    #[test]
+    fn prompt_panel_is_skipped_while_hidden() {
+        let mut panel = PromptPanelSync::default();
+        assert!(panel.on_display(false));
+        assert!(!panel.on_reveal());
+
+        assert!(!panel.on_display(true));
+        assert!(!panel.on_display(true));
+        assert!(panel.on_reveal());
+        assert!(!panel.on_reveal());
+
+        // An image shown after chrome came back needs no extra refresh
+        assert!(!panel.on_display(true));
+        assert!(panel.on_display(false));
+        assert!(!panel.on_reveal());
+    }
+
+    #[test]
+    fn image_source_follows_cache_lookup() {
+        let cache = Mutex::new(ImageCache::new(4));
+        let path = PathBuf::from("a.png");
+
+        let (source, loaded) = lookup_for_display(&cache, &path);
+        assert_eq!(source, ImageSource::Disk);
+        assert!(loaded.is_none());
+
+        lock_or_recover(&cache).put(
+            path.clone(),
+            image_loader::LoadedImageData::blank("a.png", 1, 1),
+        );
+        let (source, loaded) = lookup_for_display(&cache, &path);
+        assert_eq!(source, ImageSource::Cache);
+        assert_eq!(loaded.unwrap().file_name, "a.png");
+        assert_eq!(source.label(), "cached");
+        assert_eq!(ImageSource::Disk.label(), "loaded");
+    }
+
+    #[test]
+    fn preload_stops_when_cache_is_full() {
+        let cache = Mutex::new(ImageCache::new(1));
+        let blank = |name: &str| Ok(image_loader::LoadedImageData::blank(name, 1, 1));
+        let (a, b) = (PathBuf::from("a.png"), PathBuf::from("b.png"));
+
+        assert_eq!(
+            preload_into(&cache, &a, || false, || blank("a.png")),
+            Ok(())
+        );
+        // Cached images don't count against the room left
+        assert_eq!(
+            preload_into(&cache, &a, || false, || panic!("decoded again")),
+            Ok(())
+        );
+        assert_eq!(
+            preload_into(&cache, &b, || false, || panic!("decoded into a full cache")),
+            Err(PreloadStop::CacheFull)
+        );
+        assert!(lock_or_recover(&cache).peek(&a).is_some());
+    }
+
+    #[test]
+    fn preload_is_cancelled_by_directory_change() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["a", "b"] {
+            std::fs::create_dir(dir.path().join(name)).unwrap();
+            std::fs::write(dir.path().join(name).join("1.png"), b"").unwrap();
+        }
+        let state = Mutex::new(NavigationState::default());
+        lock_or_recover(&state)
+            .update_directory(dir.path().join("a").join("1.png"))
+            .unwrap();
+        let generation = LoadGeneration::new();
+        let run = generation.next();
+        let directory = dir.path().join("a");
+        assert!(!preload_outdated(&generation, run, &state, &directory));
+
+        let cache = Mutex::new(ImageCache::new(4));
+        let path = directory.join("1.png");
+        let cancelled = || preload_outdated(&generation, run, &state, &directory);
+        let result = preload_into(&cache, &path, cancelled, || {
+            // The user opens another directory while the image decodes
+            lock_or_recover(&state)
+                .update_directory(dir.path().join("b").join("1.png"))
+                .unwrap();
+            Ok(image_loader::LoadedImageData::blank("1.png", 1, 1))
+        });
+        assert_eq!(result, Err(PreloadStop::Cancelled));
+        assert!(lock_or_recover(&cache).peek(&path).is_none());
+
+        lock_or_recover(&state)
+            .update_directory(dir.path().join("a").join("1.png"))
+            .unwrap();
+        assert!(!preload_outdated(&generation, run, &state, &directory));
+        generation.next();
+        assert!(preload_outdated(&generation, run, &state, &directory));
+    }
+
+    #[test]
+    fn file_name_comes_from_the_cached_current_image() {
+        let path = PathBuf::from("dir/cat.png");
+        let state = Arc::new(Mutex::new(NavigationState::default()));
+        lock_or_recover(&state)
+            .set_explicit_list(vec![path.clone()])
+            .unwrap();
+        let cache = Arc::new(Mutex::new(ImageCache::new(4)));
+        assert_eq!(with_current_loaded(&state, &cache, file_name_text), None);
+
+        lock_or_recover(&cache).put(
+            path.clone(),
+            image_loader::LoadedImageData::blank("cat.png", 1, 1),
+        );
+        assert_eq!(
+            with_current_loaded(&state, &cache, file_name_text).flatten(),
+            Some("cat.png".to_string())
+        );
+
+        let unnamed = image_loader::LoadedImageData::blank("", 1, 1);
+        assert_eq!(file_name_text(&unnamed), None);
+    }
+
+    #[test]
+    fn current_loaded_image_hits_only_the_cached_current_image() {
+        let (a, b) = (PathBuf::from("a.png"), PathBuf::from("b.png"));
+        let state = Arc::new(Mutex::new(NavigationState::default()));
+        let cache = Arc::new(Mutex::new(ImageCache::new(4)));
+        assert!(current_loaded_image(&state, &cache).is_none());
+
+        lock_or_recover(&state)
+            .set_explicit_list(vec![a.clone(), b.clone()])
+            .unwrap();
+        lock_or_recover(&cache).put(
+            b.clone(),
+            image_loader::LoadedImageData::blank("b.png", 2, 3),
+        );
+        // Miss: "a.png" is current but not cached, and nothing is decoded
+        assert!(current_loaded_image(&state, &cache).is_none());
+        assert!(lock_or_recover(&cache).peek(&a).is_none());
+
+        lock_or_recover(&state).set_current_by_path(&b).unwrap();
+        let loaded = current_loaded_image(&state, &cache).expect("cached current image");
+        assert_eq!(
+            (loaded.file_name.as_str(), loaded.width, loaded.height),
+            ("b.png", 2, 3)
+        );
+        assert_eq!(loaded.data.len(), 2 * 3 * 3);
+    }
+
+    #[test]
+    fn decode_pool_bounds_concurrent_tasks() {
+        for max_decodes in [0, 1, 3] {
+            let pool = build_decode_pool(max_decodes).unwrap();
+            let running = Arc::new(AtomicUsize::new(0));
+            let peak = Arc::new(AtomicUsize::new(0));
+            let (done, finished) = std::sync::mpsc::channel();
+
+            for _ in 0..24 {
+                let (running, peak, done) = (running.clone(), peak.clone(), done.clone());
+                pool.spawn(move || {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(Duration::from_millis(2));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    done.send(()).unwrap();
+                });
+            }
+            for _ in 0..24 {
+                finished.recv_timeout(Duration::from_secs(10)).unwrap();
+            }
+
+            let peak = peak.load(Ordering::SeqCst);
+            assert!(
+                peak >= 1 && peak <= max_decodes.max(1),
+                "{} tasks ran at once",
+                peak
+            );
+        }
+    }
+
+    /// Creates a list of three PNGs and navigates to the middle one.
+    fn navigated_list() -> (tempfile::TempDir, Vec<PathBuf>, Arc<Mutex<NavigationState>>) {
+        let dir = tempfile::tempdir().unwrap();
+        let files: Vec<PathBuf> = (0..3)
+            .map(|i| {
+                let path = dir.path().join(format!("{}.png", i));
+                image::save_buffer(&path, &[0u8; 3], 1, 1, image::ExtendedColorType::Rgb8).unwrap();
+                path
+            })
+            .collect();
+        let mut nav_state = NavigationState::default();
+        nav_state.set_explicit_list(files.clone()).unwrap();
+        nav_state.navigate_next().unwrap();
+        (dir, files, Arc::new(Mutex::new(nav_state)))
+    }
+
+    /// Preloads the neighbors of the current image and waits up to a few seconds for them.
+    fn preload_and_wait(
+        settings: &crate::settings::AppSettings,
+        state: &Arc<Mutex<NavigationState>>,
+        cache: &Arc<Mutex<ImageCache>>,
+        neighbors: &[PathBuf],
+    ) {
+        preload_adjacent_with(
+            settings,
+            state.clone(),
+            cache.clone(),
+            Arc::new(Mutex::new(RatingIndex::new())),
+            crate::ui::DisplayTracker::new(),
+        );
+        let cached = || {
+            let cache = lock_or_recover(cache);
+            neighbors.iter().all(|path| cache.peek(path).is_some())
+        };
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while !cached() && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn disabled_preload_leaves_neighbors_uncached() {
+        let (_dir, files, state) = navigated_list();
+        let cache = Arc::new(Mutex::new(ImageCache::new(4)));
+        let settings = crate::settings::AppSettings {
+            preload_enabled: false,
+            ..Default::default()
+        };
+
+        preload_and_wait(&settings, &state, &cache, &[]);
+        std::thread::sleep(Duration::from_millis(100));
+
+        let cache = lock_or_recover(&cache);
+        assert!(cache.peek(&files[0]).is_none());
+        assert!(cache.peek(&files[2]).is_none());
+    }
+
+    #[test]
+    fn enabled_preload_caches_neighbors() {
+        let (_dir, files, state) = navigated_list();
+        let cache = Arc::new(Mutex::new(ImageCache::new(4)));
+
+        preload_and_wait(
+            &Default::default(),
+            &state,
+            &cache,
+            &[files[0].clone(), files[2].clone()],
+        );
+
+        let cache = lock_or_recover(&cache);
+        assert!(cache.peek(&files[0]).is_some());
+        assert!(cache.peek(&files[2]).is_some());
+        assert!(cache.peek(&files[1]).is_none());
+    }
+
+    #[test]
+    fn markdown_links_image_and_fences_parameters() {
+        let mut loaded = image_loader::LoadedImageData::blank("cat [1].png", 1, 1);
+        loaded.sd_parameters = Some(SdParameters {
+            raw: "a cat, `night`\nNegative prompt: blurry\nSteps: 20".to_string(),
+            ..SdParameters::default()
+        });
+        let base = Path::new("/images");
+        let path = base.join("2024").join("cat [1].png");
+
+        assert_eq!(
+            format_markdown(&loaded, &path, Some(base)),
+            "![cat \\[1\\].png](<2024/cat [1].png>)\n\n\
+             ```text\na cat, `night`\nNegative prompt: blurry\nSteps: 20\n```"
+        );
+    }
+
+    #[test]
+    fn markdown_without_parameters_links_absolute_path() {
+        let loaded = image_loader::LoadedImageData::blank("cat.png", 1, 1);
+        let path = Path::new("/images/cat.png");
+
+        assert_eq!(
+            format_markdown(&loaded, path, Some(Path::new("/other"))),
+            "![cat.png](</images/cat.png>)"
+        );
+        assert_eq!(
+            format_markdown(&loaded, path, None),
+            "![cat.png](</images/cat.png>)"
+        );
+    }
+
+    #[test]
+    fn markdown_fence_is_longer_than_backtick_runs() {
+        let mut loaded = image_loader::LoadedImageData::blank("cat.png", 1, 1);
+        loaded.sd_parameters = Some(SdParameters {
+            raw: "a ```` cat".to_string(),
+            ..SdParameters::default()
+        });
+
+        let markdown = format_markdown(&loaded, Path::new("cat.png"), None);
+        assert!(markdown.ends_with("\n\n`````text\na ```` cat\n`````"));
+    }
+
+    #[test]
+    fn dimensions_text_has_resolution_and_size() {
+        let mut loaded = image_loader::LoadedImageData::blank("cat.png", 3840, 2160);
+        loaded.file_size = 12_345_678;
+        assert_eq!(format_dimensions(&loaded), "3840×2160, 11.8 MB");
+
+        loaded.file_size = 512;
+        assert_eq!(format_dimensions(&loaded), "3840×2160, 512 bytes");
+    }
+
+    #[test]
+    fn wrap_overlay_text_breaks_between_words() {
+        assert_eq!(wrap_overlay_text("a  blue\nsky", 100, 6), "a blue\nsky");
+    }
+
+    #[test]
+    fn wrap_overlay_text_keeps_long_words_whole() {
+        assert_eq!(wrap_overlay_text("a masterpiece", 100, 4), "a\nmasterpiece");
+    }
+
+    #[test]
+    fn wrap_overlay_text_ellipsis_stays_within_limit() {
+        assert_eq!(wrap_overlay_text("aaa bbb ccc", 10, 100), "aaa bbb…");
+        assert_eq!(wrap_overlay_text("aaa bbb", 7, 100), "aaa bbb");
+        assert_eq!(wrap_overlay_text("abcdefgh", 5, 100), "abcd…");
+    }
+
+    #[test]
+    fn wrap_overlay_text_of_blank_prompt_is_empty() {
+        assert_eq!(wrap_overlay_text("  \n ", 10, 10), "");
+    }
+
+    #[test]
+    fn parameters_json_has_tag_arrays_and_fields() {
+        let mut loaded = image_loader::LoadedImageData::blank("cat.png", 1, 1);
+        assert_eq!(parameters_json(&loaded), None);
+
+        loaded.sd_parameters = Some(
+            SdParameters::parse("a cat, (night:1.2)\nNegative prompt: blurry\nSteps: 20, Seed: 42")
+                .unwrap(),
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&parameters_json(&loaded).unwrap()).unwrap();
+
+        assert_eq!(
+            json["positive_sd_tags"],
+            serde_json::json!([
+                { "name": "a cat", "weight": null },
+                { "name": "night", "weight": 1.2 },
+            ])
+        );
+        assert_eq!(
+            json["negative_sd_tags"],
+            serde_json::json!([{ "name": "blurry", "weight": null }])
+        );
+        assert_eq!(json["steps"], "20");
+        assert_eq!(json["seed"], "42");
+        assert_eq!(json["source"], "Automatic1111");
     }
 }
@@ -5,9 +5,11 @@
 //! - `rayon::spawn`: CPU集約的処理（画像デコード、ディレクトリスキャンなど、別スレッドで実行可能な重い処理）
 //! - `slint::invoke_from_event_loop`: rayonからUIスレッドへの結果返却時に使用
 
+pub mod display_mode;
 pub mod display_tracker;
 pub mod handlers;
 pub mod image_display;
+mod ready_signal;
 mod state_helpers;
 
 pub use display_tracker::DisplayTracker;
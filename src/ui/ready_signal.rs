@@ -0,0 +1,49 @@
+//! One-shot "ready" signal for external drivers such as screenshot automation.
+//!
+//! When `SD_VIEWER_EMIT_READY=1` is set, `SD_VIEWER_READY` is printed to stdout
+//! once the first image has been displayed.
+
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Environment variable that enables the ready marker.
+const EMIT_READY_ENV_VAR: &str = "SD_VIEWER_EMIT_READY";
+
+/// Marker printed to stdout when the first image is displayed.
+const READY_MARKER: &str = "SD_VIEWER_READY";
+
+static READY_FIRED: AtomicBool = AtomicBool::new(false);
+
+/// Marks `fired` as set, returning `true` only for the first call.
+fn fire_once(fired: &AtomicBool) -> bool {
+    !fired.swap(true, Ordering::SeqCst)
+}
+
+/// Notifies that an image has been displayed.
+///
+/// Only the first call has an effect, so this can be called on every display.
+pub fn notify_image_displayed() {
+    if !fire_once(&READY_FIRED) {
+        return;
+    }
+
+    log::info!("First image displayed");
+    if std::env::var(EMIT_READY_ENV_VAR).is_ok_and(|value| value == "1") {
+        let mut stdout = std::io::stdout().lock();
+        let _ = writeln!(stdout, "{}", READY_MARKER);
+        let _ = stdout.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fires_only_once() {
+        let fired = AtomicBool::new(false);
+        assert!(fire_once(&fired));
+        assert!(!fire_once(&fired));
+        assert!(!fire_once(&fired));
+    }
+}
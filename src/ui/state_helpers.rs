@@ -3,13 +3,63 @@
 //! Instead of calling individual setters like set_current_filename, set_file_size_formatted, etc.,
 //! these functions group related properties together for better code organization and maintainability.
 
+use crate::state::NavigationState;
 use log::error;
 use slint::ComponentHandle;
+use std::cell::Cell;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+
+thread_local! {
+    /// Clears the error message once the `error_auto_hide_secs` setting elapses.
+    static ERROR_CLEAR_TIMER: slint::Timer = slint::Timer::default();
+    /// File names shown in the file list, shared with the UI.
+    static FILE_NAMES: Rc<slint::VecModel<slint::SharedString>> =
+        Rc::new(slint::VecModel::default());
+    /// List revision the file names were built from.
+    static FILE_NAMES_REVISION: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// Resets the view to the state before any image was opened and clears the current image.
+///
+/// Groups: image-loaded, dynamic-image, file information, prompts and
+/// parameters, keywords, rating, current-index / total-index. The
+/// auto-reload state and the file list are kept.
+pub fn clear_display(ui: &crate::AppWindow, nav_state: &mut NavigationState) {
+    nav_state.clear_current();
+
+    let viewer_state = ui.global::<crate::ViewerState>();
+    viewer_state.set_image_loaded(false);
+    viewer_state.set_dynamic_image(Default::default());
+    viewer_state.set_image_source("".into());
+    viewer_state.set_raw_preview(false);
+    viewer_state.set_current_filename("".into());
+    viewer_state.set_file_size_formatted("".into());
+    viewer_state.set_image_width(0);
+    viewer_state.set_image_height(0);
+    viewer_state.set_image_aspect_ratio("".into());
+    viewer_state.set_image_megapixels("".into());
+    viewer_state.set_file_created_date("".into());
+    viewer_state.set_file_modified_date("".into());
+    viewer_state.set_file_hash("".into());
+    viewer_state.set_color_warning("".into());
+    viewer_state.set_prompt_overlay_text("".into());
+    viewer_state.set_model_lookup_available(false);
+
+    clear_prompts_and_parameters(ui);
+    set_sd_parse_error(ui, None);
+    set_keywords(ui, &[]);
+    set_rating_info(ui, -1, false);
+    let auto_reload_active = viewer_state.get_auto_reload_active();
+    set_navigation_info(ui, -1, nav_state.image_count() as i32, auto_reload_active);
+    sync_file_list(ui, nav_state);
+}
 
 /// Sets all file information properties at once.
 ///
 /// Groups: current-filename, file-size-formatted, image-width, image-height,
-/// file-created-date, file-modified-date
+/// image-aspect-ratio, image-megapixels, file-created-date, file-modified-date
 pub fn set_file_info(
     ui: &crate::AppWindow,
     filename: &str,
@@ -24,6 +74,10 @@ pub fn set_file_info(
     viewer_state.set_file_size_formatted(file_size.into());
     viewer_state.set_image_width(width as i32);
     viewer_state.set_image_height(height as i32);
+    let (ratio_w, ratio_h) = crate::ui::image_display::aspect_ratio(width, height);
+    let megapixels = crate::ui::image_display::megapixels(width, height);
+    viewer_state.set_image_aspect_ratio(format!("{}:{}", ratio_w, ratio_h).into());
+    viewer_state.set_image_megapixels(format!("{:.1} MP", megapixels).into());
     viewer_state.set_file_created_date(created_date.into());
     viewer_state.set_file_modified_date(modified_date.into());
 }
@@ -50,30 +104,228 @@ pub fn clear_prompts_and_parameters(ui: &crate::AppWindow) {
     set_prompts_and_parameters(ui, "", "", vec![]);
 }
 
+/// Sets the SD parameters parse failure, or clears it with `None`.
+///
+/// Groups: sd-parse-error, sd-parse-error-raw
+pub fn set_sd_parse_error(
+    ui: &crate::AppWindow,
+    failure: Option<&crate::metadata::SdParseFailure>,
+) {
+    let viewer_state = ui.global::<crate::ViewerState>();
+    let (message, raw) = failure
+        .map(|f| (f.message.as_str(), f.raw.as_str()))
+        .unwrap_or_default();
+    viewer_state.set_sd_parse_error(message.into());
+    viewer_state.set_sd_parse_error_raw(raw.into());
+}
+
+/// Sets the pinned image name and the prompt diff rows against it.
+///
+/// Groups: prompt-pinned, prompt-diff
+pub fn set_prompt_diff(
+    ui: &crate::AppWindow,
+    pinned: &str,
+    rows: Vec<(slint::SharedString, slint::SharedString)>,
+) {
+    let viewer_state = ui.global::<crate::ViewerState>();
+    viewer_state.set_prompt_pinned(pinned.into());
+    viewer_state.set_prompt_diff(slint::ModelRc::new(slint::VecModel::from(rows)));
+}
+
+/// Returns how long an error stays visible, or `None` to keep it until replaced.
+fn error_clear_delay(secs: u64) -> Option<Duration> {
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// Timer that clears the error message, so scheduling can be tested without Slint.
+trait ClearTimer {
+    /// Runs `clear` once after `delay`, replacing a pending run.
+    fn restart(&self, delay: Duration, clear: Box<dyn FnMut()>);
+    /// Cancels a pending run.
+    fn stop(&self);
+}
+
+impl ClearTimer for slint::Timer {
+    fn restart(&self, delay: Duration, clear: Box<dyn FnMut()>) {
+        self.start(slint::TimerMode::SingleShot, delay, clear);
+    }
+
+    fn stop(&self) {
+        slint::Timer::stop(self);
+    }
+}
+
+/// Schedules clearing of a newly shown error `message` on `timer`.
+///
+/// Each new error restarts the timeout, so only the latest error is cleared.
+/// An empty message, or an `auto_hide_secs` of 0, stops any pending clear.
+fn schedule_error_clear(
+    timer: &impl ClearTimer,
+    message: &str,
+    auto_hide_secs: u64,
+    clear: impl FnMut() + 'static,
+) {
+    match error_clear_delay(auto_hide_secs).filter(|_| !message.is_empty()) {
+        Some(delay) => timer.restart(delay, Box::new(clear)),
+        None => timer.stop(),
+    }
+}
+
+/// Sets the error message and schedules it to be cleared.
+///
+/// Each new error restarts the timeout, so only the latest error is cleared.
+/// An empty message stops any pending clear.
+pub fn set_error_message(ui: &crate::AppWindow, message: impl Into<String>) {
+    let message: String = message.into();
+    let auto_hide_secs = crate::settings::current().error_auto_hide_secs;
+    let ui_handle = ui.as_weak();
+    ERROR_CLEAR_TIMER.with(|timer| {
+        schedule_error_clear(timer, &message, auto_hide_secs, move || {
+            if let Some(ui) = ui_handle.upgrade() {
+                ui.global::<crate::ViewerState>()
+                    .set_error_message("".into());
+            }
+        })
+    });
+    ui.global::<crate::ViewerState>()
+        .set_error_message(message.into());
+}
+
 /// Sets an error message in the UI with a prefix.
 ///
 /// Logs the error and updates the ViewerState error-message property.
 pub fn set_error_with_prefix(ui: &crate::AppWindow, prefix: &str, error: String) {
     let error_message = format!("{}: {}", prefix, error);
     error!("{}", error_message);
-    ui.global::<crate::ViewerState>()
-        .set_error_message(error_message.into());
+    set_error_message(ui, error_message);
 }
 
 /// Sets error message in the UI from a background thread.
 ///
 /// Uses invoke_from_event_loop to safely update UI from non-UI threads.
 pub fn set_ui_error(ui_handle: &slint::Weak<crate::AppWindow>, message: impl Into<String>) {
+    let message: String = message.into();
+    let ui_handle = ui_handle.clone();
+    let _ = slint::invoke_from_event_loop(move || {
+        if let Some(ui) = ui_handle.upgrade() {
+            set_error_message(&ui, message);
+        }
+    });
+}
+
+/// Sets a status message in the UI from a background thread.
+///
+/// Uses invoke_from_event_loop to safely update UI from non-UI threads.
+pub fn set_ui_status(ui_handle: &slint::Weak<crate::AppWindow>, message: impl Into<String>) {
     let message: String = message.into();
     let ui_handle = ui_handle.clone();
     let _ = slint::invoke_from_event_loop(move || {
         if let Some(ui) = ui_handle.upgrade() {
             ui.global::<crate::ViewerState>()
-                .set_error_message(message.into());
+                .set_status_message(message.into());
         }
     });
 }
 
+/// Sets the progress of preloading the whole directory from a background thread.
+pub fn set_ui_preload_progress(
+    ui_handle: &slint::Weak<crate::AppWindow>,
+    progress: impl Into<String>,
+) {
+    let progress: String = progress.into();
+    let ui_handle = ui_handle.clone();
+    let _ = slint::invoke_from_event_loop(move || {
+        if let Some(ui) = ui_handle.upgrade() {
+            ui.global::<crate::ViewerState>()
+                .set_preload_progress(progress.into());
+        }
+    });
+}
+
+/// Sets the progress of the directory rating prescan from a background thread.
+pub fn set_ui_rating_prescan_progress(
+    ui_handle: &slint::Weak<crate::AppWindow>,
+    progress: impl Into<String>,
+) {
+    let progress: String = progress.into();
+    let ui_handle = ui_handle.clone();
+    let _ = slint::invoke_from_event_loop(move || {
+        if let Some(ui) = ui_handle.upgrade() {
+            ui.global::<crate::ViewerState>()
+                .set_rating_prescan_progress(progress.into());
+        }
+    });
+}
+
+/// Sets the rating summary of the current list from a background thread.
+pub fn set_ui_rating_summary(
+    ui_handle: &slint::Weak<crate::AppWindow>,
+    summary: impl Into<String>,
+) {
+    let summary: String = summary.into();
+    let ui_handle = ui_handle.clone();
+    let _ = slint::invoke_from_event_loop(move || {
+        if let Some(ui) = ui_handle.upgrade() {
+            ui.global::<crate::ViewerState>()
+                .set_rating_summary(summary.into());
+        }
+    });
+}
+
+/// Sets the recently opened files shown in the quick-open menu.
+pub fn set_recent_files(ui: &crate::AppWindow, paths: &[PathBuf]) {
+    let entries: Vec<slint::SharedString> = paths
+        .iter()
+        .map(|path| path.to_string_lossy().as_ref().into())
+        .collect();
+    ui.global::<crate::ViewerState>()
+        .set_recent_files(slint::ModelRc::new(slint::VecModel::from(entries)));
+}
+
+/// Sets the XMP keywords shown in the info panel.
+pub fn set_keywords(ui: &crate::AppWindow, keywords: &[String]) {
+    let entries: Vec<slint::SharedString> = keywords.iter().map(|k| k.as_str().into()).collect();
+    ui.global::<crate::ViewerState>()
+        .set_keywords(slint::ModelRc::new(slint::VecModel::from(entries)));
+}
+
+/// Updates the file list with the file names of the current directory.
+///
+/// The model is rebuilt only when the list changed since the last call,
+/// so calling this on every image display stays cheap.
+pub fn sync_file_list(ui: &crate::AppWindow, nav_state: &NavigationState) {
+    let revision = nav_state.list_revision();
+    if FILE_NAMES_REVISION.with(|r| r.replace(Some(revision))) == Some(revision) {
+        return;
+    }
+
+    let names: Vec<slint::SharedString> = nav_state
+        .file_names_snapshot()
+        .into_iter()
+        .map(Into::into)
+        .collect();
+    FILE_NAMES.with(|model| {
+        model.set_vec(names);
+        ui.global::<crate::ViewerState>()
+            .set_file_names(model.clone().into());
+    });
+}
+
+/// Refreshes the recently opened files from a background thread.
+///
+/// Checks file existence in the background before updating the menu.
+pub fn set_recent_files_from_disk(ui_handle: &slint::Weak<crate::AppWindow>) {
+    let ui_handle = ui_handle.clone();
+    rayon::spawn(move || {
+        let recent = crate::recent_files::existing();
+        let _ = slint::invoke_from_event_loop(move || {
+            if let Some(ui) = ui_handle.upgrade() {
+                set_recent_files(&ui, &recent);
+            }
+        });
+    });
+}
+
 /// Sets all rating-related properties at once.
 ///
 /// Groups: current-rating, rating-in-progress
@@ -97,3 +349,113 @@ pub fn set_navigation_info(
     viewer_state.set_total_index(total_index);
     viewer_state.set_auto_reload_active(auto_reload_active);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// Records restarts and stops and keeps the pending clear callback.
+    #[derive(Default)]
+    struct FakeTimer {
+        calls: RefCell<Vec<Option<Duration>>>,
+        pending: RefCell<Option<Box<dyn FnMut()>>>,
+    }
+
+    impl FakeTimer {
+        /// Runs the pending callback as if the delay elapsed.
+        fn fire(&self) {
+            if let Some(mut clear) = self.pending.borrow_mut().take() {
+                clear();
+            }
+        }
+    }
+
+    impl ClearTimer for FakeTimer {
+        fn restart(&self, delay: Duration, clear: Box<dyn FnMut()>) {
+            self.calls.borrow_mut().push(Some(delay));
+            *self.pending.borrow_mut() = Some(clear);
+        }
+
+        fn stop(&self) {
+            self.calls.borrow_mut().push(None);
+            self.pending.borrow_mut().take();
+        }
+    }
+
+    #[test]
+    fn error_clear_delay_zero_keeps_error() {
+        assert_eq!(error_clear_delay(0), None);
+    }
+
+    #[test]
+    fn error_clear_delay_uses_seconds() {
+        assert_eq!(error_clear_delay(8), Some(Duration::from_secs(8)));
+    }
+
+    #[test]
+    fn new_error_replaces_pending_clear() {
+        let timer = FakeTimer::default();
+        let cleared = Rc::new(RefCell::new(Vec::new()));
+        for message in ["first", "second"] {
+            let cleared = cleared.clone();
+            schedule_error_clear(&timer, message, 5, move || {
+                cleared.borrow_mut().push(message)
+            });
+        }
+
+        timer.fire();
+        assert_eq!(*cleared.borrow(), ["second"]);
+        assert_eq!(
+            *timer.calls.borrow(),
+            [Some(Duration::from_secs(5)), Some(Duration::from_secs(5))]
+        );
+    }
+
+    #[test]
+    fn empty_message_or_disabled_timeout_stops_pending_clear() {
+        let timer = FakeTimer::default();
+        schedule_error_clear(&timer, "error", 5, || panic!("cleared after stop"));
+        schedule_error_clear(&timer, "", 5, || {});
+        timer.fire();
+
+        schedule_error_clear(&timer, "error", 0, || {});
+        assert_eq!(
+            *timer.calls.borrow(),
+            [Some(Duration::from_secs(5)), None, None]
+        );
+    }
+
+    #[test]
+    fn clear_display_resets_image_and_keeps_list() {
+        i_slint_backend_testing::init_no_event_loop();
+        let ui = crate::AppWindow::new().unwrap();
+        let mut nav_state = NavigationState::default();
+        nav_state
+            .set_explicit_list(vec![PathBuf::from("a.png"), PathBuf::from("b.png")])
+            .unwrap();
+
+        let viewer_state = ui.global::<crate::ViewerState>();
+        viewer_state.set_image_loaded(true);
+        viewer_state.set_current_filename("a.png".into());
+        viewer_state.set_image_width(832);
+        viewer_state.set_image_height(1216);
+        set_rating_info(&ui, 4, true);
+        set_navigation_info(&ui, 0, 2, true);
+
+        clear_display(&ui, &mut nav_state);
+
+        assert_eq!(nav_state.current_path(), None);
+        assert_eq!(nav_state.image_count(), 2);
+        assert!(!viewer_state.get_image_loaded());
+        assert_eq!(viewer_state.get_current_filename(), "");
+        assert_eq!(viewer_state.get_image_width(), 0);
+        assert_eq!(viewer_state.get_image_height(), 0);
+        assert_eq!(viewer_state.get_current_rating(), -1);
+        assert!(!viewer_state.get_rating_in_progress());
+        assert_eq!(viewer_state.get_current_index(), -1);
+        assert_eq!(viewer_state.get_total_index(), 2);
+        // Auto-reload keeps running for the empty view
+        assert!(viewer_state.get_auto_reload_active());
+    }
+}